@@ -0,0 +1,351 @@
+use rodio::source::{SineWave, Source, Zero};
+use rodio::{Decoder, OutputStream, Sink};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// What the alarm should play and for how long
+#[derive(Debug, Clone)]
+pub struct AlarmSettings {
+    pub volume: f32,
+    pub duration: Duration,
+    pub fade_in: Duration,
+    pub file_path: Option<String>,
+    pub beep_frequency: f32, // Tone of the generated beep used when there is no alarm file
+}
+
+/// On/off pattern of the generated beep
+const BEEP_ON: Duration = Duration::from_millis(200);
+const BEEP_OFF: Duration = Duration::from_millis(150);
+
+/// A sound that is playing on the alarm thread (rodio's Sink, or a mock in tests)
+pub trait AlarmOutput: Send + Sync {
+    fn set_volume(&self, volume: f32);
+    fn is_finished(&self) -> bool;
+    fn stop(&self);
+}
+
+impl AlarmOutput for Sink {
+    fn set_volume(&self, volume: f32) {
+        Sink::set_volume(self, volume);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.empty()
+    }
+
+    fn stop(&self) {
+        Sink::stop(self);
+    }
+}
+
+/// Opens the audio device and plays the alarm; runs on the alarm thread
+pub trait AlarmBackend: Send + Sync {
+    fn play(&self, settings: &AlarmSettings, control: &AlarmControl);
+}
+
+/// State shared between the player and its alarm thread
+#[derive(Default)]
+pub struct AlarmControl {
+    stopped: AtomicBool,
+    output: Mutex<Option<Arc<dyn AlarmOutput>>>,
+}
+
+impl AlarmControl {
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Register the playing output so stopping silences it right away.
+    /// Returns false (and stops the output) if the alarm was already stopped.
+    pub fn attach(&self, output: Arc<dyn AlarmOutput>) -> bool {
+        let mut current = self.output.lock().unwrap();
+        if self.is_stopped() {
+            output.stop();
+            return false;
+        }
+        *current = Some(output);
+        true
+    }
+
+    fn stop(&self) {
+        let mut current = self.output.lock().unwrap();
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(output) = current.take() {
+            output.stop();
+        }
+    }
+
+    /// Drive the output for the alarm duration, fading it in, until it finishes or is stopped
+    pub fn run(&self, output: &dyn AlarmOutput, settings: &AlarmSettings) {
+        let start_time = Instant::now();
+        while !self.is_stopped() && !output.is_finished() && start_time.elapsed() < settings.duration {
+            let elapsed = start_time.elapsed();
+            output.set_volume(fade_volume(settings.volume, settings.fade_in, elapsed));
+            thread::sleep(Duration::from_millis(if elapsed < settings.fade_in { 50 } else { 100 }));
+        }
+        output.stop();
+    }
+}
+
+/// Alarm volume `elapsed` into a linear fade-in; no fade when `fade_in` is zero
+pub fn fade_volume(alarm_volume: f32, fade_in: Duration, elapsed: Duration) -> f32 {
+    if fade_in.is_zero() || elapsed >= fade_in {
+        alarm_volume
+    } else {
+        alarm_volume * elapsed.as_secs_f32() / fade_in.as_secs_f32()
+    }
+}
+
+/// Plays the alarm on the default audio device
+pub struct RodioBackend;
+
+impl AlarmBackend for RodioBackend {
+    fn play(&self, settings: &AlarmSettings, control: &AlarmControl) {
+        let alarm_path = find_alarm_file(settings.file_path.as_deref());
+
+        // The stream has to stay alive (on this thread) for as long as the sink plays
+        let Ok((_stream, stream_handle)) = OutputStream::try_default() else {
+            return;
+        };
+        let Ok(sink) = Sink::try_new(&stream_handle) else {
+            return;
+        };
+
+        let source = alarm_path
+            .and_then(|path| File::open(path).ok())
+            .and_then(|file| Decoder::new(BufReader::new(file)).ok());
+        let settings = if let Some(source) = source {
+            sink.append(source);
+            settings.clone()
+        } else {
+            // Fallback: a generated beeper pattern for the whole duration, without fading
+            append_beeps(&sink, settings.beep_frequency, settings.duration);
+            AlarmSettings { fade_in: Duration::ZERO, ..settings.clone() }
+        };
+
+        // Start silent (when fading) and ramp up to the alarm volume
+        sink.set_volume(fade_volume(settings.volume, settings.fade_in, Duration::ZERO));
+        let sink: Arc<dyn AlarmOutput> = Arc::new(sink);
+        if control.attach(sink.clone()) {
+            control.run(sink.as_ref(), &settings);
+        }
+    }
+}
+
+/// Queue short sine beeps separated by silence, enough to fill `duration`
+fn append_beeps(sink: &Sink, frequency: f32, duration: Duration) {
+    let beep_count = (duration.as_secs_f32() / (BEEP_ON + BEEP_OFF).as_secs_f32()).ceil() as u32;
+    for _ in 0..beep_count {
+        sink.append(SineWave::new(frequency).take_duration(BEEP_ON));
+        sink.append(Zero::<f32>::new(1, 48000).take_duration(BEEP_OFF));
+    }
+}
+
+/// Configured alarm file if it exists, otherwise alarm.{wav,mp3,ogg,flac,m4a} in the sessio config directory
+fn find_alarm_file(configured_path: Option<&str>) -> Option<PathBuf> {
+    if let Some(configured_path) = configured_path {
+        // Expand ~ to home directory if present
+        let expanded_path = match (configured_path.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(configured_path),
+        };
+        // A configured file that doesn't exist falls back to the beep
+        return expanded_path.exists().then_some(expanded_path);
+    }
+
+    let sessio_config_dir = dirs::config_dir()?.join("sessio");
+    ["alarm.wav", "alarm.mp3", "alarm.ogg", "alarm.flac", "alarm.m4a"]
+        .iter()
+        .map(|name| sessio_config_dir.join(name))
+        .find(|path| path.exists())
+}
+
+/// Owns the alarm thread: starting a new alarm stops the previous one, and dropping the player stops it too
+pub struct AlarmPlayer {
+    backend: Arc<dyn AlarmBackend>,
+    control: Arc<AlarmControl>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl AlarmPlayer {
+    pub fn new() -> Self {
+        Self::with_backend(Arc::new(RodioBackend))
+    }
+
+    pub fn with_backend(backend: Arc<dyn AlarmBackend>) -> Self {
+        Self {
+            backend,
+            control: Arc::new(AlarmControl::default()),
+            handle: None,
+        }
+    }
+
+    /// Play the alarm on a background thread, stopping any alarm that is still playing
+    pub fn start(&mut self, settings: AlarmSettings) {
+        self.stop();
+
+        let control = Arc::new(AlarmControl::default());
+        self.control = control.clone();
+        let backend = self.backend.clone();
+        self.handle = Some(thread::spawn(move || backend.play(&settings, &control)));
+    }
+
+    /// Silence the alarm and wait for its thread to finish
+    pub fn stop(&mut self) {
+        self.control.stop();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Whether the alarm thread is still playing
+    pub fn is_active(&self) -> bool {
+        self.handle.as_ref().is_some_and(|handle| !handle.is_finished())
+    }
+}
+
+impl Drop for AlarmPlayer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockOutput {
+        volumes: Mutex<Vec<f32>>,
+        stopped: AtomicBool,
+    }
+
+    impl AlarmOutput for MockOutput {
+        fn set_volume(&self, volume: f32) {
+            self.volumes.lock().unwrap().push(volume);
+        }
+
+        fn is_finished(&self) -> bool {
+            self.stopped.load(Ordering::SeqCst)
+        }
+
+        fn stop(&self) {
+            self.stopped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Records every output it plays so tests can inspect them
+    #[derive(Default)]
+    struct MockBackend {
+        outputs: Mutex<Vec<Arc<MockOutput>>>,
+    }
+
+    impl AlarmBackend for MockBackend {
+        fn play(&self, settings: &AlarmSettings, control: &AlarmControl) {
+            let output = Arc::new(MockOutput::default());
+            self.outputs.lock().unwrap().push(output.clone());
+            if control.attach(output.clone()) {
+                control.run(output.as_ref(), settings);
+            }
+        }
+    }
+
+    impl MockBackend {
+        fn output(&self, index: usize) -> Option<Arc<MockOutput>> {
+            self.outputs.lock().unwrap().get(index).cloned()
+        }
+
+        fn wait_for_output(&self, index: usize) -> Arc<MockOutput> {
+            let deadline = Instant::now() + Duration::from_secs(2);
+            loop {
+                if let Some(output) = self.output(index) {
+                    return output;
+                }
+                assert!(Instant::now() < deadline, "alarm thread never started");
+                thread::sleep(Duration::from_millis(5));
+            }
+        }
+    }
+
+    fn settings(duration: Duration) -> AlarmSettings {
+        AlarmSettings {
+            volume: 0.5,
+            duration,
+            fade_in: Duration::ZERO,
+            file_path: None,
+            beep_frequency: 880.0,
+        }
+    }
+
+    #[test]
+    fn test_stop_silences_alarm() {
+        let backend = Arc::new(MockBackend::default());
+        let mut player = AlarmPlayer::with_backend(backend.clone());
+        assert!(!player.is_active());
+
+        player.start(settings(Duration::from_secs(60)));
+        let output = backend.wait_for_output(0);
+        assert!(player.is_active());
+
+        player.stop();
+        assert!(!player.is_active());
+        assert!(output.stopped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_new_alarm_stops_previous() {
+        let backend = Arc::new(MockBackend::default());
+        let mut player = AlarmPlayer::with_backend(backend.clone());
+
+        player.start(settings(Duration::from_secs(60)));
+        let first = backend.wait_for_output(0);
+        player.start(settings(Duration::from_secs(60)));
+        let second = backend.wait_for_output(1);
+
+        assert!(first.stopped.load(Ordering::SeqCst));
+        assert!(!second.stopped.load(Ordering::SeqCst));
+        assert!(player.is_active());
+    }
+
+    #[test]
+    fn test_drop_stops_alarm() {
+        let backend = Arc::new(MockBackend::default());
+        let mut player = AlarmPlayer::with_backend(backend.clone());
+        player.start(settings(Duration::from_secs(60)));
+        let output = backend.wait_for_output(0);
+
+        drop(player);
+        assert!(output.stopped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_alarm_ends_after_duration() {
+        let backend = Arc::new(MockBackend::default());
+        let mut player = AlarmPlayer::with_backend(backend.clone());
+        player.start(settings(Duration::from_millis(50)));
+        let output = backend.wait_for_output(0);
+
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while player.is_active() {
+            assert!(Instant::now() < deadline, "alarm never ended");
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(output.stopped.load(Ordering::SeqCst));
+        assert_eq!(output.volumes.lock().unwrap().first(), Some(&0.5));
+    }
+
+    #[test]
+    fn test_fade_volume() {
+        let fade = Duration::from_secs(4);
+        assert_eq!(fade_volume(0.8, fade, Duration::ZERO), 0.0);
+        assert!((fade_volume(0.8, fade, Duration::from_secs(1)) - 0.2).abs() < 1e-6);
+        assert_eq!(fade_volume(0.8, fade, Duration::from_secs(4)), 0.8);
+        assert_eq!(fade_volume(0.8, fade, Duration::from_secs(10)), 0.8);
+        assert_eq!(fade_volume(0.8, Duration::ZERO, Duration::ZERO), 0.8);
+    }
+}
@@ -12,6 +12,7 @@ pub struct App {
     pub focused_quadrant: Quadrant,
     pub show_help: bool,
     pub help: Help,
+    pub show_task_detail: bool, // Detail popup of the selected todo; takes all keys while open
 }
 
 impl App {
@@ -20,6 +21,7 @@ impl App {
             focused_quadrant: Quadrant::TopLeft,
             show_help: false,
             help: Help::new(),
+            show_task_detail: false,
         }
     }
     
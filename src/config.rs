@@ -2,6 +2,7 @@ use std::fs;
 use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 use color_eyre::Result;
+use chrono::NaiveTime;
 
 /// Configuration for the sessio application
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -16,6 +17,9 @@ pub struct Config {
     pub music: MusicConfig,
     /// Theme configuration
     pub theme: ThemeConfig,
+    /// Notification configuration
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,12 +32,260 @@ pub struct TimerConfig {
     pub long_break_minutes: u64,
     /// Number of work sessions before long break (default: 4)
     pub sessions_until_long_break: u32,
+    /// Minimum seconds a skipped phase must run to count as a session (default: 60)
+    #[serde(default = "default_min_session_seconds")]
+    pub min_session_seconds: u64,
+    /// Attach the next unfinished todo when the timed one is marked done (default: false)
+    #[serde(default)]
+    pub auto_attach_next_task: bool,
+    /// Suggestions shown at the start of a break, one picked at random
+    #[serde(default = "default_break_suggestions")]
+    pub break_suggestions: Vec<String>,
+    /// Suggestions for long breaks (default: empty, uses break_suggestions)
+    #[serde(default)]
+    pub long_break_suggestions: Vec<String>,
+    /// Silence alarms and flash the timer panel instead (default: false)
+    #[serde(default)]
+    pub quiet_mode: bool,
+    /// Show the remaining time in the terminal title (default: false)
+    #[serde(default)]
+    pub terminal_title: bool,
+    /// Daily window during which alarms are silent, e.g. "22:00-07:00" (default: none)
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    /// Explicit cycle of phases, e.g. ["work", "short", "work", "long"] (default: none, every Nth break is long)
+    #[serde(default)]
+    pub sequence: Option<Vec<SequenceStep>>,
+    /// Count down pomodoros or count up as a stopwatch (default: pomodoro)
+    #[serde(default)]
+    pub mode: TimerMode,
+    /// Ask for a one-line note after each finished work phase (default: false)
+    #[serde(default)]
+    pub session_notes: bool,
+    /// Record worked minutes when a work phase is reset or stopped early (default: true)
+    #[serde(default = "default_true")]
+    pub credit_partial_work: bool,
+    /// Minimum whole minutes worked before an early reset is credited (default: 1)
+    #[serde(default = "default_min_partial_minutes")]
+    pub min_partial_minutes: u32,
+    /// Ask for a 1-5 focus rating after each finished work session (default: true)
+    #[serde(default = "default_true")]
+    pub focus_rating: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimerMode {
+    #[default]
+    Pomodoro,
+    Stopwatch,
+}
+
+impl TimerMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimerMode::Pomodoro => "pomodoro",
+            TimerMode::Stopwatch => "stopwatch",
+        }
+    }
+}
+
+/// How a task's focused time is shown in its row
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeDisplay {
+    #[default]
+    Minutes,
+    Dots,
+    Both,
+}
+
+impl TimeDisplay {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeDisplay::Minutes => "minutes",
+            TimeDisplay::Dots => "dots",
+            TimeDisplay::Both => "both",
+        }
+    }
+}
+
+/// What the music does while the alarm sounds
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlarmBehavior {
+    /// Play on more quietly (alarm_volume or duck_percent)
+    #[default]
+    Duck,
+    /// Pause, and play on once the alarm is over
+    Pause,
+    /// Play on unchanged
+    Nothing,
+}
+
+impl AlarmBehavior {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlarmBehavior::Duck => "duck",
+            AlarmBehavior::Pause => "pause",
+            AlarmBehavior::Nothing => "nothing",
+        }
+    }
+}
+
+/// Order of the music files in the track list
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackSort {
+    #[default]
+    Name,
+    Path,
+    Modified,
+    Artist,
+    Album,
+    Plays,
+}
+
+impl TrackSort {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrackSort::Name => "name",
+            TrackSort::Path => "path",
+            TrackSort::Modified => "modified",
+            TrackSort::Artist => "artist",
+            TrackSort::Album => "album",
+            TrackSort::Plays => "plays",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            TrackSort::Name => TrackSort::Path,
+            TrackSort::Path => TrackSort::Modified,
+            TrackSort::Modified => TrackSort::Artist,
+            TrackSort::Artist => TrackSort::Album,
+            TrackSort::Album => TrackSort::Plays,
+            TrackSort::Plays => TrackSort::Name,
+        }
+    }
+}
+
+/// File format of a todo list
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TodoFormat {
+    Markdown,
+    Org,
+}
+
+impl TodoFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TodoFormat::Markdown => "markdown",
+            TodoFormat::Org => "org",
+        }
+    }
+
+    /// Org for a .org file, markdown for anything else
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".org") {
+            TodoFormat::Org
+        } else {
+            TodoFormat::Markdown
+        }
+    }
+}
+
+/// One entry of a custom phase sequence
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SequenceStep {
+    Work,
+    Short,
+    Long,
+}
+
+impl SequenceStep {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SequenceStep::Work => "work",
+            SequenceStep::Short => "short",
+            SequenceStep::Long => "long",
+        }
+    }
+}
+
+/// A daily time window such as "22:00-07:00"; the end may be on the next day
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Parse "HH:MM-HH:MM"
+    pub fn parse(value: &str) -> std::result::Result<Self, String> {
+        let (start, end) = value.split_once('-')
+            .ok_or_else(|| format!("invalid quiet_hours \"{}\": expected \"HH:MM-HH:MM\"", value))?;
+        let parse_time = |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .map_err(|_| format!("invalid time \"{}\" in quiet_hours: expected HH:MM", time.trim()));
+        let start = parse_time(start)?;
+        let end = parse_time(end)?;
+        if start == end {
+            return Err(format!("invalid quiet_hours \"{}\": start and end must differ", value));
+        }
+        Ok(Self { start, end })
+    }
+
+    /// Whether the given time of day falls inside the window (start inclusive, end exclusive)
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            time >= self.start && time < self.end
+        } else {
+            // Window wraps past midnight
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl TryFrom<String> for QuietHours {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<QuietHours> for String {
+    fn from(hours: QuietHours) -> Self {
+        format!("{}-{}", hours.start.format("%H:%M"), hours.end.format("%H:%M"))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct NotificationsConfig {
+    /// Pulse the whole UI while the alarm is active (default: false)
+    #[serde(default)]
+    pub visual_alarm: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SummaryConfig {
     /// Show summary at the end of each pomodoro (default: true)
     pub daily_goal_minutes: u32,
+    /// Work sessions to aim for each day, shown next to the minutes goal (default: none)
+    #[serde(default)]
+    pub daily_goal_pomodoros: Option<u32>,
+    /// Tasks listed under "Today by task", most worked on first; 0 hides the list (default: 5)
+    #[serde(default = "default_top_tasks")]
+    pub top_tasks: usize,
+    /// Saturdays and Sundays without work don't break the streak (default: false)
+    #[serde(default)]
+    pub streak_ignores_weekends: bool,
+    /// Directory the reports written with E go to (default: ~/.config/sessio/reports)
+    #[serde(default)]
+    pub reports_directory: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -45,22 +297,142 @@ pub struct TodoConfig {
     /// Save pomodoro session data (default: true)
     #[serde(default = "default_save_pomodoro_data")]
     pub save_pomodoro_data: bool,
+    /// Keep not-done todos ordered by priority (default: false)
+    #[serde(default)]
+    pub sort_by_priority: bool,
+    /// Mark a task done once all of its subtasks are done (default: false)
+    #[serde(default)]
+    pub auto_complete_parents: bool,
+    /// Parse "!1" (priority) and "~4" (estimate) out of new tasks (default: true)
+    #[serde(default = "default_inline_syntax")]
+    pub inline_syntax: bool,
+    /// Wrap long task titles onto more lines instead of cutting them off (default: false)
+    #[serde(default)]
+    pub wrap_titles: bool,
+    /// Daily backups of the todo file to keep, as todos.md.1 (newest) to todos.md.N (default: 3, 0 disables)
+    #[serde(default = "default_backup_count")]
+    pub backup_count: usize,
+    /// Focused time in task rows as minutes "(75min)", a 🍅 per work session, or both (default: minutes)
+    #[serde(default)]
+    pub time_display: TimeDisplay,
+    /// Show how long ago each open task was added, e.g. "3d" or "2w" (default: false)
+    #[serde(default)]
+    pub show_age: bool,
+    /// Tasks at least this many days old show their age in orange (default: 14, 0 never)
+    #[serde(default = "default_stale_after_days")]
+    pub stale_after_days: u32,
+    /// Markdown file a summary of each finished day is appended to (default: none)
+    #[serde(default)]
+    pub journal_path: Option<String>,
+    /// File format of the todo lists (default: by extension, org for .org files and markdown otherwise)
+    #[serde(default)]
+    pub format: Option<TodoFormat>,
+    /// Named todo lists to switch between; empty means the single list at save_path
+    #[serde(default)]
+    pub lists: Vec<TodoList>,
+}
+
+/// A named todo list stored in its own file
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TodoList {
+    pub name: String,
+    pub path: String,
+}
+
+/// An internet radio station listed in the track list
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Stream {
+    pub name: String,
+    pub url: String,
+}
+
+impl TodoConfig {
+    /// The configured lists, or the single list at save_path (todos.md by default) when none are
+    pub fn lists(&self) -> Vec<TodoList> {
+        if self.lists.is_empty() {
+            vec![TodoList {
+                name: "todos".to_string(),
+                path: self.save_path.clone().unwrap_or_else(|| "todos.md".to_string()),
+            }]
+        } else {
+            self.lists.clone()
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MusicConfig {
     /// Default music directory to scan for tracks
     pub music_directory: Option<String>,
+    /// Music to play during work sessions (default: music_directory)
+    pub work_directory: Option<String>,
+    /// Music to play during breaks (default: music_directory)
+    pub break_directory: Option<String>,
+    /// Folder levels scanned for music; 1 is only the music directory itself (default: 3)
+    #[serde(default = "default_scan_depth")]
+    pub scan_depth: usize,
+    /// Folders the scan leaves out, by name or glob pattern (e.g. "audiobooks", "node_*")
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// Leave hidden folders (.git and the like) out of the scan (default: true)
+    #[serde(default = "default_true")]
+    pub skip_hidden: bool,
+    /// List a file only once when copies of it (same name and size) are in other folders (default: false)
+    #[serde(default)]
+    pub dedupe_copies: bool,
     /// Default volume (0.0 to 1.0, default: 0.7)
     pub default_volume: f32,
     /// Auto-play next track (default: true)
     pub auto_play_next: bool,
     /// Volume during alarm (0.0 to 1.0, default: 0.3)
     pub alarm_volume: f32,
+    /// Turn the music down to this percentage of its level while the alarm sounds, in place of
+    /// alarm_volume (default: not set)
+    #[serde(default)]
+    pub duck_percent: Option<u32>,
+    /// What the music does while the alarm sounds: duck, pause or nothing (default: duck)
+    #[serde(default)]
+    pub alarm_behavior: AlarmBehavior,
     /// Alarm duration in seconds (default: 15)
     pub alarm_duration_seconds: u64,
     /// Custom alarm sound file path
     pub alarm_file_path: Option<String>,
+    /// Pause music when a break starts and resume it when work starts again (default: false)
+    #[serde(default)]
+    pub pause_during_breaks: bool,
+    /// Start (or resume) music when a work session starts (default: false)
+    #[serde(default)]
+    pub auto_play_on_work: bool,
+    /// Turn off the music sleep timer when a work session is started (default: false)
+    #[serde(default)]
+    pub work_cancels_sleep_timer: bool,
+    /// Seconds to ramp the alarm up from silence to alarm_volume, 0 disables (default: 3)
+    #[serde(default = "default_fade_in_seconds")]
+    pub fade_in_seconds: u64,
+    /// Frequency in Hz of the generated beep used when there is no alarm file (default: 880)
+    #[serde(default = "default_beep_frequency")]
+    pub beep_frequency: f32,
+    /// List tracks as "Artist — Title" from their tags; false lists file names (default: true)
+    #[serde(default = "default_true")]
+    pub show_tags: bool,
+    /// Order of the track list: name, path, modified (newest first), artist, album or plays (most first, default: name)
+    #[serde(default)]
+    pub track_sort: TrackSort,
+    /// Start playing the track that was playing when sessio was last quit (default: false)
+    #[serde(default)]
+    pub resume_playback: bool,
+    /// Internet radio streams, listed above the music files
+    #[serde(default)]
+    pub streams: Vec<Stream>,
+    /// List white, brown and rain-like noise at the top of the track list (default: false)
+    #[serde(default)]
+    pub builtin_noise: bool,
+    /// Let media keys control the music through MPRIS (Linux only, default: true)
+    #[serde(default = "default_true")]
+    pub mpris: bool,
+    /// Play each track louder or quieter by its ReplayGain track gain tag (default: false)
+    #[serde(default)]
+    pub replaygain: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -70,10 +442,57 @@ pub struct ThemeConfig {
 }
 
 // Default functions for serde
+fn default_top_tasks() -> usize {
+    5
+}
+
 fn default_save_pomodoro_data() -> bool {
     true
 }
 
+fn default_inline_syntax() -> bool {
+    true
+}
+
+fn default_backup_count() -> usize {
+    3
+}
+
+fn default_stale_after_days() -> u32 {
+    14
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_min_partial_minutes() -> u32 {
+    1
+}
+
+fn default_min_session_seconds() -> u64 {
+    60
+}
+
+fn default_fade_in_seconds() -> u64 {
+    3
+}
+
+fn default_scan_depth() -> usize {
+    3
+}
+
+fn default_beep_frequency() -> f32 {
+    880.0
+}
+
+fn default_break_suggestions() -> Vec<String> {
+    ["Stretch", "Drink some water", "Look out the window", "Take a short walk", "Rest your eyes"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -82,6 +501,7 @@ impl Default for Config {
             todo: TodoConfig::default(),
             music: MusicConfig::default(),
             theme: ThemeConfig::default(),
+            notifications: NotificationsConfig::default(),
         }
     }
 }
@@ -93,6 +513,19 @@ impl Default for TimerConfig {
             short_break_minutes: 5,
             long_break_minutes: 15,
             sessions_until_long_break: 4,
+            min_session_seconds: default_min_session_seconds(),
+            auto_attach_next_task: false,
+            break_suggestions: default_break_suggestions(),
+            long_break_suggestions: Vec::new(),
+            quiet_mode: false,
+            terminal_title: false,
+            quiet_hours: None,
+            sequence: None,
+            mode: TimerMode::default(),
+            session_notes: false,
+            credit_partial_work: true,
+            min_partial_minutes: default_min_partial_minutes(),
+            focus_rating: true,
         }
     }
 }
@@ -101,6 +534,10 @@ impl Default for SummaryConfig {
     fn default() -> Self {
         SummaryConfig {
             daily_goal_minutes: 120,
+            daily_goal_pomodoros: None,
+            top_tasks: default_top_tasks(),
+            streak_ignores_weekends: false,
+            reports_directory: None,
         }
     }
 }
@@ -111,6 +548,17 @@ impl Default for TodoConfig {
             auto_save: true,
             save_path: Some("~/.config/sessio/todos.md".to_string()),
             save_pomodoro_data: true,
+            sort_by_priority: false,
+            auto_complete_parents: false,
+            inline_syntax: true,
+            wrap_titles: false,
+            backup_count: 3,
+            time_display: TimeDisplay::Minutes,
+            show_age: false,
+            stale_after_days: 14,
+            journal_path: None,
+            format: None,
+            lists: Vec::new(),
         }
     }
 }
@@ -119,11 +567,31 @@ impl Default for MusicConfig {
     fn default() -> Self {
         MusicConfig {
             music_directory: Some("~/Music".to_string()),
+            work_directory: None,
+            break_directory: None,
+            scan_depth: 3,
+            ignore: Vec::new(),
+            skip_hidden: true,
+            dedupe_copies: false,
             default_volume: 0.7,
             auto_play_next: true,
             alarm_volume: 0.3,
+            duck_percent: None,
+            alarm_behavior: AlarmBehavior::Duck,
             alarm_duration_seconds: 15,
             alarm_file_path: None, // Use default alarm search behavior
+            fade_in_seconds: default_fade_in_seconds(),
+            beep_frequency: default_beep_frequency(),
+            pause_during_breaks: false,
+            auto_play_on_work: false,
+            work_cancels_sleep_timer: false,
+            show_tags: true,
+            track_sort: TrackSort::Name,
+            resume_playback: false,
+            streams: Vec::new(),
+            builtin_noise: false,
+            mpris: true,
+            replaygain: false,
         }
     }
 }
@@ -159,6 +627,7 @@ impl Config {
         if config_path.exists() {
             let config_content = fs::read_to_string(&config_path)?;
             let config: Config = toml::from_str(&config_content)?;
+            config.validate()?;
             Ok(config)
         } else {
             // Create default config and save it
@@ -168,6 +637,56 @@ impl Config {
         }
     }
     
+    /// Reject values that parse but make no sense together
+    fn validate(&self) -> Result<()> {
+        if self.timer.sequence.as_ref().is_some_and(|sequence| !sequence.contains(&SequenceStep::Work)) {
+            return Err(color_eyre::eyre::eyre!(
+                "timer.sequence must contain at least one \"work\" step"
+            ));
+        }
+        if self.music.fade_in_seconds > self.music.alarm_duration_seconds {
+            return Err(color_eyre::eyre::eyre!(
+                "fade_in_seconds ({}) must not be longer than alarm_duration_seconds ({})",
+                self.music.fade_in_seconds,
+                self.music.alarm_duration_seconds
+            ));
+        }
+        for (i, list) in self.todo.lists.iter().enumerate() {
+            if list.name.trim().is_empty() || list.path.trim().is_empty() {
+                return Err(color_eyre::eyre::eyre!("todo.lists entries need a name and a path"));
+            }
+            if self.todo.lists[..i].iter().any(|other| other.name == list.name) {
+                return Err(color_eyre::eyre::eyre!("todo.lists has more than one list named \"{}\"", list.name));
+            }
+        }
+        if self.summary.daily_goal_pomodoros == Some(0) {
+            return Err(color_eyre::eyre::eyre!("summary.daily_goal_pomodoros must be at least 1"));
+        }
+        if self.music.duck_percent.is_some_and(|percent| percent > 100) {
+            return Err(color_eyre::eyre::eyre!("music.duck_percent must be between 0 and 100"));
+        }
+        if self.music.scan_depth == 0 {
+            return Err(color_eyre::eyre::eyre!("scan_depth must be at least 1"));
+        }
+        for pattern in &self.music.ignore {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                return Err(color_eyre::eyre::eyre!("music.ignore pattern \"{}\" is invalid: {}", pattern, e));
+            }
+        }
+        for stream in &self.music.streams {
+            if stream.name.trim().is_empty() || !(stream.url.starts_with("http://") || stream.url.starts_with("https://")) {
+                return Err(color_eyre::eyre::eyre!("music.streams entries need a name and an http(s) url"));
+            }
+        }
+        if !(20.0..=20000.0).contains(&self.music.beep_frequency) {
+            return Err(color_eyre::eyre::eyre!(
+                "beep_frequency ({}) must be between 20 and 20000 Hz",
+                self.music.beep_frequency
+            ));
+        }
+        Ok(())
+    }
+    
     /// Save configuration to file
     pub fn save(&self) -> Result<()> {
         let config_path = Self::config_path()?;
@@ -193,58 +712,193 @@ work_minutes = {}                    # Duration of work sessions in minutes
 short_break_minutes = {}             # Duration of short breaks in minutes
 long_break_minutes = {}              # Duration of long breaks in minutes
 sessions_until_long_break = {}       # Number of work sessions before a long break
+min_session_seconds = {}             # Skipped phases shorter than this don't count as a session
+auto_attach_next_task = {}           # Attach the next unfinished todo when the timed one is done
+break_suggestions = {}               # One is picked at random when a break starts
+long_break_suggestions = {}          # Used for long breaks instead, when not empty
+quiet_mode = {}                      # Silence alarms and flash the timer panel instead (toggle with M)
+terminal_title = {}                  # Show the remaining time in the terminal window title
+{}{}mode = "{}"                      # "pomodoro" or "stopwatch" (toggle with t)
+session_notes = {}                   # Ask "What did you get done?" after each finished work session
+credit_partial_work = {}             # Record worked minutes when a work session is reset early
+min_partial_minutes = {}             # ...once at least this many whole minutes were worked
+focus_rating = {}                    # Ask for a 1-5 focus rating after each finished work session
 
 [summary]
 # Summary panel settings (current values shown)
-daily_goal_minutes = {}              # Daily focus time goal in minutes
-
+daily_goal_minutes = {}              # Daily focus time goal in minutes (0: none)
+{}top_tasks = {}                        # Tasks listed under "Today by task" (0 hides the list)
+streak_ignores_weekends = {}      # Weekends without work don't break the streak
+{}
 [todo]
 # Todo list settings (current values shown)
-auto_save = {}                       # Automatically save todos to file
+auto_save = {}                       # Automatically save todos to file (false: save with Ctrl+s)
 save_pomodoro_data = {}             # Save pomodoro session data to todos.md
-{}
+sort_by_priority = {}               # Keep unfinished tasks ordered by priority (cycle with p)
+auto_complete_parents = {}          # Mark a task done once all of its subtasks are done
+inline_syntax = {}                  # Parse "!1".."!3" (priority) and "~4" (estimate) out of new tasks
+wrap_titles = {}                    # Wrap long task titles instead of cutting them off (toggle with W)
+backup_count = {}                   # Daily backups of the todo file to keep (todos.md.1 is the newest, 0 disables)
+time_display = "{}"               # Focused time in task rows: "minutes", "dots" (a 🍅 per work session) or "both"
+show_age = {}                       # Show how long ago open tasks were added ("3d", "2w")
+stale_after_days = {}               # Ages of at least this many days are shown in orange (0 never)
+{}{}{}{}
 
 [music]
 # Music player settings (current values shown)
-{}default_volume = {}                # Default volume (0.0 to 1.0)
+{}{}{}scan_depth = {}                       # Folder levels scanned for music (1 is only the music directory)
+ignore = {}                          # Folders left out of the scan, by name or glob pattern (e.g. ["audiobooks", "node_*"])
+skip_hidden = {}                     # Leave hidden folders (.git and the like) out of the scan
+dedupe_copies = {}                   # List copies of a file (same name and size) in other folders only once
+default_volume = {}                # Default volume (0.0 to 1.0)
 auto_play_next = {}                  # Automatically play next track when current ends
 alarm_volume = {}                    # Volume during alarm notification (0.0 to 1.0)
+{}alarm_behavior = "{}"              # What the music does during the alarm: "duck", "pause" or "nothing"
 alarm_duration_seconds = {}          # How long the alarm sound lasts in seconds
-{}
+fade_in_seconds = {}                 # Ramp the alarm up from silence over this many seconds (0 disables)
+beep_frequency = {:?}                 # Tone (Hz) of the beep played when there is no alarm file
+pause_during_breaks = {}             # Pause music during breaks and resume it when work starts
+auto_play_on_work = {}               # Start music when a work session is started
+work_cancels_sleep_timer = {}        # Turn off the music sleep timer when a work session is started
+show_tags = {}                       # List tracks by their artist/title tags instead of file names
+track_sort = "{}"                  # Order of the track list: "name", "path", "modified", "artist", "album" or "plays"
+resume_playback = {}                 # Play the track that was playing when sessio was last quit
+builtin_noise = {}                   # List generated white, brown and rain-like noise above the music
+mpris = {}                           # Let media keys control the music (Linux only)
+replaygain = {}                      # Even out loudness between tracks with their ReplayGain tags
+{}{}
 
 [theme]
 # Theme settings (current values shown)
 use_dracula = {}                     # Use the Dracula color theme
 
+[notifications]
+# Notification settings (current values shown)
+visual_alarm = {}                    # Pulse the whole UI while the alarm is active
+
 # Configuration can be reloaded at runtime by pressing 'C' (capital C) in the application
 "#,
             self.timer.work_minutes,
             self.timer.short_break_minutes,
             self.timer.long_break_minutes,
             self.timer.sessions_until_long_break,
+            self.timer.min_session_seconds,
+            self.timer.auto_attach_next_task,
+            toml::Value::from(self.timer.break_suggestions.clone()),
+            toml::Value::from(self.timer.long_break_suggestions.clone()),
+            self.timer.quiet_mode,
+            self.timer.terminal_title,
+            if let Some(hours) = self.timer.quiet_hours {
+                format!("quiet_hours = \"{}\"          # Alarms are silent during this daily window\n", String::from(hours))
+            } else {
+                "# quiet_hours = \"22:00-07:00\"        # Optional: alarms are silent during this daily window\n".to_string()
+            },
+            if let Some(ref sequence) = self.timer.sequence {
+                let names: Vec<String> = sequence.iter().map(|step| step.name().to_string()).collect();
+                format!("sequence = {}   # Phases to cycle through (work, short, long)\n", toml::Value::from(names))
+            } else {
+                "# sequence = [\"work\", \"short\", \"work\", \"long\"]  # Optional: explicit phase cycle instead of sessions_until_long_break\n".to_string()
+            },
+            self.timer.mode.name(),
+            self.timer.session_notes,
+            self.timer.credit_partial_work,
+            self.timer.min_partial_minutes,
+            self.timer.focus_rating,
             self.summary.daily_goal_minutes,
+            if let Some(pomodoros) = self.summary.daily_goal_pomodoros {
+                format!("daily_goal_pomodoros = {}            # Daily goal in work sessions\n", pomodoros)
+            } else {
+                "# daily_goal_pomodoros = 8            # Optional: daily goal in work sessions\n".to_string()
+            },
+            self.summary.top_tasks,
+            self.summary.streak_ignores_weekends,
+            if let Some(ref dir) = self.summary.reports_directory {
+                format!("reports_directory = \"{}\"          # Reports written with E go here\n", dir)
+            } else {
+                "# reports_directory = \"~/notes/reports\"  # Optional: where reports written with E go (default: ~/.config/sessio/reports)\n".to_string()
+            },
             self.todo.auto_save,
             self.todo.save_pomodoro_data,
+            self.todo.sort_by_priority,
+            self.todo.auto_complete_parents,
+            self.todo.inline_syntax,
+            self.todo.wrap_titles,
+            self.todo.backup_count,
+            self.todo.time_display.name(),
+            self.todo.show_age,
+            self.todo.stale_after_days,
             if let Some(ref path) = self.todo.save_path {
                 format!("save_path = \"{}\"                   # Custom path for saving todos\n", path)
             } else {
                 "# save_path = \"custom/path/todos.json\"  # Optional: custom path for saving todos\n".to_string()
             },
+            if let Some(ref path) = self.todo.journal_path {
+                format!("journal_path = \"{}\"                   # Daily summaries are appended here\n", path)
+            } else {
+                "# journal_path = \"~/.config/sessio/journal.md\"  # Optional: append a summary of each day to this file\n".to_string()
+            },
+            if let Some(format) = self.todo.format {
+                format!("format = \"{}\"                   # File format of the todo lists: \"markdown\" or \"org\"\n", format.name())
+            } else {
+                "# format = \"org\"  # Optional: file format (default: org for .org files, markdown otherwise)\n".to_string()
+            },
+            if self.todo.lists.is_empty() {
+                "# lists = [{ name = \"work\", path = \"~/.config/sessio/work.md\" }, { name = \"home\", path = \"~/.config/sessio/home.md\" }]  # Optional: lists to switch between with Tab\n".to_string()
+            } else {
+                format!("lists = {}  # Lists to switch between with Tab\n", toml::Value::try_from(&self.todo.lists).unwrap_or(toml::Value::Array(Vec::new())))
+            },
             if let Some(ref dir) = self.music.music_directory {
                 format!("music_directory = \"{}\"           # Directory to scan for music files\n", dir)
             } else {
                 "# music_directory = \"/path/to/music\"   # Optional: directory to scan for music files\n".to_string()
             },
+            if let Some(ref dir) = self.music.work_directory {
+                format!("work_directory = \"{}\"            # Music played during work sessions\n", dir)
+            } else {
+                "# work_directory = \"~/Music/focus\"     # Optional: music played during work sessions\n".to_string()
+            },
+            if let Some(ref dir) = self.music.break_directory {
+                format!("break_directory = \"{}\"           # Music played during breaks\n", dir)
+            } else {
+                "# break_directory = \"~/Music/breaks\"   # Optional: music played during breaks\n".to_string()
+            },
+            self.music.scan_depth,
+            toml::Value::try_from(&self.music.ignore).unwrap_or(toml::Value::Array(Vec::new())),
+            self.music.skip_hidden,
+            self.music.dedupe_copies,
             self.music.default_volume,
             self.music.auto_play_next,
             self.music.alarm_volume,
+            if let Some(percent) = self.music.duck_percent {
+                format!("duck_percent = {}                     # Music turned down to this percentage of its level during the alarm\n", percent)
+            } else {
+                "# duck_percent = 30                   # Optional: turn the music down to this percentage of its level during the alarm\n".to_string()
+            },
+            self.music.alarm_behavior.name(),
             self.music.alarm_duration_seconds,
+            self.music.fade_in_seconds,
+            self.music.beep_frequency,
+            self.music.pause_during_breaks,
+            self.music.auto_play_on_work,
+            self.music.work_cancels_sleep_timer,
+            self.music.show_tags,
+            self.music.track_sort.name(),
+            self.music.resume_playback,
+            self.music.builtin_noise,
+            self.music.mpris,
+            self.music.replaygain,
+            if self.music.streams.is_empty() {
+                "# streams = [{ name = \"lofi\", url = \"http://example.com/lofi.mp3\" }]  # Optional: internet radio (mp3 or ogg) listed with 📡\n".to_string()
+            } else {
+                format!("streams = {}  # Internet radio listed with 📡\n", toml::Value::try_from(&self.music.streams).unwrap_or(toml::Value::Array(Vec::new())))
+            },
             if let Some(ref path) = self.music.alarm_file_path {
                 format!("alarm_file_path = \"{}\"            # Custom alarm sound file path\n", path)
             } else {
                 "# alarm_file_path = \"~/alarm.wav\"      # Optional: custom alarm sound file path\n".to_string()
             },
-            self.theme.use_dracula
+            self.theme.use_dracula,
+            self.notifications.visual_alarm
         )
     }
     
@@ -282,4 +936,146 @@ mod tests {
         assert_eq!(config.timer.work_minutes, deserialized.timer.work_minutes);
         assert_eq!(config.todo.auto_save, deserialized.todo.auto_save);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_formatted_toml_round_trip() {
+        let config = Config::default();
+        let formatted = config.to_formatted_toml();
+        let parsed: Config = toml::from_str(&formatted).expect("Formatted config should parse");
+        
+        assert_eq!(parsed.timer.work_minutes, config.timer.work_minutes);
+        assert_eq!(parsed.timer.break_suggestions, config.timer.break_suggestions);
+        assert!(parsed.timer.long_break_suggestions.is_empty());
+    }
+    
+    #[test]
+    fn test_quiet_hours_parse() {
+        let hours = QuietHours::parse("22:00-07:00").unwrap();
+        assert_eq!(hours.start, NaiveTime::from_hms_opt(22, 0, 0).unwrap());
+        assert_eq!(hours.end, NaiveTime::from_hms_opt(7, 0, 0).unwrap());
+        assert_eq!(String::from(hours), "22:00-07:00");
+        
+        assert!(QuietHours::parse(" 12:30 - 13:15 ").is_ok());
+        assert!(QuietHours::parse("22:00").is_err());
+        assert!(QuietHours::parse("25:00-07:00").is_err());
+        assert!(QuietHours::parse("22:00-7pm").is_err());
+        assert!(QuietHours::parse("08:00-08:00").is_err());
+    }
+    
+    #[test]
+    fn test_quiet_hours_rejected_at_load() {
+        let mut formatted = Config::default().to_formatted_toml();
+        formatted = formatted.replace("[summary]", "quiet_hours = \"22:00-late\"\n\n[summary]");
+        assert!(toml::from_str::<Config>(&formatted).is_err());
+    }
+    
+    #[test]
+    fn test_duck_percent() {
+        let mut config = Config::default();
+        let parsed: Config = toml::from_str(&config.to_formatted_toml()).unwrap();
+        assert_eq!(parsed.music.duck_percent, None);
+
+        config.music.duck_percent = Some(30);
+        assert!(config.validate().is_ok());
+        let parsed: Config = toml::from_str(&config.to_formatted_toml()).unwrap();
+        assert_eq!(parsed.music.duck_percent, Some(30));
+
+        config.music.duck_percent = Some(101);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_daily_goal_pomodoros() {
+        let mut config = Config::default();
+        let parsed: Config = toml::from_str(&config.to_formatted_toml()).unwrap();
+        assert_eq!(parsed.summary.daily_goal_pomodoros, None);
+
+        config.summary.daily_goal_pomodoros = Some(8);
+        assert!(config.validate().is_ok());
+        let parsed: Config = toml::from_str(&config.to_formatted_toml()).unwrap();
+        assert_eq!(parsed.summary.daily_goal_pomodoros, Some(8));
+
+        config.summary.daily_goal_pomodoros = Some(0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_alarm_behavior() {
+        let mut config = Config::default();
+        assert_eq!(config.music.alarm_behavior, AlarmBehavior::Duck);
+        config.music.alarm_behavior = AlarmBehavior::Pause;
+        let parsed: Config = toml::from_str(&config.to_formatted_toml()).unwrap();
+        assert_eq!(parsed.music.alarm_behavior, AlarmBehavior::Pause);
+        // Older configs without the key keep ducking
+        let parsed: Config = toml::from_str(&config.to_formatted_toml().replace("alarm_behavior = \"pause\"", "")).unwrap();
+        assert_eq!(parsed.music.alarm_behavior, AlarmBehavior::Duck);
+    }
+
+    #[test]
+    fn test_fade_in_validation() {
+        let mut config = Config::default();
+        assert!(config.validate().is_ok());
+        
+        config.music.fade_in_seconds = 0;
+        assert!(config.validate().is_ok());
+        
+        config.music.fade_in_seconds = config.music.alarm_duration_seconds + 1;
+        assert!(config.validate().is_err());
+    }
+    
+    #[test]
+    fn test_sequence_parsing() {
+        let mut config = Config::default();
+        config.timer.sequence = Some(vec![SequenceStep::Work, SequenceStep::Short, SequenceStep::Work, SequenceStep::Long]);
+        let parsed: Config = toml::from_str(&config.to_formatted_toml()).expect("Formatted config should parse");
+        assert_eq!(parsed.timer.sequence, config.timer.sequence);
+        assert!(parsed.validate().is_ok());
+        
+        let formatted = config.to_formatted_toml().replace("\"long\"]", "\"lunch\"]");
+        let error = toml::from_str::<Config>(&formatted).unwrap_err().to_string();
+        assert!(error.contains("lunch"), "{}", error);
+        
+        config.timer.sequence = Some(vec![SequenceStep::Short, SequenceStep::Long]);
+        assert!(config.validate().is_err());
+    }
+    
+    #[test]
+    fn test_beep_frequency_validation() {
+        let mut config = Config::default();
+        config.music.beep_frequency = 0.0;
+        assert!(config.validate().is_err());
+        config.music.beep_frequency = 440.0;
+        assert!(config.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_todo_lists() {
+        let mut config = Config::default();
+        let lists = config.todo.lists();
+        assert_eq!(lists.len(), 1);
+        assert_eq!(Some(lists[0].path.clone()), config.todo.save_path);
+        
+        config.todo.lists = vec![
+            TodoList { name: "work".to_string(), path: "~/work.md".to_string() },
+            TodoList { name: "home".to_string(), path: "~/home.md".to_string() },
+        ];
+        assert!(config.validate().is_ok());
+        let parsed: Config = toml::from_str(&config.to_formatted_toml()).expect("Formatted config should parse");
+        assert_eq!(parsed.todo.lists(), config.todo.lists);
+        
+        config.todo.lists[1].name = "work".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_streams() {
+        let mut config = Config::default();
+        config.music.streams = vec![Stream { name: "lofi".to_string(), url: "https://example.com/lofi.mp3".to_string() }];
+        assert!(config.validate().is_ok());
+        let parsed: Config = toml::from_str(&config.to_formatted_toml()).expect("Formatted config should parse");
+        assert_eq!(parsed.music.streams, config.music.streams);
+
+        config.music.streams[0].url = "~/lofi.mp3".to_string();
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use chrono::{DateTime, Local, NaiveDate};
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::timer::PomodoroSession;
+use crate::todo::TodoItem;
+
+/// Version of the export format, bumped on incompatible changes
+pub const EXPORT_VERSION: u32 = 1;
+
+/// The JSON document written by an export.
+///
+/// Dates are "YYYY-MM-DD" and timestamps RFC 3339 with the local offset:
+///
+/// ```text
+/// {
+///   "version": 1,
+///   "exported_at": "2026-10-16T09:30:00+02:00",
+///   "todos": [
+///     { "id": 1, "task": "Write report #work", "done": false, "focused_time": 50,
+///       "timeline": [{ "date": "2026-10-16", "minutes": 50, "timestamp": "2026-10-16T09:25:00+02:00" }],
+///       "session_minutes": null, "estimate": 4, "priority": "high", "parent_id": null,
+///       "notes": "", "repeat": "daily", "completions": ["2026-10-15T18:00:00+02:00"],
+///       "completed_at": null, "uuid": null, "starred": false,
+///       "created_at": "2026-10-14T17:05:00+02:00", "scheduled_for": "2026-10-16",
+///       "blocked_by": null }
+///   ],
+///   "pomodoro_sessions": [
+///     { "date": "2026-10-16", "work_sessions": 2, "total_work_minutes": 50, "break_sessions": 1,
+///       "total_break_minutes": 5, "tasks_worked_on": ["Write report #work"], "ratings": [4],
+///       "entries": [{ "start": "2026-10-16T09:00:00+02:00", "phase": "work", "minutes": 25,
+///                     "task": "Write report #work", "note": null }] }
+///   ]
+/// }
+/// ```
+///
+/// `phase` is one of "work", "short_break" or "long_break"; `priority` one of "high", "medium",
+/// "low" or null; `repeat` a spec such as "daily", "every 2 weeks" or "every mon,wed,fri", or null;
+/// `completed_at` when a done task was marked done, or null; `created_at` when the task was added;
+/// `uuid` the task's Taskwarrior UUID once it has been exported there, or null; `starred` whether
+/// the task is pinned to the top; `scheduled_for` the day the task is planned for, or null;
+/// `blocked_by` the `id` of the task that has to be done first, or null.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Export {
+    pub version: u32,
+    pub exported_at: DateTime<Local>,
+    pub todos: Vec<TodoItem>,
+    pub pomodoro_sessions: Vec<PomodoroSession>,
+}
+
+impl Export {
+    pub fn new(todos: &[TodoItem], pomodoro_sessions: &[PomodoroSession]) -> Self {
+        Self {
+            version: EXPORT_VERSION,
+            exported_at: Local::now(),
+            todos: todos.to_vec(),
+            pomodoro_sessions: pomodoro_sessions.to_vec(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Write the export to `path`, or to the default export path when none is given
+    pub fn write(&self, path: Option<&Path>) -> Result<PathBuf> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => default_export_path(self.exported_at.date_naive())?,
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, self.to_json()?)?;
+        Ok(path)
+    }
+}
+
+/// ~/.config/sessio/export-YYYYMMDD.json
+pub fn default_export_path(date: NaiveDate) -> Result<PathBuf> {
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| color_eyre::eyre::eyre!("Could not find config directory"))?;
+    Ok(config_dir.join("sessio").join(format!("export-{}.json", date.format("%Y%m%d"))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recurrence::Recurrence;
+    use crate::test_util::at;
+    use crate::timer::{PomodoroPhase, SessionEntry};
+    use crate::todo::{Priority, WorkSession};
+
+    fn sample() -> Export {
+        let mut report = TodoItem::new("Write report #work".to_string());
+        report.id = 1;
+        report.focused_time = 75;
+        report.estimate = Some(4);
+        report.priority = Some(Priority::High);
+        report.notes = "Outline first\nthen details".to_string();
+        report.timeline = vec![
+            WorkSession { date: at(2026, 10, 15, 0, 0).date_naive(), minutes: 25, timestamp: at(2026, 10, 15, 10, 25) },
+            WorkSession { date: at(2026, 10, 16, 0, 0).date_naive(), minutes: 50, timestamp: at(2026, 10, 16, 9, 50) },
+        ];
+
+        let mut inbox = TodoItem::new("Review inbox".to_string());
+        inbox.id = 2;
+        inbox.done = true;
+        inbox.parent_id = Some(1);
+        inbox.repeat = Some(Recurrence::parse("every mon,wed,fri").unwrap());
+        inbox.completions = vec![at(2026, 10, 16, 8, 0)];
+
+        let session = PomodoroSession {
+            date: at(2026, 10, 16, 0, 0).date_naive(),
+            work_sessions: 2,
+            total_work_minutes: 50,
+            break_sessions: 1,
+            total_break_minutes: 5,
+            tasks_worked_on: vec!["Write report #work".to_string()],
+            entries: vec![SessionEntry {
+                start: at(2026, 10, 16, 9, 0),
+                phase: PomodoroPhase::ShortBreak,
+                minutes: 5,
+                task: None,
+                note: Some("stretched".to_string()),
+            }],
+            ratings: vec![4, 5],
+        };
+
+        Export {
+            version: EXPORT_VERSION,
+            exported_at: at(2026, 10, 16, 18, 30),
+            todos: vec![report, inbox],
+            pomodoro_sessions: vec![session],
+        }
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let export = sample();
+        let json = export.to_json().unwrap();
+        assert_eq!(serde_json::from_str::<Export>(&json).unwrap(), export);
+    }
+
+    #[test]
+    fn test_export_format() {
+        let json = sample().to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        let exported_at = value["exported_at"].as_str().unwrap();
+        assert_eq!(DateTime::parse_from_rfc3339(exported_at).unwrap(), at(2026, 10, 16, 18, 30));
+        assert_eq!(value["todos"][0]["timeline"][1]["date"], "2026-10-16");
+        assert_eq!(value["todos"][0]["priority"], "high");
+        assert_eq!(value["todos"][1]["repeat"], "every mon,wed,fri");
+        assert_eq!(value["pomodoro_sessions"][0]["entries"][0]["phase"], "short_break");
+    }
+
+    #[test]
+    fn test_export_write() {
+        let path = std::env::temp_dir().join(format!("sessio-export-test-{}.json", std::process::id()));
+        let export = sample();
+        assert_eq!(export.write(Some(&path)).unwrap(), path);
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(serde_json::from_str::<Export>(&written).unwrap(), export);
+    }
+}
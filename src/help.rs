@@ -30,25 +30,81 @@ impl Help {
   j/k     - Navigate within current panel (up/down)
   q       - Quit application
   ?       - Toggle this help (ESC to close)
+  ESC     - Dismiss a ringing alarm
   C       - Reload configuration file
 
 ⏱️  TIMER PANEL (Top-Left):
-  Space   - Start/Pause timer
+  Space   - Start/Pause timer (stopwatch: start/stop and record)
   r       - Reset current timer
   S       - Skip to next phase
-  • Plays alarm sound when timer ends (place alarm.wav in ~/.config/sessio/)
+  w/b/B   - Switch to work / short break / long break (when not running; paused work is
+            credited like a reset, no session is counted)
+  t       - Switch between pomodoro and stopwatch (count-up) mode
+  • With session_notes on, a finished work session asks for a short note
+    (Enter saves, Esc or empty skips)
+  j/k     - Scroll today's session log (shown when there is room)
+  M       - Toggle quiet mode (no alarm sound, flashing border instead)
+  A       - Preview the alarm (press again to stop)
+  1-5     - Rate your focus after a work session (ESC to skip, closes after 30s)
+  • 🔇 = quiet mode on, 🌙 = inside configured quiet_hours
+  • Plays alarm sound when timer ends (place alarm.wav in ~/.config/sessio/, otherwise it beeps)
 
 ✅ TODO PANEL (Bottom-Left):
   j/k     - Navigate within todo items  
+  0-9     - Jump to a task by its number (Enter or a short pause jumps; a tag filter or
+            today view hiding it is turned off)
+  Enter   - Show task details: full text, notes and timeline (j/k scroll, ESC closes)
+  J/K     - Move selected task down/up (not past done tasks)
+  T/B     - Move selected task to the top / to just above the done tasks
   a       - Add new task
+  • Inline syntax: "!1 #deep Write talk ~4" = high priority (!1-!3), 4 pomodoros estimated
+  e       - Edit selected task text (ESC cancels)
+  • While typing: ←/→ move the cursor, Ctrl+a/Ctrl+e jump to start/end,
+    Ctrl+w deletes a word, Backspace/Delete remove before/under the cursor
+  • Pasting inserts the whole clipboard text at the cursor (ignored outside an input)
   d       - Toggle done status
-  D       - Delete selected task
+  A       - Add subtask under selected task
+  u       - Open the link in the selected task or its notes (a picker when there are several)
+  N       - Edit notes of selected task (Enter = new line, ESC saves and closes; 📝 = has notes)
+  D       - Delete selected task (with subtasks: press D again to confirm)
+  c       - Clear all done tasks (press c again to confirm, z restores them)
   s       - Select task for timer (starts timer)
+  L       - Set custom session length for task (empty = default)
+  E       - Set estimated pomodoros for task (empty = none)
+  r       - Repeat task: daily, every 3 days, weekly, every 2 weeks, every mon,wed,fri (empty = none)
+  • 🔁 = recurring: a done recurring task comes back when it is due again
+  p       - Cycle priority: high (red) → medium (yellow) → low (grey) → none
+  b       - Blocked by: pick the task that has to be done first (b again unblocks)
+  • 🔒 = blocked: dimmed, and s won't time it until the blocker is done
+  *       - Star task: ⭐ tasks stay above the other unfinished tasks
+  P       - Schedule task for a day: 2025-07-02, today, tomorrow, mon..sun (empty = none)
+  v       - Today view: only tasks scheduled for today, plus overdue ones (📅 orange)
+  • [2/4 🍅] = pomodoros done / estimate (orange when over)
+  W       - Wrap long task titles onto more lines / cut them off (see wrap_titles)
+  f       - Filter by #tag (cycles through tags, then shows all)
+  • #tags in task text are shown in cyan
+  • 3d/2w at the right = task age (see show_age), orange once stale
+  o       - Cycle sort: manual → focus time → a-z → priority → age → due date (scheduled day,
+            unscheduled last; view only, todos.md keeps the manual order; J/K and T/B only move
+            tasks in manual order)
+  Tab     - Switch to the next todo list (see lists in [todo] config)
+  X       - Export todos and sessions to ~/.config/sessio/export-YYYYMMDD.json
   z       - Undo last action
+  Ctrl+s  - Save the todo file now (needed with auto_save = false; ● in the title = unsaved)
+  • Edits to the todo file made in another program are reloaded automatically (z undoes);
+    if sessio had changes too, m keeps sessio's list and t takes the file's
   PgUp/Dn - Page up/down in todo list
 
 📊 SUMMARY PANEL (Top-Right):
   Shows daily statistics, streaks, and progress
+  v       - Switch between today (with the last 7 days), this week against the same days
+            of last week, and a month calendar, each day colored by focus time against the
+            daily goal
+  h/l     - In the month calendar: previous/next month (v again to move between panels)
+  k/j     - Step back/forward through earlier days with sessions: their focus time, breaks
+            and the tasks worked on
+  t       - Back to today
+  E       - Write a markdown report of the day, week or month shown (see reports_directory)
 
 🎵 TRACK LIST PANEL (Bottom-Right):
   j/k     - Navigate within track list
@@ -56,7 +112,22 @@ impl Help {
   Enter   - Play selected track
   n       - Next track
   p       - Previous track
-  m       - Cycle playback mode (Track List/Random/Repeat/Current Only)
+  m       - Cycle playback mode (Track List/Random/Repeat/Current Only);
+            Random plays every track once in a shuffled order before reshuffling
+  • ⏭ after the mode = tracks advance by themselves (auto_play_next in the config)
+  ←/→     - Seek 10 seconds back/forward in the playing track (also ,/.)
+  • 03:12 / 47:00 in the title = position in the playing track (--:-- = length unknown)
+  +/-     - Volume up/down by 5% (also 0/9; up to 150%, shown as 🔊 in the title)
+  f       - Favorite: mark the selected track with ♥ (kept in ~/.config/sessio/favorites.txt)
+  F       - Favorites only: show and play just the ♥ tracks, or all of them again
+  g       - Go to the playing track: select it and scroll it into view
+  G       - Follow: keep selecting the playing track as it moves on (⤵ in the title;
+            waits while you browse with j/k)
+  z       - Sleep timer: stop the music in 15, 30 or 60 minutes, or off (💤 in the title);
+            the music fades out over 10 seconds
+  P       - Pin the work/break playlist so timer phase changes don't switch it (📌)
+  o       - Sort: cycle name, path, modified, artist, album and plays (saved in the config);
+            plays puts the most played tracks first, with ×N for how often each was played
   R       - Refresh music library
 
 🍅 POMODORO TECHNIQUE:
@@ -0,0 +1,208 @@
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use color_eyre::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::timer::{PomodoroPhase, PomodoroSession};
+
+/// Longest content line allowed by RFC 5545 (section 3.1), in octets, not counting the CRLF
+const MAX_LINE_OCTETS: usize = 75;
+
+/// The work sessions as an iCalendar (RFC 5545) document with one VEVENT each, e.g.
+///
+/// ```text
+/// BEGIN:VEVENT
+/// UID:20250630-1@sessio
+/// DTSTAMP:20250701T080000Z
+/// DTSTART:20250630T070500Z
+/// DTEND:20250630T073000Z
+/// SUMMARY:Write report
+/// DESCRIPTION:drafted intro section
+/// END:VEVENT
+/// ```
+///
+/// Times are in UTC, so calendar apps show them in their own time zone whatever the offset was
+/// that day. The n-th work session of a day keeps its UID, so importing a later export again
+/// updates the events instead of duplicating them. Days before `since` are left out.
+pub fn calendar(sessions: &[PomodoroSession], since: Option<NaiveDate>, now: DateTime<Local>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//sessio//pomodoro sessions//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    // Counted per day across the day's sessions, in the order they were logged
+    let mut counts: HashMap<NaiveDate, usize> = HashMap::new();
+    for session in sessions {
+        let entries = session.entries.iter().filter(|entry| entry.phase == PomodoroPhase::Work && entry.minutes > 0);
+        for entry in entries {
+            let count = counts.entry(session.date).or_default();
+            *count += 1;
+            if since.is_some_and(|since| session.date < since) {
+                continue;
+            }
+            let end = entry.start + chrono::Duration::minutes(entry.minutes.into());
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}-{}@sessio", session.date.format("%Y%m%d"), count));
+            lines.push(format!("DTSTAMP:{}", timestamp(now)));
+            lines.push(format!("DTSTART:{}", timestamp(entry.start)));
+            lines.push(format!("DTEND:{}", timestamp(end)));
+            lines.push(format!("SUMMARY:{}", escape(entry.task.as_deref().unwrap_or("Pomodoro"))));
+            if let Some(ref note) = entry.note {
+                lines.push(format!("DESCRIPTION:{}", escape(note)));
+            }
+            lines.push("END:VEVENT".to_string());
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// "20250630T070500Z"
+fn timestamp(time: DateTime<Local>) -> String {
+    time.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// A TEXT value with backslashes, semicolons, commas and line breaks escaped (RFC 5545 3.3.11)
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The content line ended by CRLF, split into lines of at most 75 octets, each continuation
+/// starting with a space (RFC 5545 3.1). Characters are never split across lines.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1; // The space
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Write the calendar to `path`, or to ~/.config/sessio/sessions-YYYYMMDD.ics when none is given
+pub fn write(content: &str, path: Option<&Path>, today: NaiveDate) -> Result<PathBuf> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let config_dir = dirs::config_dir()
+                .ok_or_else(|| color_eyre::eyre::eyre!("Could not find config directory"))?;
+            config_dir.join("sessio").join(format!("sessions-{}.ics", today.format("%Y%m%d")))
+        }
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{at, empty_session};
+    use crate::timer::SessionEntry;
+
+    fn entry(d: u32, hour: u32, phase: PomodoroPhase, minutes: u32, task: Option<&str>, note: Option<&str>) -> SessionEntry {
+        SessionEntry {
+            start: at(2025, 6, d, hour, 5),
+            phase,
+            minutes,
+            task: task.map(str::to_string),
+            note: note.map(str::to_string),
+        }
+    }
+
+    fn session(d: u32, entries: Vec<SessionEntry>) -> PomodoroSession {
+        PomodoroSession { entries, ..empty_session(NaiveDate::from_ymd_opt(2025, 6, d).unwrap()) }
+    }
+
+    fn sessions() -> Vec<PomodoroSession> {
+        vec![
+            session(29, vec![entry(29, 20, PomodoroPhase::Work, 25, None, None)]),
+            session(30, vec![
+                entry(30, 9, PomodoroPhase::Work, 25, Some("Write report; part 1, draft"), Some("intro\nand outline")),
+                entry(30, 10, PomodoroPhase::ShortBreak, 5, None, None),
+                entry(30, 11, PomodoroPhase::Work, 0, Some("Skipped"), None),
+                entry(30, 12, PomodoroPhase::Work, 50, Some("Review"), None),
+            ]),
+        ]
+    }
+
+    #[test]
+    fn test_calendar() {
+        let now = at(2025, 6, 30, 18, 0);
+        let ics = calendar(&sessions(), Some(NaiveDate::from_ymd_opt(2025, 6, 30).unwrap()), now);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//sessio//pomodoro sessions//EN\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        // Work only, and not the one with no minutes; the 29th is before `since`
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(!ics.contains("Skipped"));
+        assert!(!ics.contains("UID:20250629"));
+        let first = format!(
+            "BEGIN:VEVENT\r\nUID:20250630-1@sessio\r\nDTSTAMP:{}\r\nDTSTART:{}\r\nDTEND:{}\r\n\
+             SUMMARY:Write report\\; part 1\\, draft\r\nDESCRIPTION:intro\\nand outline\r\nEND:VEVENT\r\n",
+            timestamp(now),
+            timestamp(at(2025, 6, 30, 9, 5)),
+            timestamp(at(2025, 6, 30, 9, 30)),
+        );
+        assert!(ics.contains(&first));
+        assert!(ics.contains("UID:20250630-2@sessio\r\n"));
+        assert!(ics.contains("SUMMARY:Review\r\n"));
+
+        // Everything, and the UIDs stay as they were when the day gets more sessions
+        let mut more = sessions();
+        more[1].entries.push(entry(30, 14, PomodoroPhase::Work, 25, None, None));
+        let ics = calendar(&more, None, now);
+        assert!(ics.contains("UID:20250629-1@sessio\r\nDTSTAMP"));
+        assert!(ics.contains(&first));
+        assert!(ics.contains("UID:20250630-3@sessio\r\n"));
+        assert!(ics.contains("SUMMARY:Pomodoro\r\n"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a\\b;c,d\r\ne"), "a\\\\b\\;c\\,d\\ne");
+        assert_eq!(escape("Plain text: ok"), "Plain text: ok");
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("SUMMARY:short"), "SUMMARY:short\r\n");
+        let line = format!("SUMMARY:{}", "x".repeat(150));
+        let folded = fold(&line);
+        let parts: Vec<&str> = folded.trim_end_matches("\r\n").split("\r\n").collect();
+        assert_eq!(parts.len(), 3);
+        assert!(parts.iter().all(|part| part.len() <= MAX_LINE_OCTETS));
+        assert!(parts[1..].iter().all(|part| part.starts_with(' ')));
+        // Unfolding (removing CRLF and the space) gives the line back
+        assert_eq!(folded.trim_end_matches("\r\n").replace("\r\n ", ""), line);
+
+        // Multi-byte characters stay whole, even when that leaves a line short
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        let folded = fold(&line);
+        for part in folded.trim_end_matches("\r\n").split("\r\n") {
+            assert!(part.len() <= MAX_LINE_OCTETS);
+        }
+        assert_eq!(folded.trim_end_matches("\r\n").replace("\r\n ", ""), line);
+        assert_eq!(fold(&"a".repeat(75)), format!("{}\r\n", "a".repeat(75)));
+        assert_eq!(fold(&"a".repeat(76)), format!("{}\r\n a\r\n", "a".repeat(75)));
+    }
+}
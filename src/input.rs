@@ -0,0 +1,260 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    style::{Modifier, Style},
+    text::Span,
+};
+
+/// A single-line text input with a cursor. The cursor counts characters, so editing never splits a UTF-8 sequence.
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    text: String,
+    cursor: usize, // In characters, 0..=char count
+}
+
+impl TextInput {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the text, with the cursor at the end
+    pub fn set(&mut self, text: String) {
+        self.cursor = text.chars().count();
+        self.text = text;
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    /// Byte offset of the given character position
+    fn byte_index(&self, position: usize) -> usize {
+        self.text.char_indices().nth(position).map_or(self.text.len(), |(i, _)| i)
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let index = self.byte_index(self.cursor);
+        self.text.insert(index, c);
+        self.cursor += 1;
+    }
+
+    /// Insert pasted text at the cursor. Trailing line breaks are dropped, inner ones and tabs
+    /// become spaces (the input is a single line) and other control characters are removed.
+    pub fn paste(&mut self, text: &str) {
+        let text: String = text
+            .trim_end_matches(['\r', '\n'])
+            .chars()
+            .filter_map(|c| match c {
+                '\n' | '\t' => Some(' '),
+                c if c.is_control() => None,
+                c => Some(c),
+            })
+            .collect();
+        let index = self.byte_index(self.cursor);
+        self.text.insert_str(index, &text);
+        self.cursor += text.chars().count();
+    }
+
+    /// Delete the character before the cursor
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let index = self.byte_index(self.cursor);
+            self.text.remove(index);
+        }
+    }
+
+    /// Delete the character under the cursor
+    pub fn delete(&mut self) {
+        if self.cursor < self.text.chars().count() {
+            let index = self.byte_index(self.cursor);
+            self.text.remove(index);
+        }
+    }
+
+    /// Delete the word before the cursor, along with the spaces between it and the cursor
+    pub fn delete_word(&mut self) {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut start = self.cursor;
+        while start > 0 && chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        let (from, to) = (self.byte_index(start), self.byte_index(self.cursor));
+        self.text.replace_range(from..to, "");
+        self.cursor = start;
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.text.chars().count());
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.text.chars().count();
+    }
+
+    /// Apply an editing key: characters, Backspace/Delete, Left/Right, Home/End and Ctrl+a/e/w.
+    /// Returns false for keys that are not editing keys (e.g. Enter and Esc).
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('a') => self.move_home(),
+                KeyCode::Char('e') => self.move_end(),
+                KeyCode::Char('w') => self.delete_word(),
+                _ => return false,
+            }
+            return true;
+        }
+        match key.code {
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.move_home(),
+            KeyCode::End => self.move_end(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// The text as spans with the cell under the cursor in reverse video (a blank cell at the end).
+    /// Styling the cell itself keeps the cursor right on wide (e.g. CJK) characters.
+    pub fn spans(&self) -> Vec<Span<'static>> {
+        let index = self.byte_index(self.cursor);
+        let (before, rest) = self.text.split_at(index);
+        let mut after = rest.chars();
+        let under_cursor = after.next().map_or(" ".to_string(), |c| c.to_string());
+        vec![
+            Span::raw(before.to_string()),
+            Span::styled(under_cursor, Style::default().add_modifier(Modifier::REVERSED)),
+            Span::raw(after.as_str().to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input_with(text: &str) -> TextInput {
+        let mut input = TextInput::default();
+        input.set(text.to_string());
+        input
+    }
+
+    #[test]
+    fn test_insert_and_delete_at_cursor() {
+        let mut input = input_with("helo");
+        input.move_left();
+        input.insert('l');
+        assert_eq!(input.as_str(), "hello");
+
+        input.move_home();
+        input.delete();
+        input.insert('H');
+        assert_eq!(input.as_str(), "Hello");
+
+        input.move_end();
+        input.backspace();
+        assert_eq!(input.as_str(), "Hell");
+
+        input.move_home();
+        input.backspace();
+        input.move_end();
+        input.delete();
+        assert_eq!(input.as_str(), "Hell");
+    }
+
+    #[test]
+    fn test_multibyte_characters() {
+        let mut input = input_with("写报告");
+        input.move_left();
+        input.insert('的');
+        assert_eq!(input.as_str(), "写报的告");
+        input.backspace();
+        input.backspace();
+        assert_eq!(input.as_str(), "写告");
+        input.move_right();
+        input.move_right();
+        input.insert('!');
+        assert_eq!(input.as_str(), "写告!");
+
+        let spans = input.spans();
+        assert_eq!(spans[0].content, "写告!");
+        assert_eq!(spans[1].content, " ");
+    }
+
+    #[test]
+    fn test_delete_word() {
+        let mut input = input_with("fix the  typo");
+        input.delete_word();
+        assert_eq!(input.as_str(), "fix the  ");
+        input.delete_word();
+        assert_eq!(input.as_str(), "fix ");
+
+        let mut input = input_with("one two three");
+        for _ in 0..6 {
+            input.move_left();
+        }
+        input.delete_word();
+        assert_eq!(input.as_str(), "one  three");
+        input.move_home();
+        input.delete_word();
+        assert_eq!(input.as_str(), "one  three");
+    }
+
+    #[test]
+    fn test_handle_key() {
+        let mut input = input_with("task");
+        for key in [
+            KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL),
+            KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::End, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Left, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Delete, KeyModifiers::NONE),
+        ] {
+            assert!(input.handle_key(key));
+        }
+        assert_eq!(input.as_str(), "m tas");
+        assert!(!input.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)));
+        assert!(!input.handle_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL)));
+        assert_eq!(input.as_str(), "m tas");
+    }
+
+    #[test]
+    fn test_paste() {
+        let mut input = input_with("Read ");
+        input.paste("lesson notes\r\n");
+        assert_eq!(input.as_str(), "Read lesson notes");
+
+        input.move_home();
+        input.paste("a\r\nb\tc\u{1b}[0m\n\n");
+        assert_eq!(input.as_str(), "a b c[0mRead lesson notes");
+
+        input.move_end();
+        input.paste(" 写报告");
+        input.insert('!');
+        assert_eq!(input.as_str(), "a b c[0mRead lesson notes 写报告!");
+    }
+
+    #[test]
+    fn test_cursor_cell() {
+        let mut input = input_with("abc");
+        input.move_left();
+        let spans = input.spans();
+        let contents: Vec<&str> = spans.iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(contents, ["ab", "c", ""]);
+    }
+}
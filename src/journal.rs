@@ -0,0 +1,252 @@
+use chrono::NaiveDate;
+use color_eyre::Result;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::timer::PomodoroSession;
+use crate::todo::TodoItem;
+
+/// The journal section for one day, e.g.
+///
+/// ```text
+/// ## 2025-06-30
+///
+/// - Focused: 75 minutes in 3 work sessions
+/// - Worked on:
+///   - Write report: 50 min
+///   - Outline: 25 min
+/// - Completed:
+///   - Outline
+/// ```
+///
+/// Minutes per task come from the task timelines. None when nothing was worked on or completed that day.
+pub fn day_section(date: NaiveDate, items: &[TodoItem], sessions: &[PomodoroSession]) -> Option<String> {
+    let (work_minutes, work_sessions) = sessions.iter()
+        .filter(|session| session.date == date)
+        .fold((0, 0), |(minutes, count), session| (minutes + session.total_work_minutes, count + session.work_sessions));
+    let worked: Vec<(&str, u32)> = items.iter()
+        .filter_map(|item| {
+            let minutes: u32 = item.timeline.iter()
+                .filter(|session| session.date == date)
+                .map(|session| session.minutes)
+                .sum();
+            (minutes > 0).then_some((item.task.as_str(), minutes))
+        })
+        .collect();
+    // A recurring task may have been reset since, so its completion history counts too
+    let completed: Vec<&str> = items.iter()
+        .filter(|item| {
+            item.completed_at.is_some_and(|completed_at| completed_at.date_naive() == date)
+                || item.completions.iter().any(|completed| completed.date_naive() == date)
+        })
+        .map(|item| item.task.as_str())
+        .collect();
+    if work_sessions == 0 && worked.is_empty() && completed.is_empty() {
+        return None;
+    }
+
+    let mut section = format!("{}\n\n", heading(date));
+    section.push_str(&format!("- Focused: {} minutes in {} work sessions\n", work_minutes, work_sessions));
+    if !worked.is_empty() {
+        section.push_str("- Worked on:\n");
+        for (task, minutes) in worked {
+            section.push_str(&format!("  - {}: {} min\n", task, minutes));
+        }
+    }
+    if !completed.is_empty() {
+        section.push_str("- Completed:\n");
+        for task in completed {
+            section.push_str(&format!("  - {}\n", task));
+        }
+    }
+    Some(section)
+}
+
+fn heading(date: NaiveDate) -> String {
+    format!("## {}", date.format("%Y-%m-%d"))
+}
+
+/// Whether the journal already has a section for the day
+pub fn has_day(journal: &str, date: NaiveDate) -> bool {
+    let heading = heading(date);
+    journal.lines().any(|line| line.trim_end() == heading)
+}
+
+/// Append the day's section to the journal at `path` (~ is expanded). A day already in the
+/// journal is left alone, so restarting sessio never writes it twice. Returns whether it was added.
+pub fn append_day(path: &str, date: NaiveDate, items: &[TodoItem], sessions: &[PomodoroSession]) -> Result<bool> {
+    let path = expand_path(path);
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    if has_day(&existing, date) {
+        return Ok(false);
+    }
+    let Some(section) = day_section(date, items, sessions) else {
+        return Ok(false);
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    // Sections are separated by a blank line
+    let separator = if existing.is_empty() || existing.ends_with("\n\n") {
+        ""
+    } else if existing.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    write!(file, "{}{}", separator, section)?;
+    Ok(true)
+}
+
+/// Append the days before `today` the journal at `path` is still missing: those after its last
+/// section, or only the last day with anything to write when it has none yet. Today is left for
+/// when it's over, so a day is never written half done. Returns how many days were added.
+pub fn catch_up(path: &str, today: NaiveDate, items: &[TodoItem], sessions: &[PomodoroSession]) -> Result<usize> {
+    let existing = fs::read_to_string(expand_path(path)).unwrap_or_default();
+    let last_written = existing.lines()
+        .filter_map(|line| line.trim_end().strip_prefix("## "))
+        .filter_map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+        .max();
+    let days: Vec<NaiveDate> = match last_written {
+        Some(last) => last.iter_days().skip(1).take_while(|&date| date < today).collect(),
+        None => last_active_day(today, items, sessions).into_iter().collect(),
+    };
+    let mut added = 0;
+    for date in days {
+        if append_day(path, date, items, sessions)? {
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
+/// The latest day before `today` with work or a completed task
+fn last_active_day(today: NaiveDate, items: &[TodoItem], sessions: &[PomodoroSession]) -> Option<NaiveDate> {
+    let session_days = sessions.iter().filter(|session| session.work_sessions > 0).map(|session| session.date);
+    let item_days = items.iter().flat_map(|item| {
+        item.timeline.iter().map(|session| session.date)
+            .chain(item.completed_at.iter().chain(&item.completions).map(|completed| completed.date_naive()))
+    });
+    session_days.chain(item_days).filter(|&date| date < today).max()
+}
+
+pub fn expand_path(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => Path::new(path).to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{at, empty_session};
+    use crate::todo::WorkSession;
+
+    fn work(date: NaiveDate, minutes: u32, hour: u32) -> WorkSession {
+        WorkSession { date, minutes, timestamp: at(2025, 6, 30, hour, 0) }
+    }
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, 30).unwrap()
+    }
+
+    fn items() -> Vec<TodoItem> {
+        let yesterday = day().pred_opt().unwrap();
+        let mut report = TodoItem::new("Write report #work".to_string());
+        report.timeline = vec![work(yesterday, 25, 9), work(day(), 25, 10), work(day(), 25, 14)];
+        let mut outline = TodoItem::new("Outline".to_string());
+        outline.done = true;
+        outline.completed_at = Some(at(2025, 6, 30, 11, 5));
+        outline.timeline = vec![work(day(), 25, 11)];
+        let mut plants = TodoItem::new("Water plants".to_string());
+        plants.completions = vec![at(2025, 6, 30, 8, 0)];
+        let mut old = TodoItem::new("Done last week".to_string());
+        old.done = true;
+        old.completed_at = Some(at(2025, 6, 23, 16, 0));
+        vec![report, outline, plants, old]
+    }
+
+    fn sessions() -> Vec<PomodoroSession> {
+        vec![PomodoroSession {
+            work_sessions: 3,
+            total_work_minutes: 75,
+            break_sessions: 2,
+            total_break_minutes: 10,
+            ..empty_session(day())
+        }]
+    }
+
+    #[test]
+    fn test_day_section() {
+        assert_eq!(day_section(day(), &items(), &sessions()).unwrap(), "\
+## 2025-06-30
+
+- Focused: 75 minutes in 3 work sessions
+- Worked on:
+  - Write report #work: 50 min
+  - Outline: 25 min
+- Completed:
+  - Outline
+  - Water plants
+");
+        let quiet_day = NaiveDate::from_ymd_opt(2025, 7, 1).unwrap();
+        assert_eq!(day_section(quiet_day, &items(), &sessions()), None);
+    }
+
+    #[test]
+    fn test_catch_up_leaves_today_open() {
+        let path = std::env::temp_dir().join(format!("sessio-journal-catch-up-{}.md", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let path_str = path.to_string_lossy().into_owned();
+        let next_day = day().succ_opt().unwrap();
+
+        // Quitting on the 30th writes the 29th, but nothing for the 30th yet
+        assert_eq!(catch_up(&path_str, day(), &items(), &sessions()).unwrap(), 1);
+        let journal = fs::read_to_string(&path).unwrap();
+        assert!(journal.contains("## 2025-06-29\n"));
+        assert!(!journal.contains("## 2025-06-30"));
+
+        // More work later that day, then the next start writes the whole day
+        let mut items = items();
+        items[0].timeline.push(work(day(), 25, 16));
+        assert_eq!(catch_up(&path_str, next_day, &items, &sessions()).unwrap(), 1);
+        let journal = fs::read_to_string(&path).unwrap();
+        assert!(journal.contains("  - Write report #work: 75 min\n"));
+
+        // Written once; a later catch-up picks up the days after it
+        assert_eq!(catch_up(&path_str, next_day, &items, &sessions()).unwrap(), 0);
+        let mut sessions = sessions();
+        sessions.push(PomodoroSession { date: next_day, ..sessions[0].clone() });
+        let later = NaiveDate::from_ymd_opt(2025, 7, 5).unwrap();
+        assert_eq!(catch_up(&path_str, later, &items, &sessions).unwrap(), 1);
+        let journal = fs::read_to_string(&path).unwrap();
+        assert_eq!(journal.matches("## 2025-06-30").count(), 1);
+        assert!(journal.contains("## 2025-07-01\n"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_append_day_once() {
+        let path = std::env::temp_dir().join(format!("sessio-journal-{}.md", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let path_str = path.to_string_lossy().into_owned();
+        fs::write(&path, "# Journal\n").unwrap();
+
+        assert!(append_day(&path_str, day(), &items(), &sessions()).unwrap());
+        assert!(!append_day(&path_str, day(), &items(), &sessions()).unwrap());
+        let journal = fs::read_to_string(&path).unwrap();
+        assert!(journal.starts_with("# Journal\n\n## 2025-06-30\n\n- Focused: 75 minutes"));
+        assert_eq!(journal.matches("## 2025-06-30").count(), 1);
+
+        // The heading counts even when the section was edited by hand
+        fs::write(&path, "## 2025-06-30  \nmy own notes\n").unwrap();
+        assert!(has_day(&fs::read_to_string(&path).unwrap(), day()));
+        assert!(!append_day(&path_str, day(), &items(), &sessions()).unwrap());
+        assert!(!has_day("### 2025-06-30\n## 2025-06-301\n", day()));
+        fs::remove_file(&path).unwrap();
+    }
+}
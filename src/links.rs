@@ -0,0 +1,94 @@
+use std::io;
+use std::process::{Command, Stdio};
+
+/// The http(s) URLs in a text, in order and without duplicates. A URL ends at whitespace or
+/// the first non-ASCII character (so "见https://example.com的" works), and loses trailing
+/// punctuation and closing brackets it didn't open (e.g. from "(see https://example.com).").
+pub fn find_urls(text: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("http") {
+        let candidate = &rest[start..];
+        let scheme_len = ["https://", "http://"].iter()
+            .find(|scheme| candidate.starts_with(*scheme))
+            .map_or(0, |scheme| scheme.len());
+        if scheme_len == 0 {
+            rest = &candidate[4..];
+            continue;
+        }
+
+        let end = candidate.find(|c: char| !is_url_char(c)).unwrap_or(candidate.len());
+        let url = trim_url_end(&candidate[..end]);
+        if url.len() > scheme_len && !urls.iter().any(|known| known == url) {
+            urls.push(url.to_string());
+        }
+        rest = &candidate[end..];
+    }
+    urls
+}
+
+fn is_url_char(c: char) -> bool {
+    c.is_ascii_graphic() && !matches!(c, '<' | '>' | '"' | '`')
+}
+
+/// Drop sentence punctuation and unbalanced closing brackets from the end of a URL
+fn trim_url_end(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let unbalanced = |open: char, close: char| url.matches(close).count() > url.matches(open).count();
+        let trimmed = match url.chars().last() {
+            Some('.' | ',' | ';' | ':' | '!' | '?' | '\'') => &url[..url.len() - 1],
+            Some(')') if unbalanced('(', ')') => &url[..url.len() - 1],
+            Some(']') if unbalanced('[', ']') => &url[..url.len() - 1],
+            _ => return url,
+        };
+        url = trimmed;
+    }
+}
+
+/// Open a URL with the platform's handler (open on macOS, start on Windows, xdg-open elsewhere).
+/// Returns once the opener is started; its output is discarded and it is reaped in the background.
+pub fn open_url(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let mut child = command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_urls() {
+        assert_eq!(find_urls("Review https://github.com/zyl-hub/sessio/pull/12 today"), ["https://github.com/zyl-hub/sessio/pull/12"]);
+        assert_eq!(find_urls("No links, just http and https:// mentions"), Vec::<String>::new());
+        assert_eq!(
+            find_urls("Ticket http://jira.local/T-1, see https://example.com/a?b=1&c=2#frag. Again: http://jira.local/T-1"),
+            ["http://jira.local/T-1", "https://example.com/a?b=1&c=2#frag"],
+        );
+    }
+
+    #[test]
+    fn test_find_urls_next_to_cjk_and_punctuation() {
+        assert_eq!(find_urls("看一下https://example.com/issue/7的评论"), ["https://example.com/issue/7"]);
+        assert_eq!(find_urls("修复（https://example.com/x）。"), ["https://example.com/x"]);
+        assert_eq!(find_urls("(see https://example.com/path)."), ["https://example.com/path"]);
+        assert_eq!(find_urls("https://en.wikipedia.org/wiki/Rust_(programming_language)!"), ["https://en.wikipedia.org/wiki/Rust_(programming_language)"]);
+        assert_eq!(find_urls("[PR](https://github.com/o/r/pull/3) and <https://example.org/>"), ["https://github.com/o/r/pull/3", "https://example.org/"]);
+        assert_eq!(find_urls("\"https://example.com/q\"; 'https://example.com/r'"), ["https://example.com/q", "https://example.com/r"]);
+    }
+}
@@ -0,0 +1,141 @@
+use color_eyre::{eyre::eyre, Result};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Keeps a second sessio from starting while this one runs, so the two don't overwrite each
+/// other's todos. The lock file holds our PID and is removed when the lock is dropped.
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    /// ~/.config/sessio/sessio.lock
+    pub fn default_path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| eyre!("Could not find config directory"))?;
+        Ok(config_dir.join("sessio").join("sessio.lock"))
+    }
+
+    /// Take the lock at `path`. A lock left behind by a process that is no longer running is taken
+    /// over; one held by a running process is an error naming its PID.
+    pub fn acquire(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Twice at most: the second attempt follows removing a stale lock
+        for _ in 0..2 {
+            match fs::OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id())?;
+                    return Ok(Self { path: path.to_path_buf() });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let holder = fs::read_to_string(path).ok().and_then(|pid| pid.trim().parse::<u32>().ok());
+                    if let Some(pid) = holder.filter(|&pid| process_alive(pid)) {
+                        return Err(eyre!(
+                            "sessio is already running (pid {}). Quit it first, or delete {} if it isn't.",
+                            pid,
+                            path.display()
+                        ));
+                    }
+                    fs::remove_file(path)?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(eyre!("Could not take the lock at {}", path.display()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Remove the lock file; also used from the panic hook, where the lock itself can't be reached
+    pub fn release_path(path: &Path) {
+        let ours = fs::read_to_string(path).is_ok_and(|pid| pid.trim() == std::process::id().to_string());
+        if ours {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        Self::release_path(&self.path);
+    }
+}
+
+/// Whether a process with this PID exists
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return true;
+    }
+    if Path::new("/proc/self").exists() {
+        return Path::new(&format!("/proc/{}", pid)).exists();
+    }
+    // No /proc (e.g. macOS): signal 0 only checks that the process is there
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Without a cheap way to check, a lock is only stale once its file is deleted
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("sessio-{}-{}.lock", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_second_instance_is_refused() {
+        let path = lock_path("instance");
+        let lock = InstanceLock::acquire(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), std::process::id().to_string());
+
+        let error = InstanceLock::acquire(&path).unwrap_err().to_string();
+        assert!(error.contains("already running"), "{}", error);
+        assert!(path.exists());
+
+        drop(lock);
+        assert!(!path.exists());
+        drop(InstanceLock::acquire(&path).unwrap());
+    }
+
+    #[test]
+    fn test_stale_lock_is_reclaimed() {
+        let path = lock_path("stale");
+        // Far above any real PID (Linux allows at most 2^22)
+        fs::write(&path, "999999999").unwrap();
+        let lock = InstanceLock::acquire(&path).unwrap();
+        assert_eq!(fs::read_to_string(lock.path()).unwrap(), std::process::id().to_string());
+        drop(lock);
+
+        fs::write(&path, "not a pid").unwrap();
+        drop(InstanceLock::acquire(&path).unwrap());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_release_leaves_other_locks_alone() {
+        let path = lock_path("other");
+        fs::write(&path, "1").unwrap();
+        InstanceLock::release_path(&path);
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
+    }
+}
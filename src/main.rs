@@ -1,13 +1,17 @@
-use color_eyre::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use color_eyre::{Result, Section};
+use crossterm::event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::SetTitle;
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Flex, Layout},
     style::Style,
-    widgets::Block,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
     DefaultTerminal, Frame,
 };
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::time::Instant;
 
+mod alarm;
 mod app;
 mod config;
 mod theme;
@@ -16,15 +20,38 @@ mod summary;
 mod todo;
 mod track_list;
 mod help;
+mod recurrence;
+mod export;
+mod input;
+mod org;
+mod taskwarrior;
+mod links;
+mod lock;
+mod journal;
+mod metadata;
+mod noise;
+mod stream;
+mod remote;
+mod play_counts;
+mod report;
+mod ical;
+#[cfg(all(feature = "mpris", target_os = "linux"))]
+mod mpris;
+#[cfg(test)]
+mod test_util;
 
 use app::{App, Quadrant};
-use config::Config;
+use config::{AlarmBehavior, Config, TodoList};
 use theme::DraculaTheme;
-use timer::Timer;
-use summary::Summary;
+use timer::{PhaseTransition, PomodoroPhase, Timer};
+use summary::{Summary, SummaryView};
 use todo::Todo;
-use track_list::TrackList;
+use track_list::{Playlist, ScanFilter, TrackList};
 use help::Help;
+use export::Export;
+use input::TextInput;
+use lock::InstanceLock;
+use remote::RemoteCommand;
 
 /// Helper function to check if a character is Chinese (CJK)
 fn is_chinese_character(c: char) -> bool {
@@ -55,6 +82,19 @@ struct AppState {
     last_key_time: Instant,
     last_key_code: Option<KeyCode>,
     was_alarm_active_last_update: bool,
+    last_terminal_title: String,
+    last_title_update: Instant,
+    note_input: Option<TextInput>, // Note prompt shown after a finished work phase
+    music_paused_for_break: bool, // We paused the music when the current break started
+    music_paused_for_alarm: bool, // We paused the music when the alarm started (alarm_behavior = "pause")
+    today: chrono::NaiveDate, // Date the app last saw, to notice the midnight rollover
+    todo_lists: Vec<TodoList>, // Lists the todo panel can switch between
+    active_list: usize, // Index of the list shown in the todo panel
+    #[cfg_attr(not(all(feature = "mpris", target_os = "linux")), allow(dead_code))]
+    remote_sender: Sender<RemoteCommand>, // Handed to whatever controls the music from outside
+    remote_commands: Receiver<RemoteCommand>, // Carried out by the main loop, as keys are
+    #[cfg(all(feature = "mpris", target_os = "linux"))]
+    mpris: Option<mpris::Mpris>, // Media keys, while enabled in the config
 }
 
 impl AppState {
@@ -63,18 +103,27 @@ impl AppState {
         
         // Extract values to avoid partial moves
         let music_dir = config.music.music_directory.clone();
-        let work_minutes = config.timer.work_minutes;
-        let short_break_minutes = config.timer.short_break_minutes;
-        let long_break_minutes = config.timer.long_break_minutes;
-        let sessions_until_long_break = config.timer.sessions_until_long_break;
-        let daily_goal_minutes = config.summary.daily_goal_minutes;
-        let save_path = config.todo.save_path.clone();
+        let todo_lists = config.todo.lists();
         
-        let alarm_volume = config.music.alarm_volume;
-        let alarm_duration_seconds = config.music.alarm_duration_seconds;
-        let alarm_file_path = config.music.alarm_file_path.clone();
-        let mut timer = Timer::new(work_minutes, short_break_minutes, long_break_minutes, sessions_until_long_break, alarm_volume, alarm_duration_seconds, alarm_file_path);
-        let todo = Todo::new(save_path);
+        let mut timer = Timer::new(&config);
+        let mut todo = Todo::new(Some(todo_lists[0].path.clone()), config.todo.format);
+        if todo_lists.len() > 1 {
+            todo.list_name = Some(todo_lists[0].name.clone());
+        }
+        todo.default_session_minutes = config.timer.work_minutes as u32;
+        todo.sort_by_priority = config.todo.sort_by_priority;
+        todo.auto_complete_parents = config.todo.auto_complete_parents;
+        todo.inline_syntax = config.todo.inline_syntax;
+        todo.wrap_titles = config.todo.wrap_titles;
+        todo.backup_count = config.todo.backup_count;
+        todo.time_display = config.todo.time_display;
+        todo.show_age = config.todo.show_age;
+        todo.stale_after_days = config.todo.stale_after_days;
+        todo.auto_save = config.todo.auto_save;
+        todo.save_delay = std::time::Duration::from_secs(1);
+        todo.sort_items();
+        let today = chrono::Local::now().date_naive();
+        todo.reset_recurring_tasks(today);
         
         // Load pomodoro session data from the todo file if enabled
         if config.todo.save_pomodoro_data {
@@ -82,17 +131,242 @@ impl AppState {
             timer.load_daily_sessions(sessions);
         }
         
-        Ok(Self {
+        // Start in the work playlist, which is where the remembered state comes from
+        let mut track_list = TrackList::new(config.music.work_directory.as_deref().or(music_dir.as_deref()), &config.music);
+        track_list.set_playlists(config.music.work_directory.as_deref(), config.music.break_directory.as_deref(), music_dir.as_deref());
+        track_list.show_tags = config.music.show_tags;
+        track_list.set_sort(config.music.track_sort);
+        track_list.resume_playback = config.music.resume_playback;
+        let (remote_sender, remote_commands) = mpsc::channel();
+        
+        let mut app_state = Self {
             app: App::new(),
             timer,
-            summary: Summary::new(daily_goal_minutes),
+            summary: Summary::new(&config.summary),
             todo,
-            track_list: TrackList::new(music_dir.as_deref()),
+            track_list,
             config,
             last_key_time: Instant::now(),
             last_key_code: None,
             was_alarm_active_last_update: false,
-        })
+            last_terminal_title: String::new(),
+            last_title_update: Instant::now(),
+            note_input: None,
+            music_paused_for_break: false,
+            music_paused_for_alarm: false,
+            today,
+            todo_lists,
+            active_list: 0,
+            remote_sender,
+            remote_commands,
+            #[cfg(all(feature = "mpris", target_os = "linux"))]
+            mpris: None,
+        };
+        app_state.update_mpris();
+        Ok(app_state)
+    }
+    
+    /// Carry out requests for the music player from outside (media keys), and tell them what's playing
+    fn handle_remote_commands(&mut self) {
+        for command in self.remote_commands.try_iter() {
+            self.track_list.handle_remote(command);
+        }
+        #[cfg(all(feature = "mpris", target_os = "linux"))]
+        if let Some(mpris) = &mut self.mpris {
+            mpris.update(self.track_list.now_playing());
+        }
+    }
+    
+    /// Join or leave the session bus as an MPRIS player, as the config says
+    #[cfg(all(feature = "mpris", target_os = "linux"))]
+    fn update_mpris(&mut self) {
+        if !self.config.music.mpris {
+            self.mpris = None;
+        } else if self.mpris.is_none() {
+            self.mpris = Some(mpris::Mpris::start(self.remote_sender.clone()));
+        }
+    }
+    
+    #[cfg(not(all(feature = "mpris", target_os = "linux")))]
+    fn update_mpris(&mut self) {}
+    
+    /// Whether media keys may ask for something, which the main loop should notice quickly
+    #[cfg(all(feature = "mpris", target_os = "linux"))]
+    fn listens_remotely(&self) -> bool {
+        self.mpris.is_some()
+    }
+    
+    #[cfg(not(all(feature = "mpris", target_os = "linux")))]
+    fn listens_remotely(&self) -> bool {
+        false
+    }
+    
+    /// Wait up to `timeout` for a terminal event. While media keys may ask for something, the
+    /// channel is looked at every 100 ms as well, and the commands found there are carried out
+    /// and end the wait early; the loop itself (and the redraw) still only runs as often as it
+    /// would without them.
+    fn wait_for_event(&mut self, timeout: std::time::Duration) -> Result<bool> {
+        if !self.listens_remotely() {
+            return Ok(event::poll(timeout)?);
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            let slice = deadline.saturating_duration_since(Instant::now()).min(std::time::Duration::from_millis(100));
+            if event::poll(slice)? {
+                return Ok(true);
+            }
+            let commands: Vec<RemoteCommand> = self.remote_commands.try_iter().collect();
+            if !commands.is_empty() {
+                for command in commands {
+                    self.track_list.handle_remote(command);
+                }
+                return Ok(false);
+            }
+            if Instant::now() >= deadline {
+                return Ok(false);
+            }
+        }
+    }
+    
+    /// Mirror the timer into the terminal title (if enabled), at most once per second
+    fn update_terminal_title(&mut self) {
+        if !self.config.timer.terminal_title
+            || self.last_title_update.elapsed() < std::time::Duration::from_secs(1) {
+            return;
+        }
+        let title = self.timer.terminal_title();
+        if title != self.last_terminal_title {
+            let _ = crossterm::execute!(std::io::stdout(), SetTitle(&title));
+            self.last_terminal_title = title;
+            self.last_title_update = Instant::now();
+        }
+    }
+    
+    /// React to the timer moving between work and breaks
+    fn handle_phase_transition(&mut self, transition: PhaseTransition) {
+        let playlist = if transition.to == PomodoroPhase::Work { Playlist::Work } else { Playlist::Break };
+        let switched = self.track_list.switch_playlist(playlist);
+        if !self.config.music.pause_during_breaks {
+            return;
+        }
+        match (&transition.from, &transition.to) {
+            (PomodoroPhase::Work, PomodoroPhase::ShortBreak | PomodoroPhase::LongBreak) => {
+                // Only remember the pause if music was actually playing (or was until an alarm paused it)
+                self.music_paused_for_break = self.track_list.pause() || std::mem::take(&mut self.music_paused_for_alarm);
+            }
+            (_, PomodoroPhase::Work) if self.music_paused_for_break => {
+                // Resume only what we paused; music started by hand during the break keeps playing
+                self.music_paused_for_break = false;
+                if self.was_alarm_active_last_update && self.config.music.alarm_behavior == AlarmBehavior::Pause {
+                    // An alarm is still sounding, which now keeps it paused until it's over
+                    if switched {
+                        self.track_list.ensure_playing();
+                        self.track_list.pause();
+                    }
+                    self.music_paused_for_alarm = true;
+                } else if switched {
+                    // The paused break track is gone, start the work playlist instead
+                    self.track_list.ensure_playing();
+                } else {
+                    self.track_list.resume();
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    /// Get the music out of the alarm's way, as alarm_behavior says
+    fn handle_alarm_start(&mut self) {
+        match self.config.music.alarm_behavior {
+            AlarmBehavior::Duck => {
+                self.track_list.lower_volume_for_alarm(self.timer.get_alarm_volume(), self.config.music.duck_percent);
+            }
+            AlarmBehavior::Pause => {
+                // Music that was already paused stays paused afterwards; a pause still remembered
+                // from an alarm just before is kept
+                let paused = self.track_list.pause();
+                self.music_paused_for_alarm |= paused;
+            }
+            AlarmBehavior::Nothing => {}
+        }
+    }
+
+    /// The alarm is over or was dismissed: bring the music back the way it was. Both are undone,
+    /// in case alarm_behavior was changed while the alarm sounded.
+    fn handle_alarm_end(&mut self) {
+        self.track_list.restore_volume();
+        if std::mem::take(&mut self.music_paused_for_alarm) {
+            self.track_list.resume();
+        }
+    }
+
+    /// Keep the timer's attached task valid after the todo list changed.
+    /// A task that was completed is replaced by the next unfinished one (if enabled), a deleted one is dropped.
+    fn sync_timer_attachment(&mut self) {
+        self.timer.sync_attachment(&self.todo, self.config.timer.auto_attach_next_task);
+    }
+    
+    /// Insert pasted text into whichever input is open; outside of an input a paste is ignored
+    fn paste(&mut self, text: &str) {
+        if let Some(note) = self.note_input.as_mut() {
+            note.paste(text);
+        } else if let Some(notes) = self.todo.notes_editor.as_mut() {
+            // The notes popup is multi-line, so line breaks are kept
+            let text = text.replace("\r\n", "\n");
+            notes.extend(text.trim_end_matches('\n').chars().filter(|c| *c == '\n' || !c.is_control()));
+        } else if self.todo.is_input_mode {
+            self.todo.current_input.paste(text);
+        }
+    }
+    
+    /// Show the next configured todo list. The attached task belongs to the list being left, so it is dropped.
+    fn switch_todo_list(&mut self) {
+        if self.todo_lists.len() < 2 {
+            return;
+        }
+        // Write today's sessions to the list being left, like every other save does
+        if self.config.todo.save_pomodoro_data {
+            self.todo.pomodoro_sessions = self.timer.get_daily_sessions().to_vec();
+        }
+        
+        self.active_list = (self.active_list + 1) % self.todo_lists.len();
+        let list = &self.todo_lists[self.active_list];
+        self.todo.switch_list(Some(list.name.clone()), list.path.clone());
+        self.todo.reset_recurring_tasks(self.today);
+        self.timer.set_selected_todo(None);
+    }
+    
+    /// Append a day to the journal, if one is configured
+    fn write_journal(&self, date: chrono::NaiveDate) -> Result<()> {
+        if let Some(path) = &self.config.todo.journal_path {
+            journal::append_day(path, date, &self.todo.items, self.timer.get_daily_sessions())?;
+        }
+        Ok(())
+    }
+    
+    /// Append the finished days the journal doesn't have yet, such as the day sessio was last quit on
+    fn catch_up_journal(&self) -> Result<()> {
+        if let Some(path) = &self.config.todo.journal_path {
+            journal::catch_up(path, self.today, &self.todo.items, self.timer.get_daily_sessions())?;
+        }
+        Ok(())
+    }
+    
+    /// Write the report for the summary's period and say where it went
+    fn write_report(&mut self) {
+        let today = chrono::Local::now().date_naive();
+        let period = self.summary.report_period(today);
+        let content = report::report(
+            period,
+            today,
+            &self.todo.items,
+            &self.todo.pomodoro_sessions,
+            self.todo.get_streak_days(self.config.summary.streak_ignores_weekends),
+        );
+        self.summary.status_message = Some(match report::write(period, self.config.summary.reports_directory.as_deref(), &content) {
+            Ok(path) => format!("Report written to {}", path.display()),
+            Err(e) => format!("Report failed: {}", e),
+        });
     }
     
     /// Reload configuration from file and apply changes
@@ -100,50 +374,238 @@ impl AppState {
         self.config.reload()?;
         
         // Apply configuration changes to components
-        self.track_list.update_music_directory(self.config.music.music_directory.as_deref());
+        let music = &self.config.music;
+        self.track_list.scan_filter = ScanFilter::new(music);
+        self.track_list.set_playlists(music.work_directory.as_deref(), music.break_directory.as_deref(), music.music_directory.as_deref());
+        self.track_list.set_endless_tracks(&self.config.music.streams, self.config.music.builtin_noise);
+        self.track_list.refresh_library();
+        self.track_list.show_tags = self.config.music.show_tags;
+        self.track_list.set_sort(self.config.music.track_sort);
+        self.track_list.set_default_volume(self.config.music.default_volume);
+        self.track_list.auto_play_next = self.config.music.auto_play_next;
+        self.track_list.replaygain = self.config.music.replaygain;
+        self.update_mpris();
+        self.summary.daily_goal_minutes = self.config.summary.daily_goal_minutes;
+        self.summary.daily_goal_pomodoros = self.config.summary.daily_goal_pomodoros;
+        self.summary.top_tasks = self.config.summary.top_tasks;
+        self.summary.streak_ignores_weekends = self.config.summary.streak_ignores_weekends;
+        self.todo.default_session_minutes = self.config.timer.work_minutes as u32;
+        self.todo.sort_by_priority = self.config.todo.sort_by_priority;
+        self.todo.auto_complete_parents = self.config.todo.auto_complete_parents;
+        self.todo.inline_syntax = self.config.todo.inline_syntax;
+        self.todo.wrap_titles = self.config.todo.wrap_titles;
+        self.todo.backup_count = self.config.todo.backup_count;
+        self.todo.time_display = self.config.todo.time_display;
+        self.todo.show_age = self.config.todo.show_age;
+        self.todo.stale_after_days = self.config.todo.stale_after_days;
+        self.todo.auto_save = self.config.todo.auto_save;
+        self.todo.format = self.config.todo.format;
+        self.todo.sort_items();
+        
+        // The list on screen stays; switching moves on from wherever it sits in the new lists
+        self.todo_lists = self.config.todo.lists();
+        self.active_list = self.todo_lists.iter()
+            .position(|list| list.path == self.todo.file_path)
+            .unwrap_or(0);
+        self.todo.list_name = (self.todo_lists.len() > 1).then(|| self.todo_lists[self.active_list].name.clone());
         
         Ok(())
     }
 }
 
+/// Export the saved todos and pomodoro sessions to JSON and print where they went
+fn export_cli(path: Option<&std::path::Path>) -> Result<()> {
+    let config = Config::load()?;
+    let todo = Todo::new(Some(config.todo.lists()[0].path.clone()), config.todo.format);
+    let path = Export::new(&todo.items, todo.get_pomodoro_sessions()).write(path)?;
+    println!("Exported to {}", path.display());
+    Ok(())
+}
+
+/// Export the saved todos for `task import`, to a file or (with "-") to stdout.
+/// Tasks get a uuid the first time, saved with the todos so later exports update them; that
+/// save takes the instance lock, so it's refused while sessio runs instead of racing its saves.
+fn taskwarrior_cli(path: Option<&str>) -> Result<()> {
+    let _lock = InstanceLock::acquire(&InstanceLock::default_path()?)?;
+    let config = Config::load()?;
+    let mut todo = Todo::new(Some(config.todo.lists()[0].path.clone()), config.todo.format);
+    todo.backup_count = config.todo.backup_count;
+    if todo.assign_uuids() {
+        todo.save_to_file();
+    }
+    let now = chrono::Local::now();
+    let tasks = taskwarrior::tasks(&todo.items, now);
+    if path == Some("-") {
+        println!("{}", serde_json::to_string_pretty(&tasks)?);
+    } else {
+        let path = taskwarrior::write(&tasks, path.map(std::path::Path::new), now.date_naive())?;
+        println!("Exported {} tasks to {}", tasks.len(), path.display());
+    }
+    Ok(())
+}
+
+/// Export the saved work sessions as an iCalendar file: `--since YYYY-MM-DD` leaves out earlier
+/// days and `--out path` says where it goes
+fn ical_cli(args: &[String]) -> Result<()> {
+    let mut since = None;
+    let mut out = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(value) = args.next() else {
+            return Err(color_eyre::eyre::eyre!("{} needs a value", arg));
+        };
+        match arg.as_str() {
+            "--since" => {
+                since = Some(chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .map_err(|_| color_eyre::eyre::eyre!("--since takes a date like 2025-06-01, not {}", value))?);
+            }
+            "--out" => out = Some(std::path::PathBuf::from(value)),
+            _ => return Err(color_eyre::eyre::eyre!("Unknown option {} (use --since YYYY-MM-DD and --out path)", arg)),
+        }
+    }
+    let config = Config::load()?;
+    let todo = Todo::new(Some(config.todo.lists()[0].path.clone()), config.todo.format);
+    let now = chrono::Local::now();
+    let calendar = ical::calendar(todo.get_pomodoro_sessions(), since, now);
+    let path = ical::write(&calendar, out.as_deref(), now.date_naive())?;
+    println!("Exported {} sessions to {}", calendar.matches("BEGIN:VEVENT").count(), path.display());
+    Ok(())
+}
+
+/// Clear the terminal title we may have set
+fn reset_terminal_title() {
+    let _ = crossterm::execute!(std::io::stdout(), SetTitle(""));
+}
+
 fn main() -> Result<()> {
     color_eyre::install()?;
+    
+    // `sessio export [path]` writes the JSON export and exits without starting the UI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("export") {
+        return export_cli(args.get(1).map(std::path::Path::new));
+    }
+    // `sessio taskwarrior [path|-]` does the same in Taskwarrior's import format
+    if args.first().map(String::as_str) == Some("taskwarrior") {
+        return taskwarrior_cli(args.get(1).map(String::as_str));
+    }
+    // `sessio export-ical [--since YYYY-MM-DD] [--out path]` writes the work sessions as calendar events
+    if args.first().map(String::as_str) == Some("export-ical") {
+        return ical_cli(&args[1..]);
+    }
+    
+    // Only one sessio at a time, so two don't overwrite each other's todos
+    let lock = InstanceLock::acquire(&InstanceLock::default_path()?)?;
+    let lock_path = lock.path().to_path_buf();
+    
     let terminal = ratatui::init();
+    // Pasted text arrives as one event instead of a burst of (debounced) key presses
+    let _ = crossterm::execute!(std::io::stdout(), EnableBracketedPaste);
     let app_state = AppState::new()?;
+    let uses_terminal_title = app_state.config.timer.terminal_title;
+    // Also undo our terminal changes and drop the lock when panicking, before the existing restore hook runs
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // Caught by the tag reader (a corrupt music file), and sessio keeps running
+        if std::thread::current().name() == Some(metadata::TAG_THREAD) {
+            return;
+        }
+        if uses_terminal_title {
+            reset_terminal_title();
+        }
+        let _ = crossterm::execute!(std::io::stdout(), DisableBracketedPaste);
+        InstanceLock::release_path(&lock_path);
+        previous_hook(info);
+    }));
     let result = run(terminal, app_state);
+    if uses_terminal_title {
+        reset_terminal_title();
+    }
+    let _ = crossterm::execute!(std::io::stdout(), DisableBracketedPaste);
     ratatui::restore();
+    drop(lock);
     result
 }
 
 fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
-    loop {
+    if let Err(e) = app_state.catch_up_journal() {
+        app_state.todo.status_message = Some(format!("Journal not written: {}", e));
+    }
+    let result = loop {
         terminal.draw(|frame| render(frame, &mut app_state))?;
+        app_state.update_terminal_title();
+        
+        // Recurring tasks come back on a new day
+        let today = chrono::Local::now().date_naive();
+        if today != app_state.today {
+            // Before recurring tasks are reset, which forgets when they were done
+            if let Err(e) = app_state.write_journal(app_state.today) {
+                app_state.todo.status_message = Some(format!("Journal not written: {}", e));
+            }
+            app_state.today = today;
+            app_state.todo.reset_recurring_tasks(today);
+        }
+        
+        // A task number typed in the todo panel is jumped to after a pause
+        app_state.todo.check_jump_timeout();
+        
+        // Write changes the auto-save delay held back
+        app_state.todo.flush_if_due();
+        
+        // Pick up edits made to the todo file by other programs
+        if app_state.todo.check_disk_changes() {
+            app_state.sync_timer_attachment();
+        }
         
         // Update music playback state (check for track finished, auto-advance)
         app_state.track_list.update_playback_state();
         
+        // Media keys, and telling the desktop what's playing
+        app_state.handle_remote_commands();
+        
+        // Pause or resume music when the timer switches between work and breaks
+        if let Some(transition) = app_state.timer.take_phase_transition() {
+            app_state.handle_phase_transition(transition);
+        }
+        
+        // Start music along with a work session (if enabled), which is no time to fall asleep
+        if app_state.timer.take_work_start() {
+            if app_state.config.music.work_cancels_sleep_timer {
+                app_state.track_list.cancel_sleep_timer();
+            }
+            if app_state.config.music.auto_play_on_work {
+                app_state.track_list.ensure_playing();
+            }
+        }
+        
         // Coordinate music volume with alarm state
         let is_alarm_active = app_state.timer.update_alarm_state();
         
         if is_alarm_active && !app_state.was_alarm_active_last_update {
-            // Alarm just started - lower music volume
-            app_state.track_list.lower_volume_for_alarm(app_state.timer.get_alarm_volume());
+            app_state.handle_alarm_start();
         } else if !is_alarm_active && app_state.was_alarm_active_last_update {
-            // Alarm just ended - restore normal music volume
-            app_state.track_list.restore_volume(app_state.config.music.default_volume);
+            app_state.handle_alarm_end();
         }
         
         app_state.was_alarm_active_last_update = is_alarm_active;
         
-        // Use timeout when timer is running, poll immediately when stopped
-        let timeout = if matches!(app_state.timer.state, timer::TimerState::Running) {
+        // Use timeout when timer is running (or an alarm is sounding or flashing), poll immediately when stopped
+        let timeout = if matches!(app_state.timer.state, timer::TimerState::Running) || app_state.timer.is_alarm_active()
+            || app_state.todo.jump_pending() || (app_state.todo.is_dirty() && app_state.todo.auto_save)
+            || app_state.track_list.is_fading_out()
+            || app_state.track_list.is_ramping_volume() {
             std::time::Duration::from_millis(100) // Update 10 times per second when running
         } else {
             std::time::Duration::from_millis(1000) // Check once per second when stopped
         };
         
-        if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
+        if app_state.wait_for_event(timeout)? {
+            let event = event::read()?;
+            if let Event::Paste(text) = event {
+                app_state.paste(&text);
+                continue;
+            }
+            if let Event::Key(key) = event {
                 // Only handle key press events, ignore key release events
                 if key.kind != KeyEventKind::Press {
                     continue;
@@ -182,6 +644,141 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
                 app_state.last_key_time = now;
                 app_state.last_key_code = Some(key.code);
                 
+                // A conflict with changes made on disk has to be settled first
+                if app_state.todo.disk_conflict {
+                    match key.code {
+                        KeyCode::Char('m') => {
+                            app_state.todo.keep_mine();
+                        }
+                        KeyCode::Char('t') => {
+                            app_state.todo.take_theirs();
+                            app_state.sync_timer_attachment();
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                
+                // Quitting with unsaved changes waits for save, discard or Esc
+                if app_state.todo.quit_prompt {
+                    match key.code {
+                        KeyCode::Char('s') => {
+                            if app_state.todo.answer_quit_prompt(true) {
+                                break Ok(());
+                            }
+                        }
+                        KeyCode::Char('d') => {
+                            if app_state.todo.answer_quit_prompt(false) {
+                                break Ok(());
+                            }
+                        }
+                        KeyCode::Esc => {
+                            app_state.todo.quit_prompt = false;
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                
+                // The session note prompt takes all keys until it is submitted or skipped
+                if let Some(ref mut note) = app_state.note_input {
+                    match key.code {
+                        KeyCode::Enter => {
+                            let note = note.as_str().trim().to_string();
+                            if !note.is_empty() {
+                                app_state.timer.set_last_work_note(note);
+                            }
+                            app_state.note_input = None;
+                        }
+                        KeyCode::Esc => {
+                            app_state.note_input = None;
+                        }
+                        _ => {
+                            note.handle_key(key);
+                        }
+                    }
+                    continue;
+                }
+                
+                // The task notes popup takes all keys until it is closed
+                if let Some(ref mut notes) = app_state.todo.notes_editor {
+                    match key.code {
+                        KeyCode::Esc => {
+                            app_state.todo.close_notes_editor();
+                        }
+                        KeyCode::Enter => {
+                            notes.push('\n');
+                        }
+                        KeyCode::Backspace => {
+                            notes.pop();
+                        }
+                        KeyCode::Char(c) => {
+                            notes.push(c);
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                
+                // The link picker takes all keys until a link is opened or it is closed
+                if !app_state.todo.link_choices.is_empty() {
+                    match key.code {
+                        KeyCode::Char('j') | KeyCode::Down => {
+                            app_state.todo.move_link_choice(false);
+                        }
+                        KeyCode::Char('k') | KeyCode::Up => {
+                            app_state.todo.move_link_choice(true);
+                        }
+                        KeyCode::Enter => {
+                            app_state.todo.open_link_choice(None);
+                        }
+                        KeyCode::Char(c @ '1'..='9') => {
+                            app_state.todo.open_link_choice(Some(c as usize - '1' as usize));
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') => {
+                            app_state.todo.close_link_picker();
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                
+                // The blocker picker takes all keys until a task is chosen or it is closed
+                if !app_state.todo.blocker_choices.is_empty() {
+                    match key.code {
+                        KeyCode::Char('j') | KeyCode::Down => {
+                            app_state.todo.move_blocker_choice(false);
+                        }
+                        KeyCode::Char('k') | KeyCode::Up => {
+                            app_state.todo.move_blocker_choice(true);
+                        }
+                        KeyCode::Enter => {
+                            app_state.todo.choose_blocker();
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') => {
+                            app_state.todo.close_blocker_picker();
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                
+                // An open focus rating prompt takes 1-5, and ESC once no alarm is left to dismiss
+                if app_state.timer.is_rating_prompt_active() {
+                    match key.code {
+                        KeyCode::Char(c @ '1'..='5') if !app_state.todo.is_input_mode => {
+                            app_state.timer.rate_focus(c as u8 - b'0');
+                            continue;
+                        }
+                        KeyCode::Esc if !app_state.app.show_help && !app_state.todo.is_input_mode
+                            && !app_state.timer.is_alarm_active() => {
+                            app_state.timer.skip_focus_rating();
+                            continue;
+                        }
+                        _ => {}
+                    }
+                }
+                
                 // Handle help popup first (global key)
             match key.code {
                 KeyCode::Char('?') => {
@@ -192,9 +789,15 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
                     if app_state.app.show_help {
                         app_state.app.close_help();
                         continue;
+                    } else if app_state.app.show_task_detail {
+                        app_state.app.show_task_detail = false;
+                        continue;
                     } else if app_state.todo.is_input_mode {
                         app_state.todo.cancel_input_mode();
                         continue;
+                    } else if app_state.timer.is_alarm_active() {
+                        app_state.timer.dismiss_alarm();
+                        continue;
                     }
                 }
                 _ => {}
@@ -229,22 +832,50 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
                 continue;
             }
             
+            // The task detail popup takes all keys until it is closed
+            if app_state.app.show_task_detail {
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app_state.todo.scroll_task_detail_down();
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app_state.todo.scroll_task_detail_up();
+                    }
+                    KeyCode::Enter | KeyCode::Char('q') => {
+                        app_state.app.show_task_detail = false;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            
             // Check if we're in todo input mode
             if app_state.todo.is_input_mode {
                 match key.code {
                     KeyCode::Enter => {
-                        app_state.todo.submit_new_task();
-                    }
-                    KeyCode::Backspace => {
-                        app_state.todo.remove_char_from_input();
+                        app_state.todo.submit_input();
+                        app_state.sync_timer_attachment();
                     }
-                    KeyCode::Char(c) => {
-                        app_state.todo.add_char_to_input(c);
+                    _ => {
+                        app_state.todo.current_input.handle_key(key);
                     }
-                    _ => {}
                 }
             } else {
                 // Normal navigation and command mode
+                // Any key other than a second D cancels a pending delete
+                if key.code != KeyCode::Char('D') {
+                    app_state.todo.delete_confirm = None;
+                }
+                if key.code != KeyCode::Char('c') {
+                    app_state.todo.clear_done_confirm = false;
+                }
+                // A task number being typed is ended by Enter; other keys drop it
+                if !matches!(key.code, KeyCode::Char('0'..='9') | KeyCode::Enter) {
+                    app_state.todo.cancel_jump();
+                }
+                app_state.todo.status_message = None;
+                app_state.track_list.status_message = None;
+                app_state.summary.status_message = None;
                 match key.code {
                     KeyCode::Char('q') => {
                         // Save pomodoro session data before exiting
@@ -252,15 +883,27 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
                             let sessions = app_state.timer.get_daily_sessions().to_vec();
                             app_state.todo.save_pomodoro_sessions(sessions);
                         }
-                        break Ok(());
+                        // Unsaved changes (auto_save off) ask first
+                        if app_state.todo.request_quit() {
+                            break Ok(());
+                        }
                     }
-                    
-                    // h and l for cycling between panels horizontally
-                    KeyCode::Char('h') => {
-                        app_state.app.cycle_panels('h');
+                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        // Write the todo file now (the way to save with auto_save off)
+                        app_state.todo.write_to_file();
+                        if !app_state.todo.is_dirty() {
+                            app_state.todo.status_message = Some(format!("Saved {}", app_state.todo.file_path));
+                        }
                     }
-                    KeyCode::Char('l') => {
-                        app_state.app.cycle_panels('l');
+                    
+                    // h and l for cycling between panels horizontally, or months in the summary's calendar
+                    KeyCode::Char('h') | KeyCode::Char('l') => {
+                        let direction = if key.code == KeyCode::Char('h') { 'h' } else { 'l' };
+                        if app_state.app.focused_quadrant == Quadrant::TopRight && app_state.summary.view == SummaryView::Month {
+                            app_state.summary.page_month(direction == 'h');
+                        } else {
+                            app_state.app.cycle_panels(direction);
+                        }
                     }
                     KeyCode::Char('j') => {
                         // Move down within the current panel only
@@ -273,8 +916,15 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
                                 // Navigate within track list
                                 app_state.track_list.move_selection_down();
                             }
-                            _ => {
-                                // Other panels don't have internal navigation yet
+                            Quadrant::TopLeft => {
+                                // Scroll the session log towards the latest entry
+                                app_state.timer.scroll_history_down();
+                            }
+                            Quadrant::TopRight => {
+                                // The next day with sessions, up to today
+                                if app_state.summary.view == SummaryView::Today {
+                                    app_state.summary.step_day(&app_state.todo.pomodoro_sessions, chrono::Local::now().date_naive(), false);
+                                }
                             }
                         }
                     }
@@ -289,9 +939,72 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
                                 // Navigate within track list
                                 app_state.track_list.move_selection_up();
                             }
-                            _ => {
-                                // Other panels don't have internal navigation yet
+                            Quadrant::TopLeft => {
+                                // Scroll the session log towards older entries
+                                app_state.timer.scroll_history_up();
+                            }
+                            Quadrant::TopRight => {
+                                // The day before with sessions
+                                if app_state.summary.view == SummaryView::Today {
+                                    app_state.summary.step_day(&app_state.todo.pomodoro_sessions, chrono::Local::now().date_naive(), true);
+                                }
+                            }
+                        }
+                    }
+                    KeyCode::Char('J') => {
+                        // Move the selected todo item down one place (capital J)
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            app_state.todo.move_selected_task(false);
+                        }
+                    }
+                    KeyCode::Char('K') => {
+                        // Move the selected todo item up one place (capital K)
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            app_state.todo.move_selected_task(true);
+                        }
+                    }
+                    KeyCode::Char('u') => {
+                        // Open the link in the selected todo item (a picker when it has several)
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            app_state.todo.open_task_links();
+                        }
+                    }
+                    KeyCode::Char(digit @ '0'..='9') => {
+                        // Type a task number to jump to it (Enter or a short pause jumps),
+                        // or turn the music down/up with 9/0 like mpv
+                        match app_state.app.focused_quadrant {
+                            Quadrant::BottomLeft => {
+                                app_state.todo.push_jump_digit(digit);
+                            }
+                            Quadrant::BottomRight if digit == '9' || digit == '0' => {
+                                app_state.track_list.step_volume(digit == '0');
                             }
+                            _ => {}
+                        }
+                    }
+                    KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Char('-') => {
+                        // Music volume up/down when focused on track list (= is + without shift)
+                        if app_state.app.focused_quadrant == Quadrant::BottomRight {
+                            app_state.track_list.step_volume(key.code != KeyCode::Char('-'));
+                        }
+                    }
+                    KeyCode::Left | KeyCode::Right | KeyCode::Char(',') | KeyCode::Char('.') => {
+                        // Seek 10 seconds back/forward in the playing track when focused on track list
+                        if app_state.app.focused_quadrant == Quadrant::BottomRight {
+                            let forward = matches!(key.code, KeyCode::Right | KeyCode::Char('.'));
+                            app_state.track_list.seek(if forward { 10 } else { -10 });
+                        }
+                    }
+                    KeyCode::Char('W') => {
+                        // Switch between wrapping and cutting off long task titles (capital W)
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            app_state.todo.toggle_wrap_titles();
+                        }
+                    }
+                    KeyCode::Char('T') => {
+                        // Move the selected todo item to the top of the list (capital T)
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            app_state.todo.move_selected_task_to_end(true);
                         }
                     }
                     KeyCode::Char('a') => {
@@ -300,27 +1013,130 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
                             app_state.todo.start_input_mode();
                         }
                     }
+                    KeyCode::Char('e') => {
+                        // Edit the selected todo item's text in place
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            app_state.todo.start_edit_input();
+                        }
+                    }
+                    KeyCode::Char('f') => {
+                        // Cycle the todo tag filter through the #tags in the list,
+                        // or mark the selected track as a favorite
+                        match app_state.app.focused_quadrant {
+                            Quadrant::BottomLeft => {
+                                app_state.todo.cycle_tag_filter();
+                            }
+                            Quadrant::BottomRight => {
+                                app_state.track_list.toggle_favorite();
+                            }
+                            _ => {}
+                        }
+                    }
+                    KeyCode::Char('g') => {
+                        // Select the playing track, scrolling it into view
+                        if app_state.app.focused_quadrant == Quadrant::BottomRight {
+                            app_state.track_list.jump_to_playing();
+                        }
+                    }
+                    KeyCode::Char('G') => {
+                        // Keep the playing track selected as the music moves on (capital G)
+                        if app_state.app.focused_quadrant == Quadrant::BottomRight {
+                            app_state.track_list.toggle_follow_playing();
+                        }
+                    }
+                    KeyCode::Char('F') => {
+                        // Show and play only favorite tracks when focused on track list (capital F)
+                        if app_state.app.focused_quadrant == Quadrant::BottomRight {
+                            app_state.track_list.toggle_favorites_only();
+                        }
+                    }
+                    KeyCode::Char('o') => {
+                        match app_state.app.focused_quadrant {
+                            // Cycle the order the todo list is shown in
+                            Quadrant::BottomLeft => app_state.todo.cycle_sort_mode(),
+                            // Cycle the order of the track list, and remember it in the config
+                            Quadrant::BottomRight => {
+                                app_state.track_list.cycle_sort();
+                                app_state.config.music.track_sort = app_state.track_list.sort;
+                                if let Err(e) = app_state.config.save() {
+                                    eprintln!("Failed to save config: {}", e);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    KeyCode::Char('N') => {
+                        // Edit the notes of the selected todo item (capital N)
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            app_state.todo.open_notes_editor();
+                        }
+                    }
+                    KeyCode::Char('X') => {
+                        // Export todos and pomodoro sessions to JSON (capital X)
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            let export = Export::new(&app_state.todo.items, app_state.timer.get_daily_sessions());
+                            app_state.todo.status_message = Some(match export.write(None) {
+                                Ok(path) => format!("Exported to {}", path.display()),
+                                Err(e) => format!("Export failed: {}", e),
+                            });
+                        }
+                    }
+                    KeyCode::Tab => {
+                        // Switch to the next todo list (when several are configured)
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            app_state.switch_todo_list();
+                        }
+                    }
+                    KeyCode::Char('L') => {
+                        // Set a custom session length for the selected todo item (capital L)
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            app_state.todo.start_session_length_input();
+                        }
+                    }
+                    KeyCode::Char('E') => {
+                        match app_state.app.focused_quadrant {
+                            // Set the estimated pomodoros for the selected todo item (capital E)
+                            Quadrant::BottomLeft => app_state.todo.start_estimate_input(),
+                            // Write a markdown report of what the summary shows
+                            Quadrant::TopRight => app_state.write_report(),
+                            _ => {}
+                        }
+                    }
                     KeyCode::Char('d') => {
                         // Toggle done status of selected todo item
                         if app_state.app.focused_quadrant == Quadrant::BottomLeft {
                             app_state.todo.toggle_selected_task();
+                            app_state.sync_timer_attachment();
                         }
                     }
                     KeyCode::Char('D') => {
                         // Delete selected todo item
                         if app_state.app.focused_quadrant == Quadrant::BottomLeft {
                             app_state.todo.delete_selected_task();
+                            app_state.sync_timer_attachment();
+                        }
+                    }
+                    KeyCode::Char('c') => {
+                        // Clear all done todo items (asks to press c again first)
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            app_state.todo.clear_done_tasks();
+                            app_state.sync_timer_attachment();
                         }
                     }
                     KeyCode::Char('s') => {
                         // Select todo item for timer and add focused time
                         if app_state.app.focused_quadrant == Quadrant::BottomLeft {
-                            if let Some(selected_task) = app_state.todo.get_selected_task() {
+                            // A blocked task can't be worked on until its blocker is done
+                            if let Some(blocker) = app_state.todo.blocker_of(app_state.todo.selected_index) {
+                                app_state.todo.status_message = Some(format!("Blocked by: {}", blocker.task));
+                            } else if let Some(selected_task) = app_state.todo.get_selected_task() {
                                 // Set the selected TODO item in the timer with task name
                                 app_state.timer.set_selected_todo_with_task_name(
-                                    Some(app_state.todo.selected_index), 
+                                    Some(selected_task.id), 
                                     Some(selected_task.task.clone())
                                 );
+                                // Use the task's own session length, or the configured default
+                                app_state.timer.set_task_session_minutes(selected_task.session_minutes);
                                 
                                 // Start the timer if it's not running
                                 if matches!(app_state.timer.state, timer::TimerState::Stopped) {
@@ -330,9 +1146,19 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
                         }
                     }
                     KeyCode::Enter => {
-                        // Play selected track when focused on track list
-                        if app_state.app.focused_quadrant == Quadrant::BottomRight {
-                            app_state.track_list.play_selected();
+                        // Show the selected todo's details, or play the selected track
+                        match app_state.app.focused_quadrant {
+                            Quadrant::BottomLeft if app_state.todo.jump_pending() => {
+                                app_state.todo.finish_jump();
+                            }
+                            Quadrant::BottomLeft if !app_state.todo.items.is_empty() => {
+                                app_state.todo.detail_scroll = 0;
+                                app_state.app.show_task_detail = true;
+                            }
+                            Quadrant::BottomRight => {
+                                app_state.track_list.play_selected();
+                            }
+                            _ => {}
                         }
                     }
                     KeyCode::Char(' ') => {
@@ -348,9 +1174,81 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
                         }
                     }
                     KeyCode::Char('r') => {
-                        // Reset timer when focused on timer
+                        // Reset timer when focused on timer, set how the selected todo item repeats when focused on todo
+                        match app_state.app.focused_quadrant {
+                            Quadrant::TopLeft => {
+                                app_state.timer.reset();
+                            }
+                            Quadrant::BottomLeft => {
+                                app_state.todo.start_repeat_input();
+                            }
+                            _ => {}
+                        }
+                    }
+                    KeyCode::Char('t') => {
+                        match app_state.app.focused_quadrant {
+                            // Switch between pomodoro and stopwatch when focused on timer, and remember it in the config
+                            Quadrant::TopLeft => {
+                                app_state.timer.toggle_mode();
+                                app_state.config.timer.mode = app_state.timer.mode;
+                                if let Err(e) = app_state.config.save() {
+                                    eprintln!("Failed to save config: {}", e);
+                                }
+                            }
+                            // Back to today from an earlier day in the summary
+                            Quadrant::TopRight => app_state.summary.show_today(),
+                            _ => {}
+                        }
+                    }
+                    KeyCode::Char('w') => {
+                        // Switch to a work phase when focused on timer (not while running)
                         if app_state.app.focused_quadrant == Quadrant::TopLeft {
-                            app_state.timer.reset();
+                            app_state.timer.switch_phase(PomodoroPhase::Work);
+                        }
+                    }
+                    KeyCode::Char('b') => {
+                        // Switch to a short break when focused on timer (not while running),
+                        // or pick the task the selected todo item is blocked by
+                        match app_state.app.focused_quadrant {
+                            Quadrant::TopLeft => {
+                                app_state.timer.switch_phase(PomodoroPhase::ShortBreak);
+                            }
+                            Quadrant::BottomLeft => {
+                                app_state.todo.start_blocker_picker();
+                            }
+                            _ => {}
+                        }
+                    }
+                    KeyCode::Char('B') => {
+                        // Switch to a long break when focused on timer (capital B, not while running),
+                        // or move the selected todo item to just above the done tasks
+                        match app_state.app.focused_quadrant {
+                            Quadrant::TopLeft => app_state.timer.switch_phase(PomodoroPhase::LongBreak),
+                            Quadrant::BottomLeft => app_state.todo.move_selected_task_to_end(false),
+                            _ => {}
+                        }
+                    }
+                    KeyCode::Char('A') => {
+                        // Preview the alarm sound when focused on timer (capital A), again to stop it,
+                        // or add a subtask under the selected todo item
+                        match app_state.app.focused_quadrant {
+                            Quadrant::TopLeft => {
+                                app_state.timer.toggle_alarm_preview();
+                            }
+                            Quadrant::BottomLeft => {
+                                app_state.todo.start_subtask_input();
+                            }
+                            _ => {}
+                        }
+                    }
+                    KeyCode::Char('M') => {
+                        // Toggle quiet mode when focused on timer (capital M) and remember it in the config
+                        if app_state.app.focused_quadrant == Quadrant::TopLeft {
+                            app_state.timer.toggle_quiet_mode();
+                            app_state.config.timer.quiet_mode = app_state.timer.quiet_mode;
+                            if let Err(e) = app_state.config.save() {
+                                eprintln!("Failed to save config: {}", e);
+                            }
                         }
                     }
                     KeyCode::Char('S') => {
@@ -360,9 +1258,15 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
                         }
                     }
                     KeyCode::Char('z') => {
-                        // Undo last action in todo
-                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
-                            app_state.todo.undo();
+                        match app_state.app.focused_quadrant {
+                            // Undo last action in todo
+                            Quadrant::BottomLeft => {
+                                app_state.todo.undo();
+                                app_state.sync_timer_attachment();
+                            }
+                            // Cycle the music sleep timer
+                            Quadrant::BottomRight => app_state.track_list.cycle_sleep_timer(),
+                            _ => {}
                         }
                     }
                     KeyCode::Char('n') => {
@@ -372,9 +1276,39 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
                         }
                     }
                     KeyCode::Char('p') => {
-                        // Previous track when focused on track list
-                        if app_state.app.focused_quadrant == Quadrant::BottomRight {
-                            app_state.track_list.previous_track();
+                        // Previous track when focused on track list, cycle priority when focused on todo
+                        match app_state.app.focused_quadrant {
+                            Quadrant::BottomRight => {
+                                app_state.track_list.previous_track();
+                            }
+                            Quadrant::BottomLeft => {
+                                app_state.todo.cycle_selected_priority();
+                            }
+                            _ => {}
+                        }
+                    }
+                    KeyCode::Char('*') => {
+                        // Star the selected task, keeping it at the top
+                        if app_state.app.focused_quadrant == Quadrant::BottomLeft {
+                            app_state.todo.toggle_selected_star();
+                        }
+                    }
+                    KeyCode::Char('P') => {
+                        match app_state.app.focused_quadrant {
+                            // Plan the selected task for a day
+                            Quadrant::BottomLeft => app_state.todo.start_schedule_input(),
+                            // Keep the current playlist through timer phase changes
+                            Quadrant::BottomRight => app_state.track_list.toggle_playlist_pin(),
+                            _ => {}
+                        }
+                    }
+                    KeyCode::Char('v') => {
+                        match app_state.app.focused_quadrant {
+                            // Show only the tasks planned for today
+                            Quadrant::BottomLeft => app_state.todo.toggle_today_view(),
+                            // Switch the summary between today and the month calendar
+                            Quadrant::TopRight => app_state.summary.cycle_view(),
+                            _ => {}
                         }
                     }
                     KeyCode::Char('R') => {
@@ -414,6 +1348,26 @@ fn run(mut terminal: DefaultTerminal, mut app_state: AppState) -> Result<()> {
             } // Close the if let Event::Key(key) block
         } // This closes the if event::poll() block
         // Continue the loop even if no event occurred (for timer updates)
+    };
+    // Finished days go to the journal (today waits until it's over), and the player remembers where it was.
+    // Each save is tried even when one before it failed.
+    let save_errors: Vec<String> = [
+        app_state.catch_up_journal(),
+        app_state.track_list.save_state().map_err(Into::into),
+        app_state.track_list.save_play_counts().map_err(Into::into),
+    ]
+    .into_iter()
+    .filter_map(Result::err)
+    .map(|e| e.to_string())
+    .collect();
+    if save_errors.is_empty() {
+        return result;
+    }
+    let save_error = format!("Could not save on quit: {}", save_errors.join("; "));
+    // An error that ended the loop comes first, with the failed saves noted under it
+    match result {
+        Ok(()) => Err(color_eyre::eyre::eyre!(save_error)),
+        Err(e) => Err(e.note(save_error)),
     }
 }
 
@@ -423,16 +1377,21 @@ fn render(frame: &mut Frame, app_state: &mut AppState) {
     frame.render_widget(bg_block, frame.area());
     
     // Check if a work phase just completed and add time to the selected TODO
-    if app_state.timer.work_phase_just_completed() {
-        if let Some(todo_index) = app_state.timer.get_selected_todo() {
-            let work_minutes = app_state.timer.get_work_session_minutes();
-            app_state.todo.add_time_to_task_by_index(todo_index, work_minutes);
-            // Clear the selected todo and flag after adding time
+    if let Some(credit) = app_state.timer.take_work_credit() {
+        if credit.minutes > 0 {
+            app_state.todo.add_time_to_task_by_id(credit.todo_id, credit.minutes);
+        }
+        // A finished work phase releases the todo; partial credit on reset keeps it attached
+        if credit.finished {
             app_state.timer.set_selected_todo(None);
-            app_state.timer.clear_work_completed_flag();
         }
     }
     
+    // Ask what the finished work phase produced (if enabled)
+    if app_state.timer.take_note_request() {
+        app_state.note_input = Some(TextInput::default());
+    }
+    
     // Sync pomodoro session data from timer to todo whenever it's updated
     if app_state.timer.session_data_just_updated() {
         if app_state.config.todo.save_pomodoro_data {
@@ -466,8 +1425,50 @@ fn render(frame: &mut Frame, app_state: &mut AppState) {
     app_state.todo.render(frame, bottom_layout[0], &app_state.app);
     app_state.track_list.render(frame, bottom_layout[1], &app_state.app);
     
+    // Pulse the background of every panel while the alarm is active; text colors are kept so it stays readable
+    if app_state.config.notifications.visual_alarm && app_state.timer.alarm_pulse_on() {
+        let area = frame.area();
+        frame.buffer_mut().set_style(area, Style::default().bg(DraculaTheme::CURRENT_LINE));
+    }
+    
     // Render help popup on top if shown
     if app_state.app.show_help {
         app_state.app.help.render(frame);
     }
+    
+    app_state.todo.render_notes_editor(frame);
+    
+    if app_state.app.show_task_detail {
+        app_state.todo.render_task_detail(frame);
+    }
+    
+    if let Some(ref note) = app_state.note_input {
+        render_note_prompt(frame, note);
+    }
+    
+    app_state.todo.render_link_picker(frame);
+    app_state.todo.render_blocker_picker(frame);
+    app_state.todo.render_quit_prompt(frame);
+    app_state.todo.render_conflict_prompt(frame);
+}
+
+/// Single-line popup asking for a note about the work session that just finished
+fn render_note_prompt(frame: &mut Frame, note: &TextInput) {
+    let [popup_area] = Layout::vertical([Constraint::Length(4)]).flex(Flex::Center).areas(frame.area());
+    let [popup_area] = Layout::horizontal([Constraint::Percentage(60)]).flex(Flex::Center).areas(popup_area);
+    
+    frame.render_widget(Clear, popup_area);
+    
+    let block = Block::default()
+        .title("📝 What did you get done?")
+        .title_style(Style::default().fg(DraculaTheme::PINK))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(DraculaTheme::PINK))
+        .style(Style::default().bg(DraculaTheme::CURRENT_LINE).fg(DraculaTheme::FOREGROUND));
+    let mut input_line = vec![Span::raw("> ")];
+    input_line.extend(note.spans());
+    let prompt = Paragraph::new(vec![Line::from(input_line), Line::raw("Enter to save, Esc or empty to skip")])
+        .block(block);
+    
+    frame.render_widget(prompt, popup_area);
 }
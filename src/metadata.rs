@@ -0,0 +1,208 @@
+use std::fs::File;
+use std::panic;
+use std::path::Path;
+use std::time::Duration;
+
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, Tag};
+use symphonia::core::probe::Hint;
+
+/// Name of the thread tags are read on. A decoder panicking on a corrupt file is caught there, and
+/// main's panic hook leaves it alone instead of restoring the terminal under the running app.
+pub const TAG_THREAD: &str = "tag-reader";
+
+/// Artist, title, album and ReplayGain read from a music file's tags, and its length from the headers
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackTags {
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub album: Option<String>,
+    pub gain: Option<f32>, // ReplayGain track gain in dB
+    pub duration: Option<Duration>, // Not for ogg, whose length is only known once it has been played
+}
+
+impl TrackTags {
+    /// Keep a value for each field, the first one found wins; blank values don't count
+    fn set(&mut self, key: &str, value: &str) {
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+        let field = match key.to_ascii_uppercase().as_str() {
+            "ARTIST" => &mut self.artist,
+            "TITLE" => &mut self.title,
+            "ALBUM" => &mut self.album,
+            "REPLAYGAIN_TRACK_GAIN" => {
+                self.gain = self.gain.or_else(|| parse_gain(value));
+                return;
+            }
+            _ => return,
+        };
+        field.get_or_insert_with(|| value.to_string());
+    }
+
+    fn is_empty(&self) -> bool {
+        self.artist.is_none() && self.title.is_none() && self.album.is_none() && self.gain.is_none() && self.duration.is_none()
+    }
+}
+
+/// How long `frames` samples last at `sample_rate`; None when either is unknown
+fn frames_duration(frames: Option<u64>, sample_rate: Option<u32>) -> Option<Duration> {
+    let sample_rate = sample_rate.filter(|&rate| rate > 0)?;
+    Some(Duration::from_secs_f64(frames? as f64 / f64::from(sample_rate)))
+}
+
+/// A ReplayGain value such as "-6.54 dB" (the unit is optional) in dB
+fn parse_gain(value: &str) -> Option<f32> {
+    let number = value.trim();
+    let number = number.strip_suffix("dB").or_else(|| number.strip_suffix("db")).unwrap_or(number);
+    number.trim().parse::<f32>().ok().filter(|gain| gain.is_finite())
+}
+
+/// The tags of the file at `path`: ID3 for mp3, Vorbis comments for flac and ogg. None when the
+/// file has none, isn't one of those formats or can't be read; a corrupt file never panics.
+pub fn read_tags(path: &Path) -> Option<TrackTags> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    // The decoders assume well-formed files, so a broken one is skipped rather than taking sessio down
+    let tags = panic::catch_unwind(|| match extension.as_str() {
+        "mp3" => read_id3(path),
+        "flac" => read_flac(path),
+        "ogg" => read_ogg(path),
+        _ => None,
+    }).ok()??;
+    (!tags.is_empty()).then_some(tags)
+}
+
+fn read_flac(path: &Path) -> Option<TrackTags> {
+    let reader = claxon::FlacReader::open(path).ok()?;
+    let mut tags = from_comments(reader.tags());
+    let info = reader.streaminfo();
+    tags.duration = frames_duration(info.samples, Some(info.sample_rate));
+    Some(tags)
+}
+
+fn read_ogg(path: &Path) -> Option<TrackTags> {
+    let reader = lewton::inside_ogg::OggStreamReader::new(File::open(path).ok()?).ok()?;
+    Some(from_comments(reader.comment_hdr.comment_list.iter().map(|(key, value)| (key.as_str(), value.as_str()))))
+}
+
+fn read_id3(path: &Path) -> Option<TrackTags> {
+    let source = MediaSourceStream::new(Box::new(File::open(path).ok()?), Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension("mp3");
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())
+        .ok()?;
+
+    // ID3v2 sits in front of the audio, so the probe reads it; anything else is in the stream's own metadata
+    let mut tags = TrackTags::default();
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|metadata| metadata.current().cloned()) {
+        add_standard_tags(&mut tags, revision.tags());
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        add_standard_tags(&mut tags, revision.tags());
+    }
+    // Counted from the Xing header, or estimated from the file size
+    if let Some(track) = probed.format.default_track() {
+        tags.duration = frames_duration(track.codec_params.n_frames, track.codec_params.sample_rate);
+    }
+    Some(tags)
+}
+
+fn add_standard_tags(tags: &mut TrackTags, found: &[Tag]) {
+    for tag in found {
+        let key = match tag.std_key {
+            Some(StandardTagKey::Artist) => "ARTIST",
+            Some(StandardTagKey::TrackTitle) => "TITLE",
+            Some(StandardTagKey::Album) => "ALBUM",
+            Some(StandardTagKey::ReplayGainTrackGain) => "REPLAYGAIN_TRACK_GAIN",
+            _ => continue,
+        };
+        tags.set(key, &tag.value.to_string());
+    }
+}
+
+/// Vorbis comments (as in flac and ogg files) are "KEY=value" pairs with case-insensitive keys
+fn from_comments<'a>(comments: impl Iterator<Item = (&'a str, &'a str)>) -> TrackTags {
+    let mut tags = TrackTags::default();
+    for (key, value) in comments {
+        tags.set(key, value);
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_vorbis_comments() {
+        let comments = [("artist", "Nils Frahm"), ("TITLE", " Says "), ("Album", ""), ("ARTIST", "Someone else"), ("GENRE", "Ambient"), ("replaygain_track_gain", "-6.54 dB")];
+        assert_eq!(from_comments(comments.into_iter()), TrackTags {
+            artist: Some("Nils Frahm".to_string()),
+            title: Some("Says".to_string()),
+            album: None,
+            gain: Some(-6.54),
+            duration: None,
+        });
+    }
+
+    #[test]
+    fn test_frames_duration() {
+        assert_eq!(frames_duration(Some(441_000), Some(44_100)), Some(Duration::from_secs(10)));
+        assert_eq!(frames_duration(None, Some(44_100)), None);
+        assert_eq!(frames_duration(Some(441_000), Some(0)), None);
+    }
+
+    #[test]
+    fn test_parse_gain() {
+        assert_eq!(parse_gain("+2.10 dB"), Some(2.1));
+        assert_eq!(parse_gain(" -11.2db "), Some(-11.2));
+        assert_eq!(parse_gain("-0.5"), Some(-0.5));
+        assert_eq!(parse_gain("loud"), None);
+        assert_eq!(parse_gain("NaN dB"), None);
+    }
+
+    #[test]
+    fn test_truncated_files_have_no_tags() {
+        let dir = std::env::temp_dir().join(format!("sessio-truncated-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // A STREAMINFO block that claims 34 bytes and ends after 6
+        let mut flac = b"fLaC\x80\x00\x00\x22".to_vec();
+        flac.extend_from_slice(&[0x10, 0x00, 0x10, 0x00, 0x00, 0x00]);
+        // The first page header of a vorbis stream, cut off inside its segment table
+        let mut ogg = b"OggS\x00\x02".to_vec();
+        ogg.extend_from_slice(&[0; 20]);
+        ogg.extend_from_slice(&[1, 30, 0x01, b'v', b'o', b'r']);
+        let files = [(dir.join("cut.flac"), flac), (dir.join("cut.ogg"), ogg)];
+        for (path, content) in &files {
+            fs::write(path, content).unwrap();
+        }
+        // Read the way the track list does, on the named thread
+        let paths: Vec<_> = files.iter().map(|(path, _)| path.clone()).collect();
+        let tags = std::thread::Builder::new()
+            .name(TAG_THREAD.to_string())
+            .spawn(move || paths.iter().map(|path| read_tags(path)).collect::<Vec<_>>())
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(tags, vec![None, None]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_broken_files_have_no_tags() {
+        let dir = std::env::temp_dir().join(format!("sessio-tags-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["broken.mp3", "broken.flac", "broken.ogg", "notes.txt", "empty.flac"] {
+            let path = dir.join(name);
+            let content: &[u8] = if name.starts_with("empty") { b"" } else { b"ID3\x04\x00\x00\xff\xff\xff\xffnot really music" };
+            fs::write(&path, content).unwrap();
+            assert_eq!(read_tags(&path), None, "{}", name);
+        }
+        assert_eq!(read_tags(&dir.join("missing.mp3")), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
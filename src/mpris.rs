@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use zbus::blocking::connection;
+use zbus::interface;
+use zbus::zvariant::{ObjectPath, Value};
+
+use crate::remote::{NowPlaying, RemoteCommand};
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.sessio";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// sessio's music player on the session bus as an MPRIS player, so media keys and desktop
+/// widgets can control it. Their requests come in as commands for the main loop; what's
+/// playing goes out with `update`. Dropping it leaves the bus.
+pub struct Mpris {
+    updates: Sender<NowPlaying>,
+    last: Option<NowPlaying>,
+}
+
+impl Mpris {
+    /// Connect on a thread of its own. Without a session bus (or when another sessio has the
+    /// name) there is just no MPRIS player; the rest of sessio doesn't notice.
+    pub fn start(commands: Sender<RemoteCommand>) -> Self {
+        let (updates, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = serve(commands, receiver);
+        });
+        Self { updates, last: None }
+    }
+
+    /// Tell the desktop about the current track and whether it plays, when that changed
+    pub fn update(&mut self, now: NowPlaying) {
+        if self.last.as_ref() != Some(&now) {
+            let _ = self.updates.send(now.clone());
+            self.last = Some(now);
+        }
+    }
+}
+
+fn serve(commands: Sender<RemoteCommand>, updates: Receiver<NowPlaying>) -> zbus::Result<()> {
+    let connection = connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, Root)?
+        .serve_at(OBJECT_PATH, Player { commands, now: NowPlaying::default() })?
+        .build()?;
+    let player = connection.object_server().interface::<_, Player>(OBJECT_PATH)?;
+    // Ends once the Mpris is dropped, taking the connection (and the name) with it
+    for now in updates {
+        let mut iface = player.get_mut();
+        iface.now = now;
+        zbus::block_on(iface.playback_status_changed(player.signal_emitter()))?;
+        zbus::block_on(iface.metadata_changed(player.signal_emitter()))?;
+    }
+    Ok(())
+}
+
+/// org.mpris.MediaPlayer2: who we are. sessio has no window to raise and is quit from the terminal.
+struct Root;
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> &str {
+        "sessio"
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// org.mpris.MediaPlayer2.Player: the controls, passed on to the main loop
+struct Player {
+    commands: Sender<RemoteCommand>,
+    now: NowPlaying,
+}
+
+impl Player {
+    fn send(&self, command: RemoteCommand) {
+        let _ = self.commands.send(command);
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn next(&self) {
+        self.send(RemoteCommand::Next);
+    }
+
+    fn previous(&self) {
+        self.send(RemoteCommand::Previous);
+    }
+
+    fn pause(&self) {
+        self.send(RemoteCommand::Pause);
+    }
+
+    fn play_pause(&self) {
+        self.send(RemoteCommand::PlayPause);
+    }
+
+    fn stop(&self) {
+        self.send(RemoteCommand::Stop);
+    }
+
+    fn play(&self) {
+        self.send(RemoteCommand::Play);
+    }
+
+    // Seeking is left to the terminal, see CanSeek
+    fn seek(&self, _offset: i64) {}
+
+    fn set_position(&self, _track_id: ObjectPath<'_>, _position: i64) {}
+
+    fn open_uri(&self, _uri: &str) {}
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        self.now.status.name()
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<&'static str, Value<'_>> {
+        metadata(&self.now)
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// The xesam/mpris fields for the current track; just the "no track" id when there is none
+fn metadata(now: &NowPlaying) -> HashMap<&'static str, Value<'_>> {
+    let mut fields = HashMap::new();
+    let Some(track) = now.track else {
+        fields.insert("mpris:trackid", Value::from(ObjectPath::from_static_str_unchecked("/org/mpris/MediaPlayer2/TrackList/NoTrack")));
+        return fields;
+    };
+    let id = format!("/org/sessio/track/{}", track);
+    fields.insert("mpris:trackid", Value::from(ObjectPath::try_from(id).expect("a valid object path")));
+    fields.insert("xesam:title", Value::from(now.title.as_str()));
+    if let Some(artist) = &now.artist {
+        fields.insert("xesam:artist", Value::from(vec![artist.as_str()]));
+    }
+    if let Some(album) = &now.album {
+        fields.insert("xesam:album", Value::from(album.as_str()));
+    }
+    if let Some(url) = &now.url {
+        fields.insert("xesam:url", Value::from(url.as_str()));
+    }
+    if let Some(length) = now.length {
+        fields.insert("mpris:length", Value::from(length.as_micros() as i64));
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_metadata() {
+        let nothing = NowPlaying::default();
+        let stopped = metadata(&nothing);
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(stopped["mpris:trackid"], Value::from(ObjectPath::from_static_str_unchecked("/org/mpris/MediaPlayer2/TrackList/NoTrack")));
+
+        let now = NowPlaying {
+            track: Some(3),
+            title: "Says".to_string(),
+            artist: Some("Nils Frahm".to_string()),
+            length: Some(Duration::from_secs(2)),
+            ..NowPlaying::default()
+        };
+        let fields = metadata(&now);
+        assert_eq!(fields["mpris:trackid"], Value::from(ObjectPath::from_static_str_unchecked("/org/sessio/track/3")));
+        assert_eq!(fields["xesam:title"], Value::from("Says"));
+        assert_eq!(fields["xesam:artist"], Value::from(vec!["Nils Frahm"]));
+        assert_eq!(fields["mpris:length"], Value::from(2_000_000i64));
+        assert!(!fields.contains_key("xesam:album"));
+    }
+}
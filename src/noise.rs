@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rodio::source::{SeekError, Source};
+
+const SAMPLE_RATE: u32 = 44_100;
+
+/// The built-in background sounds listed above the music files
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseKind {
+    White,
+    Brown,
+    Rain,
+}
+
+impl NoiseKind {
+    pub const ALL: [NoiseKind; 3] = [NoiseKind::White, NoiseKind::Brown, NoiseKind::Rain];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoiseKind::White => "White noise",
+            NoiseKind::Brown => "Brown noise",
+            NoiseKind::Rain => "Rain-ish filtered noise",
+        }
+    }
+
+    /// Stands in for a file path, so favorites and the remembered track work as for files
+    pub fn id(&self) -> &'static str {
+        match self {
+            NoiseKind::White => "noise:white",
+            NoiseKind::Brown => "noise:brown",
+            NoiseKind::Rain => "noise:rain",
+        }
+    }
+
+    /// How much of each new sample the one-pole filter lets in (1.0 leaves white noise as it is),
+    /// and the gain bringing the result back to about the loudness of the others
+    fn filter(&self) -> (f32, f32) {
+        match self {
+            NoiseKind::White => (1.0, 0.5),
+            NoiseKind::Brown => (0.02, 3.5),
+            NoiseKind::Rain => (0.3, 1.2),
+        }
+    }
+}
+
+/// Endless mono noise: random samples, low-passed by a one-pole filter for the darker kinds
+pub struct Noise {
+    rng: StdRng,
+    smoothing: f32,
+    gain: f32,
+    level: f32, // The filter's last output
+}
+
+impl Noise {
+    pub fn new(kind: NoiseKind) -> Self {
+        Self::with_rng(kind, StdRng::from_entropy())
+    }
+
+    fn with_rng(kind: NoiseKind, rng: StdRng) -> Self {
+        let (smoothing, gain) = kind.filter();
+        Self { rng, smoothing, gain, level: 0.0 }
+    }
+}
+
+impl Iterator for Noise {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let white: f32 = self.rng.gen_range(-1.0..=1.0);
+        self.level += self.smoothing * (white - self.level);
+        Some((self.level * self.gain).clamp(-1.0, 1.0))
+    }
+}
+
+impl Source for Noise {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
+        // Noise sounds the same everywhere
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_statistics() {
+        for kind in NoiseKind::ALL {
+            let samples: Vec<f32> = Noise::with_rng(kind, StdRng::seed_from_u64(7)).take(SAMPLE_RATE as usize * 5).collect();
+            let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+            let rms = (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt();
+            assert!(mean.abs() < 0.02, "{}: mean {}", kind.name(), mean);
+            assert!(samples.iter().all(|sample| sample.abs() <= 1.0), "{}", kind.name());
+            // Audible, but not clipping all the time
+            assert!((0.1..0.5).contains(&rms), "{}: rms {}", kind.name(), rms);
+        }
+    }
+
+    #[test]
+    fn test_colored_noise_is_smoother() {
+        // Neighbouring samples differ less the more the filter smooths
+        let roughness = |kind| {
+            let samples: Vec<f32> = Noise::with_rng(kind, StdRng::seed_from_u64(7)).take(10_000).collect();
+            samples.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum::<f32>() / samples.len() as f32
+        };
+        assert!(roughness(NoiseKind::Brown) < roughness(NoiseKind::Rain));
+        assert!(roughness(NoiseKind::Rain) < roughness(NoiseKind::White));
+    }
+}
@@ -0,0 +1,631 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, TimeZone};
+use std::collections::HashSet;
+
+use crate::recurrence::Recurrence;
+use crate::timer::{PomodoroSession, SessionEntry};
+use crate::todo::{tag_of, Priority, StoredTodos, TodoItem, TodoStore, WorkSession};
+
+/// Emacs org-mode todo files:
+///
+/// ```text
+/// * TODO [#A] Write report :work:
+/// :PROPERTIES:
+/// :FOCUSED_MINUTES: 75
+/// :ESTIMATE: 4
+/// :ID: 6f1c2a9e-4b7d-4e0a-9c3b-2d5e8f7a1b40
+/// :CREATED: [2025-06-28 Sat 09:12]
+/// :END:
+/// :LOGBOOK:
+/// - State "DONE"       from "TODO"       [2025-06-29 Sun 18:00]
+/// CLOCK: [2025-06-30 Mon 16:15]--[2025-06-30 Mon 16:40] =>  0:25
+/// :END:
+/// Notes, as plain lines
+/// ** TODO A subtask
+/// * Pomodoro Sessions
+/// ** 2025-06-30
+/// :PROPERTIES:
+/// :WORK_SESSIONS: 2
+/// :WORK_MINUTES: 50
+/// :END:
+/// Tasks worked on:
+/// - Write report
+/// Log:
+/// - 16:15 Work 25m (Write report)
+/// ```
+///
+/// Each day of a task's timeline is one CLOCK line ending at the last work of that day; several
+/// CLOCK lines on one day (e.g. clocked in Emacs) add up. #tags move to org tags, so they end up
+/// at the end of the task text when read back. Headings without TODO/DONE are skipped.
+pub struct OrgStore;
+
+const SESSIONS_HEADING: &str = "* Pomodoro Sessions";
+
+/// Section of the file the parser is in
+enum Section {
+    Preamble,
+    Task,
+    Skipped, // Under a heading that isn't a task
+    Sessions,
+}
+
+impl TodoStore for OrgStore {
+    fn serialize(&self, items: &[TodoItem], pomodoro_sessions: &[PomodoroSession]) -> String {
+        let mut content = String::from("#+TITLE: TODO List\n\n");
+        // Org keeps no ids, so tasks others wait for carry theirs for the link to survive a reload
+        let blockers: HashSet<u64> = items.iter().filter_map(|item| item.blocked_by).collect();
+
+        for item in items {
+            let stars = if item.parent_id.is_some() { "**" } else { "*" };
+            let keyword = if item.done { "DONE" } else { "TODO" };
+            let cookie = item.priority.map(|priority| format!(" [#{}]", priority_letter(priority))).unwrap_or_default();
+            let (title, tags) = split_tags(&item.task);
+            let tags = if tags.is_empty() { String::new() } else { format!(" :{}:", tags.join(":")) };
+            content.push_str(&format!("{} {}{} {}{}\n", stars, keyword, cookie, title, tags));
+            // Planning line: when the task was closed and the day it is scheduled for
+            let mut planning = Vec::new();
+            if let Some(completed_at) = item.completed_at {
+                planning.push(format!("CLOSED: {}", org_timestamp(completed_at)));
+            }
+            if let Some(day) = item.scheduled_for {
+                planning.push(format!("SCHEDULED: {}", day.format("<%Y-%m-%d %a>")));
+            }
+            if !planning.is_empty() {
+                content.push_str(&format!("{}\n", planning.join(" ")));
+            }
+
+            let mut properties = Vec::new();
+            if item.focused_time > 0 {
+                properties.push(format!(":FOCUSED_MINUTES: {}", item.focused_time));
+            }
+            if let Some(minutes) = item.session_minutes {
+                properties.push(format!(":SESSION_MINUTES: {}", minutes));
+            }
+            if let Some(estimate) = item.estimate {
+                properties.push(format!(":ESTIMATE: {}", estimate));
+            }
+            if let Some(ref repeat) = item.repeat {
+                properties.push(format!(":REPEAT: {}", repeat.spec()));
+            }
+            if let Some(ref uuid) = item.uuid {
+                properties.push(format!(":ID: {}", uuid));
+            }
+            if item.starred {
+                properties.push(":STARRED: t".to_string());
+            }
+            if blockers.contains(&item.id) {
+                properties.push(format!(":SESSIO_ID: {}", item.id));
+            }
+            if let Some(blocker_id) = item.blocked_by {
+                properties.push(format!(":BLOCKED_BY: {}", blocker_id));
+            }
+            properties.push(format!(":CREATED: {}", org_timestamp(item.created_at)));
+            push_drawer(&mut content, "PROPERTIES", &properties);
+
+            // Newest first, like Emacs adds them
+            let mut logbook: Vec<String> = item.completions.iter().rev()
+                .map(|completed| format!("- State {:<12} from {:<12} {}", "\"DONE\"", "\"TODO\"", org_timestamp(*completed)))
+                .collect();
+            logbook.extend(item.timeline.iter().rev().map(|session| {
+                let start = session.timestamp - Duration::minutes(session.minutes as i64);
+                format!("CLOCK: {}--{} => {:>2}:{:02}",
+                    org_timestamp(start), org_timestamp(session.timestamp), session.minutes / 60, session.minutes % 60)
+            }));
+            push_drawer(&mut content, "LOGBOOK", &logbook);
+
+            for note_line in item.notes.lines() {
+                // A line starting with '*' would read back as a heading
+                let escape = if note_line.starts_with('*') || note_line.starts_with(',') { "," } else { "" };
+                content.push_str(&format!("{}{}\n", escape, note_line));
+            }
+        }
+
+        if !pomodoro_sessions.is_empty() {
+            content.push_str(SESSIONS_HEADING);
+            content.push('\n');
+            for session in pomodoro_sessions {
+                content.push_str(&format!("** {}\n", session.date.format("%Y-%m-%d")));
+                let mut properties = vec![
+                    format!(":WORK_SESSIONS: {}", session.work_sessions),
+                    format!(":WORK_MINUTES: {}", session.total_work_minutes),
+                    format!(":BREAK_SESSIONS: {}", session.break_sessions),
+                    format!(":BREAK_MINUTES: {}", session.total_break_minutes),
+                ];
+                if !session.ratings.is_empty() {
+                    let ratings: Vec<String> = session.ratings.iter().map(|r| r.to_string()).collect();
+                    properties.push(format!(":FOCUS_RATINGS: {}", ratings.join(" ")));
+                }
+                push_drawer(&mut content, "PROPERTIES", &properties);
+
+                if !session.tasks_worked_on.is_empty() {
+                    content.push_str("Tasks worked on:\n");
+                    for task in &session.tasks_worked_on {
+                        content.push_str(&format!("- {}\n", task));
+                    }
+                }
+                if !session.entries.is_empty() {
+                    content.push_str("Log:\n");
+                    for entry in &session.entries {
+                        content.push_str(&format!("- {}\n", entry.to_log_line()));
+                    }
+                }
+            }
+        }
+
+        content
+    }
+
+    fn parse(&self, content: &str) -> StoredTodos {
+        let mut items: Vec<TodoItem> = Vec::new();
+        let mut is_subtask = Vec::new();
+        let mut pomodoro_sessions: Vec<PomodoroSession> = Vec::new();
+
+        let mut section = Section::Preamble;
+        let mut in_drawer = false;
+        let mut in_session_log = false; // Whether "- " lines of a day belong to the log or the task list
+        let mut has_parent = false; // Whether a level-2 task has a task above it to belong to
+
+        for line in content.lines() {
+            if let Some((level, heading)) = parse_heading(line) {
+                in_drawer = false;
+                if line == SESSIONS_HEADING {
+                    section = Section::Sessions;
+                } else if matches!(section, Section::Sessions) && level > 1 {
+                    if let Ok(date) = NaiveDate::parse_from_str(heading.trim(), "%Y-%m-%d") {
+                        pomodoro_sessions.push(empty_session(date));
+                    }
+                    in_session_log = false;
+                } else if let Some(item) = parse_task_heading(heading) {
+                    let subtask = level > 1 && has_parent;
+                    has_parent |= level == 1;
+                    items.push(item);
+                    is_subtask.push(subtask);
+                    section = Section::Task;
+                } else {
+                    if level == 1 {
+                        has_parent = false;
+                    }
+                    section = Section::Skipped;
+                }
+                continue;
+            }
+
+            let trimmed = line.trim();
+            if trimmed.starts_with(':') && trimmed.ends_with(':') && trimmed.len() > 1 && !trimmed.contains(' ') {
+                // Drawer start (":PROPERTIES:") or end (":END:")
+                in_drawer = trimmed != ":END:";
+                continue;
+            }
+
+            match section {
+                Section::Task => {
+                    let Some(item) = items.last_mut() else {
+                        continue;
+                    };
+                    if in_drawer {
+                        parse_task_drawer_line(item, trimmed);
+                    } else if item.notes.is_empty() && parse_planning_line(item, trimmed) {
+                        // The planning line right under the heading
+                    } else {
+                        let note_line = line.strip_prefix(',').filter(|rest| rest.starts_with(['*', ','])).unwrap_or(line);
+                        if !item.notes.is_empty() || !note_line.trim().is_empty() {
+                            item.notes.push_str(note_line.trim_end());
+                            item.notes.push('\n');
+                        }
+                    }
+                }
+                Section::Sessions => {
+                    let Some(session) = pomodoro_sessions.last_mut() else {
+                        continue;
+                    };
+                    if in_drawer {
+                        parse_session_property(session, trimmed);
+                    } else if trimmed == "Tasks worked on:" {
+                        in_session_log = false;
+                    } else if trimmed == "Log:" {
+                        in_session_log = true;
+                    } else if let Some(value) = trimmed.strip_prefix("- ") {
+                        if in_session_log {
+                            // Malformed log lines are skipped
+                            if let Some(entry) = SessionEntry::from_log_line(session.date, value) {
+                                session.entries.push(entry);
+                            }
+                        } else {
+                            session.tasks_worked_on.push(value.to_string());
+                        }
+                    }
+                }
+                Section::Preamble | Section::Skipped => {}
+            }
+        }
+
+        for item in items.iter_mut() {
+            let notes_end = item.notes.trim_end_matches('\n').len();
+            item.notes.truncate(notes_end);
+            item.completions.sort();
+            item.timeline.sort_by_key(|session| session.date);
+        }
+
+        StoredTodos { items, is_subtask, pomodoro_sessions }
+    }
+}
+
+/// ":NAME:" drawer with the given lines; nothing when there are none
+fn push_drawer(content: &mut String, name: &str, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+    content.push_str(&format!(":{}:\n", name));
+    for line in lines {
+        content.push_str(line);
+        content.push('\n');
+    }
+    content.push_str(":END:\n");
+}
+
+fn priority_letter(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+/// Task text without its #tag words, and those tags. Tags org can't hold (e.g. with a '-')
+/// stay in the text, as does everything when the text is nothing but tags.
+fn split_tags(task: &str) -> (String, Vec<&str>) {
+    let is_org_tag = |word: &str| {
+        tag_of(word).is_some_and(|tag| tag.len() + 1 == word.len() && tag.chars().all(|c| c.is_alphanumeric() || c == '_'))
+    };
+    let (tags, words): (Vec<&str>, Vec<&str>) = task.split_whitespace().partition(|word| is_org_tag(word));
+    if tags.is_empty() || words.is_empty() {
+        return (task.to_string(), Vec::new());
+    }
+    (words.join(" "), tags.iter().map(|tag| &tag[1..]).collect())
+}
+
+/// Level (number of stars) and text of a heading line
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.find(|c: char| c != '*')?;
+    let text = line[level..].strip_prefix(' ')?;
+    (level > 0).then_some((level, text))
+}
+
+/// A task from "TODO [#A] Title :tag:other:"; None for a heading without TODO/DONE
+fn parse_task_heading(heading: &str) -> Option<TodoItem> {
+    let (keyword, rest) = heading.split_once(' ').unwrap_or((heading, ""));
+    let done = match keyword {
+        "TODO" => false,
+        "DONE" => true,
+        _ => return None,
+    };
+
+    let mut rest = rest.trim();
+    let mut priority = None;
+    for (cookie, level) in [("[#A]", Priority::High), ("[#B]", Priority::Medium), ("[#C]", Priority::Low)] {
+        if let Some(after) = rest.strip_prefix(cookie) {
+            priority = Some(level);
+            rest = after.trim_start();
+        }
+    }
+
+    let mut tags = Vec::new();
+    if let Some((title, last)) = rest.rsplit_once(' ').or(Some(("", rest))) {
+        let inner = last.strip_prefix(':').and_then(|l| l.strip_suffix(':'));
+        if let Some(inner) = inner.filter(|inner| {
+            !inner.is_empty() && inner.split(':').all(|tag| !tag.is_empty() && tag.chars().all(|c| c.is_alphanumeric() || "_@#%".contains(c)))
+        }) {
+            tags = inner.split(':').collect();
+            rest = title.trim_end();
+        }
+    }
+
+    let mut item = TodoItem::new(rest.to_string());
+    item.done = done;
+    item.priority = priority;
+    for tag in tags {
+        if !item.has_tag(tag) {
+            if !item.task.is_empty() {
+                item.task.push(' ');
+            }
+            item.task.push('#');
+            item.task.push_str(tag);
+        }
+    }
+    Some(item)
+}
+
+/// A property, state change or CLOCK line inside one of a task's drawers
+fn parse_task_drawer_line(item: &mut TodoItem, line: &str) {
+    let number = |value: &str| value.trim().parse::<u32>().ok().filter(|&n| n > 0);
+    if let Some(value) = line.strip_prefix(":FOCUSED_MINUTES:") {
+        item.focused_time = number(value).unwrap_or(0);
+    } else if let Some(value) = line.strip_prefix(":SESSION_MINUTES:") {
+        item.session_minutes = number(value);
+    } else if let Some(value) = line.strip_prefix(":ESTIMATE:") {
+        item.estimate = number(value);
+    } else if let Some(value) = line.strip_prefix(":REPEAT:") {
+        item.repeat = Recurrence::parse(value).ok();
+    } else if let Some(value) = line.strip_prefix(":ID:") {
+        item.uuid = Some(value.trim().to_string());
+    } else if let Some(created_at) = line.strip_prefix(":CREATED:").and_then(|value| parse_org_timestamp(value.trim())) {
+        item.created_at = created_at;
+    } else if let Some(value) = line.strip_prefix(":STARRED:") {
+        item.starred = value.trim() == "t";
+    } else if let Some(id) = line.strip_prefix(":SESSIO_ID:").and_then(|value| value.trim().parse().ok()) {
+        item.id = id;
+    } else if let Some(blocker_id) = line.strip_prefix(":BLOCKED_BY:").and_then(|value| value.trim().parse().ok()) {
+        item.blocked_by = Some(blocker_id);
+    } else if line.starts_with("- State \"DONE\"") {
+        if let Some(completed) = line.rfind('[').and_then(|start| parse_org_timestamp(&line[start..])) {
+            item.completions.push(completed);
+        }
+    } else if let Some(clock) = line.strip_prefix("CLOCK:").and_then(parse_clock) {
+        // Clocked time on a day that already has some adds up
+        match item.timeline.iter_mut().find(|session| session.date == clock.date) {
+            Some(session) => {
+                session.minutes += clock.minutes;
+                session.timestamp = session.timestamp.max(clock.timestamp);
+            }
+            None => item.timeline.push(clock),
+        }
+    }
+}
+
+/// "[start]--[end] =>  0:25"; a running clock (no end) is skipped
+fn parse_clock(value: &str) -> Option<WorkSession> {
+    let (start, rest) = value.trim().split_once("--")?;
+    let (end, duration) = rest.split_once("=>").unwrap_or((rest, ""));
+    let start = parse_org_timestamp(start)?;
+    let end = parse_org_timestamp(end)?;
+    let minutes = match duration.trim().split_once(':') {
+        Some((hours, minutes)) => hours.trim().parse::<u32>().ok()? * 60 + minutes.parse::<u32>().ok()?,
+        None => (end - start).num_minutes().max(0) as u32,
+    };
+    Some(WorkSession { date: end.date_naive(), minutes, timestamp: end })
+}
+
+fn parse_session_property(session: &mut PomodoroSession, line: &str) {
+    let number = |value: &str| value.trim().parse::<u32>().unwrap_or(0);
+    if let Some(value) = line.strip_prefix(":WORK_SESSIONS:") {
+        session.work_sessions = number(value);
+    } else if let Some(value) = line.strip_prefix(":WORK_MINUTES:") {
+        session.total_work_minutes = number(value);
+    } else if let Some(value) = line.strip_prefix(":BREAK_SESSIONS:") {
+        session.break_sessions = number(value);
+    } else if let Some(value) = line.strip_prefix(":BREAK_MINUTES:") {
+        session.total_break_minutes = number(value);
+    } else if let Some(value) = line.strip_prefix(":FOCUS_RATINGS:") {
+        session.ratings = value.split_whitespace()
+            .filter_map(|r| r.parse::<u8>().ok())
+            .filter(|r| (1..=5).contains(r))
+            .collect();
+    }
+}
+
+fn empty_session(date: NaiveDate) -> PomodoroSession {
+    PomodoroSession {
+        date,
+        work_sessions: 0,
+        total_work_minutes: 0,
+        break_sessions: 0,
+        total_break_minutes: 0,
+        tasks_worked_on: Vec::new(),
+        entries: Vec::new(),
+        ratings: Vec::new(),
+    }
+}
+
+/// Read a planning line such as "CLOSED: [2025-06-30 Mon 16:40] SCHEDULED: <2025-07-02 Wed>" into
+/// the task. A line with anything else on it (a DEADLINE, say) is left alone and returns false.
+fn parse_planning_line(item: &mut TodoItem, line: &str) -> bool {
+    let mut completed_at = None;
+    let mut scheduled_for = None;
+    let mut rest = line.trim();
+    while !rest.is_empty() {
+        let Some((keyword, after)) = rest.split_once(':') else {
+            return false;
+        };
+        let after = after.trim_start();
+        let Some(end) = after.find([']', '>']) else {
+            return false;
+        };
+        let Some(time) = parse_org_timestamp(&after[..=end]) else {
+            return false;
+        };
+        match keyword {
+            "CLOSED" => completed_at = Some(time),
+            "SCHEDULED" => scheduled_for = Some(time.date_naive()),
+            _ => return false,
+        }
+        rest = after[end + 1..].trim_start();
+    }
+    if completed_at.is_none() && scheduled_for.is_none() {
+        return false;
+    }
+    item.completed_at = completed_at.or(item.completed_at);
+    item.scheduled_for = scheduled_for.or(item.scheduled_for);
+    true
+}
+
+/// Inactive org timestamp, e.g. "[2025-06-30 Mon 16:40]"
+fn org_timestamp(time: DateTime<Local>) -> String {
+    time.format("[%Y-%m-%d %a %H:%M]").to_string()
+}
+
+/// "[2025-06-30 Mon 16:40]" (or an active "<...>" one); the weekday is optional and the time defaults to midnight
+fn parse_org_timestamp(value: &str) -> Option<DateTime<Local>> {
+    let value = value.trim();
+    let inner = value.strip_prefix('[').and_then(|v| v.strip_suffix(']'))
+        .or_else(|| value.strip_prefix('<').and_then(|v| v.strip_suffix('>')))?;
+    let mut parts = inner.split_whitespace();
+    let date = NaiveDate::parse_from_str(parts.next()?, "%Y-%m-%d").ok()?;
+    let time = parts.find_map(|part| NaiveTime::parse_from_str(part, "%H:%M").ok()).unwrap_or(NaiveTime::MIN);
+    Local.from_local_datetime(&date.and_time(time)).earliest()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::at;
+    use crate::timer::PomodoroPhase;
+
+    /// An org todo file as OrgStore writes it
+    const FIXTURE: &str = "\
+#+TITLE: TODO List
+
+* TODO [#A] Write report :work:deep:
+SCHEDULED: <2025-07-02 Wed>
+:PROPERTIES:
+:FOCUSED_MINUTES: 75
+:SESSION_MINUTES: 50
+:ESTIMATE: 4
+:SESSIO_ID: 1
+:CREATED: [2025-06-28 Sat 09:12]
+:END:
+:LOGBOOK:
+CLOCK: [2025-06-30 Mon 16:15]--[2025-06-30 Mon 16:40] =>  0:25
+CLOCK: [2025-06-29 Sun 09:10]--[2025-06-29 Sun 10:00] =>  0:50
+:END:
+Outline first
+,* then details
+** TODO Collect numbers
+:PROPERTIES:
+:CREATED: [2025-06-28 Sat 09:20]
+:END:
+** DONE [#C] Ask Sam for the draft
+:PROPERTIES:
+:CREATED: [2025-06-29 Sun 11:00]
+:END:
+* DONE Water plants
+CLOSED: [2025-06-30 Mon 08:00]
+:PROPERTIES:
+:REPEAT: every mon,thu
+:ID: 6f1c2a9e-4b7d-4e0a-9c3b-2d5e8f7a1b40
+:CREATED: [2025-06-01 Sun 07:30]
+:END:
+:LOGBOOK:
+- State \"DONE\"       from \"TODO\"       [2025-06-30 Mon 08:00]
+- State \"DONE\"       from \"TODO\"       [2025-06-26 Thu 08:05]
+:END:
+* TODO Fix #bug-123 in parser
+:PROPERTIES:
+:BLOCKED_BY: 1
+:CREATED: [2025-06-30 Mon 12:00]
+:END:
+* Pomodoro Sessions
+** 2025-06-30
+:PROPERTIES:
+:WORK_SESSIONS: 1
+:WORK_MINUTES: 25
+:BREAK_SESSIONS: 1
+:BREAK_MINUTES: 5
+:FOCUS_RATINGS: 4
+:END:
+Tasks worked on:
+- Write report #work #deep
+Log:
+- 16:15 Work 25m (Write report #work #deep): drafted intro
+- 16:40 Short break 5m
+";
+
+    fn serialize(stored: &StoredTodos) -> String {
+        // Link subtasks the way Todo does once ids are handed out
+        let mut items = stored.items.clone();
+        let mut parent_id = None;
+        for (index, (item, &is_subtask)) in items.iter_mut().zip(&stored.is_subtask).enumerate() {
+            item.id = index as u64 + 1;
+            if is_subtask {
+                item.parent_id = parent_id;
+            } else {
+                parent_id = Some(item.id);
+            }
+        }
+        OrgStore.serialize(&items, &stored.pomodoro_sessions)
+    }
+
+    #[test]
+    fn test_org_fixture_round_trip() {
+        let stored = OrgStore.parse(FIXTURE);
+        assert_eq!(serialize(&stored), FIXTURE);
+        assert_eq!(serialize(&OrgStore.parse(&serialize(&stored))), FIXTURE);
+    }
+
+    #[test]
+    fn test_parse_org_fixture() {
+        let stored = OrgStore.parse(FIXTURE);
+        let tasks: Vec<&str> = stored.items.iter().map(|item| item.task.as_str()).collect();
+        assert_eq!(tasks, ["Write report #work #deep", "Collect numbers", "Ask Sam for the draft", "Water plants", "Fix #bug-123 in parser"]);
+        assert_eq!(stored.is_subtask, [false, true, true, false, false]);
+
+        let report = &stored.items[0];
+        assert_eq!((report.done, report.priority, report.focused_time), (false, Some(Priority::High), 75));
+        assert_eq!((report.session_minutes, report.estimate), (Some(50), Some(4)));
+        assert_eq!(report.created_at, at(2025, 6, 28, 9, 12));
+        assert_eq!(report.scheduled_for, Some(at(2025, 7, 2, 0, 0).date_naive()));
+        assert_eq!(report.tags(), ["work", "deep"]);
+        assert_eq!(report.notes, "Outline first\n* then details");
+        assert_eq!(report.timeline, [
+            WorkSession { date: at(2025, 6, 29, 0, 0).date_naive(), minutes: 50, timestamp: at(2025, 6, 29, 10, 0) },
+            WorkSession { date: at(2025, 6, 30, 0, 0).date_naive(), minutes: 25, timestamp: at(2025, 6, 30, 16, 40) },
+        ]);
+
+        let plants = &stored.items[3];
+        assert!(plants.done);
+        assert_eq!(plants.repeat, Recurrence::parse("every mon,thu").ok());
+        assert_eq!(plants.uuid.as_deref(), Some("6f1c2a9e-4b7d-4e0a-9c3b-2d5e8f7a1b40"));
+        assert_eq!(plants.completions, [at(2025, 6, 26, 8, 5), at(2025, 6, 30, 8, 0)]);
+        assert_eq!(plants.completed_at, Some(at(2025, 6, 30, 8, 0)));
+        assert_eq!(plants.scheduled_for, None);
+        assert_eq!((report.id, stored.items[4].blocked_by), (1, Some(1)));
+        assert_eq!(stored.items[2].completed_at, None);
+        assert_eq!(stored.items[2].priority, Some(Priority::Low));
+
+        let session = &stored.pomodoro_sessions[0];
+        assert_eq!((session.work_sessions, session.total_work_minutes, session.break_sessions, session.total_break_minutes), (1, 25, 1, 5));
+        assert_eq!(session.ratings, [4]);
+        assert_eq!(session.tasks_worked_on, ["Write report #work #deep"]);
+        assert_eq!(session.entries[0].note.as_deref(), Some("drafted intro"));
+        assert_eq!(session.entries[1].phase, PomodoroPhase::ShortBreak);
+    }
+
+    #[test]
+    fn test_parse_org_written_by_emacs() {
+        let content = "\
+#+STARTUP: overview
+Some text before the first heading
+* Projects
+** TODO Not a subtask of a task
+* TODO Review PR :code:
+  CLOSED: [2025-07-01 Tue 12:00] SCHEDULED: <2025-07-01 Tue>
+  :LOGBOOK:
+  CLOCK: [2025-07-01 Tue 14:00]
+  CLOCK: [2025-07-01 Tue 11:00]--[2025-07-01 Tue 11:30] =>  0:30
+  CLOCK: [2025-07-01 Tue 09:00]--[2025-07-01 Tue 10:15] =>  1:15
+  :END:
+  Check the tests first
+** TODO [#B] Reply to comments
+   DEADLINE: <2025-07-04 Fri>
+";
+        let stored = OrgStore.parse(content);
+        let tasks: Vec<&str> = stored.items.iter().map(|item| item.task.as_str()).collect();
+        assert_eq!(tasks, ["Not a subtask of a task", "Review PR #code", "Reply to comments"]);
+        assert_eq!(stored.is_subtask, [false, false, true]);
+
+        // Clocks of one day add up; the running one is skipped
+        let review = &stored.items[1];
+        assert_eq!(review.timeline, [WorkSession { date: at(2025, 7, 1, 0, 0).date_naive(), minutes: 105, timestamp: at(2025, 7, 1, 11, 30) }]);
+        assert_eq!(review.notes, "  Check the tests first");
+        assert_eq!((review.completed_at, review.scheduled_for), (Some(at(2025, 7, 1, 12, 0)), Some(at(2025, 7, 1, 0, 0).date_naive())));
+        assert_eq!(stored.items[2].priority, Some(Priority::Medium));
+        // Deadlines aren't tracked, so that planning line is kept as a note
+        assert_eq!(stored.items[2].notes, "   DEADLINE: <2025-07-04 Fri>");
+    }
+
+    #[test]
+    fn test_split_tags() {
+        assert_eq!(split_tags("#deep Write talk #work"), ("Write talk".to_string(), vec!["deep", "work"]));
+        assert_eq!(split_tags("Fix #bug-123 and #this, now"), ("Fix #bug-123 and #this, now".to_string(), vec![]));
+        assert_eq!(split_tags("#idea"), ("#idea".to_string(), vec![]));
+        assert_eq!(split_tags("写报告 #工作"), ("写报告".to_string(), vec!["工作"]));
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// Counts that changed are written at most this often while sessio runs, and on the way out
+const SAVE_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayCount {
+    pub plays: u32,
+    pub last_played: DateTime<Local>,
+}
+
+/// How often each music file was played, kept in ~/.local/share/sessio/play_counts.json.
+/// Files go by where they really are, so a rescan (or a symlinked folder) finds their counts
+/// again; counts of files that are gone are kept, in case they come back.
+pub struct PlayCounts {
+    counts: HashMap<PathBuf, PlayCount>,
+    path: Option<PathBuf>,
+    dirty: bool, // Plays not written to the file yet
+    saved_at: Instant,
+}
+
+impl PlayCounts {
+    /// The counts in the file at `path`; none when it's missing or can't be read
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let counts = path.as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { counts, path, dirty: false, saved_at: Instant::now() }
+    }
+
+    /// The usual place for the file
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("sessio").join("play_counts.json"))
+    }
+
+    pub fn plays(&self, real_path: &Path) -> u32 {
+        self.counts.get(real_path).map_or(0, |count| count.plays)
+    }
+
+    /// Count a play of the file at `real_path`; the new count
+    pub fn record(&mut self, real_path: &Path, now: DateTime<Local>) -> u32 {
+        let count = self.counts.entry(real_path.to_path_buf()).or_insert(PlayCount { plays: 0, last_played: now });
+        count.plays += 1;
+        count.last_played = now;
+        self.dirty = true;
+        count.plays
+    }
+
+    /// Write the counts once they changed and the last write is long enough ago
+    pub fn save_if_due(&mut self) {
+        if self.dirty && self.saved_at.elapsed() >= SAVE_INTERVAL {
+            // Tried again next time round when it fails
+            let _ = self.save();
+        }
+    }
+
+    /// Write the counts if they changed
+    pub fn save(&mut self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+        self.saved_at = Instant::now();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.counts)?)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_counts_round_trip() {
+        let dir = std::env::temp_dir().join(format!("sessio-plays-{}", std::process::id()));
+        let path = dir.join("play_counts.json");
+        let mut counts = PlayCounts::load(Some(path.clone()));
+        assert_eq!(counts.plays(Path::new("/music/a.mp3")), 0);
+        counts.record(Path::new("/music/a.mp3"), Local::now());
+        assert_eq!(counts.record(Path::new("/music/a.mp3"), Local::now()), 2);
+        counts.record(Path::new("/music/gone.mp3"), Local::now());
+
+        // Not written on every play, only when due (or asked to)
+        counts.save_if_due();
+        assert!(!path.exists());
+        counts.saved_at -= SAVE_INTERVAL;
+        counts.save_if_due();
+        let loaded = PlayCounts::load(Some(path.clone()));
+        assert_eq!(loaded.plays(Path::new("/music/a.mp3")), 2);
+        assert_eq!(loaded.plays(Path::new("/music/gone.mp3")), 1);
+
+        // A broken file starts over rather than failing
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(PlayCounts::load(Some(path)).plays(Path::new("/music/a.mp3")), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
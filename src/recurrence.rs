@@ -0,0 +1,137 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// How soon a recurring task comes back after it was completed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    /// Every n days: "daily", "every day", "every 3 days"
+    Days(u32),
+    /// Every n weeks: "weekly", "every week", "every 2 weeks"
+    Weeks(u32),
+    /// On the next of these weekdays: "every mon,wed,fri"
+    Weekdays(Vec<Weekday>),
+}
+
+impl Recurrence {
+    /// Parse a spec such as "daily", "every 2 weeks" or "every mon,wed,fri" (the "every" is optional)
+    pub fn parse(value: &str) -> std::result::Result<Self, String> {
+        let spec = value.trim().to_lowercase();
+        let spec = spec.strip_prefix("every ").unwrap_or(&spec).trim();
+        let invalid = || format!("invalid repeat \"{}\": expected e.g. \"daily\", \"every 2 weeks\" or \"every mon,wed,fri\"", value.trim());
+
+        match spec {
+            "daily" | "day" => return Ok(Recurrence::Days(1)),
+            "weekly" | "week" => return Ok(Recurrence::Weeks(1)),
+            _ => {}
+        }
+
+        if let Some((count, unit)) = spec.split_once(' ').filter(|(count, _)| count.parse::<i64>().is_ok()) {
+            let count = count.parse::<u32>().ok().filter(|&n| n > 0).ok_or_else(invalid)?;
+            return match unit.trim() {
+                "day" | "days" => Ok(Recurrence::Days(count)),
+                "week" | "weeks" => Ok(Recurrence::Weeks(count)),
+                _ => Err(invalid()),
+            };
+        }
+
+        let mut weekdays = Vec::new();
+        for day in spec.split(',') {
+            let weekday = day.trim().parse::<Weekday>().map_err(|_| invalid())?;
+            if !weekdays.contains(&weekday) {
+                weekdays.push(weekday);
+            }
+        }
+        weekdays.sort_by_key(|day| day.num_days_from_monday());
+        Ok(Recurrence::Weekdays(weekdays))
+    }
+
+    /// The spec as written to todos.md; parses back to the same recurrence
+    pub fn spec(&self) -> String {
+        match self {
+            Recurrence::Days(1) => "daily".to_string(),
+            Recurrence::Days(n) => format!("every {} days", n),
+            Recurrence::Weeks(1) => "weekly".to_string(),
+            Recurrence::Weeks(n) => format!("every {} weeks", n),
+            Recurrence::Weekdays(days) => {
+                let days: Vec<String> = days.iter().map(|day| day.to_string().to_lowercase()).collect();
+                format!("every {}", days.join(","))
+            }
+        }
+    }
+
+    /// First day after `completed` on which the task is due again
+    pub fn next_after(&self, completed: NaiveDate) -> NaiveDate {
+        match self {
+            Recurrence::Days(n) => completed + Duration::days(*n as i64),
+            Recurrence::Weeks(n) => completed + Duration::weeks(*n as i64),
+            Recurrence::Weekdays(days) => (1..=7)
+                .map(|offset| completed + Duration::days(offset))
+                .find(|date| days.contains(&date.weekday()))
+                .unwrap_or(completed + Duration::days(7)),
+        }
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> Self {
+        recurrence.spec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_recurrence() {
+        assert_eq!(Recurrence::parse("daily"), Ok(Recurrence::Days(1)));
+        assert_eq!(Recurrence::parse("every day"), Ok(Recurrence::Days(1)));
+        assert_eq!(Recurrence::parse("Every 3 Days"), Ok(Recurrence::Days(3)));
+        assert_eq!(Recurrence::parse("weekly"), Ok(Recurrence::Weeks(1)));
+        assert_eq!(Recurrence::parse("every 2 weeks"), Ok(Recurrence::Weeks(2)));
+        assert_eq!(
+            Recurrence::parse("every fri, mon,wed,mon"),
+            Ok(Recurrence::Weekdays(vec![Weekday::Mon, Weekday::Wed, Weekday::Fri]))
+        );
+
+        for invalid in ["", "every", "sometimes", "every 0 days", "every -1 weeks", "every 2 months", "mon,funday"] {
+            assert!(Recurrence::parse(invalid).is_err(), "{:?} should not parse", invalid);
+        }
+    }
+
+    #[test]
+    fn test_recurrence_spec_round_trip() {
+        for spec in ["daily", "every 3 days", "weekly", "every 2 weeks", "every mon,wed,fri"] {
+            let recurrence = Recurrence::parse(spec).unwrap();
+            assert_eq!(recurrence.spec(), spec);
+            assert_eq!(Recurrence::parse(&recurrence.spec()), Ok(recurrence));
+        }
+    }
+
+    #[test]
+    fn test_next_occurrence() {
+        // 2026-10-16 is a Friday
+        let friday = date(2026, 10, 16);
+        assert_eq!(Recurrence::Days(1).next_after(friday), date(2026, 10, 17));
+        assert_eq!(Recurrence::Days(3).next_after(friday), date(2026, 10, 19));
+        assert_eq!(Recurrence::Weeks(2).next_after(friday), date(2026, 10, 30));
+
+        let mon_wed_fri = Recurrence::parse("every mon,wed,fri").unwrap();
+        assert_eq!(mon_wed_fri.next_after(friday), date(2026, 10, 19));
+        assert_eq!(mon_wed_fri.next_after(date(2026, 10, 19)), date(2026, 10, 21));
+        assert_eq!(Recurrence::parse("every fri").unwrap().next_after(friday), date(2026, 10, 23));
+    }
+}
@@ -0,0 +1,46 @@
+// Without MPRIS nothing sends commands yet, and nobody asks what is playing
+#![cfg_attr(not(all(feature = "mpris", target_os = "linux")), allow(dead_code))]
+
+use std::time::Duration;
+
+/// What media keys (or other programs) can ask of the music player, carried out by the main loop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemoteCommand {
+    PlayPause,
+    Play,
+    Pause,
+    Stop,
+    Next,
+    Previous,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    #[default]
+    Stopped,
+}
+
+impl PlaybackStatus {
+    /// As MPRIS spells it
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlaybackStatus::Playing => "Playing",
+            PlaybackStatus::Paused => "Paused",
+            PlaybackStatus::Stopped => "Stopped",
+        }
+    }
+}
+
+/// The music player as shown to other programs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NowPlaying {
+    pub status: PlaybackStatus,
+    pub track: Option<usize>, // Where the current track sits in the list; None when there is none
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub url: Option<String>, // file:// for music files, the stream's own url for radio
+    pub length: Option<Duration>,
+}
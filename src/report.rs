@@ -0,0 +1,308 @@
+use chrono::{Datelike, Months, NaiveDate};
+use color_eyre::Result;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::journal::expand_path;
+use crate::timer::{format_hours_minutes, PomodoroSession};
+use crate::todo::{write_atomically, TodoItem};
+
+/// The days a report covers: one day, a week from its Monday, or a month from its 1st
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportPeriod {
+    Day(NaiveDate),
+    Week(NaiveDate),
+    Month(NaiveDate),
+}
+
+impl ReportPeriod {
+    /// The week `date` is in
+    pub fn week_of(date: NaiveDate) -> Self {
+        ReportPeriod::Week(date - chrono::Duration::days(date.weekday().num_days_from_monday().into()))
+    }
+
+    pub fn first(&self) -> NaiveDate {
+        match *self {
+            ReportPeriod::Day(date) | ReportPeriod::Week(date) | ReportPeriod::Month(date) => date,
+        }
+    }
+
+    pub fn last(&self) -> NaiveDate {
+        match *self {
+            ReportPeriod::Day(date) => date,
+            ReportPeriod::Week(monday) => monday + chrono::Duration::days(6),
+            ReportPeriod::Month(first) => (first + Months::new(1)).pred_opt().expect("a day before the 1st"),
+        }
+    }
+
+    fn title(&self) -> String {
+        match *self {
+            ReportPeriod::Day(date) => date.format("%A, %-d %B %Y").to_string(),
+            ReportPeriod::Week(monday) => format!(
+                "week {} of {} ({} – {})",
+                monday.iso_week().week(),
+                monday.iso_week().year(),
+                monday.format("%a %-d %b"),
+                self.last().format("%a %-d %b"),
+            ),
+            ReportPeriod::Month(first) => first.format("%B %Y").to_string(),
+        }
+    }
+
+    /// "report-2026-10-16.md", "report-2026-W42.md" or "report-2026-10.md"
+    pub fn file_name(&self) -> String {
+        match *self {
+            ReportPeriod::Day(date) => format!("report-{}.md", date.format("%Y-%m-%d")),
+            ReportPeriod::Week(monday) => format!("report-{}.md", monday.format("%G-W%V")),
+            ReportPeriod::Month(first) => format!("report-{}.md", first.format("%Y-%m")),
+        }
+    }
+}
+
+/// A markdown report of the period up to `today`, e.g.
+///
+/// ```text
+/// # Focus report: week 42 of 2026 (Mon 12 Oct – Sun 18 Oct)
+///
+/// ## Totals
+///
+/// - Focus time: 3h 45m in 9 work sessions
+/// - Breaks: 0h 40m in 8 breaks
+/// - Days active: 3 of 5
+/// - Longest run of active days: 2
+/// - Current streak: 4 days
+/// - Tasks completed: 2
+///
+/// ## By day
+///
+/// | Day | Focus | Sessions | Breaks |
+/// |---|---:|---:|---:|
+/// | Mon 2026-10-12 | 2h 5m | 5 | 4 |
+/// ...
+/// ```
+///
+/// followed by the minutes per task (from the task timelines, like the journal) and the tasks
+/// completed. Days after `today` are left out; `streak` is the current streak in days.
+pub fn report(period: ReportPeriod, today: NaiveDate, items: &[TodoItem], sessions: &[PomodoroSession], streak: u32) -> String {
+    let first = period.first();
+    let last = period.last().min(today);
+    let days: Vec<NaiveDate> = first.iter_days().take_while(|&date| date <= last).collect();
+    let in_period = |date: NaiveDate| (first..=last).contains(&date);
+
+    // Work minutes, work sessions, break minutes and breaks of each day
+    let totals: Vec<(NaiveDate, [u32; 4])> = days.iter()
+        .map(|&date| {
+            let mut day = [0; 4];
+            for session in sessions.iter().filter(|session| session.date == date) {
+                day[0] += session.total_work_minutes;
+                day[1] += session.work_sessions;
+                day[2] += session.total_break_minutes;
+                day[3] += session.break_sessions;
+            }
+            (date, day)
+        })
+        .collect();
+    let sum = |i: usize| totals.iter().map(|(_, day)| day[i]).sum::<u32>();
+    let active: Vec<bool> = totals.iter().map(|(_, day)| day[0] > 0).collect();
+    let longest_run = active.split(|&is_active| !is_active).map(<[bool]>::len).max().unwrap_or(0);
+
+    let mut worked: Vec<(&str, u32)> = items.iter()
+        .filter_map(|item| {
+            let minutes: u32 = item.timeline.iter()
+                .filter(|session| in_period(session.date))
+                .map(|session| session.minutes)
+                .sum();
+            (minutes > 0).then_some((item.task.as_str(), minutes))
+        })
+        .collect();
+    worked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    // A recurring task may have been reset since, so its completion history counts too
+    let mut completed: Vec<(NaiveDate, &str)> = items.iter()
+        .flat_map(|item| {
+            item.completed_at.iter()
+                .chain(&item.completions)
+                .map(|completed| completed.date_naive())
+                .filter(|&date| in_period(date))
+                .map(|date| (date, item.task.as_str()))
+        })
+        .collect();
+    completed.sort();
+    completed.dedup();
+
+    let mut text = format!("# Focus report: {}\n\n## Totals\n\n", period.title());
+    text.push_str(&format!("- Focus time: {} in {} work sessions\n", format_hours_minutes(sum(0).into()), sum(1)));
+    text.push_str(&format!("- Breaks: {} in {} breaks\n", format_hours_minutes(sum(2).into()), sum(3)));
+    if days.len() > 1 {
+        text.push_str(&format!("- Days active: {} of {}\n", active.iter().filter(|&&is_active| is_active).count(), days.len()));
+        text.push_str(&format!("- Longest run of active days: {}\n", longest_run));
+    }
+    text.push_str(&format!("- Current streak: {} {}\n", streak, if streak == 1 { "day" } else { "days" }));
+    text.push_str(&format!("- Tasks completed: {}\n", completed.len()));
+
+    if days.len() > 1 {
+        text.push_str("\n## By day\n\n| Day | Focus | Sessions | Breaks |\n|---|---:|---:|---:|\n");
+        for (date, day) in &totals {
+            text.push_str(&format!("| {} | {} | {} | {} |\n", date.format("%a %Y-%m-%d"), format_hours_minutes(day[0].into()), day[1], day[3]));
+        }
+    }
+
+    text.push_str("\n## By task\n\n");
+    if worked.is_empty() {
+        text.push_str("No time recorded on tasks.\n");
+    } else {
+        text.push_str("| Task | Focus |\n|---|---:|\n");
+        for (task, minutes) in worked {
+            text.push_str(&format!("| {} | {} |\n", task.replace('|', "\\|"), format_hours_minutes(minutes.into())));
+        }
+    }
+
+    text.push_str("\n## Completed\n\n");
+    if completed.is_empty() {
+        text.push_str("No tasks completed.\n");
+    } else {
+        for (date, task) in completed {
+            text.push_str(&format!("- {} ({})\n", task, date.format("%a %Y-%m-%d")));
+        }
+    }
+    text
+}
+
+/// Write the report for `period` into `directory` (~ is expanded), or into ~/.config/sessio/reports;
+/// an earlier report for the same period is replaced. Returns the file written.
+pub fn write(period: ReportPeriod, directory: Option<&str>, content: &str) -> Result<PathBuf> {
+    let directory = match directory {
+        Some(directory) => expand_path(directory),
+        None => default_directory()?,
+    };
+    fs::create_dir_all(&directory)?;
+    let path = directory.join(period.file_name());
+    write_atomically(&path, content)?;
+    Ok(path)
+}
+
+fn default_directory() -> Result<PathBuf> {
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| color_eyre::eyre::eyre!("Could not find config directory"))?;
+    Ok(config_dir.join("sessio").join("reports"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{at, empty_session};
+    use crate::todo::WorkSession;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    fn items() -> Vec<TodoItem> {
+        let work = |d, minutes| WorkSession { date: day(d), minutes, timestamp: at(2026, 10, d, 9, 0) };
+        let mut report = TodoItem::new("Write report | draft".to_string());
+        report.timeline = vec![work(9, 50), work(12, 100), work(13, 25)];
+        let mut outline = TodoItem::new("Outline".to_string());
+        outline.done = true;
+        outline.completed_at = Some(at(2026, 10, 13, 11, 0));
+        outline.timeline = vec![work(12, 25)];
+        let mut plants = TodoItem::new("Water plants".to_string());
+        plants.completions = vec![at(2026, 10, 11, 8, 0), at(2026, 10, 12, 8, 0)];
+        vec![report, outline, plants]
+    }
+
+    fn sessions() -> Vec<PomodoroSession> {
+        let session = |d, work_sessions, total_work_minutes, break_sessions, total_break_minutes| PomodoroSession {
+            work_sessions,
+            total_work_minutes,
+            break_sessions,
+            total_break_minutes,
+            ..empty_session(day(d))
+        };
+        vec![session(9, 2, 50, 2, 10), session(12, 5, 125, 4, 20), session(13, 1, 25, 0, 0), session(13, 0, 0, 1, 5)]
+    }
+
+    #[test]
+    fn test_report_periods() {
+        let week = ReportPeriod::week_of(day(14));
+        assert_eq!(week, ReportPeriod::Week(day(12)));
+        assert_eq!(week.last(), day(18));
+        assert_eq!(week.file_name(), "report-2026-W42.md");
+        assert_eq!(ReportPeriod::Month(day(1)).last(), day(31));
+        assert_eq!(ReportPeriod::Month(day(1)).file_name(), "report-2026-10.md");
+        assert_eq!(ReportPeriod::Day(day(9)).file_name(), "report-2026-10-09.md");
+    }
+
+    #[test]
+    fn test_week_report() {
+        // Wednesday: the rest of the week isn't there yet
+        assert_eq!(report(ReportPeriod::week_of(day(14)), day(14), &items(), &sessions(), 2), "\
+# Focus report: week 42 of 2026 (Mon 12 Oct – Sun 18 Oct)
+
+## Totals
+
+- Focus time: 2h 30m in 6 work sessions
+- Breaks: 0h 25m in 5 breaks
+- Days active: 2 of 3
+- Longest run of active days: 2
+- Current streak: 2 days
+- Tasks completed: 2
+
+## By day
+
+| Day | Focus | Sessions | Breaks |
+|---|---:|---:|---:|
+| Mon 2026-10-12 | 2h 5m | 5 | 4 |
+| Tue 2026-10-13 | 0h 25m | 1 | 1 |
+| Wed 2026-10-14 | 0h 0m | 0 | 0 |
+
+## By task
+
+| Task | Focus |
+|---|---:|
+| Write report \\| draft | 2h 5m |
+| Outline | 0h 25m |
+
+## Completed
+
+- Water plants (Mon 2026-10-12)
+- Outline (Tue 2026-10-13)
+");
+    }
+
+    #[test]
+    fn test_day_report() {
+        // A single day has no table of days, and a quiet one says so
+        assert_eq!(report(ReportPeriod::Day(day(10)), day(14), &items(), &sessions(), 0), "\
+# Focus report: Saturday, 10 October 2026
+
+## Totals
+
+- Focus time: 0h 0m in 0 work sessions
+- Breaks: 0h 0m in 0 breaks
+- Current streak: 0 days
+- Tasks completed: 0
+
+## By task
+
+No time recorded on tasks.
+
+## Completed
+
+No tasks completed.
+");
+        let month = report(ReportPeriod::Month(day(1)), day(14), &items(), &sessions(), 1);
+        assert!(month.starts_with("# Focus report: October 2026\n"));
+        assert!(month.contains("- Days active: 3 of 14\n- Longest run of active days: 2\n- Current streak: 1 day\n- Tasks completed: 3\n"));
+    }
+
+    #[test]
+    fn test_write_report() {
+        let dir = std::env::temp_dir().join(format!("sessio-reports-{}", std::process::id()));
+        let period = ReportPeriod::Day(day(12));
+        let path = write(period, Some(&dir.join("reports").to_string_lossy()), "first").unwrap();
+        assert_eq!(path, dir.join("reports").join("report-2026-10-12.md"));
+        // Written again, the report replaces the earlier one
+        write(period, Some(&dir.join("reports").to_string_lossy()), "second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
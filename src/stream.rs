@@ -0,0 +1,221 @@
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use rodio::{Decoder, Sink};
+
+/// Where an internet stream has got to, shown in the track list's title
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamState {
+    Connecting,
+    Live,
+    Failed(String),
+    Stopped,
+}
+
+/// The stream is downloaded ahead in chunks of this size, up to BUFFERED_CHUNKS of them
+/// (about a minute of a 128 kbit/s radio stream)
+const CHUNK_SIZE: usize = 16 * 1024;
+const BUFFERED_CHUNKS: usize = 64;
+/// The start of the stream is kept, so the decoder can go back while it works out the format
+const HEAD_SIZE: u64 = 256 * 1024;
+/// A station that sends nothing for this long (without hanging up) has failed. The wait happens
+/// in the audio output, which plays nothing else meanwhile; ureq's body timeout can't be used
+/// for this, as it limits the whole body and a stream never ends.
+const STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connect to `url` and play it on `sink`, keeping `state` up to date. Meant for a thread of its own,
+/// since connecting takes a moment; a stream stopped meanwhile is dropped once connected.
+pub fn play(url: &str, sink: &Mutex<Sink>, state: Arc<Mutex<StreamState>>) {
+    let source = connect(url, Arc::clone(&state))
+        .and_then(|reader| Decoder::new(reader).map_err(|e| format!("Can't play stream: {}", e)));
+    let Ok(mut current) = state.lock() else {
+        return;
+    };
+    if *current != StreamState::Connecting {
+        return;
+    }
+    match source {
+        Ok(source) => {
+            if let Ok(sink) = sink.lock() {
+                sink.append(source);
+                sink.play();
+                *current = StreamState::Live;
+            }
+        }
+        Err(message) => *current = StreamState::Failed(message),
+    }
+}
+
+/// Open the HTTP stream at `url`, downloading it on a thread of its own
+fn connect(url: &str, state: Arc<Mutex<StreamState>>) -> Result<StreamReader, String> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_connect(Some(Duration::from_secs(10)))
+        .timeout_recv_response(Some(Duration::from_secs(10)))
+        .build()
+        .into();
+    let response = agent.get(url).call().map_err(|e| format!("Can't connect: {}", e))?;
+    let mut body = response.into_body().into_reader();
+
+    let (sender, receiver) = mpsc::sync_channel(BUFFERED_CHUNKS);
+    thread::spawn(move || loop {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let result = body.read(&mut chunk).map(|length| {
+            chunk.truncate(length);
+            chunk
+        });
+        let finished = !matches!(&result, Ok(chunk) if !chunk.is_empty());
+        // Nobody is listening any more once the stream was stopped
+        if sender.send(result).is_err() || finished {
+            return;
+        }
+    });
+    Ok(StreamReader::new(receiver, state))
+}
+
+/// An endless stream read from the chunks a download thread sends. The decoder wants to seek:
+/// going back works within the first HEAD_SIZE bytes, going forward skips what's in between.
+pub struct StreamReader {
+    chunks: Mutex<Receiver<io::Result<Vec<u8>>>>, // Only there to make the reader Sync, as rodio wants
+    buffered: Vec<u8>, // Received bytes, from `start` on
+    start: u64,
+    position: u64,
+    state: Arc<Mutex<StreamState>>, // Marked failed when the stream breaks off
+    stall_timeout: Duration, // How long to wait for the next chunk
+}
+
+impl StreamReader {
+    fn new(chunks: Receiver<io::Result<Vec<u8>>>, state: Arc<Mutex<StreamState>>) -> Self {
+        Self { chunks: Mutex::new(chunks), buffered: Vec::new(), start: 0, position: 0, state, stall_timeout: STALL_TIMEOUT }
+    }
+
+    /// Take the next chunk off the download; false at the end of the stream
+    fn fetch(&mut self) -> io::Result<bool> {
+        let received = match self.chunks.get_mut() {
+            Ok(chunks) => match chunks.recv_timeout(self.stall_timeout) {
+                Ok(received) => received,
+                Err(RecvTimeoutError::Timeout) => Err(io::Error::new(io::ErrorKind::TimedOut, "nothing received")),
+                Err(RecvTimeoutError::Disconnected) => Ok(Vec::new()),
+            },
+            Err(_) => Ok(Vec::new()),
+        };
+        let chunk = match received {
+            Ok(chunk) if chunk.is_empty() => {
+                self.fail("Stream ended".to_string());
+                return Ok(false);
+            }
+            Ok(chunk) => chunk,
+            Err(e) => {
+                self.fail(format!("Stream lost: {}", e));
+                return Err(e);
+            }
+        };
+
+        // Past the head, only what hasn't been read yet is kept
+        if self.start + self.buffered.len() as u64 >= HEAD_SIZE {
+            let read = (self.position - self.start).min(self.buffered.len() as u64);
+            self.buffered.drain(..read as usize);
+            self.start += read;
+        }
+        self.buffered.extend(chunk);
+        Ok(true)
+    }
+
+    /// A stream that was stopped on purpose isn't a failure
+    fn fail(&self, message: String) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if matches!(*state, StreamState::Connecting | StreamState::Live) {
+            *state = StreamState::Failed(message);
+        }
+    }
+}
+
+impl Read for StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let offset = (self.position - self.start) as usize;
+            if offset < self.buffered.len() {
+                let length = (&self.buffered[offset..]).read(buf)?;
+                self.position += length as u64;
+                return Ok(length);
+            }
+            if !self.fetch()? {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+impl Seek for StreamReader {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let target = match position {
+            SeekFrom::Start(target) => Some(target),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(_) => None,
+        };
+        match target {
+            Some(target) if target >= self.start => {
+                self.position = target;
+                Ok(target)
+            }
+            _ => Err(io::Error::new(io::ErrorKind::Unsupported, "can't seek that far back in a live stream")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_reader() {
+        let state = Arc::new(Mutex::new(StreamState::Live));
+        let (sender, receiver) = mpsc::sync_channel(10);
+        let mut reader = StreamReader::new(receiver, Arc::clone(&state));
+        for byte in 0..4u8 {
+            sender.send(Ok(vec![byte; 100 * 1024])).unwrap();
+        }
+        sender.send(Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))).unwrap();
+
+        // Rewinding within the head, as the decoder does while probing
+        let mut probe = [0; 4];
+        reader.read_exact(&mut probe).unwrap();
+        assert_eq!(reader.seek(SeekFrom::Start(0)).unwrap(), 0);
+        reader.read_exact(&mut probe).unwrap();
+        assert_eq!(probe, [0; 4]);
+
+        // Skipping ahead reads past what's in between
+        reader.seek(SeekFrom::Current(300 * 1024 - 4)).unwrap();
+        reader.read_exact(&mut probe).unwrap();
+        assert_eq!(probe, [3; 4]);
+        assert!(reader.seek(SeekFrom::Start(0)).is_err());
+        assert!(reader.seek(SeekFrom::End(0)).is_err());
+
+        // The connection dropping shows up as the stream failing
+        let mut rest = Vec::new();
+        assert!(reader.read_to_end(&mut rest).is_err());
+        assert_eq!(rest.len(), 100 * 1024 - 4);
+        assert_eq!(*state.lock().unwrap(), StreamState::Failed("Stream lost: reset".to_string()));
+    }
+
+    #[test]
+    fn test_stalled_stream_fails() {
+        let state = Arc::new(Mutex::new(StreamState::Live));
+        let (sender, receiver) = mpsc::sync_channel(10);
+        let mut reader = StreamReader::new(receiver, Arc::clone(&state));
+        reader.stall_timeout = Duration::from_millis(50);
+        sender.send(Ok(vec![1; 16])).unwrap();
+
+        // The station stops sending but keeps the connection open
+        let mut buf = [0; 32];
+        assert_eq!(reader.read(&mut buf).unwrap(), 16);
+        let error = reader.read(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(*state.lock().unwrap(), StreamState::Failed("Stream lost: nothing received".to_string()));
+        drop(sender);
+    }
+}
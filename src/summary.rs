@@ -1,80 +1,578 @@
 use ratatui::{
-    layout::Rect,
-    style::Style,
-    widgets::{Block, Borders, Paragraph},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Gauge, Paragraph, Sparkline, SparklineBar},
     Frame,
 };
 
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{Datelike, Local, Months, NaiveDate, Weekday};
+use unicode_width::UnicodeWidthStr;
+
 use crate::app::{App, Quadrant};
 use crate::theme::DraculaTheme;
-use crate::todo::Todo;
+use crate::config::SummaryConfig;
+use crate::report::ReportPeriod;
+use crate::timer::{format_hours_minutes, PomodoroPhase, PomodoroSession};
+use crate::todo::{truncate_to_width, Todo};
+
+/// Days shown in the chart, today being the last
+const CHART_DAYS: u32 = 7;
+/// Rows the bar chart needs below the text (a heading, the bars and their labels); with fewer,
+/// a sparkline takes what's there, from SPARKLINE_HEIGHT
+const BAR_CHART_HEIGHT: u16 = 7;
+const SPARKLINE_HEIGHT: u16 = 2;
+
+/// Days the trailing average session length is taken over
+const AVERAGE_DAYS: u32 = 7;
+/// Narrowest panel the week comparison is a table in; narrower, each number takes two lines
+const WIDE_WEEK_WIDTH: u16 = 40;
+/// Narrowest panel the goal gauge is drawn in (its label wouldn't fit); the percentage is written out instead
+const MIN_GAUGE_WIDTH: u16 = 20;
+/// Widest bar in the "Today by task" rows
+const TASK_BAR_WIDTH: usize = 10;
+/// Where work time for tasks no longer in the list goes, and work time without a task
+const REMOVED_TASKS: &str = "(removed tasks)";
+const NO_TASK: &str = "(no task)";
+
+/// Narrowest day cell of the month calendar; with less room the month is listed instead
+const MIN_CELL_WIDTH: u16 = 3;
+
+/// What the summary panel shows, cycled with v
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SummaryView {
+    Today, // The day's numbers and the last week's chart
+    Week, // This week so far next to the same days of last week
+    Month, // A calendar of one month, each day colored by its focus time
+}
+
+impl SummaryView {
+    pub fn next(&self) -> Self {
+        match self {
+            SummaryView::Today => SummaryView::Week,
+            SummaryView::Week => SummaryView::Month,
+            SummaryView::Month => SummaryView::Today,
+        }
+    }
+}
+
+/// Totals over a run of days, for comparing weeks
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct WeekStats {
+    minutes: u32,
+    sessions: u32,
+    active_days: u32, // Days with any work time
+    tasks_done: u32,
+}
+
+/// How much a day of the calendar was focused on, against the daily goal
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Heat {
+    None,
+    Low, // Less than half the goal
+    Medium, // Less than the goal
+    High, // The goal or more
+}
+
+impl Heat {
+    fn of(minutes: u32, goal: u32) -> Self {
+        match minutes {
+            0 => Heat::None,
+            m if m >= goal => Heat::High,
+            m if m * 2 < goal => Heat::Low,
+            _ => Heat::Medium,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Heat::None => DraculaTheme::CURRENT_LINE,
+            Heat::Low => DraculaTheme::COMMENT,
+            Heat::Medium => DraculaTheme::PURPLE,
+            Heat::High => DraculaTheme::GREEN,
+        }
+    }
+
+    /// Readable day numbers on top of the color
+    fn text_color(&self) -> Color {
+        match self {
+            Heat::None | Heat::Low => DraculaTheme::FOREGROUND,
+            Heat::Medium | Heat::High => DraculaTheme::BACKGROUND,
+        }
+    }
+}
 
 pub struct Summary {
-    pub daily_goal_minutes: u32, // Daily focus time goal in minutes
+    pub daily_goal_minutes: u32, // Daily focus time goal in minutes; 0 for none
+    pub daily_goal_pomodoros: Option<u32>, // Daily goal in work sessions
+    pub top_tasks: usize, // Tasks listed under "Today by task"; 0 hides the list
+    pub streak_ignores_weekends: bool, // Weekends without work don't break the streak
+    pub view: SummaryView,
+    pub months_back: u32, // Month the calendar shows, counted back from this one
+    pub viewing: Option<NaiveDate>, // Earlier day shown instead of today
+    pub status_message: Option<String>, // Shown on the panel's last line until the next key
 }
 
 impl Summary {
-    pub fn new(daily_goal_minutes: u32) -> Self {
+    pub fn new(config: &SummaryConfig) -> Self {
         Self {
-            daily_goal_minutes: daily_goal_minutes, // Default to 2 hours per day
+            daily_goal_minutes: config.daily_goal_minutes, // Default to 2 hours per day
+            daily_goal_pomodoros: config.daily_goal_pomodoros,
+            top_tasks: config.top_tasks,
+            streak_ignores_weekends: config.streak_ignores_weekends,
+            view: SummaryView::Today,
+            months_back: 0,
+            viewing: None,
+            status_message: None,
         }
     }
 
+    /// Switch between today's numbers and the month calendar, which starts at this month again
+    pub fn cycle_view(&mut self) {
+        self.view = self.view.next();
+        self.months_back = 0;
+        self.viewing = None;
+    }
+
+    /// Show the day before (or after) the one shown that has sessions, skipping the days without;
+    /// going forward past the last one before today comes back to today
+    pub fn step_day(&mut self, sessions: &[PomodoroSession], today: NaiveDate, back: bool) {
+        let shown = self.viewing.unwrap_or(today);
+        let dates = sessions.iter().map(|session| session.date).filter(|&date| date < today);
+        let next = if back {
+            dates.filter(|&date| date < shown).max()
+        } else {
+            dates.filter(|&date| date > shown).min()
+        };
+        if next.is_some() || !back {
+            self.viewing = next;
+        }
+    }
+
+    pub fn show_today(&mut self) {
+        self.viewing = None;
+    }
+
+    /// The period a report covers: what the panel shows
+    pub fn report_period(&self, today: NaiveDate) -> ReportPeriod {
+        match self.view {
+            SummaryView::Today => ReportPeriod::Day(self.viewing.unwrap_or(today)),
+            SummaryView::Week => ReportPeriod::week_of(today),
+            SummaryView::Month => ReportPeriod::Month(month_start(today, self.months_back)),
+        }
+    }
+
+    /// Show the month before (or after) in the calendar; not past this one
+    pub fn page_month(&mut self, back: bool) {
+        self.months_back = if back { self.months_back + 1 } else { self.months_back.saturating_sub(1) };
+    }
+
     pub fn render(&self, frame: &mut Frame, area: Rect, app: &App, todo: &Todo) {
         let is_focused = app.focused_quadrant == Quadrant::TopRight;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("📊 Summary")
+            .title_style(Style::default().fg(DraculaTheme::CYAN))
+            .border_style(Style::default().fg(if is_focused { DraculaTheme::PINK } else { DraculaTheme::COMMENT }))
+            .style(Style::default().bg(DraculaTheme::BACKGROUND));
+        let mut inner = block.inner(area);
+        frame.render_widget(block, area);
+        if let Some(ref message) = self.status_message {
+            let [rest, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner);
+            frame.render_widget(Paragraph::new(Line::styled(message.clone(), Style::default().fg(DraculaTheme::YELLOW))), status);
+            inner = rest;
+        }
+        match self.view {
+            SummaryView::Month => return self.render_month(frame, inner, todo),
+            SummaryView::Week => return self.render_week(frame, inner, todo),
+            SummaryView::Today => {}
+        }
+        if let Some(date) = self.viewing {
+            self.render_day(frame, inner, todo, date);
+            return;
+        }
         
         // Get statistics
         let today_minutes = todo.get_today_minutes();
         let yesterday_minutes = todo.get_yesterday_minutes();
-        let streak_days = todo.get_streak_days();
-        let completed_tasks = todo.get_completed_tasks_count();
-        
-        // Calculate progress towards daily goal
-        let goal_progress = if self.daily_goal_minutes > 0 {
-            (today_minutes as f32 / self.daily_goal_minutes as f32 * 100.0).min(100.0) as u32
+        let streak_days = todo.get_streak_days(self.streak_ignores_weekends);
+        let completed_today = todo.get_completed_on(Local::now().date_naive());
+        let (scheduled_done, scheduled_total) = todo.get_scheduled_progress(Local::now().date_naive());
+        let scheduled_info = if scheduled_total > 0 {
+            format!("\n• Scheduled today: {}/{}", scheduled_done, scheduled_total)
+        } else {
+            String::new()
+        };
+        let average_info = format!(
+            "\n• Avg session: {} today, {} over {} days",
+            format_average_minutes(todo.get_average_session_minutes(1)),
+            format_average_minutes(todo.get_average_session_minutes(AVERAGE_DAYS)),
+            AVERAGE_DAYS
+        );
+        let focus_info = if let Some(average) = todo.get_today_focus_average() {
+            format!("\n• Avg focus: {:.1}/5", average)
         } else {
-            0
+            String::new()
         };
+        let records_info = records_text(&personal_records(&todo.pomodoro_sessions, Local::now().date_naive()));
+        
+        let goal_info = self.goal_text(today_minutes, todo.get_today_pomodoros());
         
-        // Format time
-        let today_hours = today_minutes / 60;
-        let today_mins = today_minutes % 60;
-        let yesterday_hours = yesterday_minutes / 60;
-        let yesterday_mins = yesterday_minutes % 60;
-        let goal_hours = self.daily_goal_minutes / 60;
-        let goal_mins = self.daily_goal_minutes % 60;
+        // Streak and completed tasks come from the active todo list
+        let list_info = if let Some(ref name) = todo.list_name {
+            format!(" ({})", name)
+        } else {
+            String::new()
+        };
         
         let content = format!(
-            "\n🎯 Today's Progress:\n• Completed minutes: {} ({}h {}m)\n• Daily goal: {}h {}m\n• Progress: {}%\n\n📈 Statistics:\n• Yesterday: {}h {}m\n• Streak: {} days\n• Tasks completed: {}",
-            today_minutes, today_hours, today_mins,
-            goal_hours, goal_mins,
-            goal_progress,
-            yesterday_hours, yesterday_mins,
+            "\n🎯 Today's Progress:\n• Completed minutes: {} ({}){}{}\n\n📈 Statistics{}:\n• Yesterday: {}\n• Streak: {} days\n• Tasks completed today: {}{}{}{}",
+            today_minutes, format_hours_minutes(today_minutes.into()),
+            goal_info,
+            focus_info,
+            list_info,
+            format_hours_minutes(yesterday_minutes.into()),
             streak_days,
-            completed_tasks
+            completed_today,
+            scheduled_info,
+            average_info,
+            records_info
         );
         
-        let summary_widget = if is_focused {
-            Paragraph::new(content)
-                .style(Style::default().fg(DraculaTheme::FOREGROUND).bg(DraculaTheme::BACKGROUND))
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .title("📊 Summary")
-                    .title_style(Style::default().fg(DraculaTheme::CYAN))
-                    .border_style(Style::default().fg(DraculaTheme::PINK))
-                    .style(Style::default().bg(DraculaTheme::BACKGROUND)))
+        // Where today's time went, then the last days as a chart, as far as there's room for them
+        let text_height = content.lines().count() as u16;
+        let [text_area, rest] = Layout::vertical([Constraint::Length(text_height), Constraint::Min(0)]).areas(inner);
+        let text_style = Style::default().fg(DraculaTheme::FOREGROUND).bg(DraculaTheme::BACKGROUND);
+        // The progress line becomes a gauge, unless the panel is too small to show all of the text
+        let progress_line = content.lines().position(|line| line.starts_with("• Progress"));
+        match progress_line {
+            Some(index) if inner.height >= text_height && inner.width >= MIN_GAUGE_WIDTH => {
+                let lines: Vec<&str> = content.lines().collect();
+                let [above, gauge_area, below] = Layout::vertical([Constraint::Length(index as u16), Constraint::Length(1), Constraint::Min(0)]).areas(text_area);
+                frame.render_widget(Paragraph::new(lines[..index].join("\n")).style(text_style), above);
+                frame.render_widget(self.goal_gauge(today_minutes), gauge_area);
+                frame.render_widget(Paragraph::new(lines[index + 1..].join("\n")).style(text_style), below);
+            }
+            _ => frame.render_widget(Paragraph::new(content).style(text_style), text_area),
+        }
+        let names: HashSet<&str> = todo.items.iter().map(|item| item.task.as_str()).collect();
+        let tasks = if self.top_tasks > 0 {
+            minutes_by_task(&todo.pomodoro_sessions, &names, Local::now().date_naive())
         } else {
-            Paragraph::new(content)
-                .style(Style::default().fg(DraculaTheme::FOREGROUND).bg(DraculaTheme::BACKGROUND))
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .title("📊 Summary")
-                    .title_style(Style::default().fg(DraculaTheme::CYAN))
-                    .border_style(Style::default().fg(DraculaTheme::COMMENT))
-                    .style(Style::default().bg(DraculaTheme::BACKGROUND)))
+            Vec::new()
         };
+        let task_lines = self.task_lines(&tasks, rest.width as usize, rest.height as usize);
+        let [tasks_area, chart_area] = Layout::vertical([Constraint::Length(task_lines.len() as u16), Constraint::Min(0)]).areas(rest);
+        frame.render_widget(Paragraph::new(task_lines), tasks_area);
+        self.render_chart(frame, chart_area, &daily_minutes(&todo.pomodoro_sessions, Local::now().date_naive(), CHART_DAYS));
+    }
+
+    /// An earlier day in place of today: what was done, and the week up to it as a chart
+    fn render_day(&self, frame: &mut Frame, area: Rect, todo: &Todo, date: NaiveDate) {
+        let indicator = Line::styled(
+            format!("(viewing {} — press t for today)", date.format("%Y-%m-%d")),
+            Style::default().fg(DraculaTheme::ORANGE),
+        );
+        let content = self.day_text(&todo.pomodoro_sessions, date);
+        let text_height = content.lines().count() as u16;
+        let [indicator_area, text_area, chart_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(text_height),
+            Constraint::Min(0),
+        ]).areas(area);
+        frame.render_widget(Paragraph::new(indicator), indicator_area);
+        frame.render_widget(Paragraph::new(content).style(Style::default().fg(DraculaTheme::FOREGROUND).bg(DraculaTheme::BACKGROUND)), text_area);
+        self.render_chart(frame, chart_area, &daily_minutes(&todo.pomodoro_sessions, date, CHART_DAYS));
+    }
+
+    /// The numbers of one day and the tasks worked on, for as much as there is of them
+    fn day_text(&self, sessions: &[PomodoroSession], date: NaiveDate) -> String {
+        let day: Vec<&PomodoroSession> = sessions.iter().filter(|session| session.date == date).collect();
+        let work_minutes: u32 = day.iter().map(|session| session.total_work_minutes).sum();
+        let work_sessions: u32 = day.iter().map(|session| session.work_sessions).sum();
+        let break_minutes: u32 = day.iter().map(|session| session.total_break_minutes).sum();
+        let break_sessions: u32 = day.iter().map(|session| session.break_sessions).sum();
+        let plural = |count: u32, word: &str| format!("{} {}{}", count, word, if count == 1 { "" } else { "s" });
+        let mut text = format!(
+            "\n📅 {}:\n• Focus time: {} ({})\n• Breaks: {} ({})",
+            date.format("%A, %-d %B %Y"),
+            format_hours_minutes(work_minutes.into()),
+            plural(work_sessions, "session"),
+            format_hours_minutes(break_minutes.into()),
+            plural(break_sessions, "break"),
+        );
+        if self.daily_goal_minutes > 0 {
+            text.push_str(&format!("\n• Goal: {}%", goal_percent(work_minutes, self.daily_goal_minutes)));
+        }
+        if let Some(goal) = self.daily_goal_pomodoros {
+            text.push_str(&format!("\n• Pomodoros: {} / {}", work_sessions, goal));
+        }
+        let ratings: Vec<u8> = day.iter().flat_map(|session| session.ratings.iter().copied()).collect();
+        if !ratings.is_empty() {
+            let average = ratings.iter().map(|&rating| f32::from(rating)).sum::<f32>() / ratings.len() as f32;
+            text.push_str(&format!("\n• Avg focus: {:.1}/5", average));
+        }
+        let mut tasks: Vec<&str> = Vec::new();
+        for task in day.iter().flat_map(|session| &session.tasks_worked_on) {
+            if !tasks.contains(&task.as_str()) {
+                tasks.push(task);
+            }
+        }
+        if tasks.is_empty() {
+            text.push_str("\n\n📝 No tasks recorded");
+        } else {
+            text.push_str("\n\n📝 Worked on:");
+            for task in tasks {
+                text.push_str(&format!("\n• {}", task));
+            }
+        }
+        text
+    }
+
+    /// The week as bars when there's room for them, or else as a sparkline
+    fn render_chart(&self, frame: &mut Frame, area: Rect, days: &[(NaiveDate, u32)]) {
+        if area.height >= BAR_CHART_HEIGHT {
+            self.render_bar_chart(frame, area, days);
+        } else if area.height >= SPARKLINE_HEIGHT {
+            self.render_sparkline(frame, area, days);
+        }
+    }
+
+    /// The goal lines, for the goals that are set: the minutes goal with the progress towards it
+    /// (drawn as a gauge when there's room), and the sessions done out of the sessions goal
+    fn goal_text(&self, today_minutes: u32, today_pomodoros: u32) -> String {
+        let mut text = String::new();
+        if self.daily_goal_minutes > 0 {
+            text.push_str(&format!(
+                "\n• Daily goal: {}\n• Progress: {}%",
+                format_hours_minutes(self.daily_goal_minutes.into()),
+                goal_percent(today_minutes, self.daily_goal_minutes).min(100)
+            ));
+        }
+        if let Some(goal) = self.daily_goal_pomodoros {
+            text.push_str(&format!("\n• Pomodoros: {} / {}", today_pomodoros, goal));
+        }
+        text
+    }
+
+    /// Today's time against the goal, "1h 17m / 2h 0m"; past the goal it stays full and says by how much
+    fn goal_gauge(&self, today_minutes: u32) -> Gauge<'static> {
+        let percent = goal_percent(today_minutes, self.daily_goal_minutes);
+        let mut label = format!("{} / {}", format_hours_minutes(today_minutes.into()), format_hours_minutes(self.daily_goal_minutes.into()));
+        if percent > 100 {
+            label.push_str(&format!(" — {}%", percent));
+        }
+        let color = if percent >= 100 { DraculaTheme::GREEN } else { DraculaTheme::PURPLE };
+        Gauge::default()
+            .gauge_style(Style::default().fg(color).bg(DraculaTheme::CURRENT_LINE))
+            .ratio(f64::from(percent.min(100)) / 100.0)
+            .label(Span::styled(label, Style::default().fg(DraculaTheme::FOREGROUND).add_modifier(Modifier::BOLD)))
+    }
+
+    /// "Today by task": a blank line and the heading, then the top tasks with their minutes and a
+    /// bar against the longest. With less than `height` rows for them, as many as fit, or just the
+    /// heading; nothing without work time today.
+    fn task_lines(&self, tasks: &[(String, u32)], width: usize, height: usize) -> Vec<Line<'static>> {
+        if tasks.is_empty() || height < 2 {
+            return Vec::new();
+        }
+        let shown = tasks.len().min(self.top_tasks).min(height - 2);
+        let heading = if shown == 0 {
+            format!("📝 Today by task: {} {}", tasks.len(), if tasks.len() == 1 { "task" } else { "tasks" })
+        } else if shown < tasks.len() {
+            format!("📝 Today by task (top {} of {}):", shown, tasks.len())
+        } else {
+            "📝 Today by task:".to_string()
+        };
+        let mut lines = vec![Line::from(""), Line::from(heading)];
+        let longest = tasks.first().map_or(1, |&(_, minutes)| minutes.max(1));
+        let bar_width = TASK_BAR_WIDTH.min(width / 4);
+        // "• name   45m ████"; the name gets what the minutes and the bar leave
+        let name_width = width.saturating_sub(bar_width + 9);
+        for (name, minutes) in &tasks[..shown] {
+            let name = truncate_to_width(name, name_width);
+            let padding = " ".repeat(name_width.saturating_sub(name.width()));
+            let bar = "█".repeat(((*minutes as usize * bar_width).div_ceil(longest as usize)).max(1).min(bar_width));
+            let style = if name.starts_with('(') { Style::default().fg(DraculaTheme::COMMENT) } else { Style::default().fg(DraculaTheme::FOREGROUND) };
+            lines.push(Line::from(vec![
+                Span::styled(format!("• {}{}", name, padding), style),
+                Span::styled(format!(" {:>4}m ", minutes), Style::default().fg(DraculaTheme::FOREGROUND)),
+                Span::styled(bar, Style::default().fg(DraculaTheme::PURPLE)),
+            ]));
+        }
+        lines
+    }
+
+    /// This week up to today against the same weekdays of last week, so a Wednesday isn't held
+    /// up against a whole week
+    fn render_week(&self, frame: &mut Frame, area: Rect, todo: &Todo) {
+        let today = Local::now().date_naive();
+        let monday = today - chrono::Duration::days(today.weekday().num_days_from_monday().into());
+        let week_ago = chrono::Duration::days(7);
+        let completed: Vec<NaiveDate> = todo.items.iter()
+            .filter(|item| item.done)
+            .filter_map(|item| item.completed_at)
+            .map(|completed_at| completed_at.date_naive())
+            .collect();
+        let this_week = week_stats(&todo.pomodoro_sessions, &completed, monday, today);
+        let last_week = week_stats(&todo.pomodoro_sessions, &completed, monday - week_ago, today - week_ago);
+        let days = match today.weekday() {
+            Weekday::Sun => "Whole weeks".to_string(),
+            Weekday::Mon => "Mondays only".to_string(),
+            weekday => format!("Mon–{} of each week", weekday),
+        };
+        let mut lines = vec![
+            Line::from(""),
+            Line::styled("📅 This week vs last week", Style::default().fg(DraculaTheme::CYAN).add_modifier(Modifier::BOLD)),
+            Line::styled(days, Style::default().fg(DraculaTheme::COMMENT)),
+            Line::from(""),
+        ];
+        lines.extend(comparison_lines(&this_week, &last_week, area.width >= WIDE_WEEK_WIDTH));
+        frame.render_widget(Paragraph::new(lines).style(Style::default().fg(DraculaTheme::FOREGROUND)), area);
+    }
+
+    /// The month `months_back` as a calendar, a week per row, or as a list of the days with
+    /// focus time when the panel is too narrow for seven columns
+    fn render_month(&self, frame: &mut Frame, area: Rect, todo: &Todo) {
+        let today = Local::now().date_naive();
+        let first = month_start(today, self.months_back);
+        let days = month_minutes(&todo.pomodoro_sessions, first);
+        let [heading, grid] = Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(area);
+        let total: u32 = days.iter().map(|&(_, minutes)| minutes).sum();
+        let title = Line::from(vec![
+            Span::styled(first.format("%B %Y").to_string(), Style::default().fg(DraculaTheme::CYAN).add_modifier(Modifier::BOLD)),
+            Span::styled(format!("  {}  (h/l: other months)", format_hours_minutes(total.into())), Style::default().fg(DraculaTheme::COMMENT)),
+        ]);
+        frame.render_widget(Paragraph::new(title), heading);
+
+        let cell_width = grid.width / 7;
+        if cell_width < MIN_CELL_WIDTH {
+            // Most recent first, the quiet days left out
+            let lines: Vec<Line> = days.iter().rev()
+                .filter(|&&(_, minutes)| minutes > 0)
+                .map(|&(date, minutes)| {
+                    let heat = Heat::of(minutes, self.daily_goal_minutes);
+                    Line::from(vec![
+                        Span::styled("■ ", Style::default().fg(heat.color())),
+                        Span::raw(format!("{} {}", date.format("%a %d"), format_hours_minutes(minutes.into()))),
+                    ])
+                })
+                .collect();
+            let lines = if lines.is_empty() { vec![Line::styled("No focus time", Style::default().fg(DraculaTheme::COMMENT))] } else { lines };
+            frame.render_widget(Paragraph::new(lines).style(Style::default().fg(DraculaTheme::FOREGROUND)), grid);
+            return;
+        }
+
+        // Each day is a colored cell with its number, and a column of space to the next one
+        let width = cell_width as usize - 1;
+        let header: Vec<Span> = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"].iter()
+            .map(|name| Span::styled(format!("{:>width$} ", name), Style::default().fg(DraculaTheme::COMMENT)))
+            .collect();
+        let mut lines = vec![Line::from(header)];
+        // Blank cells before the 1st, to start the week on Monday
+        let lead = first.weekday().num_days_from_monday() as usize;
+        let mut week: Vec<Span> = vec![Span::raw(" ".repeat(width + 1)); lead];
+        for &(date, minutes) in &days {
+            let heat = Heat::of(minutes, self.daily_goal_minutes);
+            let mut style = Style::default().fg(heat.text_color()).bg(heat.color());
+            if date == today {
+                style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+            }
+            week.push(Span::styled(format!("{:>width$}", date.day()), style));
+            week.push(Span::raw(" "));
+            if date.weekday() == Weekday::Sun {
+                lines.push(Line::from(std::mem::take(&mut week)));
+            }
+        }
+        if !week.is_empty() {
+            lines.push(Line::from(week));
+        }
+        // What the colors mean, below the weeks when there's room
+        let goal = self.daily_goal_minutes;
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled("■", Style::default().fg(Heat::None.color())),
+            Span::styled(" none ", Style::default().fg(DraculaTheme::COMMENT)),
+            Span::styled("■", Style::default().fg(Heat::Low.color())),
+            Span::styled(format!(" <{} ", format_hours_minutes((goal / 2).into())), Style::default().fg(DraculaTheme::COMMENT)),
+            Span::styled("■", Style::default().fg(Heat::Medium.color())),
+            Span::styled(format!(" <{} ", format_hours_minutes(goal.into())), Style::default().fg(DraculaTheme::COMMENT)),
+            Span::styled("■", Style::default().fg(Heat::High.color())),
+            Span::styled(" goal", Style::default().fg(DraculaTheme::COMMENT)),
+        ]));
+        frame.render_widget(Paragraph::new(lines), grid);
+    }
+
+    /// Days that reached the goal in green, the others in orange; today in green while it's still going
+    fn bar_color(&self, date: NaiveDate, minutes: u32, today: NaiveDate) -> Color {
+        if date == today || minutes >= self.daily_goal_minutes {
+            DraculaTheme::GREEN
+        } else {
+            DraculaTheme::ORANGE
+        }
+    }
+
+    fn chart_heading(&self, days: &[(NaiveDate, u32)]) -> Line<'static> {
+        let mut heading = match days.last() {
+            Some(&(last, _)) if last != Local::now().date_naive() => format!("{} days to {}", days.len(), last.format("%a %-d %b")),
+            _ => format!("Last {} days", days.len()),
+        };
+        if self.daily_goal_minutes > 0 {
+            heading.push_str(&format!(" (goal {})", format_hours_minutes(self.daily_goal_minutes.into())));
+        }
+        Line::styled(
+            heading,
+            Style::default().fg(DraculaTheme::COMMENT),
+        )
+    }
+
+    /// Scaled so a bar at the goal is always the same height, however short the days were
+    fn chart_top(&self, days: &[(NaiveDate, u32)]) -> u64 {
+        days.iter().map(|&(_, minutes)| minutes).max().unwrap_or(0).max(self.daily_goal_minutes).into()
+    }
+
+    fn render_bar_chart(&self, frame: &mut Frame, area: Rect, days: &[(NaiveDate, u32)]) {
+        let today = Local::now().date_naive();
+        let [heading, chart] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+        frame.render_widget(Paragraph::new(self.chart_heading(days)), heading);
+        let bars: Vec<Bar> = days.iter()
+            .map(|&(date, minutes)| {
+                let color = self.bar_color(date, minutes, today);
+                let initial = date.weekday().to_string()[..1].to_string();
+                let label_style = if date == today {
+                    Style::default().fg(DraculaTheme::GREEN).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(DraculaTheme::COMMENT)
+                };
+                Bar::default()
+                    .value(minutes.into())
+                    .text_value(minutes.to_string())
+                    .label(Line::styled(initial, label_style))
+                    .style(Style::default().fg(color))
+                    .value_style(Style::default().fg(DraculaTheme::BACKGROUND).bg(color))
+            })
+            .collect();
+        // The days side by side across the panel, with a column between them
+        let count = days.len().max(1) as u16;
+        let bar_width = ((chart.width + 1) / count).saturating_sub(1).max(1);
+        let bar_chart = BarChart::default()
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(bar_width)
+            .bar_gap(1)
+            .max(self.chart_top(days));
+        frame.render_widget(bar_chart, chart);
+    }
 
-        frame.render_widget(summary_widget, area);
+    fn render_sparkline(&self, frame: &mut Frame, area: Rect, days: &[(NaiveDate, u32)]) {
+        let today = Local::now().date_naive();
+        let [heading, chart] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+        frame.render_widget(Paragraph::new(self.chart_heading(days)), heading);
+        let bars: Vec<SparklineBar> = days.iter()
+            .map(|&(date, minutes)| SparklineBar::from(u64::from(minutes)).style(Style::default().fg(self.bar_color(date, minutes, today))))
+            .collect();
+        frame.render_widget(Sparkline::default().data(bars).max(self.chart_top(days)), chart);
     }
 
     // Add summary functionality methods here
@@ -86,4 +584,501 @@ impl Summary {
         // Return daily summary string
         String::from("Daily summary placeholder")
     }
-}
\ No newline at end of file
+}
+
+/// Minutes of work on each of the `days` days up to and including `last`, oldest first; days
+/// without sessions count as 0
+fn daily_minutes(sessions: &[PomodoroSession], last: NaiveDate, days: u32) -> Vec<(NaiveDate, u32)> {
+    (0..days)
+        .rev()
+        .map(|ago| last - chrono::Duration::days(ago.into()))
+        .map(|date| {
+            let minutes = sessions.iter()
+                .filter(|session| session.date == date)
+                .map(|session| session.total_work_minutes)
+                .sum();
+            (date, minutes)
+        })
+        .collect()
+}
+
+/// A personal best: how much, and the day it was set (the first one, on a tie)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Record {
+    value: u32,
+    date: NaiveDate,
+    new_today: bool, // Today beat the record that stood before it
+}
+
+/// The bests over the whole session history
+#[derive(Debug, Default, PartialEq)]
+struct Records {
+    best_day: Option<Record>, // Most work minutes in a day
+    longest_streak: Option<Record>, // Most days in a row with work, dated by the last of them
+    most_pomodoros: Option<Record>, // Most work sessions in a day
+}
+
+/// The records as of `today`, today included. One pass over the days, so cheap enough to work
+/// out on every render; a first day of history sets them but doesn't count as breaking them.
+fn personal_records(sessions: &[PomodoroSession], today: NaiveDate) -> Records {
+    let mut days: BTreeMap<NaiveDate, (u32, u32)> = BTreeMap::new();
+    for session in sessions.iter().filter(|session| session.total_work_minutes > 0) {
+        let day = days.entry(session.date).or_default();
+        day.0 += session.total_work_minutes;
+        day.1 += session.work_sessions;
+    }
+    let before = bests(days.range(..today));
+    let mut records = bests(days.iter());
+    // Only today can hold a record it didn't hold yesterday: a tie leaves it with the earlier day
+    for (record, previous) in [
+        (&mut records.best_day, before.best_day),
+        (&mut records.longest_streak, before.longest_streak),
+        (&mut records.most_pomodoros, before.most_pomodoros),
+    ] {
+        if let Some(record) = record {
+            record.new_today = record.date == today && previous.is_some();
+        }
+    }
+    records
+}
+
+/// The records over `days` (in date order, each with its work minutes and sessions)
+fn bests<'a>(days: impl Iterator<Item = (&'a NaiveDate, &'a (u32, u32))>) -> Records {
+    fn raise(record: &mut Option<Record>, value: u32, date: NaiveDate) {
+        if value > 0 && record.is_none_or(|record| value > record.value) {
+            *record = Some(Record { value, date, new_today: false });
+        }
+    }
+    let mut records = Records::default();
+    let mut streak: Option<(NaiveDate, u32)> = None; // Last day of the current run and its length
+    for (&date, &(minutes, pomodoros)) in days {
+        raise(&mut records.best_day, minutes, date);
+        raise(&mut records.most_pomodoros, pomodoros, date);
+        let length = match streak {
+            Some((last, length)) if last.succ_opt() == Some(date) => length + 1,
+            _ => 1,
+        };
+        streak = Some((date, length));
+        raise(&mut records.longest_streak, length, date);
+    }
+    records
+}
+
+/// The "🏆 Records" lines for the statistics, with a ✨ on those set today; nothing before the first work
+fn records_text(records: &Records) -> String {
+    let Some(best_day) = records.best_day else {
+        return String::new();
+    };
+    let sparkle = |record: &Record| if record.new_today { " ✨" } else { "" };
+    let mut text = format!(
+        "\n\n🏆 Records:\n• Best day: {} ({}){}",
+        format_hours_minutes(best_day.value.into()),
+        best_day.date.format("%Y-%m-%d"),
+        sparkle(&best_day)
+    );
+    if let Some(streak) = records.longest_streak {
+        text.push_str(&format!("\n• Longest streak: {} {}{}", streak.value, if streak.value == 1 { "day" } else { "days" }, sparkle(&streak)));
+    }
+    if let Some(pomodoros) = records.most_pomodoros {
+        text.push_str(&format!("\n• Most pomodoros in a day: {}{}", pomodoros.value, sparkle(&pomodoros)));
+    }
+    text
+}
+
+/// "24m", or a dash without sessions to average
+fn format_average_minutes(minutes: Option<f32>) -> String {
+    match minutes {
+        Some(minutes) => format!("{}m", minutes.round()),
+        None => "—".to_string(),
+    }
+}
+
+/// Totals of the days from `first` to `last`; `completed` has the day each done task was finished
+fn week_stats(sessions: &[PomodoroSession], completed: &[NaiveDate], first: NaiveDate, last: NaiveDate) -> WeekStats {
+    let mut stats = WeekStats::default();
+    let mut active: HashSet<NaiveDate> = HashSet::new();
+    for session in sessions.iter().filter(|session| (first..=last).contains(&session.date)) {
+        stats.minutes += session.total_work_minutes;
+        stats.sessions += session.work_sessions;
+        if session.total_work_minutes > 0 {
+            active.insert(session.date);
+        }
+    }
+    stats.active_days = active.len() as u32;
+    stats.tasks_done = completed.iter().filter(|date| (first..=last).contains(*date)).count() as u32;
+    stats
+}
+
+/// "▲ 16%" in green or "▼ 33%" in red, from `before` to `now`; "▲ new" when there was nothing before
+fn change(now: u32, before: u32) -> (String, Color) {
+    if now == before {
+        return ("=".to_string(), DraculaTheme::COMMENT);
+    }
+    if before == 0 {
+        return ("▲ new".to_string(), DraculaTheme::GREEN);
+    }
+    let percent = ((i64::from(now) - i64::from(before)) * 100 / i64::from(before)).unsigned_abs();
+    if now > before {
+        (format!("▲ {}%", percent), DraculaTheme::GREEN)
+    } else {
+        (format!("▼ {}%", percent), DraculaTheme::RED)
+    }
+}
+
+/// A row per number with both weeks and the change, or, when not `wide`, this week and the
+/// change on one line and last week below it
+fn comparison_lines(this_week: &WeekStats, last_week: &WeekStats, wide: bool) -> Vec<Line<'static>> {
+    let rows = [
+        ("Focus time", this_week.minutes, last_week.minutes, format_hours_minutes(this_week.minutes.into()), format_hours_minutes(last_week.minutes.into())),
+        ("Sessions", this_week.sessions, last_week.sessions, this_week.sessions.to_string(), last_week.sessions.to_string()),
+        ("Days active", this_week.active_days, last_week.active_days, this_week.active_days.to_string(), last_week.active_days.to_string()),
+        ("Tasks done", this_week.tasks_done, last_week.tasks_done, this_week.tasks_done.to_string(), last_week.tasks_done.to_string()),
+    ];
+    let mut lines = Vec::new();
+    if wide {
+        lines.push(Line::styled(format!("{:<12}{:>9}{:>10}", "", "This week", "Last week"), Style::default().fg(DraculaTheme::COMMENT)));
+    }
+    for (label, now, before, now_text, before_text) in rows {
+        let (arrow, color) = change(now, before);
+        if wide {
+            lines.push(Line::from(vec![
+                Span::raw(format!("{:<12}{:>9}{:>10}  ", label, now_text, before_text)),
+                Span::styled(arrow, Style::default().fg(color)),
+            ]));
+        } else {
+            lines.push(Line::from(vec![
+                Span::raw(format!("{}: {} ", label, now_text)),
+                Span::styled(arrow, Style::default().fg(color)),
+            ]));
+            lines.push(Line::styled(format!("  last week: {}", before_text), Style::default().fg(DraculaTheme::COMMENT)));
+        }
+    }
+    lines
+}
+
+/// How much of the goal `minutes` is, in whole percent and not capped at 100; 0 without a goal
+fn goal_percent(minutes: u32, goal: u32) -> u32 {
+    if goal == 0 {
+        return 0;
+    }
+    (u64::from(minutes) * 100 / u64::from(goal)) as u32
+}
+
+/// Today's work minutes per task, most first (ties by name). Tasks that aren't in `names` any
+/// more are added up as one, and work without a task as another.
+fn minutes_by_task(sessions: &[PomodoroSession], names: &HashSet<&str>, date: NaiveDate) -> Vec<(String, u32)> {
+    let mut minutes: HashMap<&str, u32> = HashMap::new();
+    let entries = sessions.iter()
+        .filter(|session| session.date == date)
+        .flat_map(|session| &session.entries)
+        .filter(|entry| entry.phase == PomodoroPhase::Work && entry.minutes > 0);
+    for entry in entries {
+        let task = match entry.task.as_deref() {
+            Some(task) if names.contains(task) => task,
+            Some(_) => REMOVED_TASKS,
+            None => NO_TASK,
+        };
+        *minutes.entry(task).or_default() += entry.minutes;
+    }
+    let mut tasks: Vec<(String, u32)> = minutes.into_iter().map(|(task, minutes)| (task.to_string(), minutes)).collect();
+    tasks.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    tasks
+}
+
+/// The first of the month `months_back` months before the one `today` is in
+fn month_start(today: NaiveDate, months_back: u32) -> NaiveDate {
+    let first = today.with_day(1).expect("every month has a 1st");
+    first - Months::new(months_back)
+}
+
+/// Minutes of work on each day of the month starting at `first`
+fn month_minutes(sessions: &[PomodoroSession], first: NaiveDate) -> Vec<(NaiveDate, u32)> {
+    let next = first + Months::new(1);
+    let last = next.pred_opt().expect("a day before the 1st");
+    daily_minutes(sessions, last, last.day())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{at, empty_session};
+    use crate::timer::SessionEntry;
+
+    fn session(date: NaiveDate, total_work_minutes: u32) -> PomodoroSession {
+        PomodoroSession { work_sessions: 1, total_work_minutes, ..empty_session(date) }
+    }
+
+    #[test]
+    fn test_daily_minutes() {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let sessions = [session(day(16), 50), session(day(13), 25), session(day(16), 25), session(day(2), 100)];
+        let days = daily_minutes(&sessions, day(16), 7);
+        assert_eq!(days, [(day(10), 0), (day(11), 0), (day(12), 0), (day(13), 25), (day(14), 0), (day(15), 0), (day(16), 75)]);
+
+        // Nothing logged at all is a week of zeros
+        assert_eq!(daily_minutes(&[], day(16), 7).iter().map(|&(_, minutes)| minutes).collect::<Vec<_>>(), [0; 7]);
+    }
+
+    #[test]
+    fn test_month_minutes() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(month_start(date(2026, 10, 16), 0), date(2026, 10, 1));
+        assert_eq!(month_start(date(2026, 3, 31), 2), date(2026, 1, 1));
+        assert_eq!(month_start(date(2026, 1, 5), 1), date(2025, 12, 1));
+
+        let sessions = [session(date(2026, 2, 1), 30), session(date(2026, 2, 28), 90), session(date(2026, 3, 1), 60)];
+        let days = month_minutes(&sessions, date(2026, 2, 1));
+        assert_eq!(days.len(), 28);
+        assert_eq!((days[0], days[27]), ((date(2026, 2, 1), 30), (date(2026, 2, 28), 90)));
+        assert_eq!(days.iter().filter(|&&(_, minutes)| minutes == 0).count(), 26);
+    }
+
+    #[test]
+    fn test_minutes_by_task() {
+        let day = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let entry = |hour, phase, minutes, task: Option<&str>| SessionEntry {
+            start: at(2026, 10, 16, hour, 0),
+            phase,
+            minutes,
+            task: task.map(String::from),
+            note: None,
+        };
+        let mut today = session(day, 0);
+        today.entries = vec![
+            entry(9, PomodoroPhase::Work, 25, Some("Write report")),
+            entry(10, PomodoroPhase::ShortBreak, 5, None),
+            entry(11, PomodoroPhase::Work, 25, Some("Old task")),
+            entry(12, PomodoroPhase::Work, 25, Some("Write report")),
+            entry(13, PomodoroPhase::Work, 10, None),
+            entry(14, PomodoroPhase::Work, 15, Some("Another old one")),
+        ];
+        let mut yesterday = session(day.pred_opt().unwrap(), 0);
+        yesterday.entries = vec![entry(9, PomodoroPhase::Work, 100, Some("Review"))];
+        let names = HashSet::from(["Write report", "Review"]);
+        assert_eq!(minutes_by_task(&[yesterday, today], &names, day), [
+            ("Write report".to_string(), 50),
+            (REMOVED_TASKS.to_string(), 40),
+            (NO_TASK.to_string(), 10),
+        ]);
+    }
+
+    #[test]
+    fn test_task_lines() {
+        let summary = Summary::new(&SummaryConfig { top_tasks: 2, ..SummaryConfig::default() });
+        let tasks = [("写报告 and more".to_string(), 60), ("Short".to_string(), 15), ("Third".to_string(), 5)];
+        let text = |lines: Vec<Line>| lines.iter().map(|line| line.to_string()).collect::<Vec<_>>();
+        assert_eq!(text(summary.task_lines(&tasks, 28, 10)), [
+            "",
+            "📝 Today by task (top 2 of 3):",
+            "• 写报告 and …   60m ███████",
+            "• Short          15m ██",
+        ]);
+        // Squeezed, then collapsed to the heading
+        assert_eq!(summary.task_lines(&tasks, 28, 3).len(), 3);
+        assert_eq!(text(summary.task_lines(&tasks, 28, 2)), ["", "📝 Today by task: 3 tasks"]);
+        assert!(summary.task_lines(&tasks, 28, 1).is_empty());
+    }
+
+    #[test]
+    fn test_personal_records() {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let today = day(20);
+        let with_pomodoros = |date, minutes, work_sessions| PomodoroSession { work_sessions, ..session(date, minutes) };
+        assert_eq!(personal_records(&[], today), Records::default());
+        assert_eq!(records_text(&Records::default()), "");
+
+        // A history that starts today sets every record without breaking any
+        let records = personal_records(&[with_pomodoros(today, 50, 2)], today);
+        assert_eq!(records.best_day, Some(Record { value: 50, date: today, new_today: false }));
+        assert_eq!(records.longest_streak, Some(Record { value: 1, date: today, new_today: false }));
+        assert_eq!(records_text(&records), "\n\n🏆 Records:\n• Best day: 0h 50m (2026-10-20)\n• Longest streak: 1 day\n• Most pomodoros in a day: 2");
+
+        // Ties stay with the earlier day; two sessions on one day add up
+        let mut sessions = vec![
+            with_pomodoros(day(3), 90, 4),
+            with_pomodoros(day(4), 30, 1),
+            with_pomodoros(day(5), 60, 2),
+            with_pomodoros(day(5), 30, 2), // 90 and 4 again
+            with_pomodoros(day(10), 20, 1),
+            with_pomodoros(day(11), 0, 0), // Nothing done: breaks the run
+            with_pomodoros(day(18), 45, 2),
+            with_pomodoros(day(19), 45, 2),
+        ];
+        let records = personal_records(&sessions, today);
+        assert_eq!(records.best_day, Some(Record { value: 90, date: day(3), new_today: false }));
+        assert_eq!(records.most_pomodoros, Some(Record { value: 4, date: day(3), new_today: false }));
+        assert_eq!(records.longest_streak, Some(Record { value: 3, date: day(5), new_today: false }));
+
+        // Today matching a record doesn't break it, going past one does
+        sessions.push(with_pomodoros(today, 95, 4));
+        let records = personal_records(&sessions, today);
+        assert_eq!(records.best_day, Some(Record { value: 95, date: today, new_today: true }));
+        assert_eq!(records.most_pomodoros, Some(Record { value: 4, date: day(3), new_today: false }));
+        assert_eq!(records.longest_streak, Some(Record { value: 3, date: day(5), new_today: false }));
+        sessions.push(with_pomodoros(day(17), 10, 1));
+        let records = personal_records(&sessions, today);
+        assert_eq!(records.longest_streak, Some(Record { value: 4, date: today, new_today: true }));
+        assert_eq!(
+            records_text(&records),
+            "\n\n🏆 Records:\n• Best day: 1h 35m (2026-10-20) ✨\n• Longest streak: 4 days ✨\n• Most pomodoros in a day: 4"
+        );
+    }
+
+    #[test]
+    fn test_format_average_minutes() {
+        assert_eq!(format_average_minutes(Some(24.6)), "25m");
+        assert_eq!(format_average_minutes(Some(25.0)), "25m");
+        assert_eq!(format_average_minutes(None), "—");
+    }
+
+    #[test]
+    fn test_goal_text() {
+        let mut summary = Summary::new(&SummaryConfig::default());
+        assert_eq!(summary.goal_text(77, 3), "\n• Daily goal: 2h 0m\n• Progress: 64%");
+        summary.daily_goal_pomodoros = Some(8);
+        assert_eq!(summary.goal_text(145, 5), "\n• Daily goal: 2h 0m\n• Progress: 100%\n• Pomodoros: 5 / 8");
+        summary.daily_goal_minutes = 0;
+        assert_eq!(summary.goal_text(145, 5), "\n• Pomodoros: 5 / 8");
+        summary.daily_goal_pomodoros = None;
+        assert_eq!(summary.goal_text(145, 5), "");
+    }
+
+    #[test]
+    fn test_step_day() {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let today = day(20);
+        let mut summary = Summary::new(&SummaryConfig::default());
+        summary.step_day(&[], today, true);
+        assert_eq!(summary.viewing, None);
+
+        let sessions = [session(day(12), 50), session(day(15), 25), session(day(15), 25), session(today, 10)];
+        summary.step_day(&sessions, today, true);
+        assert_eq!(summary.viewing, Some(day(15)));
+        summary.step_day(&sessions, today, true);
+        assert_eq!(summary.viewing, Some(day(12)));
+        // Nothing earlier: stays put
+        summary.step_day(&sessions, today, true);
+        assert_eq!(summary.viewing, Some(day(12)));
+        summary.step_day(&sessions, today, false);
+        assert_eq!(summary.viewing, Some(day(15)));
+        summary.step_day(&sessions, today, false);
+        assert_eq!(summary.viewing, None);
+        summary.step_day(&sessions, today, false);
+        assert_eq!(summary.viewing, None);
+
+        summary.step_day(&sessions, today, true);
+        summary.show_today();
+        assert_eq!(summary.viewing, None);
+    }
+
+    #[test]
+    fn test_day_text() {
+        let day = NaiveDate::from_ymd_opt(2026, 10, 13).unwrap();
+        let summary = Summary::new(&SummaryConfig::default());
+        let worked = PomodoroSession {
+            tasks_worked_on: vec!["Write report".to_string(), "Review".to_string()],
+            ratings: vec![4, 3],
+            work_sessions: 2,
+            break_sessions: 1,
+            total_break_minutes: 5,
+            ..session(day, 50)
+        };
+        let more = PomodoroSession { tasks_worked_on: vec!["Write report".to_string()], ..session(day, 25) };
+        assert_eq!(
+            summary.day_text(&[worked, more], day),
+            "\n📅 Tuesday, 13 October 2026:\n• Focus time: 1h 15m (3 sessions)\n• Breaks: 0h 5m (1 break)\n• Goal: 62%\n• Avg focus: 3.5/5\n\n📝 Worked on:\n• Write report\n• Review"
+        );
+        // A day of nothing but a break
+        let break_only = PomodoroSession { work_sessions: 0, break_sessions: 1, total_break_minutes: 15, ..session(day, 0) };
+        assert_eq!(
+            summary.day_text(&[break_only], day),
+            "\n📅 Tuesday, 13 October 2026:\n• Focus time: 0h 0m (0 sessions)\n• Breaks: 0h 15m (1 break)\n• Goal: 0%\n\n📝 No tasks recorded"
+        );
+    }
+
+    #[test]
+    fn test_week_stats() {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let sessions = [
+            PomodoroSession { work_sessions: 2, ..session(day(5), 50) },
+            PomodoroSession { work_sessions: 1, ..session(day(5), 25) },
+            PomodoroSession { work_sessions: 0, ..session(day(6), 0) }, // Only breaks
+            PomodoroSession { work_sessions: 4, ..session(day(8), 100) },
+            PomodoroSession { work_sessions: 3, ..session(day(12), 75) },
+        ];
+        let completed = [day(4), day(5), day(7), day(12)];
+        // Mon 5th to Wed 7th, the days after it left out
+        assert_eq!(
+            week_stats(&sessions, &completed, day(5), day(7)),
+            WeekStats { minutes: 75, sessions: 3, active_days: 1, tasks_done: 2 }
+        );
+        assert_eq!(week_stats(&sessions, &completed, day(12), day(14)), WeekStats { minutes: 75, sessions: 3, active_days: 1, tasks_done: 1 });
+        assert_eq!(week_stats(&[], &[], day(12), day(14)), WeekStats::default());
+    }
+
+    #[test]
+    fn test_change() {
+        assert_eq!(change(116, 100), ("▲ 16%".to_string(), DraculaTheme::GREEN));
+        assert_eq!(change(4, 6), ("▼ 33%".to_string(), DraculaTheme::RED));
+        assert_eq!(change(0, 6), ("▼ 100%".to_string(), DraculaTheme::RED));
+        assert_eq!(change(3, 0), ("▲ new".to_string(), DraculaTheme::GREEN));
+        assert_eq!(change(0, 0), ("=".to_string(), DraculaTheme::COMMENT));
+        assert_eq!(change(5, 5), ("=".to_string(), DraculaTheme::COMMENT));
+    }
+
+    #[test]
+    fn test_comparison_lines() {
+        let this_week = WeekStats { minutes: 185, sessions: 8, active_days: 3, tasks_done: 4 };
+        let last_week = WeekStats { minutes: 160, sessions: 8, active_days: 3, tasks_done: 6 };
+        let text = |lines: Vec<Line>| lines.iter().map(|line| line.to_string()).collect::<Vec<_>>();
+        let wide = text(comparison_lines(&this_week, &last_week, true));
+        assert_eq!(wide[0], "            This week Last week");
+        assert_eq!(wide[1], "Focus time      3h 5m    2h 40m  ▲ 15%");
+        assert_eq!(wide[4], "Tasks done          4         6  ▼ 33%");
+        let narrow = text(comparison_lines(&this_week, &last_week, false));
+        assert_eq!(narrow.len(), 8);
+        assert_eq!(narrow[0..2], ["Focus time: 3h 5m ▲ 15%", "  last week: 2h 40m"]);
+        assert_eq!(narrow[2], "Sessions: 8 =");
+    }
+
+    #[test]
+    fn test_report_period() {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let mut summary = Summary::new(&SummaryConfig::default());
+        assert_eq!(summary.report_period(day(16)), ReportPeriod::Day(day(16)));
+        summary.viewing = Some(day(9));
+        assert_eq!(summary.report_period(day(16)), ReportPeriod::Day(day(9)));
+        summary.cycle_view();
+        assert_eq!(summary.report_period(day(16)), ReportPeriod::Week(day(12)));
+        summary.cycle_view();
+        summary.page_month(true);
+        assert_eq!(summary.report_period(day(16)), ReportPeriod::Month(day(1) - Months::new(1)));
+    }
+
+    #[test]
+    fn test_goal_percent() {
+        assert_eq!(goal_percent(77, 120), 64);
+        assert_eq!(goal_percent(145, 120), 120);
+        assert_eq!(goal_percent(0, 120), 0);
+        assert_eq!(goal_percent(30, 0), 0);
+
+        let summary = Summary::new(&SummaryConfig::default());
+        let label = |minutes| {
+            let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(30, 1)).unwrap();
+            terminal.draw(|frame| frame.render_widget(summary.goal_gauge(minutes), frame.area())).unwrap();
+            terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect::<String>().trim_matches(['█', ' ']).to_string()
+        };
+        assert_eq!(label(77), "1h 17m / 2h 0m");
+        assert_eq!(label(145), "2h 25m / 2h 0m — 120%");
+    }
+
+    #[test]
+    fn test_heat() {
+        assert_eq!(Heat::of(0, 120), Heat::None);
+        assert_eq!(Heat::of(59, 120), Heat::Low);
+        assert_eq!(Heat::of(60, 120), Heat::Medium);
+        assert_eq!(Heat::of(120, 120), Heat::High);
+        // Without a goal any focus time reaches it
+        assert_eq!(Heat::of(5, 0), Heat::High);
+    }
+}
@@ -0,0 +1,205 @@
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use color_eyre::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::todo::{tag_of, Priority, TodoItem};
+
+/// A task in Taskwarrior's JSON format, as read by `task import`
+/// (https://taskwarrior.org/docs/design/task/). Timestamps are UTC, e.g. "20250630T144000Z".
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskwarriorTask {
+    pub uuid: String,
+    pub description: String,
+    pub status: String, // "pending" or "completed"
+    pub entry: String,
+    pub modified: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>, // When a completed task was done
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled: Option<String>, // Start of the day the task is planned for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>, // "H", "M" or "L"
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub depends: Vec<String>, // A task depends on its subtasks
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Annotation {
+    pub entry: String,
+    pub description: String,
+}
+
+/// The todo items as Taskwarrior tasks. Items need a uuid (see `Todo::assign_uuids`); one
+/// without is left out. #tags become Taskwarrior tags and each day of the timeline an annotation.
+pub fn tasks(items: &[TodoItem], now: DateTime<Local>) -> Vec<TaskwarriorTask> {
+    items.iter()
+        .filter_map(|item| {
+            let uuid = item.uuid.clone()?;
+            let worked: Vec<DateTime<Local>> = item.timeline.iter().map(|session| session.timestamp).collect();
+            // Tasks older than their creation time (added before it was kept) were entered by their first work
+            let entry = worked.iter().chain(&item.completions).min().copied().map_or(item.created_at, |first| first.min(item.created_at));
+            let end = item.done.then(|| {
+                item.completed_at.unwrap_or_else(|| worked.iter().chain(&item.completions).max().copied().unwrap_or(now))
+            });
+            let depends = items.iter()
+                .filter(|subtask| subtask.parent_id == Some(item.id))
+                .filter_map(|subtask| subtask.uuid.clone())
+                .collect();
+
+            Some(TaskwarriorTask {
+                uuid,
+                description: description(&item.task),
+                status: if item.done { "completed" } else { "pending" }.to_string(),
+                entry: timestamp(entry),
+                modified: timestamp(now),
+                end: end.map(timestamp),
+                scheduled: item.scheduled_for
+                    .and_then(|day| day.and_hms_opt(0, 0, 0)?.and_local_timezone(Local).earliest())
+                    .map(timestamp),
+                priority: item.priority.map(|priority| match priority {
+                    Priority::High => "H",
+                    Priority::Medium => "M",
+                    Priority::Low => "L",
+                }.to_string()),
+                tags: item.tags().iter().map(|tag| tag.to_string()).collect(),
+                annotations: item.timeline.iter()
+                    .map(|session| Annotation {
+                        entry: timestamp(session.timestamp),
+                        description: format!("sessio: {}min on {}", session.minutes, session.date.format("%Y-%m-%d")),
+                    })
+                    .collect(),
+                depends,
+            })
+        })
+        .collect()
+}
+
+/// Write the tasks as a JSON array to `path`, or to the default path when none is given
+pub fn write(tasks: &[TaskwarriorTask], path: Option<&Path>, today: NaiveDate) -> Result<PathBuf> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => default_path(today)?,
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(tasks)?)?;
+    Ok(path)
+}
+
+/// ~/.config/sessio/taskwarrior-YYYYMMDD.json
+pub fn default_path(date: NaiveDate) -> Result<PathBuf> {
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| color_eyre::eyre::eyre!("Could not find config directory"))?;
+    Ok(config_dir.join("sessio").join(format!("taskwarrior-{}.json", date.format("%Y%m%d"))))
+}
+
+/// A random (version 4) UUID
+pub fn new_uuid() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// Task text without its #tag words, which are exported as tags
+fn description(task: &str) -> String {
+    let words: Vec<&str> = task.split_whitespace()
+        .filter(|word| tag_of(word).is_none_or(|tag| tag.len() + 1 != word.len()))
+        .collect();
+    if words.is_empty() { task.to_string() } else { words.join(" ") }
+}
+
+/// Taskwarrior's date format, in UTC
+fn timestamp(time: DateTime<Local>) -> String {
+    time.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::at;
+    use crate::todo::WorkSession;
+
+    fn items() -> Vec<TodoItem> {
+        let mut report = TodoItem::new("Write #work report #q3".to_string());
+        report.id = 1;
+        report.uuid = Some("11111111-1111-4111-8111-111111111111".to_string());
+        report.priority = Some(Priority::High);
+        report.scheduled_for = Some(at(2025, 7, 2, 0, 0).date_naive());
+        report.timeline = vec![
+            WorkSession { date: at(2025, 6, 29, 0, 0).date_naive(), minutes: 50, timestamp: at(2025, 6, 29, 10, 0) },
+            WorkSession { date: at(2025, 6, 30, 0, 0).date_naive(), minutes: 25, timestamp: at(2025, 6, 30, 16, 40) },
+        ];
+
+        let mut outline = TodoItem::new("Outline".to_string());
+        outline.id = 2;
+        outline.parent_id = Some(1);
+        outline.done = true;
+        outline.uuid = Some("22222222-2222-4222-8222-222222222222".to_string());
+        outline.timeline = vec![WorkSession { date: at(2025, 6, 29, 0, 0).date_naive(), minutes: 25, timestamp: at(2025, 6, 29, 9, 25) }];
+
+        let mut unsaved = TodoItem::new("Not exported yet".to_string());
+        unsaved.id = 3;
+        vec![report, outline, unsaved]
+    }
+
+    #[test]
+    fn test_taskwarrior_mapping() {
+        let now = at(2025, 7, 1, 12, 0);
+        let tasks = tasks(&items(), now);
+        assert_eq!(tasks.len(), 2);
+
+        let report = &tasks[0];
+        assert_eq!(report.uuid, "11111111-1111-4111-8111-111111111111");
+        assert_eq!(report.description, "Write report");
+        assert_eq!(report.status, "pending");
+        assert_eq!(report.entry, timestamp(at(2025, 6, 29, 10, 0)));
+        assert_eq!(report.modified, timestamp(now));
+        assert_eq!(report.end, None);
+        assert_eq!(report.scheduled, Some(timestamp(at(2025, 7, 2, 0, 0))));
+        assert_eq!(report.priority.as_deref(), Some("H"));
+        assert_eq!(report.tags, ["work", "q3"]);
+        assert_eq!(report.annotations[1], Annotation {
+            entry: timestamp(at(2025, 6, 30, 16, 40)),
+            description: "sessio: 25min on 2025-06-30".to_string(),
+        });
+        assert_eq!(report.depends, ["22222222-2222-4222-8222-222222222222"]);
+
+        let outline = &tasks[1];
+        assert_eq!(outline.status, "completed");
+        assert_eq!(outline.end, Some(timestamp(at(2025, 6, 29, 9, 25))));
+    }
+
+    #[test]
+    fn test_taskwarrior_json() {
+        let json = serde_json::to_string(&tasks(&items(), at(2025, 7, 1, 12, 0))).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        // Dates are "YYYYMMDDTHHMMSSZ" in UTC; empty optional fields are left out
+        let entry = value[0]["entry"].as_str().unwrap();
+        assert_eq!(chrono::NaiveDateTime::parse_from_str(entry, "%Y%m%dT%H%M%SZ").unwrap().and_utc(), at(2025, 6, 29, 10, 0));
+        assert_eq!(value[0]["annotations"][0]["description"], "sessio: 50min on 2025-06-29");
+        assert!(value[1].get("priority").is_none());
+        assert!(value[1].get("tags").is_none());
+        assert!(value[1].get("depends").is_none());
+    }
+
+    #[test]
+    fn test_new_uuid() {
+        let uuid = new_uuid();
+        let groups: Vec<usize> = uuid.split('-').map(str::len).collect();
+        assert_eq!(groups, [8, 4, 4, 4, 12]);
+        assert!(uuid.chars().all(|c| c == '-' || c.is_ascii_hexdigit()));
+        assert_eq!(&uuid[14..15], "4");
+        assert!("89ab".contains(&uuid[19..20]));
+        assert_ne!(uuid, new_uuid());
+    }
+}
@@ -0,0 +1,22 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+
+use crate::timer::PomodoroSession;
+
+/// Local time at `hour:minute` on the given day
+pub fn at(y: i32, m: u32, d: u32, hour: u32, minute: u32) -> DateTime<Local> {
+    Local.with_ymd_and_hms(y, m, d, hour, minute, 0).unwrap()
+}
+
+/// A day with nothing recorded yet; tests fill in what they need with `..empty_session(date)`
+pub fn empty_session(date: NaiveDate) -> PomodoroSession {
+    PomodoroSession {
+        date,
+        work_sessions: 0,
+        total_work_minutes: 0,
+        break_sessions: 0,
+        total_break_minutes: 0,
+        tasks_worked_on: Vec::new(),
+        entries: Vec::new(),
+        ratings: Vec::new(),
+    }
+}
@@ -5,33 +5,69 @@ use ratatui::{
     Frame,
 };
 use std::time::{Duration, Instant};
-use rodio::{OutputStream, Sink, Decoder};
-use std::thread;
-use std::fs::File;
-use std::io::BufReader;
 use chrono::{DateTime, Local, NaiveDate};
 use std::sync::{Arc, Mutex};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
+use crate::alarm::{AlarmPlayer, AlarmSettings};
 use crate::app::{App, Quadrant};
 use crate::theme::DraculaTheme;
-use crate::todo::TodoItem;
-use crate::config::Config;
+use crate::todo::{truncate_to_width, Todo, TodoItem};
+use crate::config::{Config, QuietHours, SequenceStep, TimerMode};
+
+/// How long the focus rating prompt stays open before counting as skipped
+const RATING_PROMPT_SECONDS: u64 = 30;
 
 // Helper function to format duration
-fn format_duration(duration: Duration) -> String {
+pub fn format_duration(duration: Duration) -> String {
     let total_secs = duration.as_secs();
     let minutes = total_secs / 60;
     let seconds = total_secs % 60;
     format!("{:02}:{:02}", minutes, seconds)
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A longer stretch of time, like focus minutes or a music library, as "27h 13m"
+pub fn format_hours_minutes(minutes: u64) -> String {
+    format!("{}h {}m", minutes / 60, minutes % 60)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PomodoroPhase {
     Work,
     ShortBreak,
     LongBreak,
 }
 
+impl PomodoroPhase {
+    /// Label used in the session log and in todos.md
+    pub fn label(&self) -> &'static str {
+        match self {
+            PomodoroPhase::Work => "Work",
+            PomodoroPhase::ShortBreak => "Short break",
+            PomodoroPhase::LongBreak => "Long break",
+        }
+    }
+
+    fn from_step(step: SequenceStep) -> Self {
+        match step {
+            SequenceStep::Work => PomodoroPhase::Work,
+            SequenceStep::Short => PomodoroPhase::ShortBreak,
+            SequenceStep::Long => PomodoroPhase::LongBreak,
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "Work" => Some(PomodoroPhase::Work),
+            "Short break" => Some(PomodoroPhase::ShortBreak),
+            "Long break" => Some(PomodoroPhase::LongBreak),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TimerState {
     Stopped,
@@ -39,7 +75,7 @@ pub enum TimerState {
     Paused,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PomodoroSession {
     pub date: chrono::NaiveDate,
     pub work_sessions: u32,
@@ -47,59 +83,203 @@ pub struct PomodoroSession {
     pub break_sessions: u32,
     pub total_break_minutes: u32,
     pub tasks_worked_on: Vec<String>, // Task names that were worked on
+    pub entries: Vec<SessionEntry>, // Every finished phase of the day, in order
+    pub ratings: Vec<u8>, // Focus ratings (1-5) given after work sessions
+}
+
+/// A single finished phase in the day's session log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionEntry {
+    pub start: DateTime<Local>,
+    pub phase: PomodoroPhase,
+    pub minutes: u32,
+    pub task: Option<String>,
+    pub note: Option<String>, // What the session produced, asked for after a work phase
+}
+
+impl SessionEntry {
+    /// Format as a log line, e.g. "09:05 Work 25m (Write report): drafted intro section"
+    pub fn to_log_line(&self) -> String {
+        let task = if let Some(ref task) = self.task {
+            format!(" ({})", task)
+        } else {
+            String::new()
+        };
+        let note = if let Some(ref note) = self.note {
+            format!(": {}", note)
+        } else {
+            String::new()
+        };
+        format!("{} {} {}m{}{}", self.start.format("%H:%M"), self.phase.label(), self.minutes, task, note)
+    }
+
+    /// Parse a line written by to_log_line; the date comes from the enclosing day section
+    pub fn from_log_line(date: NaiveDate, line: &str) -> Option<Self> {
+        let (time_str, rest) = line.split_once(' ')?;
+        let time = chrono::NaiveTime::parse_from_str(time_str, "%H:%M").ok()?;
+        let start = date.and_time(time).and_local_timezone(Local).earliest()?;
+
+        let (phase, rest) = ["Work", "Short break", "Long break"].iter()
+            .find_map(|label| rest.strip_prefix(label).map(|r| (PomodoroPhase::from_label(label), r)))?;
+        let rest = rest.trim_start();
+        let digits_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let minutes = rest[..digits_end].parse::<u32>().ok()?;
+        let rest = rest[digits_end..].strip_prefix('m')?;
+
+        // What follows is " (task)", " (task): note", ": note" or nothing
+        let (task, note) = if let Some(rest) = rest.strip_prefix(" (") {
+            match rest.split_once("): ") {
+                Some((task, note)) => (Some(task), Some(note)),
+                None => (rest.strip_suffix(')'), None),
+            }
+        } else {
+            (None, rest.strip_prefix(": "))
+        };
+
+        Some(Self {
+            start,
+            phase: phase?,
+            minutes,
+            task: task.map(|t| t.to_string()),
+            note: note.map(|n| n.to_string()),
+        })
+    }
+}
+
+/// How far away the next long break is
+#[derive(Debug, Clone, PartialEq)]
+pub enum LongBreakCountdown {
+    InProgress,
+    Upcoming { sessions: u32, time: Duration }, // Work sessions left to finish, and the time until the break starts
+}
+
+/// A change of phase, reported once to the main loop
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseTransition {
+    pub from: PomodoroPhase,
+    pub to: PomodoroPhase,
+}
+
+/// Work minutes waiting to be added to the attached todo
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkCredit {
+    pub todo_id: u64,
+    pub minutes: u32,
+    pub finished: bool, // The work phase ended (the task is released); false for partial credit on reset
 }
 
 pub struct Timer {
     pub state: TimerState,
+    pub mode: TimerMode,
+    pub stopwatch_elapsed: Duration, // Time counted up in stopwatch mode
     pub phase: PomodoroPhase,
     pub pomodoro_count: u32,
     pub time_remaining: Duration,
     pub last_tick: Option<Instant>,
-    pub selected_todo_index: Option<usize>, // Track which TODO item is being timed
-    pub work_completed_flag: bool, // Flag to track when work session completes
+    pub selected_todo_id: Option<u64>, // Track which TODO item is being timed (by stable id)
+    pub selected_task_name: Option<String>, // Name of the attached TODO item, for the session log
+    pub pending_credit: Option<WorkCredit>, // Work time for the attached TODO, picked up by main
+    pub phase_transition: Option<PhaseTransition>, // Last phase change, picked up by main
+    pub work_started: bool, // A work phase (or the stopwatch) was just started from stopped, picked up by main
     pub session_data_updated_flag: bool, // Flag to track when session data has been updated
+    pub last_completed_work_minutes: u32, // Minutes actually worked in the last finished work phase
+    pub session_notes: bool, // Ask what got done after each finished work phase
+    pub focus_rating: bool, // Ask for a 1-5 focus rating after each finished work phase
+    pub rating_prompt_deadline: Option<Instant>, // The rating prompt is open until then
+    pub note_requested: bool, // A work phase finished and its note hasn't been asked for yet
     
     // Pomodoro durations (in seconds)
     pub work_duration: Duration,
     pub short_break_duration: Duration,
     pub long_break_duration: Duration,
     pub long_break_interval: u32, // Every N pomodoros
+    pub sequence: Vec<PomodoroPhase>, // Custom phase cycle; empty means the long_break_interval rule
+    pub sequence_position: usize, // Index of the current phase in `sequence`
+    pub task_work_duration: Option<Duration>, // Work length requested by the attached task
+    pub min_session_seconds: u64, // A skipped phase shorter than this isn't counted as a session
+    pub credit_partial_work: bool, // Record the worked minutes when a work phase is reset or stopped early
+    pub min_partial_minutes: u32, // ...but only if at least this many whole minutes were worked
+    
+    // Break suggestions
+    pub break_suggestions: Vec<String>,
+    pub long_break_suggestions: Vec<String>, // Falls back to break_suggestions when empty
+    pub break_suggestion: Option<String>, // Picked when a break begins
     
     // Daily session tracking
     pub daily_sessions: Vec<PomodoroSession>,
     pub current_session_start: Option<chrono::DateTime<chrono::Local>>,
+    pub history_scroll: usize, // How many entries the session log is scrolled back from the latest
+    pub last_history_height: usize, // Session log rows shown in the last render
     
     // Alarm settings
     pub alarm_volume: f32,
     pub alarm_duration_seconds: u64,
     pub alarm_file_path: Option<String>,
-    pub alarm_active: bool,
-    pub alarm_end_time: Option<Instant>,
+    pub alarm_fade_in_seconds: u64,
+    pub alarm_beep_frequency: f32, // Used when there is no alarm file
+    pub alarm_player: AlarmPlayer, // Plays the alarm sound on its own thread
+    pub alarm_started_at: Option<Instant>, // When the last alarm (sound or visual) began, for pulsing
+    pub quiet_mode: bool, // Silence alarms and flash the panel instead
+    pub quiet_hours: Option<QuietHours>, // Daily window that behaves like quiet mode
+    pub visual_alarm_end_time: Option<Instant>, // When the silent alarm stops flashing
 }
 
 impl Timer {
-    pub fn new(work_minutes: u64, short_break_minutes: u64, long_break_minutes: u64, sessions_until_long_break: u32, alarm_volume: f32, alarm_duration_seconds: u64, alarm_file_path: Option<String>) -> Self {
-        Self {
+    pub fn new(config: &Config) -> Self {
+        let work_minutes = config.timer.work_minutes;
+        let mut timer = Self {
             state: TimerState::Stopped,
+            mode: config.timer.mode,
+            stopwatch_elapsed: Duration::ZERO,
             phase: PomodoroPhase::Work,
             pomodoro_count: 0,
             time_remaining: Duration::from_secs(work_minutes * 60), // Convert minutes to seconds
             last_tick: None,
-            selected_todo_index: None,
-            work_completed_flag: false,
+            selected_todo_id: None,
+            selected_task_name: None,
+            pending_credit: None,
+            phase_transition: None,
+            work_started: false,
             session_data_updated_flag: false,
+            last_completed_work_minutes: 0,
+            session_notes: config.timer.session_notes,
+            focus_rating: config.timer.focus_rating,
+            rating_prompt_deadline: None,
+            note_requested: false,
             work_duration: Duration::from_secs(work_minutes * 60),        // Work duration
-            short_break_duration: Duration::from_secs(short_break_minutes * 60),   // Short break duration
-            long_break_duration: Duration::from_secs(long_break_minutes * 60),   // Long break duration
-            long_break_interval: sessions_until_long_break, // Long break every N pomodoros
+            short_break_duration: Duration::from_secs(config.timer.short_break_minutes * 60),   // Short break duration
+            long_break_duration: Duration::from_secs(config.timer.long_break_minutes * 60),   // Long break duration
+            long_break_interval: config.timer.sessions_until_long_break, // Long break every N pomodoros
+            sequence: config.timer.sequence.iter().flatten().map(|&step| PomodoroPhase::from_step(step)).collect(),
+            sequence_position: 0,
+            task_work_duration: None,
+            min_session_seconds: config.timer.min_session_seconds,
+            credit_partial_work: config.timer.credit_partial_work,
+            min_partial_minutes: config.timer.min_partial_minutes,
+            break_suggestions: config.timer.break_suggestions.clone(),
+            long_break_suggestions: config.timer.long_break_suggestions.clone(),
+            break_suggestion: None,
             daily_sessions: Vec::new(),
             current_session_start: None,
-            alarm_volume,
-            alarm_duration_seconds,
-            alarm_file_path,
-            alarm_active: false,
-            alarm_end_time: None,
+            history_scroll: 0,
+            last_history_height: 0,
+            alarm_volume: config.music.alarm_volume,
+            alarm_duration_seconds: config.music.alarm_duration_seconds,
+            alarm_file_path: config.music.alarm_file_path.clone(),
+            alarm_fade_in_seconds: config.music.fade_in_seconds,
+            alarm_beep_frequency: config.music.beep_frequency,
+            alarm_player: AlarmPlayer::new(),
+            alarm_started_at: None,
+            quiet_mode: config.timer.quiet_mode,
+            quiet_hours: config.timer.quiet_hours,
+            visual_alarm_end_time: None,
+        };
+        // A custom sequence may start with something other than work
+        if let Some(first) = timer.sequence.first() {
+            timer.phase = first.clone();
+            timer.time_remaining = timer.current_phase_duration();
         }
+        timer
     }
 
     pub fn render(&mut self, frame: &mut Frame, area: Rect, app: &App, todo_items: &[TodoItem]) {
@@ -110,21 +290,29 @@ impl Timer {
         
         let is_focused = app.focused_quadrant == Quadrant::TopLeft;
         
-        // Create layout within the timer panel for content and progress bar
-        let inner_area = if is_focused {
-            Block::default()
-                .borders(Borders::ALL)
-                .title("⏱️  Pomodoro Timer")
-                .border_style(Style::default().fg(DraculaTheme::PINK))
-                .inner(area)
+        let mut title = "⏱️  Pomodoro Timer".to_string();
+        if self.quiet_mode {
+            title.push_str(" 🔇");
+        }
+        if self.is_quiet_now() {
+            title.push_str(" 🌙");
+        }
+        
+        // Pulse the border while a silent (visual) alarm is showing
+        let border_color = if let Some(color) = self.visual_alarm_color() {
+            color
+        } else if is_focused {
+            DraculaTheme::PINK
         } else {
-            Block::default()
-                .borders(Borders::ALL)
-                .title("⏱️  Pomodoro Timer")
-                .border_style(Style::default().fg(DraculaTheme::COMMENT))
-                .inner(area)
+            DraculaTheme::COMMENT
         };
         
+        // Create layout within the timer panel for content and progress bar
+        let inner_area = Block::default()
+            .borders(Borders::ALL)
+            .title(title.as_str())
+            .inner(area);
+        
         let timer_layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
@@ -140,11 +328,7 @@ impl Timer {
         let time_display = format!("{:02}:{:02}", minutes, seconds);
         
         // Calculate progress percentage
-        let total_duration = match self.phase {
-            PomodoroPhase::Work => self.work_duration,
-            PomodoroPhase::ShortBreak => self.short_break_duration,
-            PomodoroPhase::LongBreak => self.long_break_duration,
-        };
+        let total_duration = self.current_phase_duration();
         let elapsed = total_duration.saturating_sub(self.time_remaining);
         let progress_ratio = if total_duration.as_secs() > 0 {
             (elapsed.as_secs() as f64 / total_duration.as_secs() as f64 * 100.0) as u16
@@ -167,13 +351,20 @@ impl Timer {
         };
         
         // Get selected task info
-        let selected_task_info = if let Some(index) = self.selected_todo_index {
-            if let Some(task) = todo_items.get(index) {
-                format!("\n🎯 Working on: {}", 
-                    if task.task.len() > 30 { 
-                        format!("{}...", &task.task[..30]) 
-                    } else { 
-                        task.task.clone() 
+        let selected_task_info = if let Some(id) = self.selected_todo_id {
+            if let Some(task) = todo_items.iter().find(|item| item.id == id) {
+                format!("\n🎯 Working on: {}{}{}", 
+                    truncate_to_width(&task.task, 30),
+                    if let Some(minutes) = task.session_minutes {
+                        format!(" ({}m session)", minutes)
+                    } else {
+                        String::new()
+                    },
+                    if let Some(estimate) = task.estimate {
+                        let default_minutes = (self.work_duration.as_secs() / 60) as u32;
+                        format!(" (pomodoro {} of {})", task.pomodoros_done(default_minutes) + 1, estimate)
+                    } else {
+                        String::new()
                     }
                 )
             } else {
@@ -183,40 +374,104 @@ impl Timer {
             String::new()
         };
         
-        let content = format!(
-            "{} {} Phase\nPomodoros completed: {}\n\n⏱️  {}\nStatus: {}{}",
-            phase_emoji,
-            phase_name,
-            self.pomodoro_count,
-            time_display,
-            state_text,
-            selected_task_info
-        );
+        let suggestion_info = match (&self.phase, &self.break_suggestion) {
+            (PomodoroPhase::Work, _) | (_, None) => String::new(),
+            (_, Some(suggestion)) => format!("\n{} Suggestion: {}", phase_emoji, suggestion),
+        };
         
-        // Render the main timer border first
-        let timer_block = if is_focused {
-            Block::default()
-                .borders(Borders::ALL)
-                .title("⏱️  Pomodoro Timer")
-                .title_style(Style::default().fg(phase_color))
-                .border_style(Style::default().fg(DraculaTheme::PINK))
-                .style(Style::default().bg(DraculaTheme::BACKGROUND))
+        let sequence_info = if self.sequence.is_empty() {
+            String::new()
         } else {
-            Block::default()
-                .borders(Borders::ALL)
-                .title("⏱️  Pomodoro Timer")
-                .title_style(Style::default().fg(phase_color))
-                .border_style(Style::default().fg(DraculaTheme::COMMENT))
-                .style(Style::default().bg(DraculaTheme::BACKGROUND))
+            format!(" (step {}/{})", self.sequence_position + 1, self.sequence.len())
         };
         
+        let rating_info = if let Some(deadline) = self.rating_prompt_deadline.filter(|_| self.is_rating_prompt_active()) {
+            let seconds_left = deadline.saturating_duration_since(Instant::now()).as_secs() + 1;
+            format!("\n⭐ Rate focus 1-5, Esc to skip ({}s)", seconds_left)
+        } else {
+            String::new()
+        };
+        
+        let long_break_info = match self.long_break_countdown() {
+            Some(LongBreakCountdown::InProgress) => "\n🌴 Long break in progress".to_string(),
+            Some(LongBreakCountdown::Upcoming { sessions, time }) => format!(
+                "\n🌴 Long break in {} session{} (~{} min)",
+                sessions,
+                if sessions == 1 { "" } else { "s" },
+                Self::round_to_minutes(time)
+            ),
+            None => String::new(),
+        };
+        
+        let content = if self.mode == TimerMode::Stopwatch {
+            format!(
+                "⏱️  STOPWATCH\nCounting up, Space stops and records\n\n⏱️  {}\nStatus: {}{}",
+                format_duration(self.stopwatch_elapsed),
+                state_text,
+                selected_task_info
+            )
+        } else {
+            format!(
+                "{} {} Phase{}\nPomodoros completed: {}{}\n\n⏱️  {}{}\nStatus: {}{}{}",
+                phase_emoji,
+                phase_name,
+                sequence_info,
+                self.pomodoro_count,
+                long_break_info,
+                time_display,
+                suggestion_info,
+                state_text,
+                selected_task_info,
+                rating_info
+            )
+        };
+        
+        // Render the main timer border first
+        let timer_block = Block::default()
+            .borders(Borders::ALL)
+            .title(title.as_str())
+            .title_style(Style::default().fg(phase_color))
+            .border_style(Style::default().fg(border_color))
+            .style(Style::default().bg(DraculaTheme::BACKGROUND));
+        
         frame.render_widget(timer_block, area);
         
+        // Show today's session log below the main content when there is vertical room
+        let content_height = content.lines().count() as u16;
+        let today = Local::now().date_naive();
+        let entry_count = self.daily_sessions.iter()
+            .find(|s| s.date == today)
+            .map(|s| s.entries.len())
+            .unwrap_or(0);
+        let (content_area, history_area) = if entry_count > 0 && timer_layout[0].height >= content_height + 3 {
+            let split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(content_height + 1), Constraint::Min(2)])
+                .split(timer_layout[0]);
+            (split[0], Some(split[1]))
+        } else {
+            (timer_layout[0], None)
+        };
+
         // Render main timer content
         let timer_content = Paragraph::new(content)
             .style(Style::default().fg(DraculaTheme::FOREGROUND).bg(DraculaTheme::BACKGROUND));
         
-        frame.render_widget(timer_content, timer_layout[0]);
+        frame.render_widget(timer_content, content_area);
+
+        if let Some(history_area) = history_area {
+            let history = self.history_lines(history_area.height.saturating_sub(1) as usize);
+            let history_widget = Paragraph::new(history)
+                .style(Style::default().fg(DraculaTheme::COMMENT).bg(DraculaTheme::BACKGROUND));
+            frame.render_widget(history_widget, history_area);
+        } else {
+            self.last_history_height = 0;
+        }
+
+        // A stopwatch has no end, so there is no progress to show
+        if self.mode == TimerMode::Stopwatch {
+            return;
+        }
 
         // Create progress bar (no border, just the bar)
         let progress_label = format!("{}% - {} elapsed", progress_ratio, format_duration(elapsed));
@@ -229,6 +484,46 @@ impl Timer {
         frame.render_widget(progress_bar, timer_layout[1]);
     }
 
+    /// Build the session log text: a header plus the entries that fit in `rows`, newest at the bottom
+    fn history_lines(&mut self, rows: usize) -> String {
+        self.last_history_height = rows;
+        let today = Local::now().date_naive();
+        let entries = match self.daily_sessions.iter().find(|s| s.date == today) {
+            Some(session) => &session.entries,
+            None => return String::new(),
+        };
+
+        let max_scroll = entries.len().saturating_sub(rows);
+        self.history_scroll = self.history_scroll.min(max_scroll);
+        let end = entries.len() - self.history_scroll;
+        let start = end.saturating_sub(rows);
+
+        let mut lines = vec![if self.history_scroll > 0 {
+            format!("📜 Today ({}) ↓{}", entries.len(), self.history_scroll)
+        } else {
+            format!("📜 Today ({})", entries.len())
+        }];
+        lines.extend(entries[start..end].iter().map(|e| e.to_log_line()));
+        lines.join("\n")
+    }
+
+    /// Scroll the session log towards older entries
+    pub fn scroll_history_up(&mut self) {
+        let today = Local::now().date_naive();
+        let len = self.daily_sessions.iter()
+            .find(|s| s.date == today)
+            .map(|s| s.entries.len())
+            .unwrap_or(0);
+        if self.history_scroll + self.last_history_height < len {
+            self.history_scroll += 1;
+        }
+    }
+
+    /// Scroll the session log towards the latest entry
+    pub fn scroll_history_down(&mut self) {
+        self.history_scroll = self.history_scroll.saturating_sub(1);
+    }
+
     // Timer functionality methods
     pub fn update(&mut self) {
         if self.state != TimerState::Running {
@@ -238,7 +533,9 @@ impl Timer {
         let now = Instant::now();
         if let Some(last_tick) = self.last_tick {
             let elapsed = now.duration_since(last_tick);
-            if elapsed >= self.time_remaining {
+            if self.mode == TimerMode::Stopwatch {
+                self.stopwatch_elapsed += elapsed;
+            } else if elapsed >= self.time_remaining {
                 // Timer finished
                 self.time_remaining = Duration::ZERO;
                 self.complete_phase();
@@ -259,191 +556,156 @@ impl Timer {
             self.play_alarm();
         }
         
+        // A finished phase ran its full length; a skipped one only as long as it actually ran
+        let phase_duration = self.current_phase_duration();
+        let elapsed = if is_skip {
+            phase_duration.saturating_sub(self.time_remaining)
+        } else {
+            phase_duration
+        };
+        let minutes = Self::round_to_minutes(elapsed);
+        let counts_as_session = !is_skip || elapsed.as_secs() >= self.min_session_seconds;
+
+        // Add the phase to today's session log
+        if counts_as_session || minutes > 0 {
+            let start = self.current_session_start.take()
+                .unwrap_or_else(|| Local::now() - chrono::Duration::seconds(elapsed.as_secs() as i64));
+            let entry = SessionEntry {
+                start,
+                phase: self.phase.clone(),
+                minutes,
+                task: if self.phase == PomodoroPhase::Work { self.selected_task_name.clone() } else { None },
+                note: None,
+            };
+            self.get_today_session().entries.push(entry);
+            self.history_scroll = 0;
+        }
+        self.current_session_start = None;
+        let previous_phase = self.phase.clone();
+
         match self.phase {
             PomodoroPhase::Work => {
                 // Record work session completion
-                // If skipped, calculate actual elapsed time; otherwise use full duration
-                let work_minutes = if is_skip {
-                    let elapsed = self.work_duration.saturating_sub(self.time_remaining);
-                    (elapsed.as_secs() / 60) as u32
-                } else {
-                    (self.work_duration.as_secs() / 60) as u32
-                };
-                
                 {
                     let today_session = self.get_today_session();
-                    today_session.work_sessions += 1;
-                    today_session.total_work_minutes += work_minutes;
+                    if counts_as_session {
+                        today_session.work_sessions += 1;
+                    }
+                    today_session.total_work_minutes += minutes;
                 }
+                self.last_completed_work_minutes = minutes;
                 
                 // Set the session data updated flag
                 self.session_data_updated_flag = true;
                 
-                // Set the flag when work completes and we have a selected TODO
-                if self.selected_todo_index.is_some() {
-                    self.work_completed_flag = true;
+                // Hand the worked time to the selected TODO
+                self.queue_work_credit(minutes, true);
+                
+                if self.session_notes && !is_skip {
+                    self.note_requested = true;
+                }
+                if self.focus_rating && !is_skip {
+                    self.rating_prompt_deadline = Some(Instant::now() + Duration::from_secs(RATING_PROMPT_SECONDS));
                 }
                 
-                self.pomodoro_count += 1;
-                // Clear session start time
-                self.current_session_start = None;
+                if counts_as_session {
+                    self.pomodoro_count += 1;
+                }
                 
                 // Decide next break type
-                if self.pomodoro_count % self.long_break_interval == 0 {
+                if counts_as_session && self.pomodoro_count % self.long_break_interval == 0 {
                     self.phase = PomodoroPhase::LongBreak;
-                    self.time_remaining = self.long_break_duration;
                 } else {
                     self.phase = PomodoroPhase::ShortBreak;
-                    self.time_remaining = self.short_break_duration;
                 }
             }
-            PomodoroPhase::ShortBreak => {
+            PomodoroPhase::ShortBreak | PomodoroPhase::LongBreak => {
                 // Record break completion
-                // If skipped, calculate actual elapsed time; otherwise use full duration
-                let break_minutes = if is_skip {
-                    let elapsed = self.short_break_duration.saturating_sub(self.time_remaining);
-                    (elapsed.as_secs() / 60) as u32
-                } else {
-                    (self.short_break_duration.as_secs() / 60) as u32
-                };
-                
                 {
                     let today_session = self.get_today_session();
-                    today_session.break_sessions += 1;
-                    today_session.total_break_minutes += break_minutes;
-                }
-                
-                // Set the session data updated flag
-                self.session_data_updated_flag = true;
-                
-                self.phase = PomodoroPhase::Work;
-                self.time_remaining = self.work_duration;
-            }
-            PomodoroPhase::LongBreak => {
-                // Record long break completion
-                // If skipped, calculate actual elapsed time; otherwise use full duration
-                let break_minutes = if is_skip {
-                    let elapsed = self.long_break_duration.saturating_sub(self.time_remaining);
-                    (elapsed.as_secs() / 60) as u32
-                } else {
-                    (self.long_break_duration.as_secs() / 60) as u32
-                };
-                
-                {
-                    let today_session = self.get_today_session();
-                    today_session.break_sessions += 1;
-                    today_session.total_break_minutes += break_minutes;
+                    if counts_as_session {
+                        today_session.break_sessions += 1;
+                    }
+                    today_session.total_break_minutes += minutes;
                 }
                 
                 // Set the session data updated flag
                 self.session_data_updated_flag = true;
                 
                 self.phase = PomodoroPhase::Work;
-                self.time_remaining = self.work_duration;
             }
         }
+        
+        // A custom sequence overrides the default work/break alternation
+        if !self.sequence.is_empty() {
+            self.sequence_position = (self.sequence_position + 1) % self.sequence.len();
+            self.phase = self.sequence[self.sequence_position].clone();
+        }
+        self.time_remaining = self.current_phase_duration();
+        if self.phase == PomodoroPhase::Work {
+            self.break_suggestion = None;
+        } else {
+            self.pick_break_suggestion();
+        }
+        self.phase_transition = Some(PhaseTransition { from: previous_phase, to: self.phase.clone() });
         self.state = TimerState::Stopped;
         self.last_tick = None;
     }
 
+    /// Choose what to suggest for the break that just began; kept until the break ends
+    fn pick_break_suggestion(&mut self) {
+        let pool = if self.phase == PomodoroPhase::LongBreak && !self.long_break_suggestions.is_empty() {
+            &self.long_break_suggestions
+        } else {
+            &self.break_suggestions
+        };
+        self.break_suggestion = if pool.is_empty() {
+            None
+        } else {
+            Some(pool[rand::thread_rng().gen_range(0..pool.len())].clone())
+        };
+    }
+
     /// Play an alarm sound when timer completes
     /// Sets the alarm state for coordinating with music volume
     fn play_alarm(&mut self) {
-        let alarm_volume = self.alarm_volume;
-        let alarm_duration = self.alarm_duration_seconds;
-        let alarm_file_path = self.alarm_file_path.clone();
+        let alarm_duration = Duration::from_secs(self.alarm_duration_seconds);
+        self.alarm_started_at = Some(Instant::now());
         
-        // Set alarm state
-        self.alarm_active = true;
-        self.alarm_end_time = Some(Instant::now() + Duration::from_secs(alarm_duration));
+        // In quiet mode (or quiet hours) there is no sound at all, only the flashing border
+        if self.quiet_mode || self.is_quiet_now() {
+            self.visual_alarm_end_time = Some(Instant::now() + alarm_duration);
+            return;
+        }
         
-        // Spawn a thread to play the alarm sound without blocking
-        thread::spawn(move || {
-            // Try to load alarm sound - first check configured path, then fallback to default locations
-            let alarm_path = if let Some(configured_path) = alarm_file_path {
-                // Expand ~ to home directory if present
-                let expanded_path = if configured_path.starts_with("~/") {
-                    if let Some(home) = dirs::home_dir() {
-                        home.join(&configured_path[2..])
-                    } else {
-                        std::path::PathBuf::from(configured_path)
-                    }
-                } else {
-                    std::path::PathBuf::from(configured_path)
-                };
-                
-                if expanded_path.exists() {
-                    Some(expanded_path)
-                } else {
-                    None // Configured file doesn't exist, fallback to default search
-                }
-            } else {
-                // No configured path, use default search behavior
-                if let Some(config_dir) = dirs::config_dir() {
-                    let sessio_config_dir = config_dir.join("sessio");
-                    let alarm_file = sessio_config_dir.join("alarm.wav");
-                    if alarm_file.exists() {
-                        Some(alarm_file)
-                    } else {
-                        // Try other common audio formats
-                        let extensions = ["alarm.mp3", "alarm.ogg", "alarm.flac", "alarm.m4a"];
-                        extensions.iter()
-                            .map(|ext| sessio_config_dir.join(ext))
-                            .find(|path| path.exists())
-                    }
-                } else {
-                    None
-                }
-            };
-
-            if let Ok((_stream, stream_handle)) = OutputStream::try_default() {
-                if let Ok(sink) = Sink::try_new(&stream_handle) {
-                    // Set alarm volume
-                    sink.set_volume(alarm_volume);
-                    
-                    if let Some(path) = alarm_path {
-                        // Play the audio file
-                        if let Ok(file) = File::open(&path) {
-                            let buf_reader = BufReader::new(file);
-                            if let Ok(source) = Decoder::new(buf_reader) {
-                                sink.append(source);
-                                
-                                // Wait for the specified alarm duration
-                                let start_time = std::time::Instant::now();
-                                while !sink.empty() && start_time.elapsed().as_secs() < alarm_duration {
-                                    std::thread::sleep(std::time::Duration::from_millis(100));
-                                }
-                                
-                                // Stop the alarm after the duration
-                                sink.stop();
-                                return;
-                            }
-                        }
-                    }
-                    
-                    // Fallback: create a simple beep tone for the duration if no audio file found
-                    let beep_count = (alarm_duration as f32 / 0.5).ceil() as u64; // Beep every 500ms
-                    for _ in 0..beep_count {
-                        print!("\x07"); // ASCII bell character
-                        std::io::Write::flush(&mut std::io::stdout()).ok();
-                        std::thread::sleep(std::time::Duration::from_millis(500));
-                    }
-                }
-            }
+        // Starting a new alarm stops one that is still playing, so quick skips don't stack up
+        self.alarm_player.start(AlarmSettings {
+            volume: self.alarm_volume,
+            duration: alarm_duration,
+            fade_in: Duration::from_secs(self.alarm_fade_in_seconds),
+            file_path: self.alarm_file_path.clone(),
+            beep_frequency: self.alarm_beep_frequency,
         });
     }
 
     pub fn start(&mut self) {
         match self.state {
             TimerState::Stopped | TimerState::Paused => {
+                if self.state == TimerState::Stopped
+                    && (self.phase == PomodoroPhase::Work || self.mode == TimerMode::Stopwatch) {
+                    self.work_started = true;
+                }
                 self.state = TimerState::Running;
                 self.last_tick = Some(Instant::now());
                 
-                // Record session start time for work phases
-                if self.phase == PomodoroPhase::Work && self.current_session_start.is_none() {
+                // Record when the phase was first started, for the session log
+                if self.current_session_start.is_none() {
                     self.current_session_start = Some(chrono::Local::now());
                 }
             }
+            TimerState::Running if self.mode == TimerMode::Stopwatch => {
+                self.stop_stopwatch();
+            }
             TimerState::Running => {
                 // Pause
                 self.state = TimerState::Paused;
@@ -452,22 +714,203 @@ impl Timer {
         }
     }
 
+    /// Stop the count-up and record its whole minutes as work, like a finished work phase
+    fn stop_stopwatch(&mut self) {
+        self.update();
+        self.state = TimerState::Stopped;
+        self.last_tick = None;
+
+        let minutes = (self.stopwatch_elapsed.as_secs() / 60) as u32;
+        let start = self.current_session_start.take()
+            .unwrap_or_else(|| Local::now() - chrono::Duration::seconds(self.stopwatch_elapsed.as_secs() as i64));
+        self.stopwatch_elapsed = Duration::ZERO;
+        if minutes == 0 {
+            return;
+        }
+
+        let task = self.selected_task_name.clone();
+        let today_session = self.get_today_session();
+        today_session.total_work_minutes += minutes;
+        today_session.entries.push(SessionEntry { start, phase: PomodoroPhase::Work, minutes, task, note: None });
+        self.history_scroll = 0;
+        self.session_data_updated_flag = true;
+
+        // Credit the attached task through the same path as a finished work phase
+        self.last_completed_work_minutes = minutes;
+        self.queue_work_credit(minutes, true);
+    }
+
+    /// Queue minutes for the attached TODO (if any); adds up with credit that hasn't been picked up yet
+    fn queue_work_credit(&mut self, minutes: u32, finished: bool) {
+        let Some(todo_id) = self.selected_todo_id else {
+            return;
+        };
+        match self.pending_credit {
+            Some(ref mut credit) if credit.todo_id == todo_id => {
+                credit.minutes += minutes;
+                credit.finished |= finished;
+            }
+            _ => self.pending_credit = Some(WorkCredit { todo_id, minutes, finished }),
+        }
+    }
+
+    /// Record the whole minutes of a work phase that is abandoned early (reset or stop), without counting a session
+    fn credit_partial_work(&mut self) {
+        if !self.credit_partial_work
+            || self.mode != TimerMode::Pomodoro
+            || self.phase != PomodoroPhase::Work
+            || self.state == TimerState::Stopped {
+            return;
+        }
+        self.update();
+        let elapsed = self.work_phase_duration().saturating_sub(self.time_remaining);
+        let minutes = (elapsed.as_secs() / 60) as u32;
+        if minutes == 0 || minutes < self.min_partial_minutes {
+            return;
+        }
+
+        let start = self.current_session_start
+            .unwrap_or_else(|| Local::now() - chrono::Duration::seconds(elapsed.as_secs() as i64));
+        let task = self.selected_task_name.clone();
+        let today_session = self.get_today_session();
+        today_session.total_work_minutes += minutes;
+        today_session.entries.push(SessionEntry { start, phase: PomodoroPhase::Work, minutes, task, note: None });
+        self.history_scroll = 0;
+        self.session_data_updated_flag = true;
+        self.queue_work_credit(minutes, false);
+    }
+
+    /// Switch between pomodoro and stopwatch; a running stopwatch is stopped (and recorded), a running pomodoro paused
+    pub fn toggle_mode(&mut self) {
+        match self.mode {
+            TimerMode::Pomodoro => {
+                if self.state == TimerState::Running {
+                    self.start(); // pauses
+                }
+                self.mode = TimerMode::Stopwatch;
+                self.state = TimerState::Stopped;
+                self.stopwatch_elapsed = Duration::ZERO;
+                self.current_session_start = None;
+            }
+            TimerMode::Stopwatch => {
+                if self.state == TimerState::Running {
+                    self.stop_stopwatch();
+                }
+                self.mode = TimerMode::Pomodoro;
+                self.state = if self.time_remaining < self.current_phase_duration() {
+                    TimerState::Paused
+                } else {
+                    TimerState::Stopped
+                };
+            }
+        }
+    }
+
     pub fn stop(&mut self) {
+        self.credit_partial_work();
         self.state = TimerState::Stopped;
         self.last_tick = None;
     }
 
     pub fn reset(&mut self) {
+        self.credit_partial_work();
         self.state = TimerState::Stopped;
         self.last_tick = None;
-        self.time_remaining = match self.phase {
-            PomodoroPhase::Work => self.work_duration,
+        self.current_session_start = None;
+        self.time_remaining = self.current_phase_duration();
+        self.stopwatch_elapsed = Duration::ZERO;
+    }
+
+    /// Full length of the phase the timer is currently in
+    pub fn current_phase_duration(&self) -> Duration {
+        match self.phase {
+            PomodoroPhase::Work => self.work_phase_duration(),
             PomodoroPhase::ShortBreak => self.short_break_duration,
             PomodoroPhase::LongBreak => self.long_break_duration,
+        }
+    }
+
+    /// Work sessions and time left until the next long break, following the custom sequence if there is one.
+    /// None when a sequence never reaches a long break.
+    pub fn long_break_countdown(&self) -> Option<LongBreakCountdown> {
+        if self.phase == PomodoroPhase::LongBreak {
+            return Some(LongBreakCountdown::InProgress);
+        }
+
+        // The upcoming phases, after the current one, until the long break
+        let upcoming: Vec<PomodoroPhase> = if self.sequence.is_empty() {
+            let interval = self.long_break_interval.max(1);
+            let sessions_left = interval - self.pomodoro_count % interval;
+            let mut phases = Vec::new();
+            for i in 0..sessions_left {
+                // The current work phase is already one of them
+                if i > 0 || self.phase != PomodoroPhase::Work {
+                    phases.push(PomodoroPhase::Work);
+                }
+                if i + 1 < sessions_left {
+                    phases.push(PomodoroPhase::ShortBreak);
+                }
+            }
+            phases
+        } else {
+            let len = self.sequence.len();
+            let steps: Vec<PomodoroPhase> = (1..=len)
+                .map(|offset| self.sequence[(self.sequence_position + offset) % len].clone())
+                .collect();
+            let long_at = steps.iter().position(|phase| *phase == PomodoroPhase::LongBreak)?;
+            steps[..long_at].to_vec()
         };
+
+        let mut sessions = upcoming.iter().filter(|phase| **phase == PomodoroPhase::Work).count() as u32;
+        if self.phase == PomodoroPhase::Work {
+            sessions += 1;
+        }
+        let time = upcoming.iter().fold(self.time_remaining, |total, phase| total + match phase {
+            PomodoroPhase::Work => self.work_phase_duration(),
+            PomodoroPhase::ShortBreak => self.short_break_duration,
+            PomodoroPhase::LongBreak => self.long_break_duration,
+        });
+        Some(LongBreakCountdown::Upcoming { sessions, time })
+    }
+
+    /// Round a duration to the nearest whole minute
+    fn round_to_minutes(duration: Duration) -> u32 {
+        ((duration.as_secs() + 30) / 60) as u32
+    }
+    
+    /// Jump straight to a phase without counting a session; ignored while running or in stopwatch mode.
+    /// Paused work is credited first, the way a reset credits it. With a custom sequence, the position
+    /// moves to the next step of that phase (if it has one). Reported to main like any phase change.
+    pub fn switch_phase(&mut self, phase: PomodoroPhase) {
+        if self.state == TimerState::Running || self.mode == TimerMode::Stopwatch {
+            return;
+        }
+        self.credit_partial_work();
+        let previous_phase = self.phase.clone();
+        let len = self.sequence.len();
+        if let Some(offset) = (0..len).find(|offset| self.sequence[(self.sequence_position + offset) % len] == phase) {
+            self.sequence_position = (self.sequence_position + offset) % len;
+        }
+        self.phase = phase;
+        self.state = TimerState::Stopped;
+        self.last_tick = None;
+        self.current_session_start = None;
+        self.time_remaining = self.current_phase_duration();
+        if self.phase == PomodoroPhase::Work {
+            self.break_suggestion = None;
+        } else {
+            self.pick_break_suggestion();
+        }
+        if previous_phase != self.phase {
+            self.phase_transition = Some(PhaseTransition { from: previous_phase, to: self.phase.clone() });
+        }
     }
     
     pub fn skip_phase(&mut self) {
+        // There are no phases to skip in stopwatch mode
+        if self.mode == TimerMode::Stopwatch {
+            return;
+        }
         self.complete_phase_internal(true);
     }
     
@@ -475,12 +918,35 @@ impl Timer {
         self.start(); // start() already handles the toggle logic
     }
     
-    pub fn set_selected_todo(&mut self, index: Option<usize>) {
-        self.selected_todo_index = index;
+    pub fn set_selected_todo(&mut self, id: Option<u64>) {
+        self.selected_todo_id = id;
+        if id.is_none() {
+            self.selected_task_name = None;
+            self.set_task_session_minutes(None);
+        }
+    }
+
+    /// Length of the current (or next) work phase: the attached task's custom length or the configured default
+    pub fn work_phase_duration(&self) -> Duration {
+        self.task_work_duration.unwrap_or(self.work_duration)
+    }
+
+    /// Use a custom work length for the attached task (None falls back to the configured default).
+    /// Time already spent in the current work phase is kept, so only the remaining time changes.
+    pub fn set_task_session_minutes(&mut self, minutes: Option<u32>) {
+        let new_duration = minutes.map(|m| Duration::from_secs(m as u64 * 60));
+        if self.phase == PomodoroPhase::Work {
+            let elapsed = self.work_phase_duration().saturating_sub(self.time_remaining);
+            self.task_work_duration = new_duration;
+            self.time_remaining = self.work_phase_duration().saturating_sub(elapsed);
+        } else {
+            self.task_work_duration = new_duration;
+        }
     }
     
-    pub fn set_selected_todo_with_task_name(&mut self, index: Option<usize>, task_name: Option<String>) {
-        self.selected_todo_index = index;
+    pub fn set_selected_todo_with_task_name(&mut self, id: Option<u64>, task_name: Option<String>) {
+        self.selected_todo_id = id;
+        self.selected_task_name = task_name.clone();
         
         // Add task name to today's session if provided
         if let Some(name) = task_name {
@@ -491,26 +957,92 @@ impl Timer {
         }
     }
     
-    pub fn get_selected_todo(&self) -> Option<usize> {
-        self.selected_todo_index
+    /// Follow a rename of the attached task so the log uses its new text
+    pub fn rename_selected_todo(&mut self, id: u64, task_name: String) {
+        if self.selected_todo_id == Some(id) {
+            self.selected_task_name = Some(task_name);
+        }
+    }
+    
+    pub fn get_selected_todo(&self) -> Option<u64> {
+        self.selected_todo_id
+    }
+    
+    /// Keep the attached task valid after the todo list changed. A task that was completed is
+    /// replaced by the next unfinished one (with `attach_next`), a deleted one is dropped.
+    pub fn sync_attachment(&mut self, todo: &Todo, attach_next: bool) {
+        let Some(id) = self.get_selected_todo() else {
+            return;
+        };
+
+        match todo.index_of_id(id) {
+            Some(index) if !todo.items[index].done => {
+                // The task may have been renamed
+                self.rename_selected_todo(id, todo.items[index].task.clone());
+            }
+            Some(_) if attach_next => {
+                // The completed task has moved to the bottom, so the next one now sits at the selection
+                if let Some(next) = todo.next_unfinished_from(todo.selected_index) {
+                    self.set_selected_todo_with_task_name(Some(next.id), Some(next.task.clone()));
+                    self.set_task_session_minutes(next.session_minutes);
+                } else {
+                    self.set_selected_todo(None);
+                }
+            }
+            _ => self.set_selected_todo(None),
+        }
+    }
+    
+    /// Whether work was started since the last call (resuming from pause doesn't count)
+    pub fn take_work_start(&mut self) -> bool {
+        std::mem::take(&mut self.work_started)
+    }
+    
+    /// Take the phase change since the last call, if any
+    pub fn take_phase_transition(&mut self) -> Option<PhaseTransition> {
+        self.phase_transition.take()
+    }
+    
+    /// Take the work time waiting to be added to a TODO, if any
+    pub fn take_work_credit(&mut self) -> Option<WorkCredit> {
+        self.pending_credit.take()
+    }
+    
+    /// Whether to ask for a note about the work phase that just finished (only reported once)
+    pub fn take_note_request(&mut self) -> bool {
+        std::mem::take(&mut self.note_requested)
     }
     
-    // Returns the time that should be added to the TODO item when work phase completes
-    // Returns the work duration in minutes
-    pub fn get_work_session_minutes(&self) -> u32 {
-        (self.work_duration.as_secs() / 60) as u32
+    /// Whether the focus rating prompt is open (it closes by itself after RATING_PROMPT_SECONDS)
+    pub fn is_rating_prompt_active(&self) -> bool {
+        self.rating_prompt_deadline.is_some_and(|deadline| Instant::now() < deadline)
     }
     
-    // Check if a work phase just completed (to add time to TODO)
-    pub fn work_phase_just_completed(&self) -> bool {
-        self.work_completed_flag && self.selected_todo_index.is_some()
+    /// Record a 1-5 focus rating for today and close the prompt
+    pub fn rate_focus(&mut self, rating: u8) {
+        if !self.is_rating_prompt_active() || !(1..=5).contains(&rating) {
+            return;
+        }
+        self.rating_prompt_deadline = None;
+        self.get_today_session().ratings.push(rating);
+        self.session_data_updated_flag = true;
     }
     
-    // Clear the work completed flag after processing
-    pub fn clear_work_completed_flag(&mut self) {
-        self.work_completed_flag = false;
+    /// Close the focus rating prompt without rating
+    pub fn skip_focus_rating(&mut self) {
+        self.rating_prompt_deadline = None;
     }
     
+    /// Attach a note to today's most recent work entry
+    pub fn set_last_work_note(&mut self, note: String) {
+        let today_session = self.get_today_session();
+        if let Some(entry) = today_session.entries.iter_mut().rev().find(|e| e.phase == PomodoroPhase::Work) {
+            entry.note = Some(note);
+            self.session_data_updated_flag = true;
+        }
+    }
+
+    
     // Session tracking methods
     pub fn get_today_session(&mut self) -> &mut PomodoroSession {
         let today = chrono::Local::now().date_naive();
@@ -527,6 +1059,8 @@ impl Timer {
                 break_sessions: 0,
                 total_break_minutes: 0,
                 tasks_worked_on: Vec::new(),
+                entries: Vec::new(),
+                ratings: Vec::new(),
             });
         }
         
@@ -547,24 +1081,89 @@ impl Timer {
         }
     }
     
+    /// Short status for the terminal title, e.g. "🍅 24:00 — Write report" or "☕ 04:12"
+    pub fn terminal_title(&self) -> String {
+        let emoji = match self.phase {
+            PomodoroPhase::Work => "🍅",
+            PomodoroPhase::ShortBreak => "☕",
+            PomodoroPhase::LongBreak => "🌴",
+        };
+        if self.mode == TimerMode::Stopwatch {
+            let time = format_duration(self.stopwatch_elapsed);
+            return match self.selected_task_name {
+                Some(ref task) => format!("⏱️ {} — {}", time, task),
+                None => format!("⏱️ {}", time),
+            };
+        }
+        let time = format_duration(self.time_remaining);
+        match (&self.phase, &self.selected_task_name) {
+            (PomodoroPhase::Work, Some(task)) => format!("{} {} — {}", emoji, time, task),
+            _ => format!("{} {}", emoji, time),
+        }
+    }
+    
+    /// Toggle quiet mode: alarms flash the timer panel instead of making sound
+    pub fn toggle_quiet_mode(&mut self) {
+        self.quiet_mode = !self.quiet_mode;
+    }
+    
+    /// Whether the configured quiet hours are in effect right now
+    pub fn is_quiet_now(&self) -> bool {
+        self.is_quiet_at(Local::now().time())
+    }
+    
+    fn is_quiet_at(&self, time: chrono::NaiveTime) -> bool {
+        self.quiet_hours.is_some_and(|hours| hours.contains(time))
+    }
+    
+    /// Check if the silent alarm is still flashing
+    pub fn is_visual_alarm_active(&self) -> bool {
+        self.visual_alarm_end_time.is_some_and(|end| Instant::now() < end)
+    }
+    
+    /// Border color for the flashing silent alarm, alternating every 500ms
+    fn visual_alarm_color(&self) -> Option<ratatui::style::Color> {
+        let end = self.visual_alarm_end_time?;
+        let now = Instant::now();
+        if now >= end {
+            return None;
+        }
+        let remaining_ms = end.duration_since(now).as_millis();
+        Some(if (remaining_ms / 500) % 2 == 0 { DraculaTheme::RED } else { DraculaTheme::PINK })
+    }
+    
     /// Update alarm state and return true if alarm should still be active
     pub fn update_alarm_state(&mut self) -> bool {
-        if self.alarm_active {
-            if let Some(end_time) = self.alarm_end_time {
-                if Instant::now() >= end_time {
-                    self.alarm_active = false;
-                    self.alarm_end_time = None;
-                    return false;
-                }
-                return true;
-            }
-        }
-        false
+        self.alarm_player.is_active()
     }
     
-    /// Check if alarm is currently active
+    /// Check if alarm is currently active (sounding or flashing)
     pub fn is_alarm_active(&self) -> bool {
-        self.alarm_active
+        self.alarm_player.is_active() || self.is_visual_alarm_active()
+    }
+    
+    /// Play the alarm as a preview (or stop a preview that is still going); session stats and phase are untouched
+    pub fn toggle_alarm_preview(&mut self) {
+        if self.is_alarm_active() {
+            self.dismiss_alarm();
+        } else {
+            self.play_alarm();
+        }
+    }
+    
+    /// Whether an active alarm is in the "on" half of its 500ms pulse
+    pub fn alarm_pulse_on(&self) -> bool {
+        if !self.is_alarm_active() {
+            return false;
+        }
+        self.alarm_started_at
+            .is_some_and(|start| (start.elapsed().as_millis() / 500) % 2 == 0)
+    }
+    
+    /// Stop the alarm sound and the flashing border early
+    pub fn dismiss_alarm(&mut self) {
+        self.alarm_player.stop();
+        self.visual_alarm_end_time = None;
     }
     
     /// Get alarm volume setting
@@ -581,4 +1180,522 @@ impl Timer {
     pub fn clear_session_data_updated_flag(&mut self) {
         self.session_data_updated_flag = false;
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_hours_minutes() {
+        assert_eq!(format_hours_minutes(0), "0h 0m");
+        assert_eq!(format_hours_minutes(45), "0h 45m");
+        assert_eq!(format_hours_minutes(1633), "27h 13m");
+    }
+
+    #[test]
+    fn test_skip_records_elapsed_minutes() {
+        let mut timer = Timer::new(&Config::default());
+        // 3 minutes 40 seconds into a 25 minute work phase
+        timer.time_remaining = timer.work_duration - Duration::from_secs(220);
+        timer.skip_phase();
+
+        let today = timer.get_today_session().clone();
+        assert_eq!(today.total_work_minutes, 4);
+        assert_eq!(today.work_sessions, 1);
+        assert_eq!(timer.last_completed_work_minutes, 4);
+        assert_eq!(timer.pomodoro_count, 1);
+        assert_eq!(timer.phase, PomodoroPhase::ShortBreak);
+    }
+
+    #[test]
+    fn test_short_skip_is_not_a_session() {
+        let mut timer = Timer::new(&Config::default());
+        timer.time_remaining = timer.work_duration - Duration::from_secs(20);
+        timer.skip_phase();
+
+        let today = timer.get_today_session().clone();
+        assert_eq!(today.total_work_minutes, 0);
+        assert_eq!(today.work_sessions, 0);
+        assert_eq!(timer.pomodoro_count, 0);
+        // An uncounted skip never triggers the long break
+        assert_eq!(timer.phase, PomodoroPhase::ShortBreak);
+
+        // Skipping the untouched break records nothing either
+        timer.skip_phase();
+        let today = timer.get_today_session().clone();
+        assert_eq!(today.break_sessions, 0);
+        assert_eq!(today.total_break_minutes, 0);
+        assert_eq!(timer.phase, PomodoroPhase::Work);
+    }
+
+    #[test]
+    fn test_session_entry_log_line_round_trip() {
+        let date = NaiveDate::from_ymd_opt(2025, 6, 30).unwrap();
+        let start = date.and_hms_opt(9, 5, 0).unwrap().and_local_timezone(Local).earliest().unwrap();
+        let work = SessionEntry {
+            start,
+            phase: PomodoroPhase::Work,
+            minutes: 25,
+            task: Some("Write report (draft)".to_string()),
+            note: None,
+        };
+        assert_eq!(work.to_log_line(), "09:05 Work 25m (Write report (draft))");
+
+        let parsed = SessionEntry::from_log_line(date, &work.to_log_line()).unwrap();
+        assert_eq!(parsed.start, start);
+        assert_eq!(parsed.phase, PomodoroPhase::Work);
+        assert_eq!(parsed.minutes, 25);
+        assert_eq!(parsed.task.as_deref(), Some("Write report (draft)"));
+
+        let parsed = SessionEntry::from_log_line(date, "09:32 Short break 5m").unwrap();
+        assert_eq!(parsed.phase, PomodoroPhase::ShortBreak);
+        assert_eq!(parsed.minutes, 5);
+        assert!(parsed.task.is_none());
+
+        assert!(SessionEntry::from_log_line(date, "not a log line").is_none());
+    }
+
+    #[test]
+    fn test_session_entry_note_round_trip() {
+        let date = NaiveDate::from_ymd_opt(2025, 6, 30).unwrap();
+        let start = date.and_hms_opt(9, 5, 0).unwrap().and_local_timezone(Local).earliest().unwrap();
+        let mut work = SessionEntry {
+            start,
+            phase: PomodoroPhase::Work,
+            minutes: 25,
+            task: Some("Write report (draft)".to_string()),
+            note: Some("drafted intro section".to_string()),
+        };
+        assert_eq!(work.to_log_line(), "09:05 Work 25m (Write report (draft)): drafted intro section");
+        let parsed = SessionEntry::from_log_line(date, &work.to_log_line()).unwrap();
+        assert_eq!(parsed.task.as_deref(), Some("Write report (draft)"));
+        assert_eq!(parsed.note.as_deref(), Some("drafted intro section"));
+
+        work.task = None;
+        assert_eq!(work.to_log_line(), "09:05 Work 25m: drafted intro section");
+        let parsed = SessionEntry::from_log_line(date, &work.to_log_line()).unwrap();
+        assert!(parsed.task.is_none());
+        assert_eq!(parsed.note.as_deref(), Some("drafted intro section"));
+    }
+
+    #[test]
+    fn test_note_requested_after_finished_work() {
+        let mut config = Config::default();
+        config.timer.session_notes = true;
+        let mut timer = Timer::new(&config);
+        timer.quiet_mode = true;
+
+        // Skipping doesn't ask for a note
+        timer.skip_phase();
+        timer.skip_phase();
+        assert!(!timer.take_note_request());
+
+        timer.complete_phase_internal(false);
+        assert!(timer.take_note_request());
+        assert!(!timer.take_note_request());
+
+        timer.set_last_work_note("drafted intro".to_string());
+        let entries = &timer.get_today_session().entries;
+        assert_eq!(entries.last().unwrap().note.as_deref(), Some("drafted intro"));
+    }
+
+    #[test]
+    fn test_completed_phases_are_logged() {
+        let mut timer = Timer::new(&Config::default());
+        timer.set_selected_todo_with_task_name(Some(0), Some("Write report".to_string()));
+        timer.time_remaining = timer.work_duration - Duration::from_secs(600);
+        timer.skip_phase();
+
+        let entries = timer.get_today_session().entries.clone();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].phase, PomodoroPhase::Work);
+        assert_eq!(entries[0].minutes, 10);
+        assert_eq!(entries[0].task.as_deref(), Some("Write report"));
+    }
+
+    #[test]
+    fn test_quiet_hours_wrapping_midnight() {
+        let mut timer = Timer::new(&Config::default());
+        let at = |h, m| chrono::NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert!(!timer.is_quiet_at(at(23, 0)));
+
+        timer.quiet_hours = Some(QuietHours::parse("22:00-07:00").unwrap());
+        assert!(timer.is_quiet_at(at(22, 0)));
+        assert!(timer.is_quiet_at(at(23, 59)));
+        assert!(timer.is_quiet_at(at(0, 0)));
+        assert!(timer.is_quiet_at(at(6, 59)));
+        assert!(!timer.is_quiet_at(at(7, 0)));
+        assert!(!timer.is_quiet_at(at(12, 0)));
+        assert!(!timer.is_quiet_at(at(21, 59)));
+    }
+
+    #[test]
+    fn test_quiet_hours_same_day() {
+        let mut timer = Timer::new(&Config::default());
+        let at = |h, m| chrono::NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        timer.quiet_hours = Some(QuietHours::parse("12:30-13:30").unwrap());
+        assert!(!timer.is_quiet_at(at(12, 29)));
+        assert!(timer.is_quiet_at(at(12, 30)));
+        assert!(timer.is_quiet_at(at(13, 0)));
+        assert!(!timer.is_quiet_at(at(13, 30)));
+        assert!(!timer.is_quiet_at(at(0, 0)));
+    }
+
+    #[test]
+    fn test_custom_sequence_cycles() {
+        let mut config = Config::default();
+        config.timer.sequence = Some(vec![SequenceStep::Work, SequenceStep::Short, SequenceStep::Work, SequenceStep::Long]);
+        let mut timer = Timer::new(&config);
+        timer.quiet_mode = true;
+        assert_eq!(timer.phase, PomodoroPhase::Work);
+
+        let expected = [
+            PomodoroPhase::ShortBreak,
+            PomodoroPhase::Work,
+            PomodoroPhase::LongBreak,
+            PomodoroPhase::Work,
+            PomodoroPhase::ShortBreak,
+        ];
+        for phase in expected {
+            timer.complete_phase_internal(false);
+            assert_eq!(timer.phase, phase);
+            assert_eq!(timer.time_remaining, timer.current_phase_duration());
+        }
+        assert_eq!(timer.sequence_position, 1);
+
+        // Skipping moves on too, and reset stays at the current step
+        timer.skip_phase();
+        assert_eq!(timer.phase, PomodoroPhase::Work);
+        timer.reset();
+        assert_eq!(timer.sequence_position, 2);
+        assert_eq!(timer.phase, PomodoroPhase::Work);
+    }
+
+    #[test]
+    fn test_sequence_may_start_with_break() {
+        let mut config = Config::default();
+        config.timer.sequence = Some(vec![SequenceStep::Short, SequenceStep::Work]);
+        let timer = Timer::new(&config);
+        assert_eq!(timer.phase, PomodoroPhase::ShortBreak);
+        assert_eq!(timer.time_remaining, timer.short_break_duration);
+    }
+
+    #[test]
+    fn test_stopwatch_records_whole_minutes() {
+        let mut timer = Timer::new(&Config::default());
+        timer.toggle_mode();
+        assert_eq!(timer.mode, TimerMode::Stopwatch);
+        timer.set_selected_todo(Some(7));
+        timer.selected_task_name = Some("Unplanned fix".to_string());
+
+        timer.start();
+        assert_eq!(timer.state, TimerState::Running);
+        timer.stopwatch_elapsed = Duration::from_secs(12 * 60 + 59);
+        timer.last_tick = Some(Instant::now());
+        timer.toggle_start_pause();
+
+        assert_eq!(timer.state, TimerState::Stopped);
+        assert_eq!(timer.stopwatch_elapsed, Duration::ZERO);
+        assert_eq!(timer.take_work_credit(), Some(WorkCredit { todo_id: 7, minutes: 12, finished: true }));
+        assert_eq!(timer.last_completed_work_minutes, 12);
+        let today = timer.get_today_session();
+        assert_eq!(today.total_work_minutes, 12);
+        assert_eq!(today.work_sessions, 0);
+        assert_eq!(today.entries[0].task.as_deref(), Some("Unplanned fix"));
+    }
+
+    #[test]
+    fn test_stopwatch_under_a_minute_records_nothing() {
+        let mut timer = Timer::new(&Config::default());
+        timer.toggle_mode();
+        timer.set_selected_todo(Some(7));
+        timer.start();
+        timer.stopwatch_elapsed = Duration::from_secs(40);
+        timer.toggle_start_pause();
+
+        assert!(timer.take_work_credit().is_none());
+        assert_eq!(timer.get_today_session().total_work_minutes, 0);
+
+        // Switching back leaves the pomodoro where it was
+        timer.toggle_mode();
+        assert_eq!(timer.mode, TimerMode::Pomodoro);
+        assert_eq!(timer.phase, PomodoroPhase::Work);
+        assert_eq!(timer.state, TimerState::Stopped);
+    }
+
+    #[test]
+    fn test_reset_credits_partial_work() {
+        let mut timer = Timer::new(&Config::default());
+        timer.set_selected_todo(Some(3));
+        timer.start();
+        timer.start(); // pause
+        timer.time_remaining = timer.work_duration - Duration::from_secs(18 * 60 + 30);
+        timer.reset();
+
+        assert_eq!(timer.take_work_credit(), Some(WorkCredit { todo_id: 3, minutes: 18, finished: false }));
+        assert_eq!(timer.get_selected_todo(), Some(3));
+        let today = timer.get_today_session().clone();
+        assert_eq!(today.total_work_minutes, 18);
+        assert_eq!(today.work_sessions, 0);
+        assert_eq!(today.entries.len(), 1);
+        assert_eq!(timer.time_remaining, timer.work_duration);
+
+        // Resetting again (nothing elapsed, timer stopped) credits nothing more
+        timer.reset();
+        assert!(timer.take_work_credit().is_none());
+    }
+
+    #[test]
+    fn test_partial_credit_threshold_and_flag() {
+        let mut config = Config::default();
+        config.timer.min_partial_minutes = 5;
+        let mut timer = Timer::new(&config);
+        timer.start();
+        timer.time_remaining = timer.work_duration - Duration::from_secs(4 * 60 + 50);
+        timer.stop();
+        assert_eq!(timer.get_today_session().total_work_minutes, 0);
+
+        config.timer.credit_partial_work = false;
+        let mut timer = Timer::new(&config);
+        timer.start();
+        timer.time_remaining = timer.work_duration - Duration::from_secs(20 * 60);
+        timer.reset();
+        assert_eq!(timer.get_today_session().total_work_minutes, 0);
+    }
+
+    #[test]
+    fn test_work_start_reported_only_from_stopped() {
+        let mut timer = Timer::new(&Config::default());
+        timer.quiet_mode = true;
+        timer.start();
+        assert!(timer.take_work_start());
+        assert!(!timer.take_work_start());
+
+        // Pausing and resuming isn't a new start
+        timer.start();
+        timer.start();
+        assert!(!timer.take_work_start());
+
+        // Neither is starting a break
+        timer.complete_phase_internal(false);
+        timer.start();
+        assert!(!timer.take_work_start());
+    }
+
+    #[test]
+    fn test_phase_transitions_are_reported_once() {
+        let mut timer = Timer::new(&Config::default());
+        timer.quiet_mode = true;
+        assert!(timer.take_phase_transition().is_none());
+
+        timer.complete_phase_internal(false);
+        assert_eq!(
+            timer.take_phase_transition(),
+            Some(PhaseTransition { from: PomodoroPhase::Work, to: PomodoroPhase::ShortBreak })
+        );
+        assert!(timer.take_phase_transition().is_none());
+
+        timer.skip_phase();
+        assert_eq!(
+            timer.take_phase_transition(),
+            Some(PhaseTransition { from: PomodoroPhase::ShortBreak, to: PomodoroPhase::Work })
+        );
+    }
+
+    #[test]
+    fn test_alarm_preview_leaves_session_alone() {
+        let mut timer = Timer::new(&Config::default());
+        timer.quiet_mode = true;
+        timer.toggle_alarm_preview();
+        assert!(timer.is_alarm_active());
+        assert_eq!(timer.phase, PomodoroPhase::Work);
+        assert!(timer.daily_sessions.is_empty());
+
+        timer.toggle_alarm_preview();
+        assert!(!timer.is_alarm_active());
+    }
+
+    #[test]
+    fn test_long_break_countdown_default_rule() {
+        let mut timer = Timer::new(&Config::default());
+        timer.quiet_mode = true;
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+
+        // Fresh start: 4 work sessions and 3 short breaks away
+        assert_eq!(
+            timer.long_break_countdown(),
+            Some(LongBreakCountdown::Upcoming { sessions: 4, time: minutes(4 * 25 + 3 * 5) })
+        );
+
+        // Halfway through the last work session before the long break
+        timer.pomodoro_count = 3;
+        timer.time_remaining = minutes(10);
+        assert_eq!(
+            timer.long_break_countdown(),
+            Some(LongBreakCountdown::Upcoming { sessions: 1, time: minutes(10) })
+        );
+
+        timer.complete_phase_internal(false);
+        assert_eq!(timer.phase, PomodoroPhase::LongBreak);
+        assert_eq!(timer.long_break_countdown(), Some(LongBreakCountdown::InProgress));
+
+        // On a short break right after the first session of a new round
+        timer.complete_phase_internal(false);
+        timer.complete_phase_internal(false);
+        assert_eq!(timer.phase, PomodoroPhase::ShortBreak);
+        timer.time_remaining = minutes(2);
+        assert_eq!(
+            timer.long_break_countdown(),
+            Some(LongBreakCountdown::Upcoming { sessions: 3, time: minutes(2 + 3 * 25 + 2 * 5) })
+        );
+    }
+
+    #[test]
+    fn test_long_break_countdown_with_task_length() {
+        let mut timer = Timer::new(&Config::default());
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        timer.set_task_session_minutes(Some(50));
+        assert_eq!(
+            timer.long_break_countdown(),
+            Some(LongBreakCountdown::Upcoming { sessions: 4, time: minutes(4 * 50 + 3 * 5) })
+        );
+    }
+
+    #[test]
+    fn test_long_break_countdown_with_sequence() {
+        let mut config = Config::default();
+        config.timer.sequence = Some(vec![SequenceStep::Work, SequenceStep::Short, SequenceStep::Work, SequenceStep::Long]);
+        let mut timer = Timer::new(&config);
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+
+        assert_eq!(
+            timer.long_break_countdown(),
+            Some(LongBreakCountdown::Upcoming { sessions: 2, time: minutes(25 + 5 + 25) })
+        );
+
+        timer.sequence_position = 1;
+        timer.phase = PomodoroPhase::ShortBreak;
+        timer.time_remaining = minutes(1);
+        assert_eq!(
+            timer.long_break_countdown(),
+            Some(LongBreakCountdown::Upcoming { sessions: 1, time: minutes(1 + 25) })
+        );
+
+        config.timer.sequence = Some(vec![SequenceStep::Work, SequenceStep::Short]);
+        let timer = Timer::new(&config);
+        assert_eq!(timer.long_break_countdown(), None);
+    }
+
+    #[test]
+    fn test_switch_phase_records_nothing() {
+        let mut timer = Timer::new(&Config::default());
+        timer.switch_phase(PomodoroPhase::LongBreak);
+        assert_eq!(timer.phase, PomodoroPhase::LongBreak);
+        assert_eq!(timer.time_remaining, timer.long_break_duration);
+        assert_eq!(timer.pomodoro_count, 0);
+        assert!(timer.daily_sessions.is_empty());
+
+        // Ignored while running
+        timer.start();
+        timer.switch_phase(PomodoroPhase::Work);
+        assert_eq!(timer.phase, PomodoroPhase::LongBreak);
+
+        // Allowed once paused, and reported like any other phase change
+        timer.start();
+        assert_eq!(
+            timer.take_phase_transition(),
+            Some(PhaseTransition { from: PomodoroPhase::Work, to: PomodoroPhase::LongBreak })
+        );
+        timer.switch_phase(PomodoroPhase::Work);
+        assert_eq!(timer.phase, PomodoroPhase::Work);
+        assert_eq!(timer.state, TimerState::Stopped);
+        assert_eq!(timer.time_remaining, timer.work_duration);
+        assert_eq!(
+            timer.take_phase_transition(),
+            Some(PhaseTransition { from: PomodoroPhase::LongBreak, to: PomodoroPhase::Work })
+        );
+        // Switching to the phase it's already in changes nothing to report
+        timer.switch_phase(PomodoroPhase::Work);
+        assert!(timer.take_phase_transition().is_none());
+    }
+
+    #[test]
+    fn test_switch_phase_credits_paused_work() {
+        let mut timer = Timer::new(&Config::default());
+        timer.set_selected_todo_with_task_name(Some(3), Some("Write report".to_string()));
+        timer.start();
+        timer.start(); // Paused
+        timer.time_remaining = timer.work_duration - Duration::from_secs(12 * 60 + 30);
+        timer.switch_phase(PomodoroPhase::ShortBreak);
+        assert_eq!(timer.phase, PomodoroPhase::ShortBreak);
+        assert_eq!(timer.take_work_credit(), Some(WorkCredit { todo_id: 3, minutes: 12, finished: false }));
+        let session = timer.get_today_session();
+        assert_eq!(session.total_work_minutes, 12);
+        assert_eq!(session.work_sessions, 0);
+        assert_eq!(session.entries.last().map(|entry| entry.task.clone()), Some(Some("Write report".to_string())));
+    }
+
+    #[test]
+    fn test_focus_rating_prompt() {
+        let mut timer = Timer::new(&Config::default());
+        timer.quiet_mode = true;
+        assert!(!timer.is_rating_prompt_active());
+
+        // A skipped work phase doesn't ask
+        timer.skip_phase();
+        assert!(!timer.is_rating_prompt_active());
+        timer.skip_phase();
+
+        timer.complete_phase_internal(false);
+        assert!(timer.is_rating_prompt_active());
+        timer.rate_focus(6);
+        assert!(timer.is_rating_prompt_active());
+        timer.rate_focus(4);
+        assert!(!timer.is_rating_prompt_active());
+        assert_eq!(timer.get_today_session().ratings, vec![4]);
+
+        // Ratings outside the prompt are ignored
+        timer.rate_focus(5);
+        assert_eq!(timer.get_today_session().ratings, vec![4]);
+
+        // An expired prompt counts as skipped
+        timer.rating_prompt_deadline = Some(Instant::now() - Duration::from_secs(1));
+        assert!(!timer.is_rating_prompt_active());
+    }
+
+    #[test]
+    fn test_sync_attachment() {
+        let path = std::env::temp_dir().join(format!("sessio-attachment-{}.md", std::process::id()));
+        std::fs::write(&path, "- [ ] a\n- [ ] b | Session: 50 min\n- [ ] c\n").unwrap();
+        let mut todo = Todo::new(Some(path.to_string_lossy().into_owned()), None);
+        let mut timer = Timer::new(&Config::default());
+        timer.sync_attachment(&todo, true);
+        assert_eq!(timer.get_selected_todo(), None);
+
+        // Renamed
+        timer.set_selected_todo_with_task_name(Some(1), Some("a".to_string()));
+        todo.items[0].task = "a, renamed".to_string();
+        timer.sync_attachment(&todo, false);
+        assert_eq!(timer.selected_task_name.as_deref(), Some("a, renamed"));
+
+        // Completed: the next unfinished task takes over with its own length, or nothing does
+        todo.toggle_selected_task();
+        timer.sync_attachment(&todo, true);
+        assert_eq!((timer.get_selected_todo(), timer.selected_task_name.as_deref()), (Some(2), Some("b")));
+        assert_eq!(timer.work_phase_duration(), Duration::from_secs(50 * 60));
+        todo.toggle_selected_task();
+        timer.sync_attachment(&todo, false);
+        assert_eq!((timer.get_selected_todo(), timer.selected_task_name.as_deref()), (None, None));
+        assert_eq!(timer.work_phase_duration(), timer.work_duration);
+
+        // Undoing the task's creation (like deleting it) drops it
+        todo.current_input.set("new".to_string());
+        todo.submit_new_task();
+        let id = todo.items[todo.selected_index].id;
+        timer.set_selected_todo_with_task_name(Some(id), Some("new".to_string()));
+        assert!(todo.undo());
+        timer.sync_attachment(&todo, true);
+        assert_eq!(timer.get_selected_todo(), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,26 +1,150 @@
 use ratatui::{
-    layout::Rect,
-    style::Style,
-    widgets::{Block, Borders, Paragraph},
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
     Frame,
 };
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use chrono::{DateTime, Local, NaiveDate};
+use std::time::{Duration, Instant, SystemTime};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::app::{App, Quadrant};
+use crate::config::{TimeDisplay, TodoFormat};
+use crate::input::TextInput;
+use crate::links;
+use crate::org::OrgStore;
+use crate::recurrence::Recurrence;
+use crate::taskwarrior;
 use crate::theme::DraculaTheme;
-use crate::timer::PomodoroSession;
+use crate::timer::{PomodoroSession, SessionEntry};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TodoItem {
+    pub id: u64, // Stable identifier, unaffected by reordering (0 = not yet assigned)
     pub task: String,
     pub done: bool,
     pub focused_time: u32, // in minutes
     pub timeline: Vec<WorkSession>, // Track when work was done
+    pub session_minutes: Option<u32>, // Custom work session length for this task
+    pub estimate: Option<u32>, // Estimated number of pomodoros
+    pub priority: Option<Priority>,
+    pub parent_id: Option<u64>, // Set on subtasks, which follow their parent in the list
+    pub notes: String, // Free-form, possibly multi-line context (empty = none)
+    pub repeat: Option<Recurrence>, // Recurring tasks become not-done again when their next occurrence arrives
+    pub completions: Vec<DateTime<Local>>, // When a recurring task was completed
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Local>>, // When the task was last marked done; None while open
+    #[serde(default)]
+    pub uuid: Option<String>, // Handed out on the first Taskwarrior export, so later ones update the same task
+    #[serde(default)]
+    pub starred: bool, // Pinned above the other not-done tasks while not done
+    #[serde(default = "Local::now")]
+    pub created_at: DateTime<Local>, // When the task was added (for tasks from before this was kept: when first loaded)
+    #[serde(default)]
+    pub scheduled_for: Option<NaiveDate>, // Day the task is planned for
+    #[serde(default)]
+    pub blocked_by: Option<u64>, // Id of the task that has to be finished first
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+impl Priority {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "high" => Some(Priority::High),
+            "medium" => Some(Priority::Medium),
+            "low" => Some(Priority::Low),
+            _ => None,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Priority::High => DraculaTheme::RED,
+            Priority::Medium => DraculaTheme::YELLOW,
+            Priority::Low => DraculaTheme::COMMENT,
+        }
+    }
+
+    /// Next step of the none → high → medium → low → none cycle
+    pub fn cycle(priority: Option<Priority>) -> Option<Priority> {
+        match priority {
+            None => Some(Priority::High),
+            Some(Priority::High) => Some(Priority::Medium),
+            Some(Priority::Medium) => Some(Priority::Low),
+            Some(Priority::Low) => None,
+        }
+    }
+
+    /// Sort rank, most important first; tasks without a priority come last
+    fn rank(priority: Option<Priority>) -> u8 {
+        match priority {
+            Some(Priority::High) => 0,
+            Some(Priority::Medium) => 1,
+            Some(Priority::Low) => 2,
+            None => 3,
+        }
+    }
+}
+
+/// Order the todo panel shows tasks in. Only the view is sorted: todos.md keeps the manual order.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortMode {
+    #[default]
+    Manual,
+    FocusedTime,
+    Alphabetical,
+    Priority,
+    Age,
+    DueDate,
+}
+
+impl SortMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortMode::Manual => "manual",
+            SortMode::FocusedTime => "focus time",
+            SortMode::Alphabetical => "a-z",
+            SortMode::Priority => "priority",
+            SortMode::Age => "age",
+            SortMode::DueDate => "due date",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            SortMode::Manual => SortMode::FocusedTime,
+            SortMode::FocusedTime => SortMode::Alphabetical,
+            SortMode::Alphabetical => SortMode::Priority,
+            SortMode::Priority => SortMode::Age,
+            SortMode::Age => SortMode::DueDate,
+            SortMode::DueDate => SortMode::Manual,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WorkSession {
     pub date: NaiveDate,
     pub minutes: u32,
@@ -30,49 +154,756 @@ pub struct WorkSession {
 impl TodoItem {
     pub fn new(task: String) -> Self {
         Self {
+            id: 0,
             task,
             done: false,
             focused_time: 0,
             timeline: Vec::new(),
+            session_minutes: None,
+            estimate: None,
+            priority: None,
+            parent_id: None,
+            notes: String::new(),
+            repeat: None,
+            completions: Vec::new(),
+            completed_at: None,
+            uuid: None,
+            starred: false,
+            created_at: Local::now(),
+            scheduled_for: None,
+            blocked_by: None,
+        }
+    }
+
+    /// Planned for `today`, or for an earlier day and still open (carried forward)
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        self.scheduled_for.is_some_and(|day| day == today || (day < today && !self.done))
+    }
+
+    /// A starred top-level task that isn't done, kept above the other not-done tasks
+    pub fn is_pinned(&self) -> bool {
+        self.starred && !self.done && self.parent_id.is_none()
+    }
+
+    /// New task from text with inline syntax: "!1".."!3" (or "!high", "!medium", "!low") set the priority
+    /// and "~4" the estimated pomodoros. #tags stay in the text, where tags live; other words are kept
+    /// literally, as is the whole text when nothing but syntax tokens remains.
+    pub fn parse_inline(text: &str) -> Self {
+        let mut priority = None;
+        let mut estimate = None;
+        let mut words = Vec::new();
+        for word in text.split_whitespace() {
+            if let Some(level) = word.strip_prefix('!').and_then(|level| match level {
+                "1" => Some(Priority::High),
+                "2" => Some(Priority::Medium),
+                "3" => Some(Priority::Low),
+                _ => Priority::parse(level),
+            }) {
+                priority = Some(level);
+            } else if let Some(count) = word.strip_prefix('~').and_then(|count| count.parse::<u32>().ok()).filter(|&n| n > 0) {
+                estimate = Some(count);
+            } else {
+                words.push(word);
+            }
+        }
+
+        if words.is_empty() {
+            return Self::new(text.trim().to_string());
+        }
+        let mut item = Self::new(words.join(" "));
+        item.priority = priority;
+        item.estimate = estimate;
+        item
+    }
+
+    /// #tag tokens in the task text, without the '#'
+    pub fn tags(&self) -> Vec<&str> {
+        self.task.split_whitespace().filter_map(tag_of).collect()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags().contains(&tag)
+    }
+
+    /// Whole pomodoros worked so far, counting sessions of this task's length (or `default_minutes`)
+    pub fn pomodoros_done(&self, default_minutes: u32) -> u32 {
+        let session = self.session_minutes.unwrap_or(default_minutes).max(1);
+        self.focused_time / session
+    }
+
+    /// A 🍅 per finished session's worth of focused time; those past MAX_DOTS are counted as "+N"
+    pub fn pomodoro_dots(&self, default_minutes: u32) -> String {
+        const MAX_DOTS: usize = 8;
+        let done = self.pomodoros_done(default_minutes) as usize;
+        let mut dots = "🍅".repeat(done.min(MAX_DOTS));
+        if done > MAX_DOTS {
+            dots.push_str(&format!("+{}", done - MAX_DOTS));
+        }
+        dots
+    }
+}
+
+/// Tag named by a "#tag" word (letters, digits, '-' and '_'), ignoring trailing punctuation
+pub fn tag_of(word: &str) -> Option<&str> {
+    let tag = word.strip_prefix('#')?;
+    let end = tag.find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_')).unwrap_or(tag.len());
+    (end > 0).then(|| &tag[..end])
+}
+
+/// `path` with a leading "~/" replaced by `home`, when there is one
+fn expand_home(path: &str, home: Option<PathBuf>) -> PathBuf {
+    match (path.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Replace the file at `path` with `content` without ever leaving it half written: the content goes
+/// to a temporary file next to it, which is then renamed over it (atomic on the same filesystem)
+pub fn write_atomically(path: &Path, content: &str) -> io::Result<()> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// "todos.md.1" and so on: the n-th newest backup of the file at `path`
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!("{}.{}", file_name, n))
+}
+
+/// Keep `count` daily backups of the file at `path`: unless the newest backup was made `today`,
+/// shift them along (dropping the oldest) and copy the file to ".1"
+fn rotate_backups(path: &Path, count: usize, today: NaiveDate) -> io::Result<()> {
+    if count == 0 || !path.exists() {
+        return Ok(());
+    }
+    let newest_date = fs::metadata(backup_path(path, 1))
+        .and_then(|metadata| metadata.modified())
+        .map(|modified| DateTime::<Local>::from(modified).date_naive());
+    if newest_date.is_ok_and(|date| date == today) {
+        return Ok(());
+    }
+    for n in (1..count).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 1))?;
+    Ok(())
+}
+
+/// Parse a "YYYY-MM-DD HH:MM" local time
+fn parse_local_datetime(value: &str) -> Option<DateTime<Local>> {
+    let time = NaiveDateTime::parse_from_str(value.trim(), "%Y-%m-%d %H:%M").ok()?;
+    Local.from_local_datetime(&time).earliest()
+}
+
+/// A day typed in the schedule prompt: "YYYY-MM-DD", "today", "tomorrow", or a weekday ("mon" or
+/// "monday"), meaning the first such day from `today` on
+fn parse_day(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let value = value.trim().to_lowercase();
+    match value.as_str() {
+        "today" => Some(today),
+        "tomorrow" => today.succ_opt(),
+        _ => match value.parse::<chrono::Weekday>() {
+            Ok(weekday) => today.iter_days().take(7).find(|day| day.weekday() == weekday),
+            Err(_) => NaiveDate::parse_from_str(&value, "%Y-%m-%d").ok(),
+        },
+    }
+}
+
+/// Work minutes per work session over the `days` days up to and including `last`
+fn average_session_minutes(sessions: &[PomodoroSession], last: NaiveDate, days: u32) -> Option<f32> {
+    let first = last - chrono::Duration::days(i64::from(days.saturating_sub(1)));
+    let (minutes, count) = sessions.iter()
+        .filter(|session| (first..=last).contains(&session.date))
+        .fold((0, 0), |(minutes, count), session| (minutes + session.total_work_minutes, count + session.work_sessions));
+    if count == 0 {
+        return None;
+    }
+    Some(minutes as f32 / count as f32)
+}
+
+/// Days in a row with work up to `today`. Today doesn't break the streak before it's over, and
+/// with `ignore_weekends` neither do Saturdays and Sundays without work (worked ones count).
+fn streak_days(dates_with_work: &HashSet<NaiveDate>, today: NaiveDate, ignore_weekends: bool) -> u32 {
+    let mut streak = 0;
+    let mut date = today;
+    loop {
+        if dates_with_work.contains(&date) {
+            streak += 1;
+        } else if date != today && !(ignore_weekends && matches!(date.weekday(), Weekday::Sat | Weekday::Sun)) {
+            return streak;
+        }
+        let Some(previous) = date.pred_opt() else {
+            return streak;
+        };
+        date = previous;
+    }
+}
+
+/// Compact age of a task: "3d" up to two weeks, then "5w", "4mo" and "2y"
+fn age_label(days: i64) -> String {
+    match days {
+        0..14 => format!("{}d", days),
+        14..60 => format!("{}w", days / 7),
+        60..365 => format!("{}mo", days / 30),
+        _ => format!("{}y", days / 365),
+    }
+}
+
+/// Cut a string to at most `max_width` terminal cells, ending in "…" when anything was dropped.
+/// CJK characters and most emoji take two cells, so counting chars would overflow the row.
+pub fn truncate_to_width(s: &str, max_width: usize) -> String {
+    if s.width() <= max_width {
+        return s.to_string();
+    }
+    let budget = max_width.saturating_sub(1); // Leave a cell for the ellipsis
+    let mut truncated = String::new();
+    for c in s.chars() {
+        truncated.push(c);
+        // Measure the whole prefix: emoji sequences can be narrower or wider than their chars
+        if truncated.width() > budget {
+            truncated.pop();
+            break;
+        }
+    }
+    if max_width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+/// Break styled text into lines at most `width` cells wide: at the last space where there is one,
+/// otherwise between characters. Cells are counted, so a wide (CJK) character never gets split.
+fn wrap_spans(spans: &[Span<'static>], width: usize) -> Vec<Vec<Span<'static>>> {
+    let width = width.max(2); // Room for a wide character
+    let mut lines: Vec<Vec<(char, Style)>> = vec![Vec::new()];
+    let mut line_width = 0;
+    for span in spans {
+        for c in span.content.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if line_width + char_width > width {
+                let line = lines.last_mut().expect("there is always a line");
+                let mut next = Vec::new();
+                if c == ' ' {
+                    // The break takes the place of the space
+                    line_width = 0;
+                    lines.push(next);
+                    continue;
+                }
+                if let Some(space) = line.iter().rposition(|&(c, _)| c == ' ').filter(|&space| space > 0) {
+                    next = line.split_off(space + 1);
+                    line.truncate(space);
+                }
+                line_width = next.iter().map(|(c, _)| c.width().unwrap_or(0)).sum();
+                lines.push(next);
+            }
+            lines.last_mut().expect("there is always a line").push((c, span.style));
+            line_width += char_width;
+        }
+    }
+    
+    // Back to spans, one per run of equally styled characters
+    lines.into_iter()
+        .map(|line| {
+            let mut spans: Vec<Span<'static>> = Vec::new();
+            for (c, style) in line {
+                match spans.last_mut() {
+                    Some(span) if span.style == style => span.content.to_mut().push(c),
+                    _ => spans.push(Span::styled(c.to_string(), style)),
+                }
+            }
+            spans
+        })
+        .collect()
+}
+
+/// Task text as spans, with #tags in cyan
+fn task_spans(text: &str) -> Vec<Span<'static>> {
+    text.split_inclusive(' ')
+        .map(|word| {
+            if tag_of(word.trim_end()).is_some() {
+                Span::styled(word.to_string(), Style::default().fg(DraculaTheme::CYAN))
+            } else {
+                Span::raw(word.to_string())
+            }
+        })
+        .collect()
+}
+
+/// What the todo input line is currently being used for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputPurpose {
+    NewTask,
+    SessionLength,
+    Estimate,
+    EditTask,
+    NewSubtask,
+    Repeat,
+    Schedule,
+}
+
+/// Tasks and pomodoro sessions as read from a todo file, before ids are handed out
+pub struct StoredTodos {
+    pub items: Vec<TodoItem>,
+    pub is_subtask: Vec<bool>, // Parallel to items; a subtask belongs to the closest task above it
+    pub pomodoro_sessions: Vec<PomodoroSession>,
+}
+
+/// A file format the todo list can be stored in
+pub trait TodoStore {
+    fn serialize(&self, items: &[TodoItem], pomodoro_sessions: &[PomodoroSession]) -> String;
+    fn parse(&self, content: &str) -> StoredTodos;
+}
+
+/// The markdown todos.md format
+pub struct MarkdownStore;
+
+impl TodoStore for MarkdownStore {
+    fn serialize(&self, items: &[TodoItem], pomodoro_sessions: &[PomodoroSession]) -> String {
+        let mut content = String::from("# TODO List\n\n");
+        
+        for item in items {
+            let checkbox = if item.done { "- [x]" } else { "- [ ]" };
+            let time_info = if item.focused_time > 0 {
+                format!(" | Focused time: {} minutes", item.focused_time)
+            } else {
+                String::new()
+            };
+            let session_info = if let Some(minutes) = item.session_minutes {
+                format!(" | Session: {} min", minutes)
+            } else {
+                String::new()
+            };
+            let estimate_info = if let Some(estimate) = item.estimate {
+                format!(" | Est: {}", estimate)
+            } else {
+                String::new()
+            };
+            let priority_info = if let Some(priority) = item.priority {
+                format!(" | Priority: {}", priority.name())
+            } else {
+                String::new()
+            };
+            // Subtasks are indented by two spaces, along with their timeline
+            let indent = if item.parent_id.is_some() { "  " } else { "" };
+            let repeat_info = if let Some(ref repeat) = item.repeat {
+                format!(" | Repeat: {}", repeat.spec())
+            } else {
+                String::new()
+            };
+            let done_info = if let Some(completed_at) = item.completed_at {
+                format!(" | Done: {}", completed_at.format("%Y-%m-%d %H:%M"))
+            } else {
+                String::new()
+            };
+            let uuid_info = if let Some(ref uuid) = item.uuid {
+                format!(" | UUID: {}", uuid)
+            } else {
+                String::new()
+            };
+            let star_info = if item.starred { " | Starred" } else { "" };
+            let scheduled_info = if let Some(day) = item.scheduled_for {
+                format!(" | Scheduled: {}", day.format("%Y-%m-%d"))
+            } else {
+                String::new()
+            };
+            let blocked_info = if let Some(blocker_id) = item.blocked_by {
+                format!(" | Blocked by: {}", blocker_id)
+            } else {
+                String::new()
+            };
+            let created_info = format!(" | Created: {}", item.created_at.format("%Y-%m-%d %H:%M"));
+            // The id keeps the timer's task (and links between tasks) across restarts; a comment hides it in rendered markdown
+            content.push_str(&format!("{}{} {}{}{}{}{}{}{}{}{}{}{}{} <!-- id:{} -->\n", indent, checkbox, item.task, time_info, session_info, estimate_info, priority_info, repeat_info, scheduled_info, blocked_info, done_info, created_info, uuid_info, star_info, item.id));
+            
+            // Notes go right under the task, one "  > " line per line
+            if !item.notes.is_empty() {
+                for note_line in item.notes.lines() {
+                    content.push_str(format!("{}  > {}", indent, note_line).trim_end());
+                    content.push('\n');
+                }
+            }
+            
+            // Completion times of recurring tasks
+            if !item.completions.is_empty() {
+                content.push_str(&format!("{}  Completed:\n", indent));
+                for completed in &item.completions {
+                    content.push_str(&format!("{}    - {}\n", indent, completed.format("%Y-%m-%d %H:%M")));
+                }
+            }
+            
+            // Add timeline information if there are work sessions
+            if !item.timeline.is_empty() {
+                content.push_str(&format!("{}  Timeline:\n", indent));
+                for session in &item.timeline {
+                    content.push_str(&format!(
+                        "{}    - {}: {} minutes at {}\n",
+                        indent,
+                        session.date.format("%Y-%m-%d"),
+                        session.minutes,
+                        session.timestamp.format("%H:%M")
+                    ));
+                }
+            }
+        }
+        
+        // Add pomodoro session summary
+        if !pomodoro_sessions.is_empty() {
+            content.push_str("\n## Pomodoro Sessions\n\n");
+            for session in pomodoro_sessions {
+                content.push_str(&format!(
+                    "### {}\n\
+                     - Work sessions: {}\n\
+                     - Total work time: {} minutes\n\
+                     - Break sessions: {}\n\
+                     - Total break time: {} minutes\n",
+                    session.date.format("%Y-%m-%d"),
+                    session.work_sessions,
+                    session.total_work_minutes,
+                    session.break_sessions,
+                    session.total_break_minutes
+                ));
+                
+                if !session.ratings.is_empty() {
+                    let ratings: Vec<String> = session.ratings.iter().map(|r| r.to_string()).collect();
+                    content.push_str(&format!("- Focus ratings: {}\n", ratings.join(", ")));
+                }
+                
+                if !session.tasks_worked_on.is_empty() {
+                    content.push_str("- Tasks worked on:\n");
+                    for task in &session.tasks_worked_on {
+                        content.push_str(&format!("  - {}\n", task));
+                    }
+                }
+                
+                if !session.entries.is_empty() {
+                    content.push_str("- Log:\n");
+                    for entry in &session.entries {
+                        content.push_str(&format!("  - {}\n", entry.to_log_line()));
+                    }
+                }
+                content.push('\n');
+            }
+        }
+        
+        content
+    }
+
+    fn parse(&self, content: &str) -> StoredTodos {
+        let mut items: Vec<TodoItem> = Vec::new();
+        let mut pomodoro_sessions = Vec::new();
+        
+        let lines: Vec<&str> = content.lines().collect();
+        let mut i = 0;
+        let mut in_pomodoro_section = false;
+        let mut current_session: Option<PomodoroSession> = None;
+        let mut in_session_log = false; // Whether "  - " lines belong to the log or the task list
+        let mut is_subtask: Vec<bool> = Vec::new(); // Parallel to items, linked to parents once ids are assigned
+        
+        while i < lines.len() {
+            let line = lines[i];
+            
+            // Check if we've entered the pomodoro sessions section
+            if line == "## Pomodoro Sessions" {
+                in_pomodoro_section = true;
+                i += 1;
+                continue;
+            }
+            
+            if !in_pomodoro_section {
+                // Parse todo items
+                if line.starts_with("- [x] ") || line.starts_with("- [ ] ") {
+                    let done = line.starts_with("- [x]");
+                    let rest = &line[6..]; // Remove "- [x] " or "- [ ] "
+                    items.push(Todo::parse_task_line(rest, done));
+                    is_subtask.push(false);
+                }
+                // Subtask of the task above
+                else if line.starts_with("  - [x] ") || line.starts_with("  - [ ] ") {
+                    let done = line.starts_with("  - [x]");
+                    let rest = &line[8..];
+                    items.push(Todo::parse_task_line(rest, done));
+                    is_subtask.push(true);
+                }
+                // Support old emoji format for backward compatibility
+                else if line.starts_with("✅ ") || line.starts_with("⭕ ") {
+                    let done = line.starts_with("✅");
+                    let rest = &line[4..]; // Remove status emoji and space
+                    items.push(Todo::parse_task_line(rest, done));
+                    is_subtask.push(false);
+                }
+                // Work session of the task above ("    - YYYY-MM-DD: N minutes at HH:MM" under "  Timeline:")
+                else if let (Some(session), Some(item)) = (Todo::parse_timeline_line(line), items.last_mut()) {
+                    item.timeline.push(session);
+                }
+                // Completion time of the task above ("    - YYYY-MM-DD HH:MM" under "  Completed:")
+                else if let (Some(completed), Some(item)) = (Todo::parse_completion_line(line), items.last_mut()) {
+                    item.completions.push(completed);
+                }
+                // Note line of the task above ("  > text", or "    > text" under a subtask)
+                else if let (Some(note_line), Some(item)) = (
+                    line.strip_prefix("  ").map(str::trim_start).and_then(|l| l.strip_prefix('>')),
+                    items.last_mut(),
+                ) {
+                    if !item.notes.is_empty() {
+                        item.notes.push('\n');
+                    }
+                    item.notes.push_str(note_line.strip_prefix(' ').unwrap_or(note_line));
+                }
+            } else {
+                // Parse pomodoro session data
+                if line.starts_with("### ") {
+                    // Save previous session if exists
+                    if let Some(session) = current_session.take() {
+                        pomodoro_sessions.push(session);
+                    }
+                    
+                    // Start new session
+                    let date_str = &line[4..]; // Remove "### "
+                    if let Ok(date) = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
+                        current_session = Some(PomodoroSession {
+                            date,
+                            work_sessions: 0,
+                            total_work_minutes: 0,
+                            break_sessions: 0,
+                            total_break_minutes: 0,
+                            tasks_worked_on: Vec::new(),
+                            entries: Vec::new(),
+                            ratings: Vec::new(),
+                        });
+                    }
+                    in_session_log = false;
+                } else if let Some(ref mut session) = current_session {
+                    if line.starts_with("- Work sessions: ") {
+                        if let Ok(count) = line[17..].parse::<u32>() {
+                            session.work_sessions = count;
+                        }
+                    } else if line.starts_with("- Total work time: ") {
+                        if let Some(minutes_str) = line[19..].split_whitespace().next() {
+                            if let Ok(minutes) = minutes_str.parse::<u32>() {
+                                session.total_work_minutes = minutes;
+                            }
+                        }
+                    } else if line.starts_with("- Break sessions: ") {
+                        if let Ok(count) = line[18..].parse::<u32>() {
+                            session.break_sessions = count;
+                        }
+                    } else if line.starts_with("- Total break time: ") {
+                        if let Some(minutes_str) = line[20..].split_whitespace().next() {
+                            if let Ok(minutes) = minutes_str.parse::<u32>() {
+                                session.total_break_minutes = minutes;
+                            }
+                        }
+                    } else if let Some(ratings) = line.strip_prefix("- Focus ratings: ") {
+                        session.ratings = ratings.split(',')
+                            .filter_map(|r| r.trim().parse::<u8>().ok())
+                            .filter(|r| (1..=5).contains(r))
+                            .collect();
+                    } else if line == "- Tasks worked on:" {
+                        in_session_log = false;
+                    } else if line == "- Log:" {
+                        in_session_log = true;
+                    } else if line.starts_with("  - ") && in_session_log {
+                        // Session log entry; malformed lines are skipped
+                        if let Some(entry) = SessionEntry::from_log_line(session.date, &line[4..]) {
+                            session.entries.push(entry);
+                        }
+                    } else if line.starts_with("  - ") && !line.starts_with("  - Tasks worked on:") {
+                        // Task name
+                        session.tasks_worked_on.push(line[4..].to_string());
+                    }
+                }
+            }
+            
+            i += 1;
+        }
+        
+        // Save the last session if exists
+        if let Some(session) = current_session {
+            pomodoro_sessions.push(session);
         }
+        
+        StoredTodos { items, is_subtask, pomodoro_sessions }
     }
 }
 
 pub struct Todo {
     pub items: Vec<TodoItem>,
     pub is_input_mode: bool,
-    pub current_input: String,
+    pub input_purpose: InputPurpose,
+    pub current_input: TextInput,
     pub file_path: String,
     pub selected_index: usize,
     pub undo_stack: Vec<Vec<TodoItem>>,
     pub scroll_offset: usize,
     pub last_visible_height: usize, // Store the last calculated visible height
     pub pomodoro_sessions: Vec<PomodoroSession>, // Daily pomodoro sessions
+    pub next_id: u64, // Next id handed out to a new task
+    pub default_session_minutes: u32, // Configured work length, for counting pomodoros against estimates
+    pub time_display: TimeDisplay, // Focused time in rows as minutes, 🍅 dots or both
+    pub show_age: bool, // Show how long ago open tasks were added
+    pub stale_after_days: u32, // Age (in days) shown in orange from; 0 never
+    pub sort_by_priority: bool, // Keep not-done tasks ordered by priority
+    pub auto_complete_parents: bool, // Mark a task done once all of its subtasks are
+    pub inline_syntax: bool, // Parse "!1", "~4" etc. out of new tasks
+    pub delete_confirm: Option<u64>, // Task with subtasks waiting for a second D to be deleted
+    pub clear_done_confirm: bool, // Clearing done tasks is waiting for a second c
+    pub tag_filter: Option<String>, // Only tasks with this tag (or under a parent with it) are shown
+    pub today_view: bool, // Only tasks scheduled for today (or overdue, or under such a parent) are shown
+    pub sort_mode: SortMode, // Order tasks are shown in
+    pub wrap_titles: bool, // Long task titles wrap onto more lines instead of being cut off
+    pub jump_digits: String, // Task number being typed to jump to, like a vim count
+    jump_typed_at: Option<Instant>, // When the last digit was typed; the jump happens after a pause
+    last_panel_width: usize, // Width rows were laid out for at the last render (the panel, less the scrollbar)
+    last_reorder: Option<Instant>, // When J/K last moved a task, so a burst of moves is undone at once
+    pub notes_editor: Option<String>, // Notes of the selected task while the notes popup is open
+    pub detail_scroll: usize, // First line shown in the task detail popup
+    pub link_choices: Vec<String>, // Links of the selected task to pick from; the picker is open while not empty
+    pub link_choice: usize, // Highlighted link in the picker
+    pub blocker_choices: Vec<u64>, // Tasks the selected one can be blocked by; the picker is open while not empty
+    pub blocker_choice: usize, // Highlighted task in the blocker picker
+    pub status_message: Option<String>, // Result of the last action (e.g. an export), shown until the next key
+    pub list_name: Option<String>, // Name of the active list when several are configured
+    pub disk_conflict: bool, // The file changed on disk while we had changes to save; waits for m or t
+    synced_signature: Option<(SystemTime, u64)>, // File signature when we last read or wrote it
+    pub format: Option<TodoFormat>, // Configured file format; None picks it by file extension
+    pub backup_count: usize, // Daily backups kept next to the todo file (todos.md.1 is the newest)
+    pub auto_save: bool, // Write the file after every change; otherwise only on Ctrl+s
+    pub save_delay: Duration, // Auto-saves less than this apart are written as one
+    dirty: bool, // Changes not written to the file yet
+    last_write: Option<Instant>, // When the file was last written
+    pub quit_prompt: bool, // Quitting with unsaved changes waits for save or discard
 }
 
 impl Todo {
-    /// Safely truncate a string to max_chars characters (not bytes), appending "..." if truncated
-    fn truncate_chars(s: &str, max_chars: usize) -> String {
-        let char_count = s.chars().count();
-        if char_count <= max_chars {
-            s.to_string()
-        } else {
-            let truncated: String = s.chars().take(max_chars).collect();
-            format!("{}...", truncated)
+    /// Parse the text after the checkbox into a TodoItem.
+    /// Trailing " | Key: value" fields are picked up; unknown segments stay part of the task text.
+    /// A trailing "<!-- id:N -->" gives the task's id; without one it gets a new id once loaded.
+    fn parse_task_line(rest: &str, done: bool) -> TodoItem {
+        let (rest, id) = rest.trim_end().strip_suffix("-->")
+            .and_then(|line| line.rsplit_once(" <!-- id:"))
+            .and_then(|(line, id)| Some((line, id.trim().parse::<u64>().ok()?)))
+            .unwrap_or((rest, 0));
+        let mut segments = rest.split(" | ");
+        let mut item = TodoItem::new(segments.next().unwrap_or("").to_string());
+        item.id = id;
+        item.done = done;
+
+        for segment in segments {
+            if let Some(value) = segment.strip_prefix("Focused time: ") {
+                item.focused_time = value.split_whitespace().next()
+                    .and_then(|s| s.parse::<u32>().ok())
+                    .unwrap_or(0);
+            } else if let Some(value) = segment.strip_prefix("Session: ") {
+                item.session_minutes = value.split_whitespace().next()
+                    .and_then(|s| s.parse::<u32>().ok())
+                    .filter(|&m| m > 0);
+            } else if let Some(value) = segment.strip_prefix("Est: ") {
+                item.estimate = value.trim().parse::<u32>().ok().filter(|&n| n > 0);
+            } else if let Some(repeat) = segment.strip_prefix("Repeat: ").and_then(|spec| Recurrence::parse(spec).ok()) {
+                item.repeat = Some(repeat);
+            } else if let Some(priority) = segment.strip_prefix("Priority: ").and_then(Priority::parse) {
+                item.priority = Some(priority);
+            } else if let Some(completed_at) = segment.strip_prefix("Done: ").and_then(parse_local_datetime) {
+                item.completed_at = Some(completed_at);
+            } else if let Some(created_at) = segment.strip_prefix("Created: ").and_then(parse_local_datetime) {
+                item.created_at = created_at;
+            } else if let Some(day) = segment.strip_prefix("Scheduled: ").and_then(|day| NaiveDate::parse_from_str(day.trim(), "%Y-%m-%d").ok()) {
+                item.scheduled_for = Some(day);
+            } else if let Some(blocker_id) = segment.strip_prefix("Blocked by: ").and_then(|id| id.trim().parse().ok()) {
+                item.blocked_by = Some(blocker_id);
+            } else if let Some(uuid) = segment.strip_prefix("UUID: ") {
+                item.uuid = Some(uuid.trim().to_string());
+            } else if segment.trim_end() == "Starred" {
+                item.starred = true;
+            } else {
+                item.task.push_str(" | ");
+                item.task.push_str(segment);
+            }
         }
+
+        item
+    }
+
+    /// Parse a "    - YYYY-MM-DD: N minutes at HH:MM" timeline line
+    fn parse_timeline_line(line: &str) -> Option<WorkSession> {
+        let value = line.strip_prefix("  ")?.trim_start().strip_prefix("- ")?;
+        let (date, rest) = value.split_once(": ")?;
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+        let (minutes, time) = rest.split_once(" minutes at ")?;
+        let minutes = minutes.parse::<u32>().ok()?;
+        let time = chrono::NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()?;
+        let timestamp = Local.from_local_datetime(&date.and_time(time)).earliest()?;
+        Some(WorkSession { date, minutes, timestamp })
+    }
+
+    /// Parse a "    - YYYY-MM-DD HH:MM" completion line
+    fn parse_completion_line(line: &str) -> Option<DateTime<Local>> {
+        let value = line.strip_prefix("  ")?.trim_start().strip_prefix("- ")?;
+        parse_local_datetime(value)
     }
 
-    pub fn new(save_path: Option<String>) -> Self {
+    /// A list stored in `format`, or in the format its file extension implies when none is given
+    pub fn new(save_path: Option<String>, format: Option<TodoFormat>) -> Self {
         let mut todo = Self {
             items: Vec::new(),
             is_input_mode: false,
-            current_input: String::new(),
+            input_purpose: InputPurpose::NewTask,
+            current_input: TextInput::default(),
             file_path: save_path.unwrap_or_else(|| "todos.md".into()),
             selected_index: 0,
             undo_stack: Vec::new(),
             scroll_offset: 0,
             last_visible_height: 8, // Default fallback value
             pomodoro_sessions: Vec::new(),
+            next_id: 1,
+            default_session_minutes: 25,
+            time_display: TimeDisplay::Minutes,
+            show_age: false,
+            stale_after_days: 14,
+            sort_by_priority: false,
+            auto_complete_parents: false,
+            inline_syntax: true,
+            delete_confirm: None,
+            clear_done_confirm: false,
+            tag_filter: None,
+            today_view: false,
+            sort_mode: SortMode::Manual,
+            wrap_titles: false,
+            jump_digits: String::new(),
+            jump_typed_at: None,
+            last_panel_width: 0,
+            last_reorder: None,
+            notes_editor: None,
+            detail_scroll: 0,
+            link_choices: Vec::new(),
+            link_choice: 0,
+            blocker_choices: Vec::new(),
+            blocker_choice: 0,
+            status_message: None,
+            list_name: None,
+            disk_conflict: false,
+            synced_signature: None,
+            format,
+            backup_count: 0, // Set from the config once loaded
+            auto_save: true,
+            save_delay: Duration::ZERO,
+            dirty: false,
+            last_write: None,
+            quit_prompt: false,
         };
         
         // Load existing todos or create default ones
@@ -83,6 +914,7 @@ impl Todo {
                 TodoItem::new("Implement priorities".to_string()),
                 TodoItem::new("Set deadlines".to_string()),
             ];
+            todo.assign_missing_ids();
             todo.save_to_file();
         }
         
@@ -92,10 +924,6 @@ impl Todo {
     pub fn render(&mut self, frame: &mut Frame, area: Rect, app: &App) {
         let is_focused = app.focused_quadrant == Quadrant::BottomLeft;
         
-        // Calculate available width for task text (accounting for icons, selection indicator, and padding)
-        let available_width = area.width.saturating_sub(12) as usize; // Reserve space for borders, icons, etc.
-        let max_task_width = available_width.saturating_sub(20); // Reserve space for time display
-        
         // Calculate visible items based on available height
         let header_lines = if self.is_input_mode { 4 } else { 3 }; // Title + empty line + stats
         let footer_lines = if self.is_input_mode { 4 } else { 4 }; // Stats + help text
@@ -105,91 +933,136 @@ impl Todo {
         // Store the actual calculated visible height for use in navigation methods
         self.last_visible_height = visible_height;
         
-        let visible_items: Vec<String> = if !self.items.is_empty() {
-            let end_index = (self.scroll_offset + visible_height).min(self.items.len());
-            self.items[self.scroll_offset..end_index]
+        // Selection and actions work on item indices; scrolling works on positions in the (filtered) view
+        let visible = self.visible_indices();
+        
+        // Rows are laid out for this width (see task_row). When not every task fits, the last
+        // column inside the border is left to the scrollbar; narrower rows only fit fewer tasks.
+        self.last_panel_width = area.width as usize;
+        let show_scrollbar = self.max_scroll_offset(&visible) > 0;
+        if show_scrollbar {
+            self.last_panel_width -= 1;
+        }
+        let max_offset = self.max_scroll_offset(&visible);
+        let visible_items: Vec<Line> = if !visible.is_empty() {
+            let start_index = self.scroll_offset.min(visible.len());
+            let end_index = start_index + self.rows_fitting(&visible, start_index);
+            visible[start_index..end_index]
                 .iter()
-                .enumerate()
-                .map(|(relative_i, item)| {
-                    let actual_index = self.scroll_offset + relative_i;
-                    let status = if item.done { "✅" } else { "⭕" };
-                    
-                    // Truncate task text if too long (char-safe for UTF-8)
-                    let truncated_task = if item.task.chars().count() > max_task_width {
-                        Self::truncate_chars(&item.task, max_task_width.saturating_sub(3))
-                    } else {
-                        item.task.clone()
-                    };
-                    
-                    let time_str = if item.focused_time > 0 {
-                        format!(" ({}min)", item.focused_time)
-                    } else {
-                        String::new()
-                    };
-                    
-                    let selection_indicator = if actual_index == self.selected_index && is_focused && !self.is_input_mode {
-                        "►" 
-                    } else { 
-                        " " 
-                    };
-                    
-                    format!("{} {} {}{}", selection_indicator, status, truncated_task, time_str)
+                .flat_map(|&actual_index| {
+                    let selected = actual_index == self.selected_index && is_focused && !self.is_input_mode;
+                    self.task_row(actual_index, selected)
                 })
                 .collect()
+        } else if let Some(tag) = &self.tag_filter {
+            vec![Line::raw(format!("No tasks tagged #{}. Press 'f' to change the filter.", tag))]
         } else {
-            vec!["No tasks yet. Press 'a' to add one.".to_string()]
+            vec![Line::raw("No tasks yet. Press 'a' to add one.")]
         };
 
-        let task_list = visible_items.join("\n");
-
         // Show scroll indicators
-        let scroll_info = if self.items.len() > visible_height {
+        let shown = self.rows_fitting(&visible, self.scroll_offset.min(visible.len()));
+        let scroll_info = if shown < visible.len() {
             let showing_start = self.scroll_offset + 1;
-            let showing_end = (self.scroll_offset + visible_height).min(self.items.len());
-            format!(" | Showing {}-{}/{}", showing_start, showing_end, self.items.len())
+            let showing_end = (self.scroll_offset + shown).min(visible.len());
+            format!(" | Showing {}-{}/{}", showing_start, showing_end, visible.len())
         } else {
             String::new()
         };
 
         let content = if self.is_input_mode {
-            format!("TODO - Adding New Task\n\n{}\n\n📝 {} items{}{}\n\nNew task: {}_", 
-                    task_list, self.items.len(), 
-                    if self.items.is_empty() { "" } else { &format!(" | Done: {}", self.items.iter().filter(|i| i.done).count()) },
-                    scroll_info,
-                    self.current_input)
+            let done_info = if self.items.is_empty() {
+                String::new()
+            } else {
+                format!(" | Done: {}", self.items.iter().filter(|i| i.done).count())
+            };
+            let (heading, prompt) = match self.input_purpose {
+                InputPurpose::NewTask => ("TODO - Adding New Task", "New task"),
+                InputPurpose::SessionLength => ("TODO - Session Length", "Session minutes (empty = default)"),
+                InputPurpose::Estimate => ("TODO - Estimate", "Estimated pomodoros (empty = none)"),
+                InputPurpose::EditTask => ("TODO - Editing Task", "Task"),
+                InputPurpose::NewSubtask => ("TODO - Adding Subtask", "New subtask"),
+                InputPurpose::Repeat => ("TODO - Repeat", "Repeat (daily, every 2 weeks, every mon,fri; empty = none)"),
+                InputPurpose::Schedule => ("TODO - Schedule", "Day (2025-07-02, today, tomorrow, mon..sun; empty = none)"),
+            };
+            let mut lines = vec![Line::raw(heading), Line::raw("")];
+            lines.extend(visible_items);
+            lines.extend(Text::raw(format!("\n📝 {} items{}{}\n", 
+                    self.items.len(), 
+                    done_info,
+                    scroll_info)).lines);
+            let mut input_line = vec![Span::raw(format!("{}: ", prompt))];
+            input_line.extend(self.current_input.spans());
+            lines.push(Line::from(input_line));
+            Text::from(lines)
         } else {
             let done_count = self.items.iter().filter(|i| i.done).count();
             let total_time: u32 = self.items.iter().map(|i| i.focused_time).sum();
             let selected_info = if !self.items.is_empty() {
                 let selected_task = self.items.get(self.selected_index)
-                    .map(|item| {
-                        if item.task.chars().count() > 30 {
-                            Self::truncate_chars(&item.task, 27)
-                        } else {
-                            item.task.clone()
-                        }
-                    })
+                    .map(|item| truncate_to_width(&item.task, 30))
                     .unwrap_or("None".to_string());
                 format!("\n\nSelected: {}", selected_task)
             } else {
                 format!("\n\nz=undo")
             };
-            format!("\n{}\n\n📝 {} items | Done: {} | Total time: {}min{}{}", 
-                    task_list, self.items.len(), done_count, total_time, scroll_info, selected_info)
+            let mut lines = vec![Line::raw("")];
+            lines.extend(visible_items);
+            if let Some(index) = self.delete_confirm.and_then(|id| self.index_of_id(id)) {
+                let subtasks = self.block_end(index) - index - 1;
+                lines.push(Line::styled(
+                    format!("Delete with {} subtask(s)? Press D again to confirm", subtasks),
+                    Style::default().fg(DraculaTheme::RED),
+                ));
+            }
+            if self.clear_done_confirm {
+                lines.push(Line::styled(
+                    format!("Clear {} done task(s)? Press c again to confirm", self.done_tasks_to_clear().iter().filter(|&&clear| clear).count()),
+                    Style::default().fg(DraculaTheme::RED),
+                ));
+            }
+            if let Some(ref message) = self.status_message {
+                lines.push(Line::styled(message.clone(), Style::default().fg(DraculaTheme::CYAN)));
+            }
+            let jump_info = if self.jump_pending() {
+                format!(" | Go to: {}_", self.jump_digits)
+            } else {
+                String::new()
+            };
+            lines.extend(Text::raw(format!("\n📝 {} items | Done: {} | Total time: {}min{}{}{}", 
+                    self.items.len(), done_count, total_time, scroll_info, jump_info, selected_info)).lines);
+            Text::from(lines)
         };
 
-        let title = if self.is_input_mode {
-            "✅ TODO - INPUT MODE"
+        let mut title = if self.is_input_mode && self.input_purpose == InputPurpose::EditTask {
+            "✅ TODO - Editing Task".to_string()
+        } else if self.is_input_mode {
+            "✅ TODO - INPUT MODE".to_string()
         } else {
-            "✅ TODO"
+            "✅ TODO".to_string()
         };
+        if let Some(name) = &self.list_name {
+            title.push_str(&format!(" ({})", name));
+        }
+        if let Some(tag) = &self.tag_filter {
+            title.push_str(&format!(" [#{}]", tag));
+        }
+        if self.today_view {
+            title.push_str(" [today]");
+        }
+        if self.sort_mode != SortMode::Manual {
+            title.push_str(&format!(" ↕ {}", self.sort_mode.name()));
+        }
+        if self.dirty && !self.auto_save {
+            title.push_str(" ● unsaved (Ctrl+s)");
+        }
 
         let todo_widget = if is_focused {
             Paragraph::new(content)
                 .style(Style::default().fg(DraculaTheme::FOREGROUND).bg(DraculaTheme::BACKGROUND))
                 .block(Block::default()
                     .borders(Borders::ALL)
-                    .title(title)
+                    .title(title.as_str())
                     .title_style(Style::default().fg(DraculaTheme::GREEN))
                     .border_style(Style::default().fg(DraculaTheme::PINK))
                     .style(Style::default().bg(DraculaTheme::BACKGROUND)))
@@ -198,104 +1071,262 @@ impl Todo {
                 .style(Style::default().fg(DraculaTheme::FOREGROUND).bg(DraculaTheme::BACKGROUND))
                 .block(Block::default()
                     .borders(Borders::ALL)
-                    .title(title)
+                    .title(title.as_str())
                     .title_style(Style::default().fg(DraculaTheme::GREEN))
                     .border_style(Style::default().fg(DraculaTheme::COMMENT))
                     .style(Style::default().bg(DraculaTheme::BACKGROUND)))
         };
 
         frame.render_widget(todo_widget, area);
+
+        if show_scrollbar {
+            // Alongside the task rows, below the heading line(s)
+            let top = if self.is_input_mode { 3 } else { 2 };
+            let scrollbar_area = Rect {
+                x: area.x + area.width.saturating_sub(2),
+                y: area.y + top,
+                width: 1,
+                height: (visible_height as u16).min(area.height.saturating_sub(top + 1)),
+            };
+            // One position per scroll offset, so the thumb reaches the bottom with the last task shown
+            let mut scrollbar_state = ScrollbarState::new(max_offset + 1)
+                .position(self.scroll_offset.min(max_offset))
+                .viewport_content_length(shown);
+            let thumb_color = if is_focused { DraculaTheme::PINK } else { DraculaTheme::COMMENT };
+            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .track_style(Style::default().fg(DraculaTheme::COMMENT))
+                .thumb_style(Style::default().fg(thumb_color));
+            frame.render_stateful_widget(scrollbar, scrollbar_area, &mut scrollbar_state);
+        }
     }
 
     // File I/O methods
-    pub fn save_to_file(&self) {
-        let mut content = String::from("# TODO List\n\n");
-        
-        for item in &self.items {
-            let checkbox = if item.done { "- [x]" } else { "- [ ]" };
-            let time_info = if item.focused_time > 0 {
-                format!(" | Focused time: {} minutes", item.focused_time)
-            } else {
-                String::new()
-            };
-            content.push_str(&format!("{} {}{}\n", checkbox, item.task, time_info));
-            
-            // Add timeline information if there are work sessions
-            if !item.timeline.is_empty() {
-                content.push_str("  Timeline:\n");
-                for session in &item.timeline {
-                    content.push_str(&format!(
-                        "    - {}: {} minutes at {}\n",
-                        session.date.format("%Y-%m-%d"),
-                        session.minutes,
-                        session.timestamp.format("%H:%M")
-                    ));
-                }
-            }
+    /// Record that the list changed. With auto_save the file is written right away, or, within
+    /// save_delay of the last write, by flush_if_due once the delay is over; without it the changes
+    /// wait for write_to_file (Ctrl+s).
+    pub fn save_to_file(&mut self) {
+        self.dirty = true;
+        if self.auto_save && self.last_write.is_none_or(|last| last.elapsed() >= self.save_delay) {
+            self.write_to_file();
         }
-        
-        // Add pomodoro session summary
-        if !self.pomodoro_sessions.is_empty() {
-            content.push_str("\n## Pomodoro Sessions\n\n");
-            for session in &self.pomodoro_sessions {
-                content.push_str(&format!(
-                    "### {}\n\
-                     - Work sessions: {}\n\
-                     - Total work time: {} minutes\n\
-                     - Break sessions: {}\n\
-                     - Total break time: {} minutes\n",
-                    session.date.format("%Y-%m-%d"),
-                    session.work_sessions,
-                    session.total_work_minutes,
-                    session.break_sessions,
-                    session.total_break_minutes
-                ));
-                
-                if !session.tasks_worked_on.is_empty() {
-                    content.push_str("- Tasks worked on:\n");
-                    for task in &session.tasks_worked_on {
-                        content.push_str(&format!("  - {}\n", task));
-                    }
-                }
-                content.push('\n');
-            }
+    }
+
+    /// Write changes held back by the auto-save delay once it has passed
+    pub fn flush_if_due(&mut self) {
+        if self.dirty && self.auto_save && !self.disk_conflict
+            && self.last_write.is_none_or(|last| last.elapsed() >= self.save_delay) {
+            self.write_to_file();
+        }
+    }
+
+    /// Changes not written to the file yet
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Whether quitting can go ahead. Unsaved changes (with auto_save off) open the
+    /// save/discard prompt instead; pending auto-saves are written. Not when that write ran
+    /// into changes made on disk, which then need settling first, or failed, which opens the prompt.
+    pub fn request_quit(&mut self) -> bool {
+        if !self.dirty {
+            return true;
+        }
+        if self.auto_save {
+            self.write_to_file();
+            self.quit_prompt = self.dirty && !self.disk_conflict;
+            return !self.dirty;
+        }
+        self.quit_prompt = true;
+        false
+    }
+
+    /// Settle the quit prompt by saving or discarding. Returns whether to quit: not when
+    /// saving ran into changes made on disk, which then need settling first.
+    pub fn answer_quit_prompt(&mut self, save: bool) -> bool {
+        self.quit_prompt = false;
+        if save {
+            self.write_to_file();
+            return !self.dirty;
+        }
+        true
+    }
+
+    /// Write the list to its file now. A failed write says so in the status line and leaves
+    /// the changes unsaved.
+    pub fn write_to_file(&mut self) {
+        // Don't overwrite edits made by another program; the user decides (see keep_mine / take_theirs)
+        if self.disk_conflict || self.changed_on_disk() {
+            self.disk_conflict = true;
+            return;
         }
+        let content = self.store().serialize(&self.items, &self.pomodoro_sessions);
         
-        // Expand ~ to home directory and create parent directories if needed
-        let expanded_path = if self.file_path.starts_with("~/") {
-            if let Some(home) = dirs::home_dir() {
-                home.join(&self.file_path[2..])
-            } else {
-                Path::new(&self.file_path).to_path_buf()
-            }
-        } else {
-            Path::new(&self.file_path).to_path_buf()
-        };
+        let expanded_path = self.expanded_path();
+        
+        // Tried again after the auto-save delay, not on every pass of the main loop
+        self.last_write = Some(Instant::now());
         
         // Create parent directories if they don't exist
         if let Some(parent) = expanded_path.parent() {
             if let Err(e) = fs::create_dir_all(parent) {
-                eprintln!("Failed to create directories for todos: {}", e);
+                self.status_message = Some(format!("Could not save {}: {}", self.file_path, e));
                 return;
             }
         }
         
-        if let Err(e) = fs::write(&expanded_path, content) {
-            eprintln!("Failed to save todos: {}", e);
+        // The day's first save keeps a copy of the file as it was; the write itself replaces the file in one step
+        if let Err(e) = rotate_backups(&expanded_path, self.backup_count, Local::now().date_naive()) {
+            self.status_message = Some(format!("Could not back up {}: {}", self.file_path, e));
         }
+        if let Err(e) = write_atomically(&expanded_path, &content) {
+            self.status_message = Some(format!("Could not save {}: {}", self.file_path, e));
+            return;
+        }
+        self.synced_signature = self.file_signature();
+        self.dirty = false;
     }
 
-    pub fn load_from_file(&mut self) -> bool {
-        // Expand ~ to home directory
-        let expanded_path = if self.file_path.starts_with("~/") {
-            if let Some(home) = dirs::home_dir() {
-                home.join(&self.file_path[2..])
-            } else {
-                Path::new(&self.file_path).to_path_buf()
+    /// Reader and writer for the format of the todo file
+    fn store(&self) -> Box<dyn TodoStore> {
+        match self.format.unwrap_or_else(|| TodoFormat::from_path(&self.file_path)) {
+            TodoFormat::Markdown => Box::new(MarkdownStore),
+            TodoFormat::Org => Box::new(OrgStore),
+        }
+    }
+
+    /// The todo file path with ~ expanded to the home directory
+    fn expanded_path(&self) -> PathBuf {
+        expand_home(&self.file_path, dirs::home_dir())
+    }
+
+    /// Modification time and size of the todo file, to notice changes made by other programs
+    fn file_signature(&self) -> Option<(SystemTime, u64)> {
+        let metadata = fs::metadata(self.expanded_path()).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    /// Whether something other than sessio wrote the todo file since we last read or wrote it
+    fn changed_on_disk(&self) -> bool {
+        let signature = self.file_signature();
+        signature.is_some() && signature != self.synced_signature
+    }
+
+    /// Reload the todo file if another program changed it. Changes waiting to be saved
+    /// turn into a conflict for the user to settle instead. Returns whether the list was reloaded.
+    pub fn check_disk_changes(&mut self) -> bool {
+        if self.disk_conflict || !self.changed_on_disk() {
+            return false;
+        }
+        // Reloading would lose the changes that haven't been written yet
+        if self.dirty {
+            self.disk_conflict = true;
+            return false;
+        }
+        self.reload_from_disk();
+        true
+    }
+
+    /// Read the todo file again, keeping ids (and with them the selection and the timer's task)
+    /// of tasks whose text didn't change. The reload can be undone.
+    fn reload_from_disk(&mut self) {
+        let previous = self.items.clone();
+        let selected_id = self.items.get(self.selected_index).map(|item| item.id);
+        self.save_state_for_undo();
+        if !self.load_from_file() {
+            return;
+        }
+        
+        // Tasks keep the ids stored with them. Lines without one (added by another program) were
+        // handed fresh ids; give those matching a task by text its old id back.
+        let known = |id: u64| previous.iter().any(|old| old.id == id);
+        let mut unmatched: Vec<&TodoItem> = previous.iter()
+            .filter(|old| !self.items.iter().any(|item| item.id == old.id))
+            .collect();
+        let mut id_map = HashMap::new();
+        for item in self.items.iter().filter(|item| !known(item.id)) {
+            if let Some(position) = unmatched.iter().position(|old| old.task == item.task) {
+                id_map.insert(item.id, unmatched.remove(position).id);
             }
-        } else {
-            Path::new(&self.file_path).to_path_buf()
-        };
+        }
+        for item in self.items.iter_mut() {
+            item.id = id_map.get(&item.id).copied().unwrap_or(item.id);
+            item.parent_id = item.parent_id.map(|id| id_map.get(&id).copied().unwrap_or(id));
+        }
+        
+        self.selected_index = selected_id
+            .and_then(|id| self.index_of_id(id))
+            .unwrap_or(self.selected_index.min(self.items.len().saturating_sub(1)));
+        self.sort_items();
+        self.keep_selection_visible();
+        self.status_message = Some("Reloaded from disk".to_string());
+    }
+
+    /// Settle a conflict by writing the list as sessio has it over the file on disk
+    pub fn keep_mine(&mut self) {
+        self.disk_conflict = false;
+        self.synced_signature = self.file_signature();
+        self.write_to_file();
+    }
+
+    /// Settle a conflict by dropping sessio's changes for the file on disk (undo brings them back)
+    pub fn take_theirs(&mut self) {
+        self.disk_conflict = false;
+        self.reload_from_disk();
+    }
+
+    /// Popup asking whether to save unsaved changes before quitting
+    pub fn render_quit_prompt(&self, frame: &mut Frame) {
+        if !self.quit_prompt {
+            return;
+        }
+        let [popup_area] = Layout::vertical([Constraint::Length(4)]).flex(Flex::Center).areas(frame.area());
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(60)]).flex(Flex::Center).areas(popup_area);
+        
+        frame.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title("● Unsaved changes")
+            .title_style(Style::default().fg(DraculaTheme::ORANGE))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(DraculaTheme::ORANGE))
+            .style(Style::default().bg(DraculaTheme::CURRENT_LINE).fg(DraculaTheme::FOREGROUND));
+        let prompt = Paragraph::new(format!("Save {} before quitting?\ns = save and quit, d = discard and quit, Esc = cancel", self.file_path))
+            .wrap(Wrap { trim: false })
+            .block(block);
+        
+        frame.render_widget(prompt, popup_area);
+    }
+
+    /// Popup asking how to settle a conflict with changes made on disk
+    pub fn render_conflict_prompt(&self, frame: &mut Frame) {
+        if !self.disk_conflict {
+            return;
+        }
+        let [popup_area] = Layout::vertical([Constraint::Length(5)]).flex(Flex::Center).areas(frame.area());
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(60)]).flex(Flex::Center).areas(popup_area);
+        
+        frame.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title("⚠️  Todo file changed on disk")
+            .title_style(Style::default().fg(DraculaTheme::ORANGE))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(DraculaTheme::ORANGE))
+            .style(Style::default().bg(DraculaTheme::CURRENT_LINE).fg(DraculaTheme::FOREGROUND));
+        let prompt = Paragraph::new(format!(
+            "{} was edited outside sessio while you made changes.\nm = keep mine (overwrite the file), t = take theirs (z undoes)",
+            self.file_path
+        ))
+            .wrap(Wrap { trim: false })
+            .block(block);
+        
+        frame.render_widget(prompt, popup_area);
+    }
+
+    pub fn load_from_file(&mut self) -> bool {
+        let expanded_path = self.expanded_path();
         
         if !expanded_path.exists() {
             return false;
@@ -303,133 +1334,23 @@ impl Todo {
         
         match fs::read_to_string(&expanded_path) {
             Ok(content) => {
-                self.items.clear();
-                self.pomodoro_sessions.clear();
+                let stored = self.store().parse(&content);
+                self.items = stored.items;
+                self.pomodoro_sessions = stored.pomodoro_sessions;
+                let is_subtask = stored.is_subtask;
                 
-                let lines: Vec<&str> = content.lines().collect();
-                let mut i = 0;
-                let mut in_pomodoro_section = false;
-                let mut current_session: Option<PomodoroSession> = None;
+                self.assign_missing_ids();
+                self.synced_signature = self.file_signature();
+                self.dirty = false;
                 
-                while i < lines.len() {
-                    let line = lines[i];
-                    
-                    // Check if we've entered the pomodoro sessions section
-                    if line == "## Pomodoro Sessions" {
-                        in_pomodoro_section = true;
-                        i += 1;
-                        continue;
-                    }
-                    
-                    if !in_pomodoro_section {
-                        // Parse todo items
-                        if line.starts_with("- [x] ") || line.starts_with("- [ ] ") {
-                            let done = line.starts_with("- [x]");
-                            let rest = &line[6..]; // Remove "- [x] " or "- [ ] "
-                            
-                            if let Some(time_pos) = rest.find(" | Focused time: ") {
-                                let task = rest[..time_pos].to_string();
-                                let time_str = &rest[time_pos + 16..]; // Skip " | Focused time: "
-                                let focused_time = time_str.split_whitespace().next()
-                                    .and_then(|s| s.parse::<u32>().ok())
-                                    .unwrap_or(0);
-                                
-                                self.items.push(TodoItem {
-                                    task,
-                                    done,
-                                    focused_time,
-                                    timeline: Vec::new(),
-                                });
-                            } else {
-                                self.items.push(TodoItem {
-                                    task: rest.to_string(),
-                                    done,
-                                    focused_time: 0,
-                                    timeline: Vec::new(),
-                                });
-                            }
-                        }
-                        // Support old emoji format for backward compatibility
-                        else if line.starts_with("✅ ") || line.starts_with("⭕ ") {
-                            let done = line.starts_with("✅");
-                            let rest = &line[4..]; // Remove status emoji and space
-                            
-                            if let Some(time_pos) = rest.find(" | Focused time: ") {
-                                let task = rest[..time_pos].to_string();
-                                let time_str = &rest[time_pos + 16..]; // Skip " | Focused time: "
-                                let focused_time = time_str.split_whitespace().next()
-                                    .and_then(|s| s.parse::<u32>().ok())
-                                    .unwrap_or(0);
-                                
-                                self.items.push(TodoItem {
-                                    task,
-                                    done,
-                                    focused_time,
-                                    timeline: Vec::new(),
-                                });
-                            } else {
-                                self.items.push(TodoItem {
-                                    task: rest.to_string(),
-                                    done,
-                                    focused_time: 0,
-                                    timeline: Vec::new(),
-                                });
-                            }
-                        }
+                // Attach subtasks to the task above them; one at the top of the file stays a task
+                let mut parent_id = None;
+                for (item, is_subtask) in self.items.iter_mut().zip(is_subtask) {
+                    if is_subtask {
+                        item.parent_id = parent_id;
                     } else {
-                        // Parse pomodoro session data
-                        if line.starts_with("### ") {
-                            // Save previous session if exists
-                            if let Some(session) = current_session.take() {
-                                self.pomodoro_sessions.push(session);
-                            }
-                            
-                            // Start new session
-                            let date_str = &line[4..]; // Remove "### "
-                            if let Ok(date) = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-                                current_session = Some(PomodoroSession {
-                                    date,
-                                    work_sessions: 0,
-                                    total_work_minutes: 0,
-                                    break_sessions: 0,
-                                    total_break_minutes: 0,
-                                    tasks_worked_on: Vec::new(),
-                                });
-                            }
-                        } else if let Some(ref mut session) = current_session {
-                            if line.starts_with("- Work sessions: ") {
-                                if let Ok(count) = line[17..].parse::<u32>() {
-                                    session.work_sessions = count;
-                                }
-                            } else if line.starts_with("- Total work time: ") {
-                                if let Some(minutes_str) = line[19..].split_whitespace().next() {
-                                    if let Ok(minutes) = minutes_str.parse::<u32>() {
-                                        session.total_work_minutes = minutes;
-                                    }
-                                }
-                            } else if line.starts_with("- Break sessions: ") {
-                                if let Ok(count) = line[18..].parse::<u32>() {
-                                    session.break_sessions = count;
-                                }
-                            } else if line.starts_with("- Total break time: ") {
-                                if let Some(minutes_str) = line[20..].split_whitespace().next() {
-                                    if let Ok(minutes) = minutes_str.parse::<u32>() {
-                                        session.total_break_minutes = minutes;
-                                    }
-                                }
-                            } else if line.starts_with("  - ") && !line.starts_with("  - Tasks worked on:") {
-                                // Task name
-                                session.tasks_worked_on.push(line[4..].to_string());
-                            }
-                        }
+                        parent_id = Some(item.id);
                     }
-                    
-                    i += 1;
-                }
-                
-                // Save the last session if exists
-                if let Some(session) = current_session {
-                    self.pomodoro_sessions.push(session);
                 }
                 
                 true
@@ -438,10 +1359,104 @@ impl Todo {
         }
     }
 
+    /// Save this list and show the one at `path` instead, starting it empty if the file doesn't exist.
+    /// Pomodoro sessions aren't tied to a list, so the current ones are kept.
+    pub fn switch_list(&mut self, name: Option<String>, path: String) {
+        self.write_to_file();
+        let sessions = std::mem::take(&mut self.pomodoro_sessions);
+        
+        self.file_path = path;
+        self.list_name = name;
+        if !self.load_from_file() {
+            self.items.clear();
+        }
+        self.pomodoro_sessions = sessions;
+        
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+        self.undo_stack.clear();
+        self.tag_filter = None;
+        self.today_view = false;
+        self.delete_confirm = None;
+        self.last_reorder = None;
+        self.sort_items();
+        self.write_to_file();
+    }
+
+    /// Give every item without a Taskwarrior uuid a new one. Returns whether any was handed out.
+    pub fn assign_uuids(&mut self) -> bool {
+        let mut assigned = false;
+        for item in self.items.iter_mut().filter(|item| item.uuid.is_none()) {
+            item.uuid = Some(taskwarrior::new_uuid());
+            assigned = true;
+        }
+        assigned
+    }
+
+    /// Give every item that has no id yet a fresh one, as well as all but the first of tasks
+    /// sharing an id (a line copied by hand, id comment and all)
+    fn assign_missing_ids(&mut self) {
+        let max_id = self.items.iter().map(|item| item.id).max().unwrap_or(0);
+        self.next_id = self.next_id.max(max_id + 1);
+        let mut seen = HashSet::new();
+        for item in self.items.iter_mut() {
+            if item.id == 0 || !seen.insert(item.id) {
+                item.id = self.next_id;
+                self.next_id += 1;
+            }
+        }
+    }
+
+    /// Current position of the task with the given id
+    pub fn index_of_id(&self, id: u64) -> Option<usize> {
+        self.items.iter().position(|item| item.id == id)
+    }
+
+    /// First not-done task at or after `index` that isn't blocked, wrapping around to the top of the list
+    pub fn next_unfinished_from(&self, index: usize) -> Option<&TodoItem> {
+        let start = index.min(self.items.len());
+        (start..self.items.len()).chain(0..start)
+            .find(|&i| !self.items[i].done && self.blocker_of(i).is_none())
+            .map(|i| &self.items[i])
+    }
+
+    /// The unfinished task the task at `index` is waiting for, if any
+    pub fn blocker_of(&self, index: usize) -> Option<&TodoItem> {
+        let blocker_id = self.items.get(index)?.blocked_by?;
+        self.items.iter().find(|item| item.id == blocker_id && !item.done)
+    }
+
+    /// Drop dependencies on tasks that are done or gone, so their dependents can be started
+    fn unblock_dependents(&mut self) {
+        let open: HashSet<u64> = self.items.iter().filter(|item| !item.done).map(|item| item.id).collect();
+        for item in self.items.iter_mut() {
+            if item.blocked_by.is_some_and(|blocker_id| !open.contains(&blocker_id)) {
+                item.blocked_by = None;
+            }
+        }
+    }
+
+    /// Whether blocking the task `id` by `blocker_id` would close a loop (a task waiting on itself)
+    fn would_cycle(&self, id: u64, blocker_id: u64) -> bool {
+        let mut next = Some(blocker_id);
+        let mut seen = HashSet::new();
+        while let Some(current) = next {
+            if current == id {
+                return true;
+            }
+            if !seen.insert(current) {
+                return false;
+            }
+            next = self.items.iter().find(|item| item.id == current).and_then(|item| item.blocked_by);
+        }
+        false
+    }
+
     // Todo functionality methods
     pub fn add_task(&mut self, task: String) {
         if !task.trim().is_empty() {
             self.items.insert(0, TodoItem::new(task));
+            self.assign_missing_ids();
             self.save_to_file();
         }
     }
@@ -456,6 +1471,7 @@ impl Todo {
     pub fn toggle_task(&mut self, index: usize) {
         if index < self.items.len() {
             self.items[index].done = !self.items[index].done;
+            self.record_completion(index);
             self.save_to_file();
         }
     }
@@ -472,21 +1488,11 @@ impl Todo {
     pub fn undo(&mut self) -> bool {
         if let Some(previous_state) = self.undo_stack.pop() {
             self.items = previous_state;
-            // Adjust selection index if it's out of bounds
-            if self.selected_index >= self.items.len() && !self.items.is_empty() {
-                self.selected_index = self.items.len() - 1;
-            } else if self.items.is_empty() {
-                self.selected_index = 0;
-            }
-            
-            // Adjust scroll offset to keep selection visible
-            if self.selected_index < self.scroll_offset {
-                self.scroll_offset = self.selected_index;
-            }
-            let visible_height = self.calculate_visible_height();
-            if self.selected_index >= self.scroll_offset + visible_height {
-                self.scroll_offset = self.selected_index.saturating_sub(visible_height - 1);
-            }
+            // A move right after this starts a new undo step rather than joining the undone burst
+            self.last_reorder = None;
+            // Adjust selection index if it's out of bounds, and keep it visible
+            self.selected_index = self.selected_index.min(self.items.len().saturating_sub(1));
+            self.keep_selection_visible();
             
             self.save_to_file();
             true
@@ -500,27 +1506,314 @@ impl Todo {
         self.last_visible_height
     }
 
+    /// The line(s) showing the task at `index`: one line with the title cut to fit, or with
+    /// wrap_titles the whole title, continued under the title with a hanging indent
+    fn task_row(&self, index: usize, selected: bool) -> Vec<Line<'static>> {
+        let item = &self.items[index];
+        let status = if item.done { "✅" } else { "⭕" };
+        let selection_indicator = if selected { "►" } else { " " };
+        let indent = if item.parent_id.is_some() { "   " } else { "" };
+        // Numbers count the whole list, so they stay put while scrolling or filtering
+        let number_width = self.items.len().to_string().len();
+        let mut prefix = vec![
+            Span::raw(format!("{} ", selection_indicator)),
+            Span::styled(format!("{:0width$} ", index + 1, width = number_width), Style::default().fg(DraculaTheme::COMMENT)),
+            Span::raw(format!("{}{} ", indent, status)),
+        ];
+        if item.starred {
+            prefix.push(Span::raw("⭐ "));
+        }
+        let blocked = self.blocker_of(index).is_some();
+        if blocked {
+            prefix.push(Span::raw("🔒 "));
+        }
+        if let Some(priority) = item.priority {
+            prefix.push(Span::styled("● ", Style::default().fg(priority.color())));
+        }
+        
+        // Planned day: orange once it has passed with the task still open
+        let schedule = item.scheduled_for.map(|day| {
+            let today = Local::now().date_naive();
+            let label = if day == today {
+                "today".to_string()
+            } else if day > today && (day - today).num_days() < 7 {
+                day.format("%a").to_string()
+            } else {
+                day.format("%m-%d").to_string()
+            };
+            let color = if day < today && !item.done { DraculaTheme::ORANGE } else { DraculaTheme::CYAN };
+            Span::styled(format!(" 📅 {}", label), Style::default().fg(color))
+        });
+        let schedule_width = schedule.as_ref().map_or(0, |schedule| schedule.width());
+
+        let dots = if self.time_display == TimeDisplay::Minutes {
+            String::new()
+        } else {
+            item.pomodoro_dots(self.default_session_minutes)
+        };
+        let dots_str = if dots.is_empty() { String::new() } else { format!(" {}", dots) };
+        // Open tasks show how long ago they were added, right-aligned; orange once stale
+        let age = (self.show_age && !item.done).then(|| {
+            let days = (Local::now() - item.created_at).num_days().max(0);
+            let stale = self.stale_after_days > 0 && days >= self.stale_after_days as i64;
+            let color = if stale { DraculaTheme::ORANGE } else { DraculaTheme::COMMENT };
+            Span::styled(age_label(days), Style::default().fg(color))
+        });
+        let age_width = age.as_ref().map_or(0, |age| age.width() + 1);
+        let mut spans = if self.wrap_titles {
+            task_spans(&item.task)
+        } else {
+            // Truncate task text by display width, so wide characters don't push the row past the panel
+            // (room is left for borders, icons and the time display, plus the dots)
+            let star_width = if item.starred { 3 } else { 0 } + if blocked { 3 } else { 0 };
+            let max_task_width = self.last_panel_width.saturating_sub(13 + number_width + star_width).saturating_sub(20 + dots_str.width() + schedule_width + age_width);
+            task_spans(&truncate_to_width(&item.task, max_task_width))
+        };
+        let time_str = if item.focused_time > 0 && self.time_display != TimeDisplay::Dots {
+            format!(" ({}min)", item.focused_time)
+        } else {
+            String::new()
+        };
+        let session_str = if let Some(minutes) = item.session_minutes {
+            format!(" [⏱{}m]", minutes)
+        } else {
+            String::new()
+        };
+        let notes_str = if item.notes.is_empty() { "" } else { " 📝" };
+        let repeat_str = if item.repeat.is_some() { " 🔁" } else { "" };
+        spans.push(Span::raw(format!("{}{}{}{}{}", notes_str, repeat_str, dots_str, time_str, session_str)));
+        spans.extend(schedule);
+        if let Some(estimate) = item.estimate {
+            // Running over the estimate is shown in orange
+            let done = item.pomodoros_done(self.default_session_minutes);
+            let estimate_str = format!(" [{}/{} 🍅]", done, estimate);
+            if done > estimate {
+                spans.push(Span::styled(estimate_str, Style::default().fg(DraculaTheme::ORANGE)));
+            } else {
+                spans.push(Span::raw(estimate_str));
+            }
+        }
+        // A blocked task is dimmed until the task it waits for is done
+        if blocked {
+            for span in spans.iter_mut() {
+                span.style = span.style.fg(DraculaTheme::COMMENT);
+            }
+        }
+        
+        let row_width = self.last_panel_width.saturating_sub(2); // 2 for borders
+        // The age goes at the right end of the (first) line
+        let push_age = |row: &mut Vec<Span<'static>>| {
+            if let Some(age) = age.clone() {
+                let used: usize = row.iter().map(|span| span.width()).sum();
+                row.push(Span::raw(" ".repeat(row_width.saturating_sub(used + age.width()).max(1))));
+                row.push(age);
+            }
+        };
+        if !self.wrap_titles {
+            prefix.extend(spans);
+            push_age(&mut prefix);
+            return vec![Line::from(prefix)];
+        }
+        let prefix_width: usize = prefix.iter().map(|span| span.content.width()).sum();
+        let text_width = row_width.saturating_sub(prefix_width + age_width);
+        wrap_spans(&spans, text_width)
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let mut row = if i == 0 { prefix.clone() } else { vec![Span::raw(" ".repeat(prefix_width))] };
+                row.extend(line);
+                if i == 0 {
+                    push_age(&mut row);
+                }
+                Line::from(row)
+            })
+            .collect()
+    }
+
+    fn row_height(&self, index: usize) -> usize {
+        if self.wrap_titles { self.task_row(index, false).len() } else { 1 }
+    }
+
+    /// How many of the shown tasks from position `start` fit in the panel (at least one, if any are left)
+    fn rows_fitting(&self, visible: &[usize], start: usize) -> usize {
+        let height = self.calculate_visible_height();
+        let mut used = 0;
+        let mut count = 0;
+        for &index in visible.iter().skip(start) {
+            used += self.row_height(index);
+            if used > height && count > 0 {
+                break;
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Furthest scroll position: the one that fits the last shown tasks
+    fn max_scroll_offset(&self, visible: &[usize]) -> usize {
+        let height = self.calculate_visible_height();
+        let mut start = visible.len();
+        let mut used = 0;
+        while start > 0 && used + self.row_height(visible[start - 1]) <= height {
+            used += self.row_height(visible[start - 1]);
+            start -= 1;
+        }
+        start.min(visible.len().saturating_sub(1))
+    }
+
+    /// Add a digit to the task number being typed. The jump happens on Enter, after a pause
+    /// (see check_jump_timeout), or right away once no further digit could give a valid number.
+    pub fn push_jump_digit(&mut self, digit: char) {
+        self.jump_digits.push(digit);
+        self.jump_typed_at = Some(Instant::now());
+        let number: usize = self.jump_digits.parse().unwrap_or(0);
+        if number * 10 > self.items.len() {
+            self.finish_jump();
+        }
+    }
+
+    pub fn jump_pending(&self) -> bool {
+        !self.jump_digits.is_empty()
+    }
+
+    /// Jump once typing the number has paused for a second
+    pub fn check_jump_timeout(&mut self) {
+        if self.jump_typed_at.is_some_and(|typed_at| typed_at.elapsed() >= Duration::from_secs(1)) {
+            self.finish_jump();
+        }
+    }
+
+    /// Select the task with the typed number (1 = first in the list), or say there is none.
+    /// A tag filter or today view hiding the task is turned off, so the jump lands on it.
+    pub fn finish_jump(&mut self) {
+        let digits = std::mem::take(&mut self.jump_digits);
+        self.jump_typed_at = None;
+        match digits.parse::<usize>() {
+            Ok(number) if (1..=self.items.len()).contains(&number) => {
+                self.selected_index = number - 1;
+                if !self.visible_indices().contains(&self.selected_index) {
+                    self.tag_filter = None;
+                    self.today_view = false;
+                    self.scroll_offset = 0;
+                    self.status_message = Some(format!("Showing all tasks to reach task {}", number));
+                }
+                self.keep_selection_visible();
+            }
+            _ => self.status_message = Some(format!("No task {}", digits)),
+        }
+    }
+
+    pub fn cancel_jump(&mut self) {
+        self.jump_digits.clear();
+        self.jump_typed_at = None;
+    }
+
+    /// Switch between cutting long titles off and wrapping them
+    pub fn toggle_wrap_titles(&mut self) {
+        self.wrap_titles = !self.wrap_titles;
+        self.keep_selection_visible();
+    }
+
+    /// Indices into `items` of the tasks shown, in display order.
+    /// With a tag filter, a task is shown if it or its parent has the tag; in the today view, if it or its parent is due.
+    /// Outside manual order, whole tasks (with their subtasks) are sorted, starred tasks still first and done tasks last.
+    pub fn visible_indices(&self) -> Vec<usize> {
+        let mut visible: Vec<usize> = match &self.tag_filter {
+            Some(tag) => (0..self.items.len())
+                .filter(|&i| self.items[i].has_tag(tag) || self.items[self.parent_index(i)].has_tag(tag))
+                .collect(),
+            None => (0..self.items.len()).collect(),
+        };
+        if self.today_view {
+            let today = Local::now().date_naive();
+            visible.retain(|&i| self.items[i].is_due(today) || self.items[self.parent_index(i)].is_due(today));
+        }
+        
+        if self.sort_mode != SortMode::Manual {
+            // Subtasks sort by their parent; the sort is stable, so blocks stay together in manual order
+            visible.sort_by(|&a, &b| {
+                let (a, b) = (&self.items[self.parent_index(a)], &self.items[self.parent_index(b)]);
+                a.done.cmp(&b.done).then(b.is_pinned().cmp(&a.is_pinned())).then_with(|| match self.sort_mode {
+                    SortMode::Manual => Ordering::Equal,
+                    SortMode::FocusedTime => b.focused_time.cmp(&a.focused_time),
+                    SortMode::Alphabetical => a.task.to_lowercase().cmp(&b.task.to_lowercase()),
+                    SortMode::Priority => Priority::rank(a.priority).cmp(&Priority::rank(b.priority)),
+                    SortMode::Age => a.created_at.cmp(&b.created_at),
+                    // Soonest first, unscheduled tasks last
+                    SortMode::DueDate => a.scheduled_for.is_none().cmp(&b.scheduled_for.is_none())
+                        .then(a.scheduled_for.cmp(&b.scheduled_for)),
+                })
+            });
+        }
+        visible
+    }
+
+    /// Step to the next sort mode, keeping the selected task in view
+    pub fn cycle_sort_mode(&mut self) {
+        self.sort_mode = self.sort_mode.next();
+        self.keep_selection_visible();
+    }
+
     pub fn move_selection_up(&mut self) {
-        if !self.items.is_empty() && self.selected_index > 0 {
-            self.selected_index -= 1;
+        let visible = self.visible_indices();
+        if let Some(position) = visible.iter().position(|&i| i == self.selected_index).filter(|&p| p > 0) {
+            self.selected_index = visible[position - 1];
             // Auto-scroll if selection goes above visible area
-            if self.selected_index < self.scroll_offset {
-                self.scroll_offset = self.selected_index;
-            }
+            self.keep_selection_visible();
         }
     }
 
     pub fn move_selection_down(&mut self) {
-        if !self.items.is_empty() && self.selected_index < self.items.len() - 1 {
-            self.selected_index += 1;
-            // Use dynamic visible height calculation
-            let visible_height = self.calculate_visible_height();
-            
-            // Auto-scroll if selection goes below visible area  
-            if self.selected_index >= self.scroll_offset + visible_height {
-                self.scroll_offset = self.selected_index - visible_height + 1;
-            }
+        let visible = self.visible_indices();
+        if let Some(position) = visible.iter().position(|&i| i == self.selected_index).filter(|&p| p + 1 < visible.len()) {
+            self.selected_index = visible[position + 1];
+            // Auto-scroll if selection goes below visible area
+            self.keep_selection_visible();
+        }
+    }
+
+    /// Distinct tags used in the list, sorted
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self.items.iter()
+            .flat_map(|item| item.tags())
+            .map(|tag| tag.to_string())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// Step the tag filter through the tags in the list, then back to showing everything
+    pub fn cycle_tag_filter(&mut self) {
+        let tags = self.all_tags();
+        let next = match &self.tag_filter {
+            Some(current) => tags.iter().position(|tag| tag == current).and_then(|i| tags.get(i + 1)),
+            None => tags.first(),
+        };
+        self.tag_filter = next.cloned();
+        self.scroll_offset = 0;
+        self.keep_selection_visible();
+    }
+
+    /// Show only the tasks planned for today (and overdue ones), or everything again
+    pub fn toggle_today_view(&mut self) {
+        let today = Local::now().date_naive();
+        if !self.today_view && !self.items.iter().any(|item| item.is_due(today)) {
+            self.status_message = Some("Nothing scheduled for today".to_string());
+            return;
         }
+        self.today_view = !self.today_view;
+        self.scroll_offset = 0;
+        self.keep_selection_visible();
+    }
+
+    /// Scheduled tasks due today (including overdue ones) that are done, and in total
+    pub fn get_scheduled_progress(&self, today: NaiveDate) -> (usize, usize) {
+        let due: Vec<&TodoItem> = self.items.iter()
+            .filter(|item| item.scheduled_for.is_some_and(|day| day <= today))
+            .filter(|item| item.is_due(today) || item.completed_at.is_some_and(|at| at.date_naive() == today))
+            .collect();
+        (due.iter().filter(|item| item.done).count(), due.len())
     }
 
     // New scrolling methods
@@ -531,8 +1824,7 @@ impl Todo {
     }
 
     pub fn scroll_down(&mut self) {
-        let visible_height = self.calculate_visible_height();
-        if self.scroll_offset + visible_height < self.items.len() {
+        if self.scroll_offset < self.max_scroll_offset(&self.visible_indices()) {
             self.scroll_offset += 1;
         }
     }
@@ -544,8 +1836,7 @@ impl Todo {
 
     pub fn page_down(&mut self) {
         let page_size = 5; // Scroll by 5 items at a time
-        let visible_height = self.calculate_visible_height();
-        let max_scroll = self.items.len().saturating_sub(visible_height);
+        let max_scroll = self.max_scroll_offset(&self.visible_indices());
         self.scroll_offset = (self.scroll_offset + page_size).min(max_scroll);
     }
 
@@ -554,75 +1845,402 @@ impl Todo {
         if self.selected_index < self.items.len() {
             self.save_state_for_undo();
             
-            let was_done = self.items[self.selected_index].done;
-            self.items[self.selected_index].done = !self.items[self.selected_index].done;
+            let index = self.selected_index;
+            self.items[index].done = !self.items[index].done;
+            self.record_completion(index);
             
-            // If the task was just marked as done, move it to the bottom
-            if !was_done && self.items[self.selected_index].done {
-                let completed_task = self.items.remove(self.selected_index);
-                self.items.push(completed_task);
+            if let Some(parent_id) = self.items[index].parent_id {
+                // Subtasks stay under their parent; finishing the last one may finish the parent too
+                if self.items[index].done && self.auto_complete_parents {
+                    self.complete_parent_if_finished(parent_id);
+                }
+            }
+            // If the task was just marked as done, move it (with its subtasks) to the bottom
+            else if self.items[index].done {
+                self.move_block(index);
                 
                 // Adjust selection to stay within bounds
                 if self.selected_index >= self.items.len() {
-                    self.selected_index = if self.items.len() > 0 { self.items.len() - 1 } else { 0 };
-                }
-                
-                // Adjust scroll offset if needed to keep selection visible
-                let visible_height = self.calculate_visible_height();
-                if self.selected_index < self.scroll_offset {
-                    self.scroll_offset = self.selected_index;
-                } else if self.selected_index >= self.scroll_offset + visible_height {
-                    self.scroll_offset = self.selected_index.saturating_sub(visible_height - 1);
+                    self.selected_index = self.items.len().saturating_sub(1);
                 }
+                self.keep_selection_visible();
             }
-            // If the task was unmarked (done -> not done), move it back to its natural position
-            // For simplicity, we'll move it to the top of uncompleted tasks
-            else if was_done && !self.items[self.selected_index].done {
-                let uncompleted_task = self.items.remove(self.selected_index);
-                
-                // Find the first completed task position, or end of list if no completed tasks
-                let insert_position = self.items.iter()
-                    .position(|item| item.done)
-                    .unwrap_or(self.items.len());
-                
-                self.items.insert(insert_position, uncompleted_task);
-                
+            // If the task was unmarked (done -> not done), move it to the top of uncompleted tasks
+            else {
                 // Update selection to follow the moved item
-                self.selected_index = insert_position;
-                
-                // Adjust scroll offset if needed
-                let visible_height = self.calculate_visible_height();
-                if self.selected_index < self.scroll_offset {
-                    self.scroll_offset = self.selected_index;
-                } else if self.selected_index >= self.scroll_offset + visible_height {
-                    self.scroll_offset = self.selected_index.saturating_sub(visible_height - 1);
-                }
+                self.selected_index = self.move_block(index);
+                self.keep_selection_visible();
             }
             
+            self.unblock_dependents();
+            self.sort_items();
             self.save_to_file();
         }
     }
 
-    pub fn delete_selected_task(&mut self) {
-        if self.selected_index < self.items.len() {
-            self.save_state_for_undo();
-            self.items.remove(self.selected_index);
-            // Adjust selection index if needed
-            if self.selected_index >= self.items.len() && !self.items.is_empty() {
-                self.selected_index = self.items.len() - 1;
-            } else if self.items.is_empty() {
-                self.selected_index = 0;
-            }
-            
-            // Adjust scroll offset if needed
-            if self.scroll_offset > 0 && self.selected_index < self.scroll_offset {
-                self.scroll_offset = self.selected_index;
-            }
-            
+    /// Mark the parent done (and move it to the bottom) once all of its subtasks are done
+    fn complete_parent_if_finished(&mut self, parent_id: u64) {
+        let Some(parent_index) = self.index_of_id(parent_id) else {
+            return;
+        };
+        let end = self.block_end(parent_index);
+        if self.items[parent_index].done || !self.items[parent_index + 1..end].iter().all(|item| item.done) {
+            return;
+        }
+        
+        self.items[parent_index].done = true;
+        self.record_completion(parent_index);
+        self.move_block(parent_index);
+        self.selected_index = parent_index.min(self.items.len().saturating_sub(1));
+        self.keep_selection_visible();
+    }
+
+    /// Keep the completion time in step with the done flag. Recurring tasks also keep a history:
+    /// completing records the time, un-completing drops a completion recorded today.
+    fn record_completion(&mut self, index: usize) {
+        let item = &mut self.items[index];
+        let now = Local::now();
+        item.completed_at = item.done.then_some(now);
+        if item.repeat.is_none() {
+            return;
+        }
+        if item.done {
+            item.completions.push(now);
+        } else if item.completions.last().is_some_and(|last| last.date_naive() == now.date_naive()) {
+            item.completions.pop();
+        }
+    }
+
+    /// Bring back done recurring tasks whose next occurrence is on or before `today`.
+    /// Focused time and timeline are kept. Returns whether any task came back.
+    pub fn reset_recurring_tasks(&mut self, today: NaiveDate) -> bool {
+        let due: Vec<u64> = self.items.iter()
+            .filter(|item| item.done)
+            .filter(|item| item.repeat.as_ref().is_some_and(|repeat| {
+                item.completions.last().is_none_or(|last| repeat.next_after(last.date_naive()) <= today)
+            }))
+            .map(|item| item.id)
+            .collect();
+        if due.is_empty() {
+            return false;
+        }
+        
+        let selected_id = self.items.get(self.selected_index).map(|item| item.id);
+        for id in due {
+            if let Some(index) = self.index_of_id(id) {
+                self.items[index].done = false;
+                self.items[index].completed_at = None;
+                if self.items[index].parent_id.is_none() {
+                    self.move_block(index);
+                }
+            }
+        }
+        if let Some(index) = selected_id.and_then(|id| self.index_of_id(id)) {
+            self.selected_index = index;
+        }
+        self.keep_selection_visible();
+        self.sort_items();
+        self.save_to_file();
+        true
+    }
+
+    /// End (exclusive) of the task at `index` together with its subtasks
+    fn block_end(&self, index: usize) -> usize {
+        let mut end = index + 1;
+        if self.items[index].parent_id.is_none() {
+            let id = self.items[index].id;
+            while end < self.items.len() && self.items[end].parent_id == Some(id) {
+                end += 1;
+            }
+        }
+        end
+    }
+
+    /// Index of the top-level task the item at `index` belongs to (itself if it isn't a subtask)
+    fn parent_index(&self, index: usize) -> usize {
+        let mut parent_index = index;
+        while parent_index > 0 && self.items[parent_index].parent_id.is_some() {
+            parent_index -= 1;
+        }
+        parent_index
+    }
+
+    /// Move the top-level task at `index` with its subtasks to the bottom when done, otherwise to
+    /// the top of the done tasks (a starred one to the end of the starred tasks). Returns the task's new index.
+    fn move_block(&mut self, index: usize) -> usize {
+        let end = self.block_end(index);
+        let block: Vec<TodoItem> = self.items.drain(index..end).collect();
+        let position = if block[0].done {
+            self.items.len()
+        } else if block[0].is_pinned() {
+            self.pinned_end()
+        } else {
+            // Find the first completed task position, or end of list if no completed tasks
+            self.items.iter()
+                .position(|item| item.parent_id.is_none() && item.done)
+                .unwrap_or(self.items.len())
+        };
+        self.items.splice(position..position, block);
+        position
+    }
+
+    /// End (exclusive) of the starred tasks at the top of the list
+    fn pinned_end(&self) -> usize {
+        self.items.iter()
+            .position(|item| item.parent_id.is_none() && !item.is_pinned())
+            .unwrap_or(self.items.len())
+    }
+
+    /// Move the selected not-done task one place up or down among its siblings, keeping it selected.
+    /// Tasks don't move into the done tasks at the bottom or past the starred tasks at the top,
+    /// and with sort_by_priority only within their priority.
+    pub fn move_selected_task(&mut self, up: bool) {
+        if !self.can_reorder() {
+            return;
+        }
+        let Some(neighbor) = self.swappable_neighbor(up) else {
+            return;
+        };
+        
+        // A burst of moves less than a second apart is undone in one step
+        let now = Instant::now();
+        if self.last_reorder.is_none_or(|last| now.duration_since(last) > Duration::from_secs(1)) {
+            self.save_state_for_undo();
+        }
+        self.last_reorder = Some(now);
+        
+        self.swap_selected_with(neighbor, up);
+        self.keep_selection_visible();
+        self.save_to_file();
+    }
+
+    /// Move the selected not-done task as far up or down among its siblings as J/K would take it:
+    /// to the top of the list, or to just above the first done task. One undo step puts it back.
+    pub fn move_selected_task_to_end(&mut self, top: bool) {
+        if !self.can_reorder() || self.swappable_neighbor(top).is_none() {
+            return;
+        }
+        self.save_state_for_undo();
+        self.last_reorder = None;
+        while let Some(neighbor) = self.swappable_neighbor(top) {
+            self.swap_selected_with(neighbor, top);
+        }
+        self.keep_selection_visible();
+        self.save_to_file();
+    }
+
+    /// Tasks are only reordered in manual order; says so in the status line otherwise
+    fn can_reorder(&mut self) -> bool {
+        if self.selected_index >= self.items.len() {
+            return false;
+        }
+        if self.sort_mode != SortMode::Manual {
+            self.status_message = Some("Tasks can only be moved in manual order (o)".to_string());
+            return false;
+        }
+        true
+    }
+
+    /// Start of the sibling the selected task can swap places with, if it may move that way
+    fn swappable_neighbor(&self, up: bool) -> Option<usize> {
+        let index = self.selected_index;
+        let item = self.items.get(index)?;
+        let neighbor = if up { self.previous_sibling(index) } else { self.next_sibling(index) }?;
+        let other = &self.items[neighbor];
+        let blocked = item.parent_id.is_none()
+            && (other.done || other.is_pinned() != item.is_pinned()
+                || (self.sort_by_priority && other.priority != item.priority));
+        (!item.done && !blocked).then_some(neighbor)
+    }
+
+    /// Swap the selected task (with its subtasks) and the neighboring task (with its subtasks)
+    fn swap_selected_with(&mut self, neighbor: usize, up: bool) {
+        let index = self.selected_index;
+        let end = self.block_end(index);
+        if up {
+            self.items[neighbor..end].rotate_left(index - neighbor);
+            self.selected_index = neighbor;
+        } else {
+            let neighbor_end = self.block_end(neighbor);
+            self.items[index..neighbor_end].rotate_right(neighbor_end - end);
+            self.selected_index = index + (neighbor_end - end);
+        }
+    }
+
+    /// Start of the task before the one at `index` on the same level (under the same parent for subtasks)
+    fn previous_sibling(&self, index: usize) -> Option<usize> {
+        if index == 0 {
+            return None;
+        }
+        match self.items[index].parent_id {
+            Some(parent_id) => (self.items[index - 1].parent_id == Some(parent_id)).then_some(index - 1),
+            None => Some(self.parent_index(index - 1)),
+        }
+    }
+
+    /// Start of the task after the one at `index` on the same level (under the same parent for subtasks)
+    fn next_sibling(&self, index: usize) -> Option<usize> {
+        let end = self.block_end(index);
+        let next = self.items.get(end)?;
+        match self.items[index].parent_id {
+            Some(parent_id) => (next.parent_id == Some(parent_id)).then_some(end),
+            None => Some(end),
+        }
+    }
+
+    /// Cycle the selected task's priority (none → high → medium → low → none)
+    pub fn cycle_selected_priority(&mut self) {
+        if self.selected_index < self.items.len() {
+            self.save_state_for_undo();
+            let item = &mut self.items[self.selected_index];
+            item.priority = Priority::cycle(item.priority);
+            self.sort_items();
+            self.save_to_file();
+        }
+    }
+
+    /// Star the selected task, pinning it above the other not-done tasks, or unstar it
+    pub fn toggle_selected_star(&mut self) {
+        let Some(item) = self.items.get(self.selected_index) else {
+            return;
+        };
+        if item.parent_id.is_some() {
+            self.status_message = Some("Subtasks stay under their task; star the task instead".to_string());
+            return;
+        }
+        self.save_state_for_undo();
+        let item = &mut self.items[self.selected_index];
+        item.starred = !item.starred;
+        self.sort_items();
+        self.save_to_file();
+    }
+
+    /// Keep starred not-done tasks at the top. With sort_by_priority on, also order not-done tasks by
+    /// priority (keeping their order within a priority) and done tasks after them. Subtasks move
+    /// with their parent. The selection follows the selected task.
+    pub fn sort_items(&mut self) {
+        let selected_id = self.items.get(self.selected_index).map(|item| item.id);
+        
+        let mut blocks: Vec<Vec<TodoItem>> = Vec::new();
+        for item in self.items.drain(..) {
+            match blocks.last_mut() {
+                Some(block) if item.parent_id.is_some() => block.push(item),
+                _ => blocks.push(vec![item]),
+            }
+        }
+        let by_priority = self.sort_by_priority;
+        blocks.sort_by_key(|block| {
+            let item = &block[0];
+            if !by_priority {
+                (false, !item.is_pinned(), 0)
+            } else if item.done {
+                (true, true, 0)
+            } else {
+                (false, !item.is_pinned(), Priority::rank(item.priority))
+            }
+        });
+        self.items = blocks.into_iter().flatten().collect();
+        
+        if let Some(index) = selected_id.and_then(|id| self.index_of_id(id)) {
+            self.selected_index = index;
+            self.keep_selection_visible();
+        }
+    }
+
+    /// Scroll so the selected task is inside the visible area. A selection the filter hides
+    /// moves to the next shown task (or the last one); a filter or today view that hides everything is dropped.
+    fn keep_selection_visible(&mut self) {
+        let mut visible = self.visible_indices();
+        if visible.is_empty() && self.tag_filter.take().is_some() {
+            visible = self.visible_indices();
+        }
+        if visible.is_empty() && std::mem::take(&mut self.today_view) {
+            visible = self.visible_indices();
+        }
+        
+        // A hidden selection moves to the shown task that follows it in the list
+        let position = visible.iter()
+            .position(|&i| i == self.selected_index)
+            .or_else(|| visible.iter().enumerate()
+                .filter(|&(_, &i)| i > self.selected_index)
+                .min_by_key(|&(_, &i)| i)
+                .map(|(position, _)| position))
+            .unwrap_or(visible.len().saturating_sub(1));
+        if let Some(&index) = visible.get(position) {
+            self.selected_index = index;
+        }
+        
+        // Rows can take several lines, so scroll until the whole selected row fits
+        if position < self.scroll_offset {
+            self.scroll_offset = position;
+        }
+        while self.scroll_offset < position && position >= self.scroll_offset + self.rows_fitting(&visible, self.scroll_offset) {
+            self.scroll_offset += 1;
+        }
+    }
+
+    /// Delete the selected task with its subtasks. A task that has subtasks
+    /// is only deleted when this is called a second time in a row.
+    pub fn delete_selected_task(&mut self) {
+        if self.selected_index < self.items.len() {
+            let end = self.block_end(self.selected_index);
+            let id = self.items[self.selected_index].id;
+            if end - self.selected_index > 1 && self.delete_confirm != Some(id) {
+                self.delete_confirm = Some(id);
+                return;
+            }
+            self.delete_confirm = None;
+            
+            self.save_state_for_undo();
+            self.items.drain(self.selected_index..end);
+            self.unblock_dependents();
+            // Adjust selection index if needed, and keep it visible
+            self.selected_index = self.selected_index.min(self.items.len().saturating_sub(1));
+            self.keep_selection_visible();
+            
             self.save_to_file();
         }
     }
 
+    /// Which items clearing done tasks removes: done tasks, and subtasks of done tasks
+    fn done_tasks_to_clear(&self) -> Vec<bool> {
+        (0..self.items.len())
+            .map(|i| self.items[i].done || self.items[self.parent_index(i)].done)
+            .collect()
+    }
+
+    /// Remove all done tasks (with their subtasks) in one undoable step.
+    /// Only happens when called a second time in a row; returns how many items were removed.
+    pub fn clear_done_tasks(&mut self) -> usize {
+        let to_clear = self.done_tasks_to_clear();
+        if !to_clear.contains(&true) {
+            return 0;
+        }
+        if !self.clear_done_confirm {
+            self.clear_done_confirm = true;
+            return 0;
+        }
+        self.clear_done_confirm = false;
+        
+        // Keep the selection on the selected task, or move it to the nearest task that stays (below first)
+        let len = self.items.len();
+        let selected_id = (self.selected_index.min(len)..len)
+            .chain((0..self.selected_index.min(len)).rev())
+            .find(|&i| !to_clear[i])
+            .map(|i| self.items[i].id);
+        
+        self.save_state_for_undo();
+        let mut to_clear = to_clear.into_iter();
+        self.items.retain(|_| !to_clear.next().unwrap_or(false));
+        let removed = len - self.items.len();
+        self.unblock_dependents();
+        
+        self.selected_index = selected_id.and_then(|id| self.index_of_id(id)).unwrap_or(0);
+        self.keep_selection_visible();
+        self.save_to_file();
+        removed
+    }
+
     pub fn get_selected_task(&self) -> Option<&TodoItem> {
         self.items.get(self.selected_index)
     }
@@ -635,7 +2253,7 @@ impl Todo {
         }
     }
     
-    pub fn add_time_to_task_by_index(&mut self, index: usize, minutes: u32) {
+    fn add_time_to_task_by_index(&mut self, index: usize, minutes: u32) {
         if index < self.items.len() {
             self.save_state_for_undo();
             self.items[index].focused_time += minutes;
@@ -662,6 +2280,12 @@ impl Todo {
         }
     }
     
+    pub fn add_time_to_task_by_id(&mut self, id: u64, minutes: u32) {
+        if let Some(index) = self.index_of_id(id) {
+            self.add_time_to_task_by_index(index, minutes);
+        }
+    }
+    
     // Statistics methods for summary panel
     pub fn get_today_minutes(&self) -> u32 {
         let today = chrono::Local::now().date_naive();
@@ -672,6 +2296,29 @@ impl Todo {
             .sum()
     }
     
+    /// Work sessions finished today
+    pub fn get_today_pomodoros(&self) -> u32 {
+        let today = chrono::Local::now().date_naive();
+        self.pomodoro_sessions.iter()
+            .filter(|session| session.date == today)
+            .map(|session| session.work_sessions)
+            .sum()
+    }
+    
+    /// Average of today's focus ratings, if any were given
+    pub fn get_today_focus_average(&self) -> Option<f32> {
+        let today = chrono::Local::now().date_naive();
+        let ratings: Vec<u8> = self.pomodoro_sessions.iter()
+            .filter(|session| session.date == today)
+            .flat_map(|session| session.ratings.iter().copied())
+            .collect();
+        if ratings.is_empty() {
+            None
+        } else {
+            Some(ratings.iter().map(|&r| r as f32).sum::<f32>() / ratings.len() as f32)
+        }
+    }
+    
     pub fn get_yesterday_minutes(&self) -> u32 {
         let yesterday = chrono::Local::now().date_naive() - chrono::Duration::days(1);
         // Calculate from pomodoro sessions instead of task timelines
@@ -681,75 +2328,2135 @@ impl Todo {
             .sum()
     }
     
-    pub fn get_streak_days(&self) -> u32 {
-        let today = chrono::Local::now().date_naive();
-        let dates_with_work: std::collections::HashSet<chrono::NaiveDate> = 
-            self.items.iter()
-                .flat_map(|item| &item.timeline)
-                .map(|session| session.date)
-                .collect();
+    /// Average length of the finished work sessions of the last `days` days, today included; None
+    /// before the first one
+    pub fn get_average_session_minutes(&self, days: u32) -> Option<f32> {
+        average_session_minutes(&self.pomodoro_sessions, chrono::Local::now().date_naive(), days)
+    }
+
+    /// Days in a row with focus time, from the same sessions as today's and yesterday's minutes
+    pub fn get_streak_days(&self, ignore_weekends: bool) -> u32 {
+        let dates_with_work: HashSet<NaiveDate> = self.pomodoro_sessions.iter()
+            .filter(|session| session.total_work_minutes > 0)
+            .map(|session| session.date)
+            .collect();
+        streak_days(&dates_with_work, chrono::Local::now().date_naive(), ignore_weekends)
+    }
+    
+    /// Tasks marked done on the given day
+    pub fn get_completed_on(&self, date: NaiveDate) -> usize {
+        self.items.iter()
+            .filter(|item| item.done && item.completed_at.is_some_and(|completed_at| completed_at.date_naive() == date))
+            .count()
+    }
+
+    pub fn start_input_mode(&mut self) {
+        self.is_input_mode = true;
+        self.input_purpose = InputPurpose::NewTask;
+        self.current_input.clear();
+    }
+
+    /// Open the input line to set the selected task's custom session length
+    pub fn start_session_length_input(&mut self) {
+        if let Some(item) = self.items.get(self.selected_index) {
+            self.current_input.set(item.session_minutes.map(|m| m.to_string()).unwrap_or_default());
+            self.is_input_mode = true;
+            self.input_purpose = InputPurpose::SessionLength;
+        }
+    }
+
+    /// Open the input line to set the selected task's estimated pomodoros
+    pub fn start_estimate_input(&mut self) {
+        if let Some(item) = self.items.get(self.selected_index) {
+            self.current_input.set(item.estimate.map(|n| n.to_string()).unwrap_or_default());
+            self.is_input_mode = true;
+            self.input_purpose = InputPurpose::Estimate;
+        }
+    }
+
+    /// Open the notes popup for the selected task
+    pub fn open_notes_editor(&mut self) {
+        if let Some(item) = self.items.get(self.selected_index) {
+            self.notes_editor = Some(item.notes.clone());
+        }
+    }
+
+    /// Close the notes popup, saving the notes into the selected task if they changed
+    pub fn close_notes_editor(&mut self) {
+        let Some(notes) = self.notes_editor.take() else {
+            return;
+        };
+        let notes = notes.trim_end().to_string();
+        if self.selected_index < self.items.len() && self.items[self.selected_index].notes != notes {
+            self.save_state_for_undo();
+            self.items[self.selected_index].notes = notes;
+            self.save_to_file();
+        }
+    }
+
+    /// Popup editing the selected task's notes, drawn over the whole screen
+    pub fn render_notes_editor(&self, frame: &mut Frame) {
+        let (Some(notes), Some(item)) = (&self.notes_editor, self.items.get(self.selected_index)) else {
+            return;
+        };
+        let [popup_area] = Layout::vertical([Constraint::Length(12)]).flex(Flex::Center).areas(frame.area());
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(60)]).flex(Flex::Center).areas(popup_area);
         
-        let mut streak = 0;
-        let mut current_date = today;
+        frame.render_widget(Clear, popup_area);
         
-        loop {
-            if dates_with_work.contains(&current_date) {
-                streak += 1;
-                current_date = current_date - chrono::Duration::days(1);
-            } else {
-                break;
+        let block = Block::default()
+            .title(format!("📝 Notes: {}", truncate_to_width(&item.task, 40)))
+            .title_bottom("Enter = new line, Esc = save and close")
+            .title_style(Style::default().fg(DraculaTheme::PINK))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(DraculaTheme::PINK))
+            .style(Style::default().bg(DraculaTheme::CURRENT_LINE).fg(DraculaTheme::FOREGROUND));
+        
+        // Keep the end of long notes (where the cursor is) in view
+        let inner_height = popup_area.height.saturating_sub(2) as usize;
+        let line_count = notes.split('\n').count();
+        let scroll = line_count.saturating_sub(inner_height) as u16;
+        let editor = Paragraph::new(format!("{}_", notes))
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0))
+            .block(block);
+        
+        frame.render_widget(editor, popup_area);
+    }
+
+    /// Everything about the selected task, for the detail popup
+    pub fn task_detail_lines(&self) -> Vec<Line<'static>> {
+        let Some(item) = self.items.get(self.selected_index) else {
+            return Vec::new();
+        };
+        let label = |text: &str| Span::styled(text.to_string(), Style::default().fg(DraculaTheme::CYAN));
+        
+        let mut lines = vec![Line::from(task_spans(&item.task)), Line::raw("")];
+        let status = match item.completed_at.filter(|_| item.done) {
+            Some(completed_at) => format!("✓ done {}", completed_at.format("%Y-%m-%d %H:%M")),
+            None if item.done => "✓ done".to_string(),
+            None => "○ open".to_string(),
+        };
+        lines.push(Line::from(vec![label("Status:    "), Span::raw(status)]));
+        lines.push(Line::from(vec![label("Focused:   "), Span::raw(format!("{} min", item.focused_time))]));
+        if let Some(estimate) = item.estimate {
+            let done = item.pomodoros_done(self.default_session_minutes);
+            lines.push(Line::from(vec![label("Estimate:  "), Span::raw(format!("{}/{} 🍅", done, estimate))]));
+        }
+        if let Some(priority) = item.priority {
+            lines.push(Line::from(vec![
+                label("Priority:  "),
+                Span::styled(priority.name(), Style::default().fg(priority.color())),
+            ]));
+        }
+        if let Some(repeat) = &item.repeat {
+            lines.push(Line::from(vec![label("Repeat:    "), Span::raw(repeat.spec())]));
+        }
+        
+        if !item.notes.is_empty() {
+            lines.push(Line::raw(""));
+            lines.push(Line::from(label("Notes:")));
+            lines.extend(item.notes.split('\n').map(|line| Line::raw(format!("  {}", line))));
+        }
+        
+        lines.push(Line::raw(""));
+        lines.push(Line::from(label("Timeline:")));
+        if item.timeline.is_empty() {
+            lines.push(Line::styled("  No focused time yet", Style::default().fg(DraculaTheme::COMMENT)));
+        }
+        for session in &item.timeline {
+            lines.push(Line::raw(format!("  {}: {} min (last at {})",
+                session.date, session.minutes, session.timestamp.format("%H:%M"))));
+        }
+        lines
+    }
+
+    pub fn scroll_task_detail_up(&mut self) {
+        self.detail_scroll = self.detail_scroll.saturating_sub(1);
+    }
+
+    pub fn scroll_task_detail_down(&mut self) {
+        if self.detail_scroll + 1 < self.task_detail_lines().len() {
+            self.detail_scroll += 1;
+        }
+    }
+
+    /// Centered popup with the selected task's full text, status, notes and timeline
+    pub fn render_task_detail(&self, frame: &mut Frame) {
+        let [popup_area] = Layout::vertical([Constraint::Percentage(70)]).flex(Flex::Center).areas(frame.area());
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(60)]).flex(Flex::Center).areas(popup_area);
+        
+        frame.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title("🔍 Task Details")
+            .title_bottom("j/k = scroll, Esc = close")
+            .title_style(Style::default().fg(DraculaTheme::PINK))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(DraculaTheme::PINK))
+            .style(Style::default().bg(DraculaTheme::CURRENT_LINE).fg(DraculaTheme::FOREGROUND));
+        
+        let detail = Paragraph::new(self.task_detail_lines())
+            .wrap(Wrap { trim: false })
+            .scroll((self.detail_scroll as u16, 0))
+            .block(block);
+        
+        frame.render_widget(detail, popup_area);
+    }
+
+    /// Open the link in the selected task's text or notes, or a picker when there are several
+    pub fn open_task_links(&mut self) {
+        let Some(item) = self.items.get(self.selected_index) else {
+            return;
+        };
+        let urls = links::find_urls(&format!("{}\n{}", item.task, item.notes));
+        match urls.as_slice() {
+            [] => self.status_message = Some("No links in this task".to_string()),
+            [url] => self.open_link(url),
+            _ => {
+                self.link_choices = urls;
+                self.link_choice = 0;
             }
         }
+    }
+
+    fn open_link(&mut self, url: &str) {
+        self.status_message = Some(match links::open_url(url) {
+            Ok(()) => format!("Opened {}", url),
+            Err(e) => format!("Could not open {}: {}", url, e),
+        });
+    }
+
+    /// Open the link at `index` in the picker (the highlighted one when None) and close the picker
+    pub fn open_link_choice(&mut self, index: Option<usize>) {
+        let index = index.unwrap_or(self.link_choice);
+        if let Some(url) = self.link_choices.get(index).cloned() {
+            self.close_link_picker();
+            self.open_link(&url);
+        }
+    }
+
+    pub fn move_link_choice(&mut self, up: bool) {
+        if up {
+            self.link_choice = self.link_choice.saturating_sub(1);
+        } else if self.link_choice + 1 < self.link_choices.len() {
+            self.link_choice += 1;
+        }
+    }
+
+    pub fn close_link_picker(&mut self) {
+        self.link_choices.clear();
+        self.link_choice = 0;
+    }
+
+    /// Numbered list of the selected task's links, while there are several to choose from
+    pub fn render_link_picker(&self, frame: &mut Frame) {
+        if self.link_choices.is_empty() {
+            return;
+        }
+        let height = self.link_choices.len() as u16 + 2;
+        let [popup_area] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(frame.area());
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(60)]).flex(Flex::Center).areas(popup_area);
+        
+        frame.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title("🔗 Open link")
+            .title_bottom("j/k + Enter or 1-9 = open, Esc = cancel")
+            .title_style(Style::default().fg(DraculaTheme::PINK))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(DraculaTheme::PINK))
+            .style(Style::default().bg(DraculaTheme::CURRENT_LINE).fg(DraculaTheme::FOREGROUND));
+        let width = popup_area.width.saturating_sub(6) as usize;
+        let lines: Vec<Line> = self.link_choices.iter().enumerate()
+            .map(|(i, url)| {
+                let line = format!("{} {}", i + 1, truncate_to_width(url, width));
+                if i == self.link_choice {
+                    Line::styled(format!("► {}", line), Style::default().fg(DraculaTheme::CYAN))
+                } else {
+                    Line::raw(format!("  {}", line))
+                }
+            })
+            .collect();
         
-        streak
+        frame.render_widget(Paragraph::new(lines).block(block), popup_area);
     }
-    
-    pub fn get_completed_tasks_count(&self) -> usize {
-        self.items.iter().filter(|item| item.done).count()
+
+    /// Pick the task the selected one has to wait for (other tasks that aren't done), or
+    /// unblock the selected task when it is already waiting
+    pub fn start_blocker_picker(&mut self) {
+        let Some(item) = self.items.get(self.selected_index) else {
+            return;
+        };
+        if item.blocked_by.is_some() {
+            self.save_state_for_undo();
+            self.items[self.selected_index].blocked_by = None;
+            self.status_message = Some("Unblocked".to_string());
+            self.save_to_file();
+            return;
+        }
+        let id = item.id;
+        let choices: Vec<u64> = self.items.iter()
+            .filter(|other| other.id != id && !other.done)
+            .map(|other| other.id)
+            .collect();
+        if choices.is_empty() {
+            self.status_message = Some("No other open tasks to wait for".to_string());
+            return;
+        }
+        self.blocker_choices = choices;
+        self.blocker_choice = 0;
     }
 
-    pub fn start_input_mode(&mut self) {
-        self.is_input_mode = true;
-        self.current_input.clear();
+    /// Block the selected task by the highlighted task in the picker and close the picker.
+    /// A link that would make tasks wait on each other is refused.
+    pub fn choose_blocker(&mut self) {
+        let Some(&blocker_id) = self.blocker_choices.get(self.blocker_choice) else {
+            return;
+        };
+        self.close_blocker_picker();
+        let Some(item) = self.items.get(self.selected_index) else {
+            return;
+        };
+        if self.would_cycle(item.id, blocker_id) {
+            self.status_message = Some("Not blocked: that task is already waiting for this one".to_string());
+            return;
+        }
+        self.save_state_for_undo();
+        self.items[self.selected_index].blocked_by = Some(blocker_id);
+        self.save_to_file();
+    }
+
+    pub fn move_blocker_choice(&mut self, up: bool) {
+        if up {
+            self.blocker_choice = self.blocker_choice.saturating_sub(1);
+        } else if self.blocker_choice + 1 < self.blocker_choices.len() {
+            self.blocker_choice += 1;
+        }
+    }
+
+    pub fn close_blocker_picker(&mut self) {
+        self.blocker_choices.clear();
+        self.blocker_choice = 0;
+    }
+
+    /// List of open tasks to pick the selected task's blocker from, scrolled to the highlighted one
+    pub fn render_blocker_picker(&self, frame: &mut Frame) {
+        if self.blocker_choices.is_empty() {
+            return;
+        }
+        let shown = self.blocker_choices.len().min(12);
+        let [popup_area] = Layout::vertical([Constraint::Length(shown as u16 + 2)]).flex(Flex::Center).areas(frame.area());
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(60)]).flex(Flex::Center).areas(popup_area);
+        
+        frame.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title("🔒 Blocked by")
+            .title_bottom("j/k + Enter = choose, Esc = cancel")
+            .title_style(Style::default().fg(DraculaTheme::PINK))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(DraculaTheme::PINK))
+            .style(Style::default().bg(DraculaTheme::CURRENT_LINE).fg(DraculaTheme::FOREGROUND));
+        let width = popup_area.width.saturating_sub(6) as usize;
+        let number_width = self.items.len().to_string().len();
+        let first = self.blocker_choice.saturating_sub(shown - 1);
+        let lines: Vec<Line> = self.blocker_choices.iter().enumerate().skip(first).take(shown)
+            .filter_map(|(i, &id)| {
+                let index = self.index_of_id(id)?;
+                let line = format!("{:0width$} {}", index + 1, self.items[index].task, width = number_width);
+                let line = truncate_to_width(&line, width);
+                Some(if i == self.blocker_choice {
+                    Line::styled(format!("► {}", line), Style::default().fg(DraculaTheme::CYAN))
+                } else {
+                    Line::raw(format!("  {}", line))
+                })
+            })
+            .collect();
+        
+        frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+    }
+
+    /// Open the input line to add a subtask under the selected task (or next to the selected subtask)
+    pub fn start_subtask_input(&mut self) {
+        if self.selected_index < self.items.len() {
+            self.is_input_mode = true;
+            self.input_purpose = InputPurpose::NewSubtask;
+            self.current_input.clear();
+        }
+    }
+
+    /// Open the input line to set how the selected task repeats
+    pub fn start_repeat_input(&mut self) {
+        if let Some(item) = self.items.get(self.selected_index) {
+            self.current_input.set(item.repeat.as_ref().map(|repeat| repeat.spec()).unwrap_or_default());
+            self.is_input_mode = true;
+            self.input_purpose = InputPurpose::Repeat;
+        }
+    }
+
+    /// Open the input line to plan the selected task for a day
+    pub fn start_schedule_input(&mut self) {
+        if let Some(item) = self.items.get(self.selected_index) {
+            self.current_input.set(item.scheduled_for.map(|day| day.format("%Y-%m-%d").to_string()).unwrap_or_default());
+            self.is_input_mode = true;
+            self.input_purpose = InputPurpose::Schedule;
+        }
+    }
+
+    /// Open the input line prefilled with the selected task's text, to edit it in place
+    pub fn start_edit_input(&mut self) {
+        if let Some(item) = self.items.get(self.selected_index) {
+            self.current_input.set(item.task.clone());
+            self.is_input_mode = true;
+            self.input_purpose = InputPurpose::EditTask;
+        }
     }
 
     pub fn cancel_input_mode(&mut self) {
         self.is_input_mode = false;
+        self.input_purpose = InputPurpose::NewTask;
         self.current_input.clear();
     }
 
-    pub fn submit_new_task(&mut self) {
-        if !self.current_input.trim().is_empty() {
+    /// Submit the input line according to what it was opened for
+    pub fn submit_input(&mut self) {
+        match self.input_purpose {
+            InputPurpose::NewTask => self.submit_new_task(),
+            InputPurpose::SessionLength => self.submit_session_length(),
+            InputPurpose::Estimate => self.submit_estimate(),
+            InputPurpose::EditTask => self.submit_edit_task(),
+            InputPurpose::NewSubtask => self.submit_new_subtask(),
+            InputPurpose::Repeat => self.submit_repeat(),
+            InputPurpose::Schedule => self.submit_schedule(),
+        }
+    }
+
+    /// Replace the selected task's text, keeping its status, focused time and timeline
+    pub fn submit_edit_task(&mut self) {
+        let task = self.current_input.as_str().trim();
+        // Empty input leaves the task unchanged
+        if !task.is_empty() && self.selected_index < self.items.len()
+            && self.items[self.selected_index].task != task {
+            let task = task.to_string();
             self.save_state_for_undo();
-            self.items.insert(0, TodoItem::new(self.current_input.clone()));
-            // Set selection to the newly added item at the top
-            self.selected_index = 0;
-            self.scroll_offset = 0;
+            self.items[self.selected_index].task = task;
             self.save_to_file();
         }
         self.is_input_mode = false;
+        self.input_purpose = InputPurpose::NewTask;
         self.current_input.clear();
     }
 
-    pub fn add_char_to_input(&mut self, c: char) {
-        if self.is_input_mode {
-            self.current_input.push(c);
-        }
-    }
+    pub fn submit_repeat(&mut self) {
+        let input = self.current_input.as_str().trim();
+        // Empty input stops the task repeating; an invalid spec is ignored
+        let repeat = if input.is_empty() {
+            Some(None)
+        } else {
+            Recurrence::parse(input).ok().map(Some)
+        };
 
-    pub fn remove_char_from_input(&mut self) {
-        if self.is_input_mode {
-            self.current_input.pop();
+        match repeat {
+            Some(repeat) if self.selected_index < self.items.len()
+                && self.items[self.selected_index].repeat != repeat => {
+                self.save_state_for_undo();
+                self.items[self.selected_index].repeat = repeat;
+                self.save_to_file();
+            }
+            _ => {}
         }
+        self.is_input_mode = false;
+        self.input_purpose = InputPurpose::NewTask;
+        self.current_input.clear();
     }
-    
+
+    pub fn submit_schedule(&mut self) {
+        let input = self.current_input.as_str().trim();
+        // Empty input unschedules the task; a day that can't be read is reported
+        let day = if input.is_empty() {
+            Some(None)
+        } else {
+            parse_day(input, Local::now().date_naive()).map(Some)
+        };
+
+        match day {
+            Some(day) if self.selected_index < self.items.len()
+                && self.items[self.selected_index].scheduled_for != day => {
+                self.save_state_for_undo();
+                self.items[self.selected_index].scheduled_for = day;
+                // Unscheduled in the today view, the task is hidden and the selection moves on
+                self.keep_selection_visible();
+                self.save_to_file();
+            }
+            Some(_) => {}
+            None => self.status_message = Some(format!("Not a day: {}", input)),
+        }
+        self.is_input_mode = false;
+        self.input_purpose = InputPurpose::NewTask;
+        self.current_input.clear();
+    }
+
+    pub fn submit_estimate(&mut self) {
+        let input = self.current_input.as_str().trim();
+        // Empty input clears the estimate; anything unparsable is ignored
+        let estimate = if input.is_empty() {
+            Some(None)
+        } else {
+            input.parse::<u32>().ok().filter(|&n| n > 0).map(Some)
+        };
+
+        match estimate {
+            Some(estimate) if self.selected_index < self.items.len()
+                && self.items[self.selected_index].estimate != estimate => {
+                self.save_state_for_undo();
+                self.items[self.selected_index].estimate = estimate;
+                self.save_to_file();
+            }
+            _ => {}
+        }
+        self.is_input_mode = false;
+        self.input_purpose = InputPurpose::NewTask;
+        self.current_input.clear();
+    }
+
+    pub fn submit_session_length(&mut self) {
+        let input = self.current_input.as_str().trim();
+        // Empty input clears the custom length; anything unparsable is ignored
+        let session_minutes = if input.is_empty() {
+            Some(None)
+        } else {
+            input.parse::<u32>().ok().filter(|&m| m > 0).map(Some)
+        };
+
+        match session_minutes {
+            Some(session_minutes) if self.selected_index < self.items.len()
+                && self.items[self.selected_index].session_minutes != session_minutes => {
+                self.save_state_for_undo();
+                self.items[self.selected_index].session_minutes = session_minutes;
+                self.save_to_file();
+            }
+            _ => {}
+        }
+        self.is_input_mode = false;
+        self.input_purpose = InputPurpose::NewTask;
+        self.current_input.clear();
+    }
+
+    /// Task typed into the input line, with inline syntax parsed when enabled
+    fn item_from_input(&self) -> TodoItem {
+        if self.inline_syntax {
+            TodoItem::parse_inline(self.current_input.as_str())
+        } else {
+            TodoItem::new(self.current_input.as_str().to_string())
+        }
+    }
+
+    pub fn submit_new_task(&mut self) {
+        if !self.current_input.as_str().trim().is_empty() {
+            self.save_state_for_undo();
+            let mut item = self.item_from_input();
+            // Added in the today view, a task is planned for today so it stays in view
+            if self.today_view {
+                item.scheduled_for = Some(Local::now().date_naive());
+            }
+            // New tasks go on top, under the starred ones
+            let position = self.pinned_end();
+            self.items.insert(position, item);
+            self.assign_missing_ids();
+            // Set selection to the newly added item
+            self.selected_index = position;
+            self.scroll_offset = 0;
+            self.keep_selection_visible();
+            self.sort_items();
+            self.save_to_file();
+        }
+        self.is_input_mode = false;
+        self.current_input.clear();
+    }
+
+    pub fn submit_new_subtask(&mut self) {
+        if !self.current_input.as_str().trim().is_empty() && self.selected_index < self.items.len() {
+            // Subtasks only nest one level, so a subtask's sibling goes under the same parent
+            let parent_index = self.parent_index(self.selected_index);
+            let position = self.block_end(parent_index);
+            
+            self.save_state_for_undo();
+            let mut subtask = self.item_from_input();
+            subtask.parent_id = Some(self.items[parent_index].id);
+            self.items.insert(position, subtask);
+            self.assign_missing_ids();
+            // Select the new subtask
+            self.selected_index = position;
+            self.keep_selection_visible();
+            self.save_to_file();
+        }
+        self.is_input_mode = false;
+        self.input_purpose = InputPurpose::NewTask;
+        self.current_input.clear();
+    }
+
+    
     // Pomodoro session management methods
     pub fn save_pomodoro_sessions(&mut self, sessions: Vec<PomodoroSession>) {
-        self.pomodoro_sessions = sessions;
-        self.save_to_file();
+        if self.pomodoro_sessions != sessions {
+            self.pomodoro_sessions = sessions;
+            self.save_to_file();
+        }
     }
     
     pub fn get_pomodoro_sessions(&self) -> &[PomodoroSession] {
         &self.pomodoro_sessions
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::empty_session;
+
+    /// A todo file path in the temp directory that doesn't exist yet
+    fn temp_todo_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("sessio-{}-{}.md", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    fn session(date: NaiveDate, minutes: u32, hour: u32, minute: u32) -> WorkSession {
+        let timestamp = Local.from_local_datetime(&date.and_hms_opt(hour, minute, 0).unwrap()).unwrap();
+        WorkSession { date, minutes, timestamp }
+    }
+
+    #[test]
+    fn test_timeline_survives_reload() {
+        let path = temp_todo_path("timeline");
+        let today = Local::now().date_naive();
+        let mut todo = Todo::new(Some(path.clone()), None);
+        todo.items[0].timeline = vec![
+            session(today - chrono::Duration::days(2), 25, 9, 30),
+            session(today - chrono::Duration::days(1), 50, 14, 5),
+        ];
+        todo.items[1].parent_id = Some(todo.items[0].id);
+        todo.items[1].timeline = vec![session(today, 15, 8, 45)];
+        todo.save_to_file();
+
+        let reloaded = Todo::new(Some(path.clone()), None);
+        fs::remove_file(&path).unwrap();
+        for (saved, loaded) in todo.items.iter().zip(&reloaded.items) {
+            assert_eq!(loaded.timeline, saved.timeline);
+        }
+    }
+
+    #[test]
+    fn test_average_session_minutes() {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let session = |d, work_sessions, total_work_minutes| PomodoroSession { work_sessions, total_work_minutes, ..empty_session(day(d)) };
+        assert_eq!(average_session_minutes(&[], day(16), 1), None);
+        let sessions = [session(9, 4, 200), session(10, 2, 50), session(16, 3, 75)];
+        assert_eq!(average_session_minutes(&sessions, day(16), 1), Some(25.0));
+        // Seven days back from the 16th is the 10th
+        assert_eq!(average_session_minutes(&sessions, day(16), 7), Some(25.0));
+        assert_eq!(average_session_minutes(&sessions, day(16), 8), Some(325.0 / 9.0));
+        // Nothing today, or only minutes credited from a session reset early
+        assert_eq!(average_session_minutes(&sessions, day(15), 1), None);
+        assert_eq!(average_session_minutes(&[session(16, 0, 12)], day(16), 1), None);
+    }
+
+    #[test]
+    fn test_streak_days() {
+        // 2026-10-16 is a Friday
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let worked = |days: &[u32]| days.iter().map(|&d| day(d)).collect::<HashSet<_>>();
+        assert_eq!(streak_days(&worked(&[]), day(16), false), 0);
+        // Worked today only
+        assert_eq!(streak_days(&worked(&[16]), day(16), false), 1);
+        // Nothing yet today: the streak up to yesterday still stands
+        assert_eq!(streak_days(&worked(&[14, 15]), day(16), false), 2);
+        assert_eq!(streak_days(&worked(&[14, 15, 16]), day(16), false), 3);
+        // A gap yesterday ends it, today or not
+        assert_eq!(streak_days(&worked(&[13, 14, 16]), day(16), false), 1);
+        assert_eq!(streak_days(&worked(&[13, 14]), day(16), false), 0);
+
+        // Monday after a weekend off (Fri 9, Sat 10, Sun 11, Mon 12)
+        assert_eq!(streak_days(&worked(&[8, 9, 12]), day(12), false), 1);
+        assert_eq!(streak_days(&worked(&[8, 9, 12]), day(12), true), 3);
+        assert_eq!(streak_days(&worked(&[8, 9]), day(12), true), 2);
+        // Worked weekend days still count; a weekday gap still breaks it
+        assert_eq!(streak_days(&worked(&[9, 10, 12]), day(12), true), 3);
+        assert_eq!(streak_days(&worked(&[7, 9, 12]), day(12), true), 2);
+        // Today being a Sunday off is fine either way
+        assert_eq!(streak_days(&worked(&[8, 9]), day(11), false), 0);
+        assert_eq!(streak_days(&worked(&[8, 9, 10]), day(11), false), 3);
+        assert_eq!(streak_days(&worked(&[8, 9]), day(11), true), 2);
+    }
+
+    #[test]
+    fn test_load_skips_malformed_timeline_lines() {
+        let path = temp_todo_path("malformed");
+        fs::write(&path, "# TODO List\n\n\
+            - [ ] Old task without a timeline | Focused time: 30 minutes\n\
+            - [ ] Task\n  \
+              Timeline:\n    \
+                - 2026-10-14: 25 minutes at 09:00\n    \
+                - 2026-13-01: 25 minutes at 09:00\n    \
+                - 2026-10-15: lots of minutes at 10:00\n    \
+                - 2026-10-15 25 minutes\n    \
+                - 2026-10-16: 50 minutes at 11:30\n").unwrap();
+
+        let todo = Todo::new(Some(path.clone()), None);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(todo.items.len(), 2);
+        assert!(todo.items[0].timeline.is_empty());
+        assert_eq!(todo.items[0].focused_time, 30);
+        let dates: Vec<String> = todo.items[1].timeline.iter().map(|s| s.date.to_string()).collect();
+        assert_eq!(dates, ["2026-10-14", "2026-10-16"]);
+        assert_eq!(todo.items[1].timeline[1].minutes, 50);
+        assert_eq!(todo.items[1].timeline[1].timestamp.format("%H:%M").to_string(), "11:30");
+    }
+
+    /// Load a todo list from markdown written to a fresh temp file
+    /// Saved file content without the creation times, which are the time the test ran
+    fn without_created(content: &str) -> String {
+        content.lines()
+            .map(|line| match line.find(" | Created: ") {
+                Some(start) => format!("{}{}", &line[..start], &line[start + " | Created: YYYY-MM-DD HH:MM".len()..]),
+                None => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n") + "\n"
+    }
+
+    fn load_todo(name: &str, content: &str) -> Todo {
+        let path = temp_todo_path(name);
+        fs::write(&path, content).unwrap();
+        Todo::new(Some(path), None)
+    }
+
+    fn task_names(todo: &Todo) -> Vec<&str> {
+        todo.items.iter().map(|item| item.task.as_str()).collect()
+    }
+
+    #[test]
+    fn test_clear_done_needs_confirmation_and_is_undoable() {
+        let mut todo = load_todo("clear-confirm", "- [ ] a\n- [x] b\n- [ ] c\n- [x] d\n");
+        assert_eq!(todo.clear_done_tasks(), 0);
+        assert!(todo.clear_done_confirm);
+        assert_eq!(todo.items.len(), 4);
+
+        assert_eq!(todo.clear_done_tasks(), 2);
+        assert_eq!(task_names(&todo), ["a", "c"]);
+        assert_eq!(todo.undo_stack.len(), 1);
+
+        assert!(todo.undo());
+        assert_eq!(task_names(&todo), ["a", "b", "c", "d"]);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_clear_done_moves_selection_off_removed_task() {
+        // Selected task removed: the next remaining one is selected
+        let mut todo = load_todo("clear-next", "- [ ] a\n- [x] b\n- [ ] c\n- [x] d\n");
+        todo.selected_index = 1;
+        todo.clear_done_confirm = true;
+        todo.clear_done_tasks();
+        assert_eq!(todo.get_selected_task().unwrap().task, "c");
+        fs::remove_file(&todo.file_path).unwrap();
+
+        // Selected task removed with nothing left below it: the one above is selected
+        let mut todo = load_todo("clear-previous", "- [ ] a\n- [ ] b\n- [x] c\n- [x] d\n");
+        todo.selected_index = 3;
+        todo.clear_done_confirm = true;
+        todo.clear_done_tasks();
+        assert_eq!(todo.get_selected_task().unwrap().task, "b");
+        fs::remove_file(&todo.file_path).unwrap();
+
+        // Selected task kept: it stays selected at its new position
+        let mut todo = load_todo("clear-kept", "- [x] a\n- [ ] b\n- [x] c\n");
+        todo.selected_index = 1;
+        todo.clear_done_confirm = true;
+        todo.clear_done_tasks();
+        assert_eq!(todo.selected_index, 0);
+        assert_eq!(todo.get_selected_task().unwrap().task, "b");
+        fs::remove_file(&todo.file_path).unwrap();
+
+        // Everything removed
+        let mut todo = load_todo("clear-all", "- [x] a\n- [x] b\n");
+        todo.selected_index = 1;
+        todo.clear_done_confirm = true;
+        assert_eq!(todo.clear_done_tasks(), 2);
+        assert!(todo.items.is_empty());
+        assert_eq!(todo.selected_index, 0);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_clear_done_takes_subtasks_of_done_tasks() {
+        let mut todo = load_todo("clear-subtasks", "- [x] parent\n  - [ ] child\n- [ ] open\n  - [x] done child\n");
+        todo.clear_done_confirm = true;
+        assert_eq!(todo.clear_done_tasks(), 3);
+        assert_eq!(task_names(&todo), ["open"]);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_inline_syntax() {
+        let item = TodoItem::parse_inline("!1 #deep Write conference talk ~4");
+        assert_eq!(item.task, "#deep Write conference talk");
+        assert_eq!(item.priority, Some(Priority::High));
+        assert_eq!(item.estimate, Some(4));
+        assert_eq!(item.tags(), ["deep"]);
+
+        let item = TodoItem::parse_inline("Call   mom !low");
+        assert_eq!((item.task.as_str(), item.priority, item.estimate), ("Call mom", Some(Priority::Low), None));
+        assert_eq!(TodoItem::parse_inline("Plan ~2 !2").priority, Some(Priority::Medium));
+
+        // Unknown or malformed tokens stay literal
+        for text in ["Say hi!", "Fix !4 bug", "~0 days", "Wait ~ 3 hours", "~two", "!! now"] {
+            let item = TodoItem::parse_inline(text);
+            assert_eq!((item.task.as_str(), item.priority, item.estimate), (text, None, None), "{:?}", text);
+        }
+
+        // Nothing but syntax keeps the text as the task
+        let item = TodoItem::parse_inline(" !1 ~3 ");
+        assert_eq!((item.task.as_str(), item.priority, item.estimate), ("!1 ~3", None, None));
+    }
+
+    #[test]
+    fn test_parse_inline_syntax_cjk() {
+        let item = TodoItem::parse_inline("写报告 !2 #工作 ~3");
+        assert_eq!(item.task, "写报告 #工作");
+        assert_eq!(item.priority, Some(Priority::Medium));
+        assert_eq!(item.estimate, Some(3));
+        assert_eq!(item.tags(), ["工作"]);
+
+        // Tokens have to be separate words
+        let item = TodoItem::parse_inline("!1写报告～2");
+        assert_eq!((item.task.as_str(), item.priority, item.estimate), ("!1写报告～2", None, None));
+    }
+
+    #[test]
+    fn test_submit_new_task_inline_syntax() {
+        let mut todo = load_todo("inline-syntax", "");
+        todo.start_input_mode();
+        todo.current_input.set("Review PR !1 ~2".to_string());
+        todo.submit_input();
+        assert_eq!(todo.items[0].task, "Review PR");
+        assert_eq!(todo.items[0].priority, Some(Priority::High));
+
+        todo.inline_syntax = false;
+        todo.start_input_mode();
+        todo.current_input.set("Review PR !1 ~2".to_string());
+        todo.submit_input();
+        assert_eq!(todo.items[0].task, "Review PR !1 ~2");
+        assert_eq!(todo.items[0].priority, None);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_sort_modes_only_change_the_view() {
+        let content = "- [ ] beta | Focused time: 10 minutes\n  - [ ] beta sub\n\
+                       - [ ] Alpha | Focused time: 50 minutes | Priority: low\n\
+                       - [ ] gamma | Focused time: 0 minutes | Priority: high\n\
+                       - [x] aardvark | Focused time: 90 minutes\n";
+        let mut todo = load_todo("sort-modes", content);
+        fn shown(todo: &Todo) -> Vec<&str> {
+            todo.visible_indices().iter().map(|&i| todo.items[i].task.as_str()).collect()
+        }
+        assert_eq!(shown(&todo), ["beta", "beta sub", "Alpha", "gamma", "aardvark"]);
+
+        todo.selected_index = 1; // beta sub
+        todo.cycle_sort_mode();
+        assert_eq!(todo.sort_mode, SortMode::FocusedTime);
+        assert_eq!(shown(&todo), ["Alpha", "beta", "beta sub", "gamma", "aardvark"]);
+        todo.cycle_sort_mode();
+        assert_eq!(shown(&todo), ["Alpha", "beta", "beta sub", "gamma", "aardvark"]);
+        todo.cycle_sort_mode();
+        assert_eq!(shown(&todo), ["gamma", "Alpha", "beta", "beta sub", "aardvark"]);
+
+        // Selection follows the task; moving it is refused outside manual order
+        assert_eq!(todo.items[todo.selected_index].task, "beta sub");
+        todo.move_selection_up();
+        assert_eq!(todo.items[todo.selected_index].task, "beta");
+        todo.move_selected_task(true);
+        assert!(todo.status_message.is_some());
+        assert_eq!(task_names(&todo), ["beta", "beta sub", "Alpha", "gamma", "aardvark"]);
+
+        // Oldest first
+        todo.items[3].created_at = Local::now() - Duration::from_secs(30 * 86400);
+        todo.cycle_sort_mode();
+        assert_eq!(todo.sort_mode, SortMode::Age);
+        assert_eq!(shown(&todo), ["gamma", "beta", "beta sub", "Alpha", "aardvark"]);
+
+        // Soonest planned first, unscheduled last
+        todo.items[0].scheduled_for = NaiveDate::from_ymd_opt(2026, 10, 20);
+        todo.items[3].scheduled_for = NaiveDate::from_ymd_opt(2026, 10, 18);
+        todo.items[4].scheduled_for = NaiveDate::from_ymd_opt(2026, 10, 1);
+        todo.cycle_sort_mode();
+        assert_eq!(todo.sort_mode, SortMode::DueDate);
+        assert_eq!(shown(&todo), ["gamma", "beta", "beta sub", "Alpha", "aardvark"]);
+        todo.items[3].scheduled_for = None;
+        assert_eq!(shown(&todo), ["beta", "beta sub", "Alpha", "gamma", "aardvark"]);
+
+        todo.cycle_sort_mode();
+        assert_eq!(todo.sort_mode, SortMode::Manual);
+        assert_eq!(shown(&todo), task_names(&todo));
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_task_detail_lines() {
+        let mut todo = load_todo("task-detail", "- [ ] Write a very long report title that the panel would truncate #work\n");
+        let item = &mut todo.items[0];
+        item.focused_time = 100;
+        item.estimate = Some(6);
+        item.notes = "Outline first\nthen details".to_string();
+        let date = NaiveDate::from_ymd_opt(2025, 6, 30).unwrap();
+        item.timeline = vec![session(date, 75, 16, 40), session(date.succ_opt().unwrap(), 25, 9, 5)];
+
+        let lines: Vec<String> = todo.task_detail_lines().iter().map(|line| line.to_string()).collect();
+        assert_eq!(lines[0], "Write a very long report title that the panel would truncate #work");
+        assert!(lines.contains(&"Estimate:  4/6 🍅".to_string()));
+        assert!(lines.contains(&"  then details".to_string()));
+        assert!(lines.ends_with(&["2025-06-30: 75 min (last at 16:40)", "2025-07-01: 25 min (last at 09:05)"]
+            .map(|line| format!("  {}", line))));
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_reload_external_changes() {
+        let mut todo = load_todo("external-reload", "- [ ] Write report\n- [ ] Call mom\n- [ ] Pay rent\n");
+        let ids: Vec<u64> = todo.items.iter().map(|item| item.id).collect();
+        todo.selected_index = 1;
+        todo.toggle_selected_task(); // our own write isn't an external change
+        assert!(!todo.check_disk_changes());
+
+        todo.selected_index = todo.index_of_id(ids[2]).unwrap();
+        fs::write(&todo.file_path, "- [ ] Pay rent\n- [ ] Write the report\n- [ ] Buy milk and eggs\n").unwrap();
+        assert!(todo.check_disk_changes());
+        assert_eq!(task_names(&todo), ["Pay rent", "Write the report", "Buy milk and eggs"]);
+        // Unchanged tasks keep their id, so the selection stays on them
+        assert_eq!(todo.items[0].id, ids[2]);
+        assert_eq!(todo.selected_index, 0);
+        assert!(!ids.contains(&todo.items[1].id));
+        assert_eq!(todo.status_message.as_deref(), Some("Reloaded from disk"));
+        assert!(!todo.check_disk_changes());
+
+        assert!(todo.undo());
+        assert_eq!(todo.items.len(), 3);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_disk_conflict() {
+        let mut todo = load_todo("disk-conflict", "- [ ] Write report\n");
+        fs::write(&todo.file_path, "- [ ] Write report\n- [ ] Added in an editor\n").unwrap();
+
+        // A change before the reload noticed the edit doesn't overwrite it
+        todo.toggle_selected_task();
+        assert!(todo.disk_conflict);
+        assert!(!todo.check_disk_changes());
+        assert_eq!(fs::read_to_string(&todo.file_path).unwrap(), "- [ ] Write report\n- [ ] Added in an editor\n");
+
+        todo.take_theirs();
+        assert!(!todo.disk_conflict);
+        assert_eq!(task_names(&todo), ["Write report", "Added in an editor"]);
+        assert!(!todo.items[0].done);
+
+        fs::write(&todo.file_path, "- [ ] Theirs\n").unwrap();
+        todo.toggle_selected_task();
+        assert!(todo.disk_conflict);
+        todo.keep_mine();
+        assert!(!todo.disk_conflict);
+        let saved = fs::read_to_string(&todo.file_path).unwrap();
+        assert!(saved.contains("- [x] Write report") && !saved.contains("Theirs"));
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_format_follows_extension_or_config() {
+        let org_path = temp_todo_path("org-format").replace(".md", ".org");
+        fs::write(&org_path, "* TODO [#A] Write report :work:\n** DONE Outline\n").unwrap();
+        let mut todo = Todo::new(Some(org_path.clone()), None);
+        assert_eq!(task_names(&todo), ["Write report #work", "Outline"]);
+        assert_eq!(todo.items[1].parent_id, Some(todo.items[0].id));
+        todo.toggle_selected_task();
+        let saved = fs::read_to_string(&org_path).unwrap();
+        assert!(saved.contains("* DONE [#A] Write report :work:\nCLOSED: [") && saved.contains(":END:\n** DONE Outline\n"));
+        fs::remove_file(&org_path).unwrap();
+
+        // An explicit format wins over the extension
+        let path = temp_todo_path("explicit-org");
+        fs::write(&path, "* TODO Call mom\n").unwrap();
+        let todo = Todo::new(Some(path.clone()), Some(TodoFormat::Org));
+        assert_eq!(task_names(&todo), ["Call mom"]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_uuids_are_remembered() {
+        let mut todo = load_todo("uuids", "- [ ] Write report | Focused time: 25 minutes\n- [ ] Call mom\n");
+        assert!(todo.assign_uuids());
+        assert!(!todo.assign_uuids());
+        todo.save_to_file();
+
+        let uuids: Vec<Option<String>> = todo.items.iter().map(|item| item.uuid.clone()).collect();
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        assert_eq!(task_names(&reloaded), ["Write report", "Call mom"]);
+        assert_eq!(reloaded.items.iter().map(|item| item.uuid.clone()).collect::<Vec<_>>(), uuids);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_ids_are_persisted() {
+        let mut todo = load_todo("ids", "- [ ] Write report | Focused time: 25 minutes\n- [ ] Call mom\n");
+        assert_eq!(todo.items.iter().map(|item| item.id).collect::<Vec<_>>(), [1, 2]);
+        todo.selected_index = 1;
+        todo.move_selected_task(true);
+        let saved = without_created(&fs::read_to_string(&todo.file_path).unwrap());
+        assert!(saved.contains("- [ ] Call mom <!-- id:2 -->\n- [ ] Write report | Focused time: 25 minutes <!-- id:1 -->\n"), "{}", saved);
+
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        assert_eq!(task_names(&reloaded), ["Call mom", "Write report"]);
+        assert_eq!(reloaded.items.iter().map(|item| item.id).collect::<Vec<_>>(), [2, 1]);
+        assert_eq!(reloaded.items[1].focused_time, 25);
+        assert_eq!(reloaded.next_id, 3);
+        fs::remove_file(&todo.file_path).unwrap();
+
+        // A copied line gets its own id; a broken comment stays part of the text
+        let todo = load_todo("ids-copied", "- [ ] a <!-- id:7 -->\n- [ ] a again <!-- id:7 -->\n- [ ] b\n- [ ] c <!-- id:x -->\n");
+        assert_eq!(task_names(&todo), ["a", "a again", "b", "c <!-- id:x -->"]);
+        assert_eq!(todo.items.iter().map(|item| item.id).collect::<Vec<_>>(), [7, 8, 9, 10]);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_timer_credit_follows_task_by_id() {
+        use crate::config::Config;
+        use crate::timer::Timer;
+
+        let mut todo = load_todo("credit-by-id", "- [ ] a\n- [ ] b\n- [ ] c\n- [ ] d\n- [ ] e\n");
+        let mut timer = Timer::new(&Config::default());
+        todo.selected_index = 2;
+        timer.set_selected_todo(Some(todo.items[2].id));
+        let credit = |todo: &mut Todo, timer: &Timer| {
+            todo.add_time_to_task_by_id(timer.get_selected_todo().unwrap(), 5);
+            todo.items.iter().map(|item| (item.task.clone(), item.focused_time)).filter(|&(_, minutes)| minutes > 0).collect::<Vec<_>>()
+        };
+
+        // Finishing a task above moves it to the bottom
+        todo.selected_index = 0;
+        todo.toggle_selected_task();
+        assert_eq!(task_names(&todo), ["b", "c", "d", "e", "a"]);
+        assert_eq!(credit(&mut todo, &timer), [("c".to_string(), 5)]);
+
+        // Reordering, deleting and sorting
+        todo.selected_index = 3; // e
+        todo.move_selected_task_to_end(true);
+        todo.selected_index = 1; // b
+        todo.delete_selected_task();
+        todo.items[2].priority = Some(Priority::High); // d
+        todo.sort_by_priority = true;
+        todo.sort_items();
+        assert_eq!(task_names(&todo), ["d", "e", "c", "a"]);
+        assert_eq!(credit(&mut todo, &timer), [("c".to_string(), 10)]);
+
+        // The file reordered by another program, and sessio restarted
+        let saved = fs::read_to_string(&todo.file_path).unwrap();
+        let mut lines: Vec<&str> = saved.lines().filter(|line| line.starts_with("- [")).collect();
+        lines.reverse();
+        fs::write(&todo.file_path, lines.join("\n")).unwrap();
+        assert!(todo.check_disk_changes());
+        assert_eq!(credit(&mut todo, &timer), [("c".to_string(), 15)]);
+        let mut restarted = Todo::new(Some(todo.file_path.clone()), None);
+        assert_eq!(credit(&mut restarted, &timer), [("c".to_string(), 20)]);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width("Write report", 12), "Write report");
+        assert_eq!(truncate_to_width("Write report", 8), "Write r…");
+        // Each CJK character is two cells: a half-cell left over stays empty
+        assert_eq!(truncate_to_width("写报告给老板", 12), "写报告给老板");
+        assert_eq!(truncate_to_width("写报告给老板", 8), "写报告…");
+        assert_eq!(truncate_to_width("写报告给老板", 9), "写报告给…");
+        assert_eq!(truncate_to_width("abc", 0), "");
+
+        for text in ["Fix 🐛 in 登录 page", "🍅🍅🍅 focus", "mixed 中文 and ascii ✅", "👩‍💻 pair with 李"] {
+            for max_width in 0..25 {
+                let truncated = truncate_to_width(text, max_width);
+                assert!(truncated.width() <= max_width, "{:?} at {} is {:?}", text, max_width, truncated);
+                assert_eq!(truncated.ends_with('…'), max_width > 0 && text.width() > max_width);
+            }
+        }
+    }
+
+    #[test]
+    fn test_completion_time_is_recorded_and_saved() {
+        let mut todo = load_todo("completed-at", "- [ ] Write report\n- [x] Old task | Done: 2025-06-30 17:42\n");
+        let today = Local::now().date_naive();
+        assert_eq!(todo.items[1].completed_at.map(|t| t.format("%Y-%m-%d %H:%M").to_string()).as_deref(), Some("2025-06-30 17:42"));
+        assert_eq!(todo.get_completed_on(NaiveDate::from_ymd_opt(2025, 6, 30).unwrap()), 1);
+        assert_eq!(todo.get_completed_on(today), 0);
+
+        todo.toggle_selected_task();
+        assert!(todo.items.iter().all(|item| item.done));
+        assert_eq!(todo.get_completed_on(today), 1);
+        let content = fs::read_to_string(&todo.file_path).unwrap();
+        assert!(content.contains(&format!("- [x] Write report | Done: {}", today.format("%Y-%m-%d"))));
+
+        let mut reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        assert_eq!(reloaded.get_completed_on(today), 1);
+        reloaded.selected_index = reloaded.items.iter().position(|item| item.task == "Write report").unwrap();
+        reloaded.toggle_selected_task();
+        fs::remove_file(&reloaded.file_path).unwrap();
+        assert_eq!(reloaded.get_completed_on(today), 0);
+        assert!(reloaded.items.iter().find(|item| item.task == "Write report").unwrap().completed_at.is_none());
+    }
+
+    #[test]
+    fn test_move_selected_task_to_top_and_bottom() {
+        let content = "- [ ] a\n- [ ] b\n  - [ ] b1\n  - [ ] b2\n- [ ] c\n- [x] done\n";
+        let mut todo = load_todo("move-to-end", content);
+        todo.selected_index = 4; // c
+        todo.move_selected_task_to_end(true);
+        assert_eq!(task_names(&todo), ["c", "a", "b", "b1", "b2", "done"]);
+        assert_eq!(todo.items[todo.selected_index].task, "c");
+
+        // Subtasks travel with their parent, which stops above the done tasks
+        todo.selected_index = 2; // b
+        todo.move_selected_task_to_end(false);
+        assert_eq!(task_names(&todo), ["c", "a", "b", "b1", "b2", "done"]);
+        todo.selected_index = 0;
+        todo.move_selected_task_to_end(false);
+        assert_eq!(task_names(&todo), ["a", "b", "b1", "b2", "c", "done"]);
+        assert_eq!(todo.selected_index, 4);
+        let saved = without_created(&fs::read_to_string(&todo.file_path).unwrap());
+        assert!(saved.starts_with("# TODO List\n\n- [ ] a <!-- id:1 -->\n- [ ] b <!-- id:2 -->\n  - [ ] b1 <!-- id:3 -->\n  - [ ] b2 <!-- id:4 -->\n- [ ] c <!-- id:5 -->\n- [x] done <!-- id:6 -->\n"));
+
+        // A subtask moves among its siblings; each move is one undo step
+        todo.selected_index = 3; // b2
+        todo.move_selected_task_to_end(true);
+        assert_eq!(task_names(&todo), ["a", "b", "b2", "b1", "c", "done"]);
+        assert_eq!(todo.selected_index, 2);
+        todo.undo();
+        assert_eq!(task_names(&todo), ["a", "b", "b1", "b2", "c", "done"]);
+        todo.undo();
+        assert_eq!(task_names(&todo), ["c", "a", "b", "b1", "b2", "done"]);
+
+        // Done tasks stay put
+        todo.selected_index = 5;
+        todo.move_selected_task_to_end(true);
+        fs::remove_file(&todo.file_path).unwrap();
+        assert_eq!(task_names(&todo), ["c", "a", "b", "b1", "b2", "done"]);
+    }
+
+    #[test]
+    fn test_wrap_spans() {
+        fn texts(lines: &[Vec<Span>]) -> Vec<String> {
+            lines.iter().map(|line| line.iter().map(|span| span.content.as_ref()).collect()).collect()
+        }
+        let plain = |text: &str| vec![Span::raw(text.to_string())];
+        assert_eq!(texts(&wrap_spans(&plain("Write the quarterly report"), 12)), ["Write the", "quarterly", "report"]);
+        assert_eq!(texts(&wrap_spans(&plain("Supercalifragilistic"), 8)), ["Supercal", "ifragili", "stic"]);
+        // Two cells per character: a line of 7 cells holds three of them
+        let cjk = wrap_spans(&plain("写季度报告给老板看"), 7);
+        assert_eq!(texts(&cjk), ["写季度", "报告给", "老板看"]);
+        assert!(cjk.iter().all(|line| line.iter().map(|span| span.content.width()).sum::<usize>() <= 7));
+        assert_eq!(texts(&wrap_spans(&plain("Fix 登录页面的问题 today"), 9)), ["Fix", "登录页面", "的问题", "today"]);
+
+        // Styles stay with their characters across the break
+        let wrapped = wrap_spans(&task_spans("Plan #work trip"), 8);
+        assert_eq!(texts(&wrapped), ["Plan", "#work", "trip"]);
+        assert_eq!(wrapped[1][0].style.fg, Some(DraculaTheme::CYAN));
+        assert_eq!(wrapped[2][0].style.fg, None);
+    }
+
+    #[test]
+    fn test_wrapped_rows_scroll_by_lines() {
+        let long = "Write the quarterly report for the board with every number checked";
+        let content = format!("- [ ] a\n- [ ] {}\n- [ ] b\n- [ ] c\n- [ ] d\n", long);
+        let mut todo = load_todo("wrap-rows", &content);
+        todo.last_panel_width = 30;
+        todo.last_visible_height = 4;
+        assert_eq!(todo.task_row(1, false).len(), 1);
+
+        todo.toggle_wrap_titles();
+        let rows = todo.task_row(1, true);
+        assert_eq!(rows.len(), 4);
+        for line in &rows {
+            assert!(line.width() <= 28, "{:?}", line);
+        }
+        // Continuation lines start under the title, after "► 2 ⭕ "
+        let first: String = rows[0].spans.iter().map(|span| span.content.as_ref()).collect();
+        let second: String = rows[1].spans.iter().map(|span| span.content.as_ref()).collect();
+        assert!(first.starts_with("► 2 ⭕ Write"));
+        assert!(second.starts_with("       ") && !second.starts_with("        "));
+
+        // Selecting the row below the tall one scrolls it fully into view
+        let visible = todo.visible_indices();
+        todo.selected_index = 0;
+        assert_eq!(todo.rows_fitting(&visible, 0), 1);
+        todo.move_selection_down();
+        assert_eq!(todo.scroll_offset, 1);
+        todo.move_selection_down();
+        assert_eq!(todo.items[todo.selected_index].task, "b");
+        assert_eq!((todo.scroll_offset, todo.rows_fitting(&visible, 2)), (2, 3));
+        assert_eq!(todo.max_scroll_offset(&visible), 2);
+        todo.scroll_down();
+        assert_eq!(todo.scroll_offset, 2);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_scrollbar_only_when_tasks_overflow() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let mut app = App::new();
+        app.focused_quadrant = Quadrant::BottomLeft;
+        let mut terminal = Terminal::new(TestBackend::new(40, 16)).unwrap();
+        // Next to the task rows, inside the right border
+        let scrollbar_column = |terminal: &Terminal<TestBackend>, rows: u16| -> String {
+            let buffer = terminal.backend().buffer();
+            (2..2 + rows).map(|y| buffer[(38, y)].symbol().to_string()).collect()
+        };
+
+        let mut todo = load_todo("scrollbar-few", "- [ ] a\n- [ ] b\n");
+        terminal.draw(|frame| todo.render(frame, frame.area(), &app)).unwrap();
+        assert_eq!(todo.last_panel_width, 40);
+        assert_eq!(scrollbar_column(&terminal, 2).trim(), "");
+        fs::remove_file(&todo.file_path).unwrap();
+
+        let content: String = (1..=20).map(|n| format!("- [ ] task {}\n", n)).collect();
+        let mut todo = load_todo("scrollbar-many", &content);
+        terminal.draw(|frame| todo.render(frame, frame.area(), &app)).unwrap();
+        // The column is taken from the rows, thumb at the top
+        assert_eq!(todo.last_panel_width, 39);
+        let column = scrollbar_column(&terminal, 7);
+        assert!(column.starts_with('█') && column.ends_with('║'), "{}", column);
+        assert_eq!(terminal.backend().buffer()[(38, 2)].fg, DraculaTheme::PINK);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_pomodoro_dots() {
+        let content = "- [ ] Write report | Focused time: 75 minutes\n- [ ] Long one | Focused time: 260 minutes\n- [ ] Short | Focused time: 20 minutes | Session: 10 min\n- [ ] New\n";
+        let mut todo = load_todo("dots", content);
+        todo.last_panel_width = 80;
+        let row = |todo: &Todo, index: usize| -> String {
+            todo.task_row(index, false)[0].spans.iter().map(|span| span.content.as_ref()).collect()
+        };
+        assert!(row(&todo, 0).ends_with("Write report (75min)"));
+
+        todo.time_display = TimeDisplay::Dots;
+        assert!(row(&todo, 0).ends_with("Write report 🍅🍅🍅"));
+        assert!(row(&todo, 1).ends_with("Long one 🍅🍅🍅🍅🍅🍅🍅🍅+2"));
+        // A task's own session length counts, like for estimates
+        assert!(row(&todo, 2).ends_with("Short 🍅🍅 [⏱10m]"));
+        assert!(row(&todo, 3).ends_with("New"));
+        todo.default_session_minutes = 50;
+        assert!(row(&todo, 0).ends_with("Write report 🍅"));
+
+        todo.time_display = TimeDisplay::Both;
+        assert!(row(&todo, 0).ends_with("Write report 🍅 (75min)"));
+
+        // The dots take their room from the title, so the row still fits inside the borders
+        todo.items[1].task = "A title long enough to need cutting off in a panel this narrow".to_string();
+        todo.time_display = TimeDisplay::Both;
+        assert!(todo.task_row(1, false)[0].width() <= 78);
+        assert!(row(&todo, 1).ends_with("… 🍅🍅🍅🍅🍅 (260min)"));
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_starred_tasks_stay_on_top() {
+        let mut todo = load_todo("starred", "- [ ] a\n- [ ] b\n  - [ ] b1\n- [ ] c\n- [x] done\n");
+        todo.selected_index = 3; // c
+        todo.toggle_selected_star();
+        assert_eq!(task_names(&todo), ["c", "a", "b", "b1", "done"]);
+        assert_eq!(todo.items[todo.selected_index].task, "c");
+        todo.selected_index = 3; // b1
+        todo.toggle_selected_star();
+        assert!(!todo.items[3].starred && todo.status_message.is_some());
+        todo.selected_index = 2; // b
+        todo.toggle_selected_star();
+        assert_eq!(task_names(&todo), ["c", "b", "b1", "a", "done"]);
+
+        // New tasks go under the starred ones; others can't be moved above them
+        todo.current_input.paste("new");
+        todo.submit_new_task();
+        assert_eq!(task_names(&todo), ["c", "b", "b1", "new", "a", "done"]);
+        assert_eq!(todo.selected_index, 3);
+        todo.move_selected_task_to_end(true);
+        assert_eq!(todo.selected_index, 3);
+        todo.selected_index = 1; // b
+        todo.move_selected_task(false);
+        assert_eq!(task_names(&todo), ["c", "b", "b1", "new", "a", "done"]);
+        todo.move_selected_task(true);
+        assert_eq!(task_names(&todo), ["b", "b1", "c", "new", "a", "done"]);
+
+        // Done, a starred task sinks like any other; undone, it goes back to the starred ones
+        todo.selected_index = 2; // c
+        todo.toggle_selected_task();
+        assert_eq!(task_names(&todo), ["b", "b1", "new", "a", "done", "c"]);
+        todo.selected_index = 5;
+        todo.toggle_selected_task();
+        assert_eq!(task_names(&todo), ["b", "b1", "c", "new", "a", "done"]);
+
+        // With sort_by_priority, starred tasks still come first
+        todo.items[4].priority = Some(Priority::High); // a
+        todo.sort_by_priority = true;
+        todo.sort_items();
+        assert_eq!(task_names(&todo), ["b", "b1", "c", "a", "new", "done"]);
+        todo.sort_mode = SortMode::Alphabetical;
+        let view: Vec<&str> = todo.visible_indices().iter().map(|&i| todo.items[i].task.as_str()).collect();
+        assert_eq!(view, ["b", "b1", "c", "a", "new", "done"]);
+
+        // Stars are saved, and unstarring is one undo step
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        assert_eq!(reloaded.items.iter().filter(|item| item.starred).map(|item| item.task.as_str()).collect::<Vec<_>>(), ["b", "c"]);
+        todo.sort_by_priority = false;
+        todo.selected_index = 0;
+        todo.toggle_selected_star();
+        assert_eq!(task_names(&todo), ["c", "b", "b1", "a", "new", "done"]);
+        assert!(todo.undo());
+        assert!(todo.items[0].starred && todo.items[0].task == "b");
+        fs::remove_file(&todo.file_path).unwrap();
+
+        let org_path = temp_todo_path("starred-org").replace(".md", ".org");
+        fs::write(&org_path, "* TODO a\n* TODO b\n").unwrap();
+        let mut todo = Todo::new(Some(org_path.clone()), None);
+        todo.selected_index = 1;
+        todo.toggle_selected_star();
+        assert!(fs::read_to_string(&org_path).unwrap().contains("* TODO b\n:PROPERTIES:\n:STARRED: t\n:CREATED: ["));
+        assert!(Todo::new(Some(org_path.clone()), None).items[0].starred);
+        fs::remove_file(&org_path).unwrap();
+    }
+
+    #[test]
+    fn test_task_age() {
+        assert_eq!([0, 13, 14, 59, 60, 400].map(age_label), ["0d", "13d", "2w", "8w", "2mo", "1y"]);
+
+        let created = (Local::now() - Duration::from_secs(400 * 86400)).format("%Y-%m-%d %H:%M").to_string();
+        let content = format!("- [ ] Old one | Created: {0}\n- [ ] Legacy line\n- [x] Done long ago | Created: {0}\n", created);
+        let mut todo = load_todo("age", &content);
+        assert!(todo.items[1].created_at.date_naive() == Local::now().date_naive());
+        todo.last_panel_width = 50;
+        let row_without_age: String = todo.task_row(0, false)[0].spans.iter().map(|span| span.content.as_ref()).collect();
+        assert!(row_without_age.ends_with("Old one"));
+
+        todo.show_age = true;
+        let row = |todo: &Todo, index: usize| -> (String, Option<Color>, usize) {
+            let line = &todo.task_row(index, false)[0];
+            let text = line.spans.iter().map(|span| span.content.as_ref()).collect();
+            (text, line.spans.last().unwrap().style.fg, line.width())
+        };
+        // Right-aligned against the border, orange once stale
+        let (text, color, width) = row(&todo, 0);
+        assert!(text.starts_with("  1 ⭕ Old one ") && text.ends_with(" 1y"), "{}", text);
+        assert_eq!((color, width), (Some(DraculaTheme::ORANGE), 48));
+        let (text, color, _) = row(&todo, 1);
+        assert!(text.ends_with(" 0d"));
+        assert_eq!(color, Some(DraculaTheme::COMMENT));
+        assert!(!row(&todo, 2).0.ends_with('y'));
+        todo.stale_after_days = 0;
+        assert_eq!(row(&todo, 0).1, Some(DraculaTheme::COMMENT));
+
+        // Long titles make room for it
+        todo.items[0].task = "A title that is far too long for a panel of this width".to_string();
+        let (text, _, width) = row(&todo, 0);
+        assert!(text.contains("… ") && text.ends_with(" 1y") && width <= 48, "{}", text);
+        todo.wrap_titles = true;
+        let lines = todo.task_row(0, false);
+        assert!(lines.len() > 1 && lines.iter().all(|line| line.width() <= 48));
+        assert!(lines[0].spans.last().unwrap().content.ends_with("1y"));
+
+        // Created times are kept
+        todo.save_to_file();
+        assert!(fs::read_to_string(&todo.file_path).unwrap().contains(&format!("far too long for a panel of this width | Created: {}", created)));
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_day() {
+        let wednesday = NaiveDate::from_ymd_opt(2025, 7, 2).unwrap();
+        let day = |value: &str| parse_day(value, wednesday).map(|day| day.format("%Y-%m-%d").to_string());
+        assert_eq!(day("today").as_deref(), Some("2025-07-02"));
+        assert_eq!(day(" Tomorrow ").as_deref(), Some("2025-07-03"));
+        assert_eq!(day("wed").as_deref(), Some("2025-07-02"));
+        assert_eq!(day("tuesday").as_deref(), Some("2025-07-08"));
+        assert_eq!(day("fri").as_deref(), Some("2025-07-04"));
+        assert_eq!(day("2025-12-24").as_deref(), Some("2025-12-24"));
+        assert_eq!(day("someday"), None);
+        assert_eq!(day("2025-13-01"), None);
+    }
+
+    #[test]
+    fn test_schedule_and_today_view() {
+        let today = Local::now().date_naive();
+        let yesterday = today.pred_opt().unwrap();
+        let content = format!(
+            "- [ ] later\n- [ ] overdue | Scheduled: {0}\n  - [ ] sub\n- [x] missed but done | Scheduled: {0}\n- [ ] plain\n",
+            yesterday.format("%Y-%m-%d"),
+        );
+        let mut todo = load_todo("schedule", &content);
+        assert_eq!(todo.items[1].scheduled_for, Some(yesterday));
+        assert_eq!(todo.get_scheduled_progress(today), (0, 1));
+
+        todo.selected_index = 0;
+        todo.start_schedule_input();
+        todo.current_input.paste("today");
+        todo.submit_input();
+        assert_eq!(todo.items[0].scheduled_for, Some(today));
+        todo.selected_index = 4;
+        todo.start_schedule_input();
+        todo.current_input.paste("next week");
+        todo.submit_input();
+        assert_eq!(todo.items[4].scheduled_for, None);
+        assert!(todo.status_message.take().unwrap().contains("next week"));
+
+        // Today's tasks, overdue open ones and their subtasks
+        todo.toggle_today_view();
+        assert!(todo.today_view);
+        let view: Vec<&str> = todo.visible_indices().iter().map(|&i| todo.items[i].task.as_str()).collect();
+        assert_eq!(view, ["later", "overdue", "sub"]);
+        todo.last_panel_width = 60;
+        let row: String = todo.task_row(1, false)[0].spans.iter().map(|span| span.content.as_ref()).collect();
+        assert!(row.ends_with(&format!("overdue 📅 {}", yesterday.format("%m-%d"))), "{}", row);
+        assert_eq!(todo.task_row(1, false)[0].spans.last().unwrap().style.fg, Some(DraculaTheme::ORANGE));
+
+        // Tasks added here are planned for today
+        todo.current_input.paste("new");
+        todo.submit_new_task();
+        assert_eq!(todo.items[todo.selected_index].task, "new");
+        assert_eq!(todo.items[todo.selected_index].scheduled_for, Some(today));
+        todo.toggle_selected_task();
+        assert_eq!(todo.get_scheduled_progress(today), (1, 3));
+
+        // Unscheduling is one undo step; once nothing is left the view closes
+        for task in ["new", "later", "overdue"] {
+            todo.selected_index = todo.items.iter().position(|item| item.task == task).unwrap();
+            todo.start_schedule_input();
+            todo.current_input.clear();
+            todo.submit_input();
+        }
+        assert!(!todo.today_view);
+        todo.toggle_today_view();
+        assert_eq!(todo.status_message.as_deref(), Some("Nothing scheduled for today"));
+        assert!(todo.undo());
+        assert_eq!(todo.items[todo.items.iter().position(|item| item.task == "overdue").unwrap()].scheduled_for, Some(yesterday));
+
+        // Saved with the task
+        todo.save_to_file();
+        let saved = fs::read_to_string(&todo.file_path).unwrap();
+        assert!(saved.contains(&format!("- [ ] overdue | Scheduled: {}", yesterday.format("%Y-%m-%d"))));
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        assert_eq!(reloaded.items.iter().filter(|item| item.scheduled_for.is_some()).count(), 2);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_blocked_tasks() {
+        let mut todo = load_todo("blocked", "- [ ] draft\n- [ ] review\n- [ ] publish\n- [x] old\n");
+        let id = |todo: &Todo, task: &str| todo.items.iter().find(|item| item.task == task).unwrap().id;
+        let select = |todo: &mut Todo, task: &str| todo.selected_index = todo.items.iter().position(|item| item.task == task).unwrap();
+
+        // The picker lists the other open tasks
+        select(&mut todo, "review");
+        todo.start_blocker_picker();
+        assert_eq!(todo.blocker_choices, [id(&todo, "draft"), id(&todo, "publish")]);
+        todo.choose_blocker();
+        assert!(todo.blocker_choices.is_empty());
+        assert_eq!(todo.blocker_of(1).map(|item| item.task.as_str()), Some("draft"));
+        select(&mut todo, "publish");
+        todo.start_blocker_picker();
+        todo.move_blocker_choice(false);
+        todo.choose_blocker();
+        assert_eq!(todo.items[2].blocked_by, Some(id(&todo, "review")));
+
+        // Waiting on each other is refused
+        select(&mut todo, "draft");
+        todo.start_blocker_picker();
+        todo.move_blocker_choice(false);
+        assert_eq!(todo.blocker_choices[todo.blocker_choice], id(&todo, "publish"));
+        todo.choose_blocker();
+        assert_eq!(todo.items[0].blocked_by, None);
+        assert!(todo.status_message.take().is_some());
+
+        // Dimmed with a lock; the timer skips blocked tasks when moving on
+        todo.last_panel_width = 60;
+        let row = &todo.task_row(1, false)[0];
+        assert!(row.spans.iter().any(|span| span.content == "🔒 "));
+        assert_eq!(row.spans.iter().find(|span| span.content == "review").unwrap().style.fg, Some(DraculaTheme::COMMENT));
+        assert_eq!(todo.next_unfinished_from(1).map(|item| item.task.as_str()), Some("draft"));
+
+        // Saved by id
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        assert_eq!(reloaded.blocker_of(2).map(|item| item.task.as_str()), Some("review"));
+
+        // Finishing the blocker frees its dependents; undo brings the block back
+        select(&mut todo, "draft");
+        todo.toggle_selected_task();
+        assert_eq!(todo.items.iter().find(|item| item.task == "review").unwrap().blocked_by, None);
+        assert!(todo.undo());
+        assert!(todo.blocker_of(1).is_some());
+
+        // So does deleting it, and pressing b again on a blocked task
+        select(&mut todo, "review");
+        todo.delete_selected_task();
+        assert!(todo.items.iter().all(|item| item.blocked_by.is_none()));
+        assert!(todo.undo());
+        select(&mut todo, "review");
+        todo.start_blocker_picker();
+        assert!(todo.blocker_choices.is_empty() && todo.items[1].blocked_by.is_none());
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_jump_to_task_number() {
+        let content: String = (1..=12).map(|n| format!("- [ ] task {}\n", n)).collect();
+        let mut todo = load_todo("jump", &content);
+        todo.last_visible_height = 5;
+
+        // "1" could still become 10-12, so it waits; a second digit settles it
+        todo.push_jump_digit('1');
+        assert!(todo.jump_pending());
+        assert_eq!(todo.selected_index, 0);
+        todo.push_jump_digit('1');
+        assert!(!todo.jump_pending());
+        assert_eq!(todo.items[todo.selected_index].task, "task 11");
+        assert_eq!(todo.scroll_offset, 6);
+
+        // "3" can't grow into a valid number, and "02" is 2
+        todo.push_jump_digit('3');
+        assert_eq!(todo.items[todo.selected_index].task, "task 3");
+        assert_eq!(todo.scroll_offset, 2);
+        todo.push_jump_digit('0');
+        todo.push_jump_digit('2');
+        assert_eq!(todo.items[todo.selected_index].task, "task 2");
+
+        todo.push_jump_digit('1');
+        todo.finish_jump();
+        assert_eq!(todo.items[todo.selected_index].task, "task 1");
+
+        // Invalid numbers leave the selection alone and say so
+        todo.push_jump_digit('0');
+        todo.finish_jump();
+        assert_eq!(todo.selected_index, 0);
+        assert_eq!(todo.status_message.as_deref(), Some("No task 0"));
+        todo.push_jump_digit('1');
+        todo.push_jump_digit('3');
+        assert_eq!(todo.status_message.as_deref(), Some("No task 13"));
+        assert_eq!(todo.selected_index, 0);
+
+        todo.push_jump_digit('1');
+        todo.cancel_jump();
+        todo.check_jump_timeout();
+        assert!(!todo.jump_pending());
+        assert_eq!(todo.selected_index, 0);
+
+        // A task the tag filter or today view hides is shown rather than a neighbor selected
+        todo.items[4].task = "task 5 #home".to_string();
+        todo.items[6].scheduled_for = Some(Local::now().date_naive());
+        todo.cycle_tag_filter();
+        todo.push_jump_digit('9');
+        assert_eq!(todo.tag_filter, None);
+        assert_eq!(todo.items[todo.selected_index].task, "task 9");
+        assert!(todo.status_message.take().unwrap().contains("task 9"));
+        todo.toggle_today_view();
+        todo.push_jump_digit('7');
+        assert!(todo.today_view && todo.status_message.is_none());
+        assert_eq!(todo.items[todo.selected_index].task, "task 7");
+        todo.push_jump_digit('8');
+        assert!(!todo.today_view);
+        assert_eq!(todo.items[todo.selected_index].task, "task 8");
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    /// A fresh, empty directory in the temp directory
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sessio-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_write_atomically() {
+        let dir = temp_dir("atomic");
+        let path = dir.join("todos.md");
+        write_atomically(&path, "- [ ] first\n").unwrap();
+        write_atomically(&path, "- [ ] second\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "- [ ] second\n");
+        assert_eq!(file_names(&dir), ["todos.md"]);
+
+        // A failed write leaves the old file alone and no temporary file behind
+        assert!(write_atomically(&dir.join("missing").join("todos.md"), "lost").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_backups_rotate_once_a_day() {
+        let dir = temp_dir("backups");
+        let path = dir.join("todos.md");
+        let today = Local::now().date_naive();
+        rotate_backups(&path, 2, today).unwrap();
+        assert!(file_names(&dir).is_empty());
+
+        fs::write(&path, "monday").unwrap();
+        rotate_backups(&path, 2, today).unwrap();
+        fs::write(&path, "monday, later").unwrap();
+        rotate_backups(&path, 2, today).unwrap();
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "monday");
+
+        // On the following days the backups move along, the oldest falling off
+        rotate_backups(&path, 2, today + chrono::Duration::days(1)).unwrap();
+        fs::write(&path, "wednesday").unwrap();
+        rotate_backups(&path, 2, today + chrono::Duration::days(2)).unwrap();
+        assert_eq!(file_names(&dir), ["todos.md", "todos.md.1", "todos.md.2"]);
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "wednesday");
+        assert_eq!(fs::read_to_string(backup_path(&path, 2)).unwrap(), "monday, later");
+
+        rotate_backups(&path, 0, today + chrono::Duration::days(3)).unwrap();
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "wednesday");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = std::env::temp_dir().join("sessio-home");
+        assert_eq!(expand_home("~/notes/todos.md", Some(home.clone())), home.join("notes/todos.md"));
+        assert_eq!(expand_home("/srv/todos.md", Some(home.clone())), PathBuf::from("/srv/todos.md"));
+        assert_eq!(expand_home("~user/todos.md", Some(home)), PathBuf::from("~user/todos.md"));
+        assert_eq!(expand_home("~/todos.md", None), PathBuf::from("~/todos.md"));
+    }
+
+    #[test]
+    fn test_without_auto_save_changes_wait_for_a_save() {
+        let mut todo = load_todo("manual-save", "- [ ] Write report\n");
+        todo.auto_save = false;
+        todo.toggle_selected_task();
+        assert!(todo.is_dirty());
+        assert_eq!(fs::read_to_string(&todo.file_path).unwrap(), "- [ ] Write report\n");
+
+        // Quitting asks; Esc (closing the prompt) keeps everything as it is
+        assert!(!todo.request_quit());
+        assert!(todo.quit_prompt);
+        todo.quit_prompt = false;
+
+        // A change on disk doesn't silently replace the unsaved changes
+        fs::write(&todo.file_path, "- [ ] Write report\n- [ ] Theirs\n").unwrap();
+        assert!(!todo.check_disk_changes());
+        assert!(todo.disk_conflict);
+        todo.keep_mine();
+        assert!(!todo.is_dirty());
+        assert!(fs::read_to_string(&todo.file_path).unwrap().contains("- [x] Write report"));
+        assert!(todo.request_quit());
+
+        // Discarding quits without writing; saving writes first
+        todo.toggle_selected_task();
+        assert!(!todo.request_quit());
+        assert!(todo.answer_quit_prompt(false));
+        assert!(fs::read_to_string(&todo.file_path).unwrap().contains("- [x] Write report"));
+        assert!(!todo.request_quit());
+        assert!(todo.answer_quit_prompt(true));
+        assert!(!todo.quit_prompt && !todo.is_dirty());
+        assert!(fs::read_to_string(&todo.file_path).unwrap().contains("- [ ] Write report"));
+
+        // Saving into a conflict doesn't quit
+        todo.toggle_selected_task();
+        fs::write(&todo.file_path, "- [ ] Theirs\n").unwrap();
+        assert!(!todo.request_quit());
+        assert!(!todo.answer_quit_prompt(true));
+        assert!(todo.disk_conflict);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_auto_save_writes_a_burst_once() {
+        let mut todo = load_todo("debounce", "- [ ] a\n- [ ] b\n- [ ] c\n");
+        todo.save_delay = Duration::from_millis(200);
+        todo.toggle_selected_task();
+        assert!(!todo.is_dirty());
+        let first = fs::read_to_string(&todo.file_path).unwrap();
+        assert!(first.contains("- [x] a"));
+
+        // Changes right after a write are held back until the delay is over
+        todo.toggle_selected_task();
+        todo.toggle_selected_task();
+        assert!(todo.is_dirty());
+        todo.flush_if_due();
+        assert_eq!(fs::read_to_string(&todo.file_path).unwrap(), first);
+
+        std::thread::sleep(Duration::from_millis(250));
+        todo.flush_if_due();
+        assert!(!todo.is_dirty());
+        let saved = fs::read_to_string(&todo.file_path).unwrap();
+        assert!(saved.contains("- [x] b") && saved.contains("- [x] c"));
+
+        // Quitting writes whatever is still pending
+        todo.selected_index = 0;
+        todo.toggle_selected_task();
+        assert!(todo.is_dirty());
+        assert!(todo.request_quit());
+        assert!(!todo.is_dirty());
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_subtasks_are_saved_and_move_with_their_task() {
+        let mut todo = load_todo("subtasks", "- [ ] a\n  - [ ] a1\n  - [x] a2\n- [ ] b\n- [x] done\n");
+        let parents: Vec<Option<u64>> = todo.items.iter().map(|item| item.parent_id).collect();
+        assert_eq!(parents, [None, Some(1), Some(1), None, None]);
+        assert!(todo.items[2].done);
+
+        // A subtask added on a subtask goes to the end of the same parent's block
+        todo.selected_index = 1;
+        todo.start_subtask_input();
+        todo.current_input.paste("a3");
+        todo.submit_input();
+        assert_eq!(task_names(&todo), ["a", "a1", "a2", "a3", "b", "done"]);
+        assert_eq!(todo.selected_index, 3);
+        let saved = without_created(&fs::read_to_string(&todo.file_path).unwrap());
+        assert!(saved.contains("- [ ] a <!-- id:1 -->\n  - [ ] a1 <!-- id:2 -->\n  - [x] a2"));
+        assert!(saved.contains("  - [ ] a3 <!-- id:6 -->\n- [ ] b <!-- id:4 -->\n"));
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        assert_eq!(task_names(&reloaded), task_names(&todo));
+        assert!(reloaded.items.iter().zip(&todo.items).all(|(loaded, saved)| loaded.parent_id == saved.parent_id));
+
+        // Moving the task takes its subtasks along
+        todo.selected_index = 0;
+        todo.move_selected_task(false);
+        assert_eq!(task_names(&todo), ["b", "a", "a1", "a2", "a3", "done"]);
+        assert_eq!(todo.selected_index, 1);
+
+        // Sorting by priority moves whole blocks; a subtask's own priority keeps it under its task
+        todo.sort_by_priority = true;
+        todo.selected_index = 2; // a1
+        todo.cycle_selected_priority();
+        assert_eq!(task_names(&todo), ["b", "a", "a1", "a2", "a3", "done"]);
+        todo.selected_index = 1; // a
+        todo.cycle_selected_priority();
+        assert_eq!(task_names(&todo), ["a", "a1", "a2", "a3", "b", "done"]);
+        assert_eq!(todo.selected_index, 0);
+
+        // Finishing the last open subtask finishes the task too, when asked to; one undo takes both back
+        todo.selected_index = 1; // a1
+        todo.toggle_selected_task();
+        assert!(!todo.items[0].done);
+        todo.selected_index = 3; // a3
+        todo.toggle_selected_task();
+        assert!(!todo.items[0].done);
+        todo.undo();
+        todo.auto_complete_parents = true;
+        todo.toggle_selected_task();
+        assert_eq!(task_names(&todo), ["b", "done", "a", "a1", "a2", "a3"]);
+        assert!(todo.items[2..].iter().all(|item| item.done));
+        assert!(todo.undo());
+        assert_eq!(task_names(&todo), ["a", "a1", "a2", "a3", "b", "done"]);
+        assert!(!todo.items[0].done && !todo.items[3].done && todo.items[1].done);
+
+        // A task with subtasks needs a second delete; a subtask goes on the first
+        todo.selected_index = 3; // a3
+        todo.delete_selected_task();
+        assert_eq!(task_names(&todo), ["a", "a1", "a2", "b", "done"]);
+        todo.selected_index = 0;
+        todo.delete_selected_task();
+        assert_eq!(task_names(&todo), ["a", "a1", "a2", "b", "done"]);
+        todo.delete_selected_task();
+        assert_eq!(task_names(&todo), ["b", "done"]);
+        assert!(todo.undo());
+        assert_eq!(task_names(&todo), ["a", "a1", "a2", "b", "done"]);
+        assert_eq!(todo.items[1].parent_id, Some(1));
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_tag_filter_acts_on_the_shown_tasks() {
+        let mut todo = load_todo("tag-filter", "- [ ] a #home\n- [ ] b #work\n  - [ ] b1\n- [ ] c #home\n- [ ] d #work\n- [x] e #work\n");
+        assert_eq!(todo.all_tags(), ["home", "work"]);
+        todo.cycle_tag_filter();
+        assert_eq!(todo.tag_filter.as_deref(), Some("home"));
+        assert_eq!(todo.visible_indices(), [0, 3]);
+
+        // Subtasks show with their parent's tag; the hidden selection moves to the first shown task
+        todo.cycle_tag_filter();
+        assert_eq!(todo.visible_indices(), [1, 2, 4, 5]);
+        assert_eq!(todo.items[todo.selected_index].task, "b #work");
+        todo.move_selection_down();
+        todo.move_selection_down();
+        assert_eq!(todo.items[todo.selected_index].task, "d #work");
+        todo.move_selection_up();
+        todo.move_selection_up();
+        todo.move_selection_up();
+        assert_eq!(todo.items[todo.selected_index].task, "b #work");
+
+        // Toggling and deleting act on the selected shown task, not on the row at that position
+        todo.selected_index = todo.visible_indices()[2]; // d
+        todo.toggle_selected_task();
+        assert_eq!(task_names(&todo), ["a #home", "b #work", "b1", "c #home", "e #work", "d #work"]);
+        assert!(todo.items[5].done && !todo.items[0].done && !todo.items[3].done);
+        todo.selected_index = todo.visible_indices()[1]; // b1
+        todo.delete_selected_task();
+        assert_eq!(task_names(&todo), ["a #home", "b #work", "c #home", "e #work", "d #work"]);
+        assert_eq!(todo.items[todo.selected_index].task, "e #work");
+        assert!(todo.visible_indices().contains(&todo.selected_index));
+
+        todo.cycle_tag_filter();
+        assert_eq!(todo.tag_filter, None);
+        assert_eq!(todo.visible_indices(), [0, 1, 2, 3, 4]);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_move_selected_task() {
+        let content = "- [ ] a\n  - [ ] a1\n- [ ] b\n  - [ ] b1\n  - [ ] b2\n- [ ] c\n- [x] done\n";
+        let mut todo = load_todo("move-task", content);
+
+        // Blocks swap whole, and a quick burst of moves is undone in one step
+        todo.selected_index = 2; // b
+        todo.move_selected_task(true);
+        assert_eq!(task_names(&todo), ["b", "b1", "b2", "a", "a1", "c", "done"]);
+        assert_eq!(todo.selected_index, 0);
+        todo.move_selected_task(false);
+        todo.move_selected_task(false);
+        assert_eq!(task_names(&todo), ["a", "a1", "c", "b", "b1", "b2", "done"]);
+        assert_eq!(todo.selected_index, 3);
+        assert_eq!(todo.undo_stack.len(), 1);
+        assert!(todo.undo());
+        assert_eq!(task_names(&todo), ["a", "a1", "b", "b1", "b2", "c", "done"]);
+
+        // Moves further apart, or right after an undo, are separate steps
+        todo.selected_index = 5; // c
+        todo.move_selected_task(true);
+        todo.last_reorder = None;
+        todo.move_selected_task(true);
+        assert_eq!(task_names(&todo), ["c", "a", "a1", "b", "b1", "b2", "done"]);
+        assert!(todo.undo());
+        assert_eq!(task_names(&todo), ["a", "a1", "c", "b", "b1", "b2", "done"]);
+        assert!(todo.undo());
+        assert_eq!(task_names(&todo), ["a", "a1", "b", "b1", "b2", "c", "done"]);
+
+        // Subtasks only move among their siblings
+        todo.selected_index = 4; // b2
+        todo.move_selected_task(true);
+        assert_eq!(task_names(&todo), ["a", "a1", "b", "b2", "b1", "c", "done"]);
+        todo.move_selected_task(true);
+        assert_eq!(todo.selected_index, 3);
+        todo.selected_index = 1; // a1
+        todo.move_selected_task(false);
+        todo.move_selected_task(true);
+        assert_eq!(task_names(&todo), ["a", "a1", "b", "b2", "b1", "c", "done"]);
+
+        // Open tasks don't move into the done ones, which don't move at all
+        todo.selected_index = 5; // c
+        todo.move_selected_task(false);
+        assert_eq!(todo.selected_index, 5);
+        todo.selected_index = 6;
+        todo.move_selected_task(true);
+        assert_eq!(task_names(&todo), ["a", "a1", "b", "b2", "b1", "c", "done"]);
+
+        // With sort_by_priority, tasks stay within their priority
+        todo.sort_by_priority = true;
+        todo.items[0].priority = Some(Priority::High);
+        todo.items[2].priority = Some(Priority::High);
+        todo.selected_index = 5; // c
+        todo.move_selected_task(true);
+        assert_eq!(todo.selected_index, 5);
+        todo.selected_index = 2; // b
+        todo.move_selected_task(false);
+        assert_eq!(todo.selected_index, 2);
+        todo.move_selected_task(true);
+        assert_eq!(task_names(&todo), ["b", "b2", "b1", "a", "a1", "c", "done"]);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_notes_round_trip_with_timeline_and_subtask() {
+        let mut todo = load_todo("notes", "- [ ] Write report\n- [ ] Other\n");
+        let day = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        todo.items[0].timeline = vec![session(day, 25, 9, 30)];
+        todo.selected_index = 0;
+        todo.open_notes_editor();
+        todo.notes_editor = Some("Outline first\n\n> quoted line\n".to_string());
+        todo.close_notes_editor();
+        todo.current_input.paste("Draft intro");
+        todo.submit_new_subtask();
+        todo.open_notes_editor();
+        todo.notes_editor = Some("See https://example.com".to_string());
+        todo.close_notes_editor();
+        todo.items[1].timeline = vec![session(day, 15, 10, 0)];
+        todo.save_to_file();
+
+        let saved = without_created(&fs::read_to_string(&todo.file_path).unwrap());
+        assert!(saved.starts_with("# TODO List\n\n\
+            - [ ] Write report <!-- id:1 -->\n  \
+              > Outline first\n  \
+              >\n  \
+              > > quoted line\n  \
+              Timeline:\n    \
+                - 2026-10-15: 25 minutes at 09:30\n  \
+            - [ ] Draft intro <!-- id:3 -->\n    \
+                > See https://example.com\n    \
+                Timeline:\n      \
+                  - 2026-10-15: 15 minutes at 10:00\n\
+            - [ ] Other <!-- id:2 -->\n"));
+
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        fs::remove_file(&todo.file_path).unwrap();
+        assert_eq!(task_names(&reloaded), ["Write report", "Draft intro", "Other"]);
+        assert_eq!(reloaded.items[0].notes, "Outline first\n\n> quoted line");
+        assert_eq!(reloaded.items[1].notes, "See https://example.com");
+        assert_eq!(reloaded.items[1].parent_id, Some(1));
+        assert!(reloaded.items[2].notes.is_empty());
+        for (loaded, saved) in reloaded.items.iter().zip(&todo.items) {
+            assert_eq!(loaded.timeline, saved.timeline);
+        }
+    }
+
+    #[test]
+    fn test_priorities() {
+        let content = "- [ ] a\n- [ ] b | Priority: LOW\n- [ ] c | Priority: urgent\n- [ ] d | Priority: high\n  - [ ] d1\n- [x] e | Priority: high\n";
+        let mut todo = load_todo("priorities", content);
+        let priorities: Vec<Option<Priority>> = todo.items.iter().map(|item| item.priority).collect();
+        assert_eq!(priorities, [None, Some(Priority::Low), None, Some(Priority::High), None, Some(Priority::High)]);
+        // An unknown priority stays part of the text
+        assert_eq!(todo.items[2].task, "c | Priority: urgent");
+
+        // none → high → medium → low → none, each an undo step
+        todo.selected_index = 0;
+        let mut cycled = Vec::new();
+        for _ in 0..4 {
+            todo.cycle_selected_priority();
+            cycled.push(todo.items[0].priority);
+        }
+        assert_eq!(cycled, [Some(Priority::High), Some(Priority::Medium), Some(Priority::Low), None]);
+        assert!(todo.undo());
+        assert_eq!(todo.items[0].priority, Some(Priority::Low));
+
+        // Open tasks by priority, keeping their order within one, subtasks under their task, done ones last
+        todo.sort_by_priority = true;
+        todo.cycle_selected_priority(); // a: low → none
+        assert_eq!(task_names(&todo), ["d", "d1", "b", "a", "c | Priority: urgent", "e"]);
+        assert_eq!(todo.items[todo.selected_index].task, "a");
+        todo.cycle_selected_priority(); // a: high
+        assert_eq!(task_names(&todo), ["d", "d1", "a", "b", "c | Priority: urgent", "e"]);
+        assert_eq!(todo.selected_index, 2);
+
+        let saved = without_created(&fs::read_to_string(&todo.file_path).unwrap());
+        assert!(saved.contains("- [ ] a | Priority: high <!-- id:1 -->\n"));
+        assert!(saved.contains("- [ ] b | Priority: low <!-- id:2 -->\n"));
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        fs::remove_file(&todo.file_path).unwrap();
+        assert!(reloaded.items.iter().zip(&todo.items).all(|(loaded, saved)| loaded.priority == saved.priority));
+    }
+
+    #[test]
+    fn test_edit_task_keeps_status_and_time() {
+        let mut todo = load_todo("edit-task", "- [x] Write reprot | Focused time: 50 minutes\n- [ ] Other\n");
+        let day = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        todo.items[0].timeline = vec![session(day, 50, 9, 0)];
+        todo.start_edit_input();
+        assert_eq!(todo.current_input.as_str(), "Write reprot");
+        assert_eq!(todo.input_purpose, InputPurpose::EditTask);
+        todo.current_input.set("  Write report  ".to_string());
+        todo.submit_input();
+        assert!(!todo.is_input_mode);
+        assert_eq!(todo.input_purpose, InputPurpose::NewTask);
+        let item = &todo.items[0];
+        assert_eq!((item.task.as_str(), item.done, item.focused_time), ("Write report", true, 50));
+        assert_eq!(item.timeline, [session(day, 50, 9, 0)]);
+        assert_eq!(todo.undo_stack.len(), 1);
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        assert_eq!(reloaded.items[0].task, "Write report");
+
+        // Empty or unchanged input leaves the task (and the undo stack) alone
+        for input in ["   ", "Write report"] {
+            todo.start_edit_input();
+            todo.current_input.set(input.to_string());
+            todo.submit_input();
+            assert_eq!(todo.items[0].task, "Write report");
+            assert_eq!(todo.undo_stack.len(), 1);
+        }
+        assert!(todo.undo());
+        assert_eq!(todo.items[0].task, "Write reprot");
+        assert_eq!(todo.items[0].focused_time, 50);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_index_of_id_and_next_unfinished() {
+        let mut todo = load_todo("next-unfinished", "- [ ] a\n- [ ] b\n- [ ] c\n- [x] d\n");
+        assert_eq!(todo.index_of_id(3), Some(2));
+        assert_eq!(todo.index_of_id(42), None);
+        let next = |todo: &Todo, index| todo.next_unfinished_from(index).map(|item| item.task.clone());
+
+        assert_eq!(next(&todo, 1).as_deref(), Some("b"));
+        // Done and blocked tasks are passed over, wrapping around to the top
+        todo.items[2].blocked_by = Some(1);
+        assert_eq!(next(&todo, 2).as_deref(), Some("a"));
+        assert_eq!(next(&todo, 10).as_deref(), Some("a"));
+        for item in &mut todo.items[..2] {
+            item.done = true;
+        }
+        // c's blocker is done now, so it no longer waits
+        assert_eq!(next(&todo, 0).as_deref(), Some("c"));
+        todo.items[2].done = true;
+        assert_eq!(next(&todo, 0), None);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_estimates() {
+        let content = "- [ ] a | Focused time: 60 minutes | Est: 4\n- [ ] b | Focused time: 60 minutes | Session: 50 min | Est: 0\n- [ ] c | Est: lots\n";
+        let mut todo = load_todo("estimates", content);
+        let estimates: Vec<Option<u32>> = todo.items.iter().map(|item| item.estimate).collect();
+        assert_eq!(estimates, [Some(4), None, None]);
+        // A malformed estimate is dropped rather than kept in the text
+        assert_eq!(todo.items[2].task, "c");
+        // Whole sessions of the task's own length, or the default
+        assert_eq!(todo.items[0].pomodoros_done(25), 2);
+        assert_eq!(todo.items[0].pomodoros_done(0), 60);
+        assert_eq!(todo.items[1].pomodoros_done(25), 1);
+
+        // The input starts from the estimate; a change is saved and one undo step
+        todo.selected_index = 0;
+        todo.start_estimate_input();
+        assert_eq!(todo.current_input.as_str(), "4");
+        todo.current_input.set("6".to_string());
+        todo.submit_input();
+        assert_eq!(todo.items[0].estimate, Some(6));
+        assert!(without_created(&fs::read_to_string(&todo.file_path).unwrap()).contains("- [ ] a | Focused time: 60 minutes | Est: 6 <!-- id:1 -->\n"));
+
+        // Zero or nonsense is ignored; the same estimate isn't an undo step
+        for input in ["0", "six", "6"] {
+            todo.start_estimate_input();
+            todo.current_input.set(input.to_string());
+            todo.submit_input();
+            assert_eq!(todo.items[0].estimate, Some(6));
+        }
+        assert_eq!(todo.undo_stack.len(), 1);
+
+        // Empty clears it
+        todo.start_estimate_input();
+        todo.current_input.clear();
+        todo.submit_input();
+        assert_eq!(todo.items[0].estimate, None);
+        assert!(!fs::read_to_string(&todo.file_path).unwrap().contains("Est:"));
+        assert!(todo.undo());
+        assert!(todo.undo());
+        assert_eq!(todo.items[0].estimate, Some(4));
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        fs::remove_file(&todo.file_path).unwrap();
+        assert_eq!(reloaded.items[0].estimate, Some(4));
+    }
+
+    #[test]
+    fn test_recurring_task_comes_back_with_its_history() {
+        let content = "- [ ] Other\n\
+                       - [x] Water plants | Repeat: daily | Done: 2026-10-14 18:00\n  \
+                         Completed:\n    \
+                           - 2026-10-13 08:00\n    \
+                           - 2026-10-14 18:00\n\
+                       - [x] Weekly review | Repeat: weekly | Done: 2026-10-14 17:00\n  \
+                         Completed:\n    \
+                           - 2026-10-14 17:00\n";
+        let mut todo = load_todo("recurring", content);
+        let today = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        let find = |todo: &Todo, task: &str| todo.items.iter().find(|item| item.task == task).unwrap().clone();
+        let completions = |item: &TodoItem| item.completions.iter().map(|at| at.format("%Y-%m-%d %H:%M").to_string()).collect::<Vec<_>>();
+        assert_eq!(find(&todo, "Water plants").repeat, Some(Recurrence::Days(1)));
+
+        // Done yesterday, the daily task is open again today, above the done ones; the weekly one waits
+        assert!(todo.reset_recurring_tasks(today));
+        assert_eq!(task_names(&todo), ["Other", "Water plants", "Weekly review"]);
+        let plants = find(&todo, "Water plants");
+        assert!(!plants.done && plants.completed_at.is_none());
+        assert_eq!(completions(&plants), ["2026-10-13 08:00", "2026-10-14 18:00"]);
+        assert!(find(&todo, "Weekly review").done);
+        assert!(!todo.reset_recurring_tasks(today));
+
+        // The history and the repeat survive a save and load
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        let plants = find(&reloaded, "Water plants");
+        assert_eq!(plants.repeat, Some(Recurrence::Days(1)));
+        assert!(!plants.done);
+        assert_eq!(completions(&plants), ["2026-10-13 08:00", "2026-10-14 18:00"]);
+        assert_eq!(completions(&find(&reloaded, "Weekly review")), ["2026-10-14 17:00"]);
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_focus_ratings_round_trip() {
+        let mut todo = load_todo("ratings", "- [ ] Write report\n");
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let session = |d, ratings: Vec<u8>| PomodoroSession {
+            work_sessions: ratings.len() as u32,
+            total_work_minutes: 25 * ratings.len() as u32,
+            tasks_worked_on: vec!["Write report".to_string()],
+            ratings,
+            ..empty_session(day(d))
+        };
+        todo.save_pomodoro_sessions(vec![session(14, vec![4, 5, 2]), session(15, Vec::new())]);
+        let saved = fs::read_to_string(&todo.file_path).unwrap();
+        assert!(saved.contains("- Total break time: 0 minutes\n- Focus ratings: 4, 5, 2\n- Tasks worked on:\n"));
+        assert_eq!(saved.matches("- Focus ratings:").count(), 1);
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        assert_eq!(reloaded.get_pomodoro_sessions(), todo.get_pomodoro_sessions());
+
+        // Ratings out of range, or not numbers, are dropped when loading
+        fs::write(&todo.file_path, saved.replace("- Focus ratings: 4, 5, 2", "- Focus ratings: 4, 9, x, 0, 1")).unwrap();
+        let reloaded = Todo::new(Some(todo.file_path.clone()), None);
+        fs::remove_file(&todo.file_path).unwrap();
+        assert_eq!(reloaded.get_pomodoro_sessions()[0].ratings, [4, 1]);
+        assert!(reloaded.get_pomodoro_sessions()[1].ratings.is_empty());
+    }
+
+    #[test]
+    fn test_quit_waits_for_a_disk_conflict() {
+        let mut todo = load_todo("quit-conflict", "- [ ] a\n- [ ] b\n");
+        todo.save_delay = Duration::from_secs(60);
+        todo.toggle_selected_task();
+        todo.toggle_selected_task();
+        assert!(todo.is_dirty());
+
+        // The pending auto-save would overwrite the other program's edit, so the conflict is settled first
+        fs::write(&todo.file_path, "- [ ] Theirs\n- [ ] and more\n").unwrap();
+        assert!(!todo.request_quit());
+        assert!(todo.disk_conflict && todo.is_dirty() && !todo.quit_prompt);
+        todo.keep_mine();
+        assert!(todo.request_quit());
+        assert!(fs::read_to_string(&todo.file_path).unwrap().contains("- [x] b"));
+        fs::remove_file(&todo.file_path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_save_stays_unsaved() {
+        // A file under a regular file can't be written
+        let blocker = temp_todo_path("save-fails");
+        fs::write(&blocker, "").unwrap();
+        let mut todo = load_todo("save-fails-list", "- [ ] a\n- [ ] b\n");
+        let path = todo.file_path.clone();
+        todo.file_path = format!("{}/todos.md", blocker);
+        todo.toggle_selected_task();
+        assert!(todo.is_dirty());
+        assert!(todo.status_message.take().unwrap().starts_with("Could not save"));
+
+        // Quitting asks instead of losing the change; saving again fails the same way, discarding quits
+        assert!(!todo.request_quit());
+        assert!(todo.quit_prompt);
+        assert!(!todo.answer_quit_prompt(true));
+        assert!(todo.is_dirty() && todo.status_message.is_some());
+        assert!(!todo.request_quit());
+        assert!(todo.answer_quit_prompt(false));
+        fs::remove_file(&blocker).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,23 +1,43 @@
 use ratatui::{
-    layout::Rect,
+    layout::{Alignment, Constraint, Flex, Layout, Rect},
     style::Style,
-    widgets::{Block, Borders, List, ListItem, ListState},
+    text::Line,
+    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
     Frame,
 };
-use std::path::PathBuf;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::fs;
-use walkdir::WalkDir;
-use rodio::{Decoder, OutputStream, Sink};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use walkdir::{DirEntry, WalkDir};
+use rodio::{source::SeekError, Decoder, OutputStream, Sink, Source};
 use std::io::BufReader;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::thread;
-use rand::Rng;
+use std::time::{Duration, Instant, SystemTime};
+use chrono::Local;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use serde::{Deserialize, Serialize};
 
 use crate::app::{App, Quadrant};
+use crate::config::{MusicConfig, Stream, TrackSort};
+use crate::metadata::{self, TrackTags};
+use crate::noise::{Noise, NoiseKind};
+use crate::play_counts::PlayCounts;
+use crate::remote::{NowPlaying, PlaybackStatus, RemoteCommand};
+use crate::stream::{self, StreamState};
+use crate::timer::{format_duration, format_hours_minutes};
 use crate::theme::DraculaTheme;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PlaybackMode {
+    #[default]
     TrackList,   // Play tracks in order
     Random,      // Play tracks randomly
     Repeat,      // Repeat the entire playlist
@@ -53,11 +73,432 @@ impl PlaybackMode {
     }
 }
 
+/// What to do once a track has played to the end
+#[derive(Debug, PartialEq)]
+enum AfterTrack {
+    Stop,
+    Next { wrap: bool }, // The next file in the list, back to the first after the last when wrapping
+    Shuffle,
+    Replay,
+}
+
+/// The playback mode decides what follows a track; without auto_play_next only Current Only goes
+/// on (playing the same track again), the other modes, Random included, stop
+fn after_track(mode: &PlaybackMode, auto_play_next: bool, current: Option<usize>, len: usize) -> AfterTrack {
+    if current.is_none_or(|current| current >= len) {
+        return AfterTrack::Stop;
+    }
+    match mode {
+        PlaybackMode::CurrentOnly => AfterTrack::Replay,
+        _ if !auto_play_next => AfterTrack::Stop,
+        PlaybackMode::TrackList => AfterTrack::Next { wrap: false },
+        PlaybackMode::Repeat => AfterTrack::Next { wrap: true },
+        PlaybackMode::Random => AfterTrack::Shuffle,
+    }
+}
+
+/// The folder to scan for a configured directory (~ is expanded), or the system's music folder
+fn music_folder(music_directory: Option<&str>) -> PathBuf {
+    if let Some(dir) = music_directory {
+        // Expand ~ to home directory if present
+        if let Some(rest) = dir.strip_prefix("~/") {
+            if let Some(home) = dirs::home_dir() {
+                return home.join(rest);
+            }
+        }
+        PathBuf::from(dir)
+    } else {
+        // Use default logic if no config provided
+        dirs::audio_dir()
+            .or_else(|| dirs::home_dir().map(|p| p.join("Music")))
+            .unwrap_or_else(|| PathBuf::from("./music"))
+    }
+}
+
+/// While a scan finds files, they are handed over at least this often
+const SCAN_BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How deep a scan goes and what it leaves out
+#[derive(Debug, Clone)]
+pub struct ScanFilter {
+    pub depth: usize, // Folder levels, 1 being only the music folder itself
+    pub ignore: Vec<glob::Pattern>,
+    pub skip_hidden: bool,
+    pub dedupe_copies: bool, // Also list a file only once when copies of it (same name and size) sit in other folders
+}
+
+impl ScanFilter {
+    /// From the [music] settings; invalid patterns (which the config doesn't let through) are left out
+    pub fn new(config: &MusicConfig) -> Self {
+        Self {
+            depth: config.scan_depth,
+            ignore: config.ignore.iter().filter_map(|pattern| glob::Pattern::new(pattern).ok()).collect(),
+            skip_hidden: config.skip_hidden,
+            dedupe_copies: config.dedupe_copies,
+        }
+    }
+
+    /// Whether the scan leaves out this folder: a hidden one, or one whose name (or path below
+    /// the music folder) matches an ignore pattern. Files and the music folder itself are kept.
+    fn skips(&self, entry: &DirEntry, folder: &Path) -> bool {
+        if entry.depth() == 0 || !entry.file_type().is_dir() {
+            return false;
+        }
+        let name = entry.file_name().to_string_lossy();
+        let below = entry.path().strip_prefix(folder).unwrap_or(entry.path());
+        (self.skip_hidden && name.starts_with('.'))
+            || self.ignore.iter().any(|pattern| pattern.matches(&name) || pattern.matches_path(below))
+    }
+}
+
+/// How a scan is getting on, shared between its thread and the track list
+#[derive(Debug, Default)]
+struct ScanProgress {
+    cancelled: AtomicBool,
+    skipped: AtomicUsize, // Folders left out
+    duplicates: AtomicUsize, // Files already listed under another path (or as a copy elsewhere)
+}
+
+/// Walk `folder` for music files, handing them to `send` in batches as they turn up. Symlinked
+/// folders are followed, and a file reached through more than one path is listed once. Stops early
+/// once the scan is cancelled or `send` fails.
+fn scan_folder(folder: &Path, filter: &ScanFilter, progress: &ScanProgress, send: impl Fn(Vec<Track>) -> bool) {
+    if !folder.exists() {
+        // Create the default music folder, for the files to go into
+        let _ = fs::create_dir_all(folder);
+        return;
+    }
+
+    // Supported audio formats
+    let audio_extensions = vec!["mp3", "wav", "flac", "m4a", "aac", "ogg"];
+
+    let mut batch = Vec::new();
+    let mut last_sent = Instant::now();
+    let mut listed: HashSet<PathBuf> = HashSet::new(); // Where the files found so far really are
+    let mut copies: HashSet<(u64, OsString)> = HashSet::new(); // Their sizes and names, with dedupe_copies
+    // In name order, so the same one of two paths to a file wins every time (favorites go by path)
+    for entry in WalkDir::new(folder)
+        .max_depth(filter.depth)
+        .follow_links(true)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let skip = filter.skips(entry, folder);
+            if skip {
+                progress.skipped.fetch_add(1, atomic::Ordering::Relaxed);
+            }
+            !skip
+        })
+        .filter_map(|e| e.ok())
+    {
+        if progress.cancelled.load(atomic::Ordering::Relaxed) {
+            return;
+        }
+        if let Some(extension) = entry.path().extension() {
+            if audio_extensions.contains(&extension.to_string_lossy().to_lowercase().as_str()) {
+                let metadata = entry.metadata().ok();
+                // A file that can't be resolved is taken as it is
+                let real_path = fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path().to_path_buf());
+                let copy = filter.dedupe_copies
+                    && metadata.as_ref().is_some_and(|metadata| !copies.insert((metadata.len(), entry.file_name().to_os_string())));
+                if !listed.insert(real_path.clone()) || copy {
+                    progress.duplicates.fetch_add(1, atomic::Ordering::Relaxed);
+                    continue;
+                }
+
+                let name = entry.path()
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("Unknown")
+                    .to_string();
+
+                let mut track = Track::new(name, entry.path().to_path_buf());
+                track.modified = metadata.and_then(|metadata| metadata.modified().ok());
+                track.real_path = Some(real_path);
+                batch.push(track);
+            }
+        }
+        if !batch.is_empty() && last_sent.elapsed() >= SCAN_BATCH_INTERVAL {
+            if !send(std::mem::take(&mut batch)) {
+                return;
+            }
+            last_sent = Instant::now();
+        }
+    }
+
+    if !batch.is_empty() {
+        send(batch);
+    }
+}
+
+/// A scan of a music folder on a thread of its own; dropping it cancels the scan
+struct Scan {
+    receiver: Receiver<Vec<Track>>,
+    progress: Arc<ScanProgress>,
+    found: usize, // Music files received so far
+}
+
+impl Scan {
+    fn start(folder: PathBuf, filter: ScanFilter) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let progress = Arc::new(ScanProgress::default());
+        let shared = Arc::clone(&progress);
+        thread::spawn(move || scan_folder(&folder, &filter, &shared, |batch| sender.send(batch).is_ok()));
+        Self { receiver, progress, found: 0 }
+    }
+
+    /// The tracks found since the last call, and whether the scan is done
+    fn receive(&mut self) -> (Vec<Track>, bool) {
+        let mut tracks = Vec::new();
+        let finished = loop {
+            match self.receiver.try_recv() {
+                Ok(batch) => tracks.extend(batch),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        self.found += tracks.len();
+        (tracks, finished)
+    }
+}
+
+impl Drop for Scan {
+    fn drop(&mut self) {
+        self.progress.cancelled.store(true, atomic::Ordering::Relaxed);
+    }
+}
+
+/// The decoder for the music file at `path`, or what's wrong with it
+fn open_track(path: &Path) -> Result<Decoder<BufReader<fs::File>>, String> {
+    let file = fs::File::open(path).map_err(|e| format!("can't open file ({})", e))?;
+    Decoder::new(BufReader::new(file)).map_err(|e| format!("can't decode ({})", e))
+}
+
+/// The now-playing line scrolls one cell this often, and rests this long at either end
+const MARQUEE_STEP: Duration = Duration::from_millis(300);
+const MARQUEE_PAUSE: Duration = Duration::from_millis(1500);
+
+/// How many cells a line `overflow` cells too wide is scrolled, `elapsed` after it was first shown:
+/// it rests at the start, scrolls to the end, rests there and starts over
+fn marquee_offset(elapsed: Duration, overflow: usize) -> usize {
+    if overflow == 0 {
+        return 0;
+    }
+    let scrolling = MARQUEE_STEP * overflow as u32;
+    let round = MARQUEE_PAUSE * 2 + scrolling;
+    let into_round = Duration::from_nanos((elapsed.as_nanos() % round.as_nanos()) as u64);
+    let Some(scrolled) = into_round.checked_sub(MARQUEE_PAUSE) else {
+        return 0;
+    };
+    ((scrolled.as_nanos() / MARQUEE_STEP.as_nanos()) as usize).min(overflow)
+}
+
+/// The part of `text` `width` cells wide that starts `offset` cells in. A wide (CJK) character
+/// cut in half at either edge leaves a space instead.
+fn marquee_window(text: &str, offset: usize, width: usize) -> String {
+    let mut window = String::new();
+    let mut cells = 0; // Cells of `text` up to and including the current character
+    let mut used = 0; // Cells of `window`
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        cells += char_width;
+        if cells <= offset {
+            continue;
+        }
+        // Only partly past the left edge, or not fitting at the right one
+        let shown = (cells - offset).min(char_width);
+        if shown < char_width || used + char_width > width {
+            let blank = shown.min(width - used);
+            window.push_str(&" ".repeat(blank));
+            used += blank;
+            if used == width || shown == char_width {
+                break;
+            }
+            continue;
+        }
+        window.push(c);
+        used += char_width;
+    }
+    window
+}
+
+/// Put `tracks` in `sort` order and return where each one went, by its old position.
+/// Streams and noise stay on top in the order they were listed.
+fn order_tracks(tracks: &mut Vec<Track>, sort: TrackSort) -> Vec<usize> {
+    let mut indexed: Vec<(usize, Track)> = std::mem::take(tracks).into_iter().enumerate().collect();
+    indexed.sort_by(|(_, a), (_, b)| compare_entries(sort, a, b));
+
+    let mut moved_to = vec![0; indexed.len()];
+    for (new, (old, track)) in indexed.into_iter().enumerate() {
+        moved_to[old] = new;
+        tracks.push(track);
+    }
+    moved_to
+}
+
+/// Add `batch` to `tracks`, which are in `sort` order already, and return where each of the
+/// tracks before went, by its old position. Spares re-sorting everything for each scan batch.
+fn merge_tracks(tracks: &mut Vec<Track>, mut batch: Vec<Track>, sort: TrackSort) -> Vec<usize> {
+    batch.sort_by(|a, b| compare_entries(sort, a, b));
+    let old = std::mem::take(tracks);
+    let mut moved_to = Vec::with_capacity(old.len());
+    tracks.reserve(old.len() + batch.len());
+    let mut batch = batch.into_iter().peekable();
+    for track in old {
+        while let Some(new) = batch.next_if(|new| compare_entries(sort, new, &track) == Ordering::Less) {
+            tracks.push(new);
+        }
+        moved_to.push(tracks.len());
+        tracks.push(track);
+    }
+    tracks.extend(batch);
+    moved_to
+}
+
+/// Streams and noise first, in the order they were listed, then the music files in `sort` order
+fn compare_entries(sort: TrackSort, a: &Track, b: &Track) -> Ordering {
+    let group = |track: &Track| if track.is_endless() { 0 } else { 1 };
+    group(a).cmp(&group(b)).then_with(|| if group(a) == 1 { compare_tracks(sort, a, b) } else { Ordering::Equal })
+}
+
+/// Case-insensitive; tracks with the same sort key are ordered by name
+fn compare_tracks(sort: TrackSort, a: &Track, b: &Track) -> Ordering {
+    let by_name = || a.sort_name.cmp(&b.sort_name).then_with(|| a.path.cmp(&b.path));
+    match sort {
+        TrackSort::Name => by_name(),
+        TrackSort::Path => a.path.cmp(&b.path),
+        TrackSort::Modified => b.modified.cmp(&a.modified).then_with(by_name),
+        TrackSort::Artist => compare_tags(&a.artist, &b.artist).then_with(|| compare_tags(&a.album, &b.album)).then_with(by_name),
+        TrackSort::Album => compare_tags(&a.album, &b.album).then_with(by_name),
+        TrackSort::Plays => b.plays.cmp(&a.plays).then_with(by_name),
+    }
+}
+
+/// Tracks without the tag go last
+fn compare_tags(a: &Option<String>, b: &Option<String>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.chars().flat_map(char::to_lowercase).cmp(b.chars().flat_map(char::to_lowercase)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Which of the work and break playlists is playing, when they are configured
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Playlist {
+    Work,
+    Break,
+}
+
+impl Playlist {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Playlist::Work => "Work",
+            Playlist::Break => "Break",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Playlist::Work => "💼",
+            Playlist::Break => "☕",
+        }
+    }
+}
+
+/// The playlist not in use, kept as it was left
+struct Standby {
+    folder: PathBuf,
+    tracks: Vec<Track>,
+    selected_index: usize,
+    scan: Option<Scan>,
+}
+
+impl Standby {
+    fn sort(&mut self, sort: TrackSort) {
+        let moved_to = order_tracks(&mut self.tracks, sort);
+        self.selected_index = moved_to.get(self.selected_index).copied().unwrap_or(0);
+    }
+
+    fn merge(&mut self, found: Vec<Track>, sort: TrackSort) {
+        let moved_to = merge_tracks(&mut self.tracks, found, sort);
+        self.selected_index = moved_to.get(self.selected_index).copied().unwrap_or(0);
+    }
+}
+
+/// What the player remembers between runs, kept in ~/.config/sessio/music_state.json.
+/// Anything missing (or a track no longer in the library) is left at its default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MusicState {
+    pub playback_mode: PlaybackMode,
+    pub current_track: Option<PathBuf>,
+    pub selected_track: Option<PathBuf>,
+    pub volume: Option<f32>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Track {
     pub name: String,
+    sort_name: String, // `name` in lowercase, which sorting goes by
     pub path: PathBuf,
-    pub duration: Option<String>,
+    pub duration: Option<Duration>, // Length, once the track has been played and its decoder knew it
+    pub artist: Option<String>, // From the file's tags, read in the background after a scan
+    pub title: Option<String>,
+    pub album: Option<String>,
+    pub url: Option<String>, // Set for internet radio, whose path is the url (so favorites and state find it)
+    pub noise: Option<NoiseKind>, // Set for the built-in noise, whose path is the kind's id
+    pub modified: Option<SystemTime>, // When the file last changed, found while scanning
+    pub real_path: Option<PathBuf>, // Where a music file really is (symlinks resolved), which play counts go by
+    pub plays: u32, // How often it was played, from the play counts
+    pub gain: Option<f32>, // ReplayGain track gain in dB, from the tags
+    pub error: Option<String>, // Why the file wouldn't play last time; auto-advance passes it by
+}
+
+impl Track {
+    /// Entry for a file; tags come later
+    fn new(name: String, path: PathBuf) -> Self {
+        Self { sort_name: name.to_lowercase(), name, path, duration: None, artist: None, title: None, album: None, url: None, noise: None, modified: None, real_path: None, plays: 0, gain: None, error: None }
+    }
+
+    fn noise(kind: NoiseKind) -> Self {
+        Self { noise: Some(kind), ..Self::new(kind.name().to_string(), PathBuf::from(kind.id())) }
+    }
+
+    fn stream(stream: &Stream) -> Self {
+        Self { url: Some(stream.url.clone()), ..Self::new(stream.name.clone(), PathBuf::from(&stream.url)) }
+    }
+
+    /// A stream, noise or a file that's still there
+    pub fn is_playable(&self) -> bool {
+        self.is_endless() || self.path.is_file()
+    }
+
+    /// Streams and noise play until stopped, so playback never moves on from them (or to them) by itself
+    pub fn is_endless(&self) -> bool {
+        self.url.is_some() || self.noise.is_some()
+    }
+
+    /// Whether playback may move on to it by itself: a file that played (or hasn't been tried)
+    fn can_follow(&self) -> bool {
+        !self.is_endless() && self.error.is_none()
+    }
+
+    /// Pick up how often the file was played
+    fn count_plays(&mut self, play_counts: &PlayCounts) {
+        self.plays = self.real_path.as_ref().map_or(0, |real_path| play_counts.plays(real_path));
+    }
+
+    /// "Artist — Title" from the tags, or the file name when the track has no title tag
+    /// (or tags aren't wanted)
+    pub fn label(&self, show_tags: bool) -> String {
+        match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) if show_tags => format!("{} — {}", artist, title),
+            (None, Some(title)) if show_tags => title.clone(),
+            _ => self.name.clone(),
+        }
+    }
 }
 
 pub struct TrackList {
@@ -71,28 +512,96 @@ pub struct TrackList {
     pub is_playing: bool,
     pub is_paused: bool,
     pub playback_mode: PlaybackMode,
+    pub volume: f32, // Music level set with +/- (1.0 = full), kept across tracks and alarms
+    default_volume: f32, // The configured level, for when no volume was remembered
+    pub status_message: Option<String>, // Short notice at the bottom of the panel, cleared by the next key
+    probed_duration: Arc<Mutex<Option<(PathBuf, Duration)>>>, // Length found by the playback thread, picked up on the next update
+    failed_playback: Arc<Mutex<Option<(PathBuf, String)>>>, // A file the playback thread couldn't open or decode, and why
+    started_playback: Arc<Mutex<Option<PathBuf>>>, // A file the playback thread got going, to be counted as played
+    play_counts: PlayCounts,
+    pub show_tags: bool, // List tracks as "Artist — Title" from their tags, rather than by file name
+    tag_receiver: Option<Receiver<(PathBuf, TrackTags)>>, // Tags read in the background since the last scan
+    pub favorites: HashSet<PathBuf>, // Tracks marked with ♥, kept in favorites_path
+    pub favorites_path: Option<PathBuf>, // ~/.config/sessio/favorites.txt, one track path per line
+    pub favorites_only: bool, // Show and play only the favorite tracks
+    shuffle_order: Vec<usize>, // Random mode: the shown tracks in shuffled order, each played once per round
+    shuffle_position: Option<usize>, // Where in shuffle_order playback is; None before the first track
+    rng: StdRng, // Shuffles the tracks; seeded in tests
+    pub state_path: Option<PathBuf>, // ~/.config/sessio/music_state.json, see MusicState
+    pub playlist: Playlist, // Which playlist `tracks` holds while separate work and break playlists are set up
+    standby: Option<Standby>, // The other playlist; None without separate playlists
+    pub playlist_pinned: bool, // Stay on the current playlist when the timer changes phase
+    streams: Vec<Stream>, // Internet radio, listed above the files of either playlist
+    builtin_noise: bool, // List the noise generators at the very top
+    pub sort: TrackSort, // Order of the music files, in both playlists
+    pub auto_play_next: bool, // Go on to another track when one ends, as the playback mode says
+    pub replaygain: bool, // Play each track louder or quieter by its ReplayGain tag
+    ducked_to: Option<f32>, // The music's level while the alarm sounds, in place of `volume`
+    volume_ramp: Option<VolumeRamp>, // Coming back up from the alarm's level
+    scan: Option<Scan>, // Looking for the music files of `music_folder`, which come in while it runs
+    pending_state: Option<MusicState>, // Remembered tracks to go back to once the first scan is done
+    pub resume_playback: bool, // Play the remembered track once it has been found
+    pub scan_filter: ScanFilter, // Used by the next scan
+    skipped_folders: usize, // Left out by the last scan of `music_folder`, shown at the bottom
+    duplicates_skipped: usize, // Files the last scan found twice, shown in the title
+    stream_state: Option<Arc<Mutex<StreamState>>>, // The stream being played, updated by its thread
+    pub follow_playing: bool, // Move the selection along when auto-advance starts the next track
+    selection_moved_at: Option<Instant>, // Last time the selection was moved by hand, which holds off following
+    list_height: usize, // Rows the list had when last drawn, for scrolling the playing track into the middle
+    sleep_timer: Option<SleepTimer>,
+    position: Duration, // How far into the current track playback is, read from the sink once per frame
+    marquee: (String, Instant), // The now-playing label, and since when it's been shown (which sets how far it has scrolled)
+}
+
+/// Sleep timer lengths in minutes, cycled through with z (and then off again)
+const SLEEP_PRESETS: [u64; 3] = [15, 30, 60];
+/// How long the music fades out once the sleep timer is up
+const SLEEP_FADE: Duration = Duration::from_secs(10);
+
+/// Stops the music a set time after it was started, fading it out first
+struct SleepTimer {
+    minutes: u64, // The preset it was started with
+    ends_at: Instant, // When the fade starts
+}
+
+/// Following the playing track waits this long after the selection was last moved by hand
+const FOLLOW_PAUSE: Duration = Duration::from_secs(5);
+
+/// Step for the volume keys, and the loudest level they go to
+const VOLUME_STEP: f32 = 0.05;
+const MAX_VOLUME: f32 = 1.5;
+
+/// The most a ReplayGain tag may turn a track up
+const MAX_TRACK_GAIN: f32 = 1.5;
+
+/// How long the music takes to come back up after the alarm
+const VOLUME_RAMP: Duration = Duration::from_millis(1500);
+
+/// Moves the music's level from one volume to another over `VOLUME_RAMP`, a bit further on every update
+struct VolumeRamp {
+    from: f32,
+    to: f32,
+    started: Instant,
+}
+
+impl VolumeRamp {
+    /// Where it has got to by `now`; `to` once it's over
+    fn level(&self, now: Instant) -> f32 {
+        let done = (now.saturating_duration_since(self.started).as_secs_f32() / VOLUME_RAMP.as_secs_f32()).min(1.0);
+        self.from + (self.to - self.from) * done
+    }
+}
+
+/// The level a new sink starts at: the one remembered from last time, or else the configured default
+fn starting_volume(default_volume: f32, remembered: Option<f32>) -> f32 {
+    remembered.filter(|volume| volume.is_finite()).unwrap_or(default_volume).clamp(0.0, MAX_VOLUME)
 }
 
 impl TrackList {
 
-    pub fn new(music_directory: Option<&str>) -> Self {
-        let music_folder = if let Some(dir) = music_directory {
-            // Expand ~ to home directory if present
-            if dir.starts_with("~/") {
-                if let Some(home) = dirs::home_dir() {
-                    home.join(&dir[2..])
-                } else {
-                    PathBuf::from(dir)
-                }
-            } else {
-                PathBuf::from(dir)
-            }
-        } else {
-            // Use default logic if no config provided
-            dirs::audio_dir()
-                .or_else(|| dirs::home_dir().map(|p| p.join("Music")))
-                .unwrap_or_else(|| PathBuf::from("./music"))
-        };
+    /// The track list for `music_directory`, with the rest of its settings from `config`
+    pub fn new(music_directory: Option<&str>, config: &MusicConfig) -> Self {
+        let music_folder = music_folder(music_directory);
 
         let mut track_list = Self {
             tracks: Vec::new(),
@@ -105,73 +614,225 @@ impl TrackList {
             is_playing: false,
             is_paused: false,
             playback_mode: PlaybackMode::TrackList,
+            volume: starting_volume(config.default_volume, None),
+            default_volume: config.default_volume,
+            status_message: None,
+            probed_duration: Arc::new(Mutex::new(None)),
+            failed_playback: Arc::new(Mutex::new(None)),
+            started_playback: Arc::new(Mutex::new(None)),
+            play_counts: PlayCounts::load(PlayCounts::default_path()),
+            show_tags: true,
+            tag_receiver: None,
+            favorites: HashSet::new(),
+            favorites_path: dirs::config_dir().map(|dir| dir.join("sessio").join("favorites.txt")),
+            favorites_only: false,
+            shuffle_order: Vec::new(),
+            shuffle_position: None,
+            rng: StdRng::from_entropy(),
+            state_path: dirs::config_dir().map(|dir| dir.join("sessio").join("music_state.json")),
+            playlist: Playlist::Work,
+            standby: None,
+            playlist_pinned: false,
+            streams: config.streams.clone(),
+            builtin_noise: config.builtin_noise,
+            sort: TrackSort::Name,
+            auto_play_next: config.auto_play_next,
+            replaygain: config.replaygain,
+            ducked_to: None,
+            volume_ramp: None,
+            scan: None,
+            pending_state: None,
+            resume_playback: false,
+            scan_filter: ScanFilter::new(config),
+            skipped_folders: 0,
+            duplicates_skipped: 0,
+            stream_state: None,
+            follow_playing: false,
+            selection_moved_at: None,
+            list_height: 0,
+            sleep_timer: None,
+            position: Duration::ZERO,
+            marquee: (String::new(), Instant::now()),
         };
 
+        track_list.load_favorites();
         track_list.load_tracks();
         track_list.list_state.select(Some(0));
+        // The mode and volume apply right away, the tracks once the scan has found them
+        if let Some(state) = track_list.load_state() {
+            track_list.restore_state(state.clone());
+            track_list.pending_state = Some(state);
+        }
         track_list
     }
 
+    /// Start over with the streams and noise, and scan for the music files in the background
+    /// (a scan still running is cancelled); they come in with `update_playback_state`
     pub fn load_tracks(&mut self) {
-        self.tracks.clear();
-        
-        if !self.music_folder.exists() {
-            // Create a default music folder and add some sample entries
-            let _ = fs::create_dir_all(&self.music_folder);
-            self.tracks.push(Track {
-                name: "No music files found".to_string(),
-                path: PathBuf::new(),
-                duration: None,
-            });
-            self.tracks.push(Track {
-                name: format!("Looking in: {}", self.music_folder.display()),
-                path: PathBuf::new(),
-                duration: None,
-            });
-            return;
-        }
-
-        // Supported audio formats
-        let audio_extensions = vec!["mp3", "wav", "flac", "m4a", "aac", "ogg"];
-
-        for entry in WalkDir::new(&self.music_folder)
-            .max_depth(3)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if let Some(extension) = entry.path().extension() {
-                if audio_extensions.contains(&extension.to_string_lossy().to_lowercase().as_str()) {
-                    let name = entry.path()
-                        .file_stem()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("Unknown")
-                        .to_string();
-
-                    self.tracks.push(Track {
-                        name,
-                        path: entry.path().to_path_buf(),
-                        duration: None, // TODO: Could extract duration with metadata
-                    });
+        self.tracks = self.endless_tracks();
+        self.scan = Some(Scan::start(self.music_folder.clone(), self.scan_filter.clone()));
+    }
+
+    /// Add the tracks the scans found since the last call, in sort order. Tags are read once a scan
+    /// is done, and the end of the first one brings back the tracks remembered from last time.
+    fn receive_scans(&mut self) {
+        if let Some((mut found, finished)) = self.scan.as_mut().map(Scan::receive) {
+            if !found.is_empty() {
+                found.iter_mut().for_each(|track| track.count_plays(&self.play_counts));
+                let moved_to = merge_tracks(&mut self.tracks, found, self.sort);
+                self.follow_moves(&moved_to);
+            }
+            if finished {
+                if let Some(scan) = self.scan.take() {
+                    self.skipped_folders = scan.progress.skipped.load(atomic::Ordering::Relaxed);
+                    self.duplicates_skipped = scan.progress.duplicates.load(atomic::Ordering::Relaxed);
+                }
+                if let Some(state) = self.pending_state.take() {
+                    self.restore_state(state);
+                    if self.resume_playback && self.current_track.is_some() {
+                        self.ensure_playing();
+                    }
+                }
+                self.read_tags_in_background();
+            }
+        }
+
+        let Some(standby) = &mut self.standby else {
+            return;
+        };
+        if let Some((mut found, finished)) = standby.scan.as_mut().map(Scan::receive) {
+            if !found.is_empty() {
+                found.iter_mut().for_each(|track| track.count_plays(&self.play_counts));
+                standby.merge(found, self.sort);
+            }
+            if finished {
+                standby.scan = None;
+                self.read_tags_in_background();
+            }
+        }
+    }
+
+    /// The entries listed above the music files: the noise generators (when wanted), then the streams
+    fn endless_tracks(&self) -> Vec<Track> {
+        let noise = NoiseKind::ALL.into_iter().filter(|_| self.builtin_noise).map(Track::noise);
+        noise.chain(self.streams.iter().map(Track::stream)).collect()
+    }
+
+    /// List these internet radio streams (and the noise generators, or not) instead of the ones
+    /// before, in both playlists
+    pub fn set_endless_tracks(&mut self, streams: &[Stream], builtin_noise: bool) {
+        if self.streams == streams && self.builtin_noise == builtin_noise {
+            return;
+        }
+        if self.current_track.is_some_and(|i| self.tracks[i].is_endless()) {
+            self.stop();
+        }
+        let state = self.state();
+        self.streams = streams.to_vec();
+        self.builtin_noise = builtin_noise;
+        let endless = self.endless_tracks();
+        let standby_tracks = self.standby.as_mut().map(|standby| &mut standby.tracks);
+        for tracks in std::iter::once(&mut self.tracks).chain(standby_tracks) {
+            tracks.retain(|track| !track.is_endless());
+            tracks.splice(0..0, endless.iter().cloned());
+        }
+        // The files moved down the list
+        self.clear_shuffle();
+        self.restore_state(state);
+    }
+
+    /// Read the tracks' tags on a thread of its own, since that takes a while for a big library;
+    /// they are picked up as they come in. A new scan drops the results of the last one.
+    fn read_tags_in_background(&mut self) {
+        let standby_tracks = self.standby.iter().flat_map(|standby| &standby.tracks);
+        let paths: Vec<PathBuf> = self.tracks.iter().chain(standby_tracks).map(|track| track.path.clone()).filter(|path| path.is_file()).collect();
+        let (sender, receiver) = mpsc::channel();
+        self.tag_receiver = Some(receiver);
+        // Without a thread the tracks just go without tags
+        let _ = thread::Builder::new().name(metadata::TAG_THREAD.to_string()).spawn(move || {
+            for path in paths {
+                if let Some(tags) = metadata::read_tags(&path) {
+                    // The list was scanned again and nobody is listening any more
+                    if sender.send((path, tags)).is_err() {
+                        return;
+                    }
                 }
             }
+        });
+    }
+
+    /// Put tags read since the last call onto their tracks
+    fn receive_tags(&mut self) {
+        let Some(receiver) = &self.tag_receiver else {
+            return;
+        };
+        let received: Vec<(PathBuf, TrackTags)> = receiver.try_iter().collect();
+        if received.is_empty() {
+            return;
+        }
+        // The tracks of each file in either playlist, looked up once for the whole lot
+        let mut by_path: HashMap<PathBuf, Vec<&mut Track>> = HashMap::new();
+        let standby_tracks = self.standby.iter_mut().flat_map(|standby| &mut standby.tracks);
+        for track in self.tracks.iter_mut().chain(standby_tracks) {
+            by_path.entry(track.path.clone()).or_default().push(track);
+        }
+        for (path, tags) in received {
+            for track in by_path.get_mut(&path).into_iter().flatten() {
+                track.artist = tags.artist.clone();
+                track.title = tags.title.clone();
+                track.album = tags.album.clone();
+                track.gain = tags.gain;
+                // One found while playing is exact, the headers' can be an estimate
+                track.duration = track.duration.or(tags.duration);
+            }
+        }
+        // The playing track may have started before its gain was known
+        if self.replaygain {
+            self.apply_volume();
+        }
+        if matches!(self.sort, TrackSort::Artist | TrackSort::Album) {
+            self.sort_tracks();
+        }
+    }
+
+    /// Sort both playlists again
+    fn sort_tracks(&mut self) {
+        self.sort_current_playlist();
+        if let Some(standby) = &mut self.standby {
+            standby.sort(self.sort);
         }
+    }
+
+    /// Sort `tracks` again, keeping the playing, selected and shuffled tracks where they are
+    fn sort_current_playlist(&mut self) {
+        let moved_to = order_tracks(&mut self.tracks, self.sort);
+        self.follow_moves(&moved_to);
+    }
+
+    /// Point the playing, selected and shuffled tracks to where `moved_to` says they went
+    fn follow_moves(&mut self, moved_to: &[usize]) {
+        self.current_track = self.current_track.map(|index| moved_to[index]);
+        self.selected_index = moved_to.get(self.selected_index).copied().unwrap_or(0);
+        for index in &mut self.shuffle_order {
+            *index = moved_to[*index];
+        }
+        self.keep_selection_visible();
+    }
 
-        if self.tracks.is_empty() {
-            self.tracks.push(Track {
-                name: "No audio files found".to_string(),
-                path: PathBuf::new(),
-                duration: None,
-            });
-            self.tracks.push(Track {
-                name: format!("Searched in: {}", self.music_folder.display()),
-                path: PathBuf::new(),
-                duration: None,
-            });
+    pub fn set_sort(&mut self, sort: TrackSort) {
+        if self.sort != sort {
+            self.sort = sort;
+            self.sort_tracks();
         }
     }
 
+    pub fn cycle_sort(&mut self) {
+        self.set_sort(self.sort.next());
+    }
+
     pub fn render(&mut self, frame: &mut Frame, area: Rect, app: &App) {
         let is_focused = app.focused_quadrant == Quadrant::BottomRight;
+        self.read_position();
         
         let status = if self.is_playing && !self.is_paused {
             "▶ Playing"
@@ -181,10 +842,11 @@ impl TrackList {
             "⏹ Stopped"
         };
 
-        let items: Vec<ListItem> = self.tracks
+        let visible = self.visible_indices();
+        let items: Vec<ListItem> = visible
             .iter()
-            .enumerate()
-            .map(|(i, track)| {
+            .map(|&i| {
+                let track = &self.tracks[i];
                 let prefix = if Some(i) == self.current_track {
                     if self.is_playing && !self.is_paused {
                         "▶ "
@@ -197,7 +859,20 @@ impl TrackList {
                     "  "
                 };
                 
-                ListItem::new(format!("{}{}", prefix, track.name))
+                let heart = if self.favorites.contains(&track.path) { "♥ " } else { "" };
+                let radio = if track.url.is_some() {
+                    "📡 "
+                } else if track.noise.is_some() {
+                    "〰 "
+                } else {
+                    ""
+                };
+                if track.error.is_some() {
+                    return ListItem::new(format!("⚠ {}{}", heart, track.label(self.show_tags)))
+                        .style(Style::default().fg(DraculaTheme::COMMENT));
+                }
+                let plays = if self.sort == TrackSort::Plays && track.plays > 0 { format!("  ×{}", track.plays) } else { String::new() };
+                ListItem::new(format!("{}{}{}{}{}", prefix, heart, radio, track.label(self.show_tags), plays))
                     .style(if Some(i) == self.current_track {
                         Style::default().fg(DraculaTheme::GREEN)
                     } else {
@@ -214,12 +889,43 @@ impl TrackList {
             )
             .highlight_symbol("► ");
 
-        let title = format!("🎵 Music Player - {} | {} {}", 
-                            status, 
-                            self.playback_mode.icon(), 
-                            self.playback_mode.to_string());
+        // ⏭ when the mode goes on to other tracks by itself, which auto_play_next can turn off
+        let advances = self.auto_play_next && self.playback_mode != PlaybackMode::CurrentOnly;
+        let mut title = format!("🎵 Music Player - {} | {} {}{} | 🔊 {}%", 
+                                status, 
+                                self.playback_mode.icon(), 
+                                self.playback_mode.to_string(),
+                                if advances { " ⏭" } else { "" },
+                                (self.volume * 100.0).round() as u32);
+        if let Some(scan) = &self.scan {
+            title.push_str(&format!(" | Scanning… ({} found)", scan.found));
+        } else if self.duplicates_skipped > 0 {
+            let duplicates = if self.duplicates_skipped == 1 { "duplicate" } else { "duplicates" };
+            title.push_str(&format!(" | {} {} skipped", self.duplicates_skipped, duplicates));
+        }
+        let skipped = self.scan.as_ref().map_or(self.skipped_folders, |scan| scan.progress.skipped.load(atomic::Ordering::Relaxed));
+        if self.sort != TrackSort::Name {
+            title.push_str(&format!(" | ↕ {}", self.sort.name()));
+        }
+        if self.favorites_only {
+            title.push_str(" | ♥ only");
+        }
+        if self.standby.is_some() {
+            let pin = if self.playlist_pinned { " 📌" } else { "" };
+            title.push_str(&format!(" | {} {}{}", self.playlist.icon(), self.playlist.name(), pin));
+        }
+        if self.follow_playing {
+            title.push_str(" | ⤵ follow");
+        }
+        if let Some(timer) = &self.sleep_timer {
+            let left = timer.ends_at.saturating_duration_since(Instant::now());
+            title.push_str(&format!(" | 💤 {}m", left.as_secs().div_ceil(60)));
+        }
+        if let Some(position) = self.position_label() {
+            title.push_str(&format!(" | {}", position));
+        }
 
-        let block = if is_focused {
+        let mut block = if is_focused {
             Block::default()
                 .borders(Borders::ALL)
                 .title(title.as_str())
@@ -232,82 +938,398 @@ impl TrackList {
                 .title_style(Style::default().fg(DraculaTheme::YELLOW))
                 .border_style(Style::default().fg(DraculaTheme::COMMENT))
         };
+        // How much music there is, of what ♥ only leaves shown; the scan counts as it goes
+        if let Some(size) = self.scan.is_none().then(|| self.library_size()).flatten() {
+            block = block.title(Line::from(format!(" {} ", size)).right_aligned());
+        }
+        if let Some(message) = &self.status_message {
+            block = block.title_bottom(Line::styled(format!(" {} ", message), Style::default().fg(DraculaTheme::ORANGE)));
+        }
+        if skipped > 0 {
+            // Explains files missing from the list (see ignore and skip_hidden in the config)
+            let folders = if skipped == 1 { "folder" } else { "folders" };
+            block = block.title_bottom(Line::styled(format!(" {} {} skipped ", skipped, folders), Style::default().fg(DraculaTheme::COMMENT)).right_aligned());
+        }
 
-        let inner = block.inner(area);
+        // The playing track stays in view on the bottom line, whatever the list is scrolled to,
+        // with how far it has got just above when its length is known
+        let progress = self.progress();
+        let gauge_height = if progress.is_some() { 1 } else { 0 };
+        let [inner, gauge_area, footer] = Layout::vertical([Constraint::Min(0), Constraint::Length(gauge_height), Constraint::Length(1)]).areas(block.inner(area));
         frame.render_widget(block, area);
+        if let Some(ratio) = progress {
+            let gauge = Gauge::default()
+                .gauge_style(Style::default().fg(DraculaTheme::PURPLE).bg(DraculaTheme::CURRENT_LINE))
+                .ratio(ratio)
+                .label("");
+            frame.render_widget(gauge, gauge_area);
+        }
+        frame.render_widget(self.now_playing_line(footer.width as usize), footer);
+
+        if visible.is_empty() {
+            self.render_empty(frame, inner);
+            return;
+        }
+
+        // Use the full inner area for the track list, less a column for the scrollbar when it doesn't fit
+        let height = inner.height as usize;
+        self.list_height = height;
+        if visible.len() <= height {
+            frame.render_stateful_widget(list, inner, &mut self.list_state);
+            return;
+        }
+        let [list_area, scrollbar_area] = Layout::horizontal([Constraint::Min(0), Constraint::Length(1)]).areas(inner);
+        frame.render_stateful_widget(list, list_area, &mut self.list_state);
 
-        // Use the full inner area for the track list
-        frame.render_stateful_widget(list, inner, &mut self.list_state);
+        // The list settles its offset while rendering, so the scrollbar follows it
+        let max_offset = visible.len() - height;
+        let mut scrollbar_state = ScrollbarState::new(max_offset + 1)
+            .position(self.list_state.offset().min(max_offset))
+            .viewport_content_length(height);
+        let thumb_color = if is_focused { DraculaTheme::PINK } else { DraculaTheme::COMMENT };
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_style(Style::default().fg(DraculaTheme::COMMENT))
+            .thumb_style(Style::default().fg(thumb_color));
+        frame.render_stateful_widget(scrollbar, scrollbar_area, &mut scrollbar_state);
     }
 
-    pub fn move_selection_up(&mut self) {
-        if !self.tracks.is_empty() {
-            self.selected_index = if self.selected_index == 0 {
-                self.tracks.len() - 1
-            } else {
-                self.selected_index - 1
-            };
-            self.list_state.select(Some(self.selected_index));
+    /// "412 tracks, 27h 13m" for the music files in view, "+" when some lengths aren't known yet,
+    /// just the count before any are; None without files
+    fn library_size(&self) -> Option<String> {
+        let files: Vec<&Track> = self.visible_indices().into_iter().map(|i| &self.tracks[i]).filter(|track| !track.is_endless()).collect();
+        let count = match files.len() {
+            0 => return None,
+            1 => "1 track".to_string(),
+            n => format!("{} tracks", n),
+        };
+        let known: Vec<Duration> = files.iter().filter_map(|track| track.duration).collect();
+        if known.is_empty() {
+            return Some(count);
         }
+        let minutes = known.iter().sum::<Duration>().as_secs() / 60;
+        let partly = if known.len() < files.len() { "+" } else { "" };
+        Some(format!("{}, {}{}", count, format_hours_minutes(minutes), partly))
     }
 
-    pub fn move_selection_down(&mut self) {
-        if !self.tracks.is_empty() {
-            self.selected_index = (self.selected_index + 1) % self.tracks.len();
-            self.list_state.select(Some(self.selected_index));
+    /// In place of the list while there's nothing in it: where the files were looked for, and what to do about it
+    fn render_empty(&self, frame: &mut Frame, area: Rect) {
+        let folder = self.music_folder.display();
+        let lines = if self.scan.is_some() {
+            vec![Line::from(format!("Looking for music in {}…", folder))]
+        } else {
+            vec![
+                Line::styled("No music files found", Style::default().fg(DraculaTheme::FOREGROUND)),
+                Line::from(format!("Looked in {}", folder)),
+                Line::from(""),
+                Line::from("Add mp3, flac, ogg, wav or m4a files there and press R,"),
+                Line::from("or set music_directory in the config and press C"),
+            ]
+        };
+        let height = (lines.len() as u16).min(area.height);
+        let [message] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(area);
+        let paragraph = Paragraph::new(lines)
+            .style(Style::default().fg(DraculaTheme::COMMENT))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(paragraph, message);
+    }
+
+    /// "▶ Artist — Title" for the bottom line, scrolled along when it's wider than `width`
+    fn now_playing_line(&mut self, width: usize) -> Line<'static> {
+        let Some(track) = self.current_track.and_then(|i| self.tracks.get(i)).filter(|_| self.is_playing) else {
+            return Line::styled("⏹ Nothing playing", Style::default().fg(DraculaTheme::COMMENT));
+        };
+        let (icon, color) = if self.is_paused { ("⏸ ", DraculaTheme::COMMENT) } else { ("▶ ", DraculaTheme::GREEN) };
+        let label = track.label(self.show_tags);
+        if label != self.marquee.0 {
+            self.marquee = (label, Instant::now());
         }
+        // Scrolling goes by cells, so wide characters move as far as they take up
+        let room = width.saturating_sub(icon.width());
+        let offset = marquee_offset(self.marquee.1.elapsed(), self.marquee.0.width().saturating_sub(room));
+        Line::styled(format!("{}{}", icon, marquee_window(&self.marquee.0, offset, room)), Style::default().fg(color))
     }
 
-    pub fn play_selected(&mut self) {
-        if self.selected_index < self.tracks.len() {
-            self.play_track(self.selected_index);
+    pub fn move_selection_up(&mut self) {
+        self.pending_state = None;
+        self.selection_moved_at = Some(Instant::now());
+        if let Some(index) = self.neighbor(Some(self.selected_index), false, true) {
+            self.selected_index = index;
+            self.keep_selection_visible();
         }
     }
 
-    pub fn play_track(&mut self, index: usize) {
-        if index >= self.tracks.len() {
-            return;
+    pub fn move_selection_down(&mut self) {
+        self.pending_state = None;
+        self.selection_moved_at = Some(Instant::now());
+        if let Some(index) = self.neighbor(Some(self.selected_index), true, true) {
+            self.selected_index = index;
+            self.keep_selection_visible();
         }
+    }
 
-        let track_path = self.tracks[index].path.clone();
-        if !track_path.exists() {
+    /// Select the playing track and scroll it into the middle of the list
+    pub fn jump_to_playing(&mut self) {
+        let Some(current) = self.current_track else {
+            self.status_message = Some("Nothing playing".to_string());
             return;
-        }
+        };
+        let Some(position) = self.visible_indices().iter().position(|&i| i == current) else {
+            self.status_message = Some("The playing track isn't shown (favorites only)".to_string());
+            return;
+        };
+        self.pending_state = None;
+        self.selected_index = current;
+        self.keep_selection_visible();
+        // The list would only scroll as far as needed, leaving the track at the very edge
+        *self.list_state.offset_mut() = position.saturating_sub(self.list_height / 2);
+    }
 
-        // Stop current playback
-        self.stop();
+    /// Keep (or stop) moving the selection along with auto-advance, starting with the track playing now
+    pub fn toggle_follow_playing(&mut self) {
+        self.follow_playing = !self.follow_playing;
+        if self.follow_playing && self.current_track.is_some() {
+            self.jump_to_playing();
+        }
+    }
 
-        // Initialize audio stream if needed
-        if self.sink.is_none() {
-            if let Ok((stream, stream_handle)) = OutputStream::try_default() {
-                if let Ok(sink) = Sink::try_new(&stream_handle) {
-                    self.sink = Some(Arc::new(Mutex::new(sink)));
-                    self._stream = Some(stream);
-                }
-            }
+    /// After auto-advance: go to the new track when following, unless the selection was just moved by hand
+    fn follow(&mut self) {
+        let browsing = self.selection_moved_at.is_some_and(|moved| moved.elapsed() < FOLLOW_PAUSE);
+        if self.follow_playing && !browsing && self.current_track.is_some() {
+            self.jump_to_playing();
         }
+    }
 
-        if let Some(sink_arc) = &self.sink {
-            let sink_clone = Arc::clone(sink_arc);
-            
-            thread::spawn(move || {
-                if let Ok(file) = fs::File::open(&track_path) {
-                    if let Ok(source) = Decoder::new(BufReader::new(file)) {
-                        if let Ok(sink) = sink_clone.lock() {
-                            sink.append(source);
-                            sink.play();
-                        }
-                    }
+    /// Indices into `tracks` of the tracks shown, and played by next/previous/random/auto-advance:
+    /// all of them, or only the favorites
+    pub fn visible_indices(&self) -> Vec<usize> {
+        (0..self.tracks.len())
+            .filter(|&i| !self.favorites_only || self.favorites.contains(&self.tracks[i].path))
+            .collect()
+    }
+
+    /// The shown track after (or before) `index`, wrapping around the ends when `wrap`; the first
+    /// one for no index. A track that isn't shown counts from where it sits in the whole list.
+    fn neighbor(&self, index: Option<usize>, forward: bool, wrap: bool) -> Option<usize> {
+        let visible = self.visible_indices();
+        let Some(index) = index else {
+            return visible.first().copied();
+        };
+        let found = if forward {
+            visible.iter().find(|&&i| i > index).or(visible.first().filter(|_| wrap))
+        } else {
+            visible.iter().rev().find(|&&i| i < index).or(visible.last().filter(|_| wrap))
+        };
+        found.copied()
+    }
+
+    /// Keep the selection on a shown track (the next one, if it was hidden) and the list scrolled
+    /// to it. Favorites-only is dropped once no favorite is left to show.
+    fn keep_selection_visible(&mut self) {
+        let mut visible = self.visible_indices();
+        // Favorites may still turn up while scanning
+        if visible.is_empty() && self.favorites_only && self.scan.is_none() {
+            self.favorites_only = false;
+            visible = self.visible_indices();
+        }
+        if !visible.contains(&self.selected_index) {
+            self.selected_index = self.neighbor(Some(self.selected_index), true, false)
+                .or(visible.last().copied())
+                .unwrap_or(0);
+        }
+        let first = (!visible.is_empty()).then_some(0);
+        self.list_state.select(visible.iter().position(|&i| i == self.selected_index).or(first));
+    }
+
+    /// Mark the selected track as a favorite (♥), or unmark it, and save the favorites
+    pub fn toggle_favorite(&mut self) {
+        let Some(track) = self.tracks.get(self.selected_index).filter(|track| track.is_playable()) else {
+            return;
+        };
+        // A new favorite joins the next shuffle round; a removed one is skipped until then
+        if !self.favorites.remove(&track.path) {
+            self.favorites.insert(track.path.clone());
+        }
+        self.keep_selection_visible();
+        if let Err(e) = self.save_favorites() {
+            self.status_message = Some(format!("Favorites not saved: {}", e));
+        }
+    }
+
+    /// Show and play only the favorites, or all tracks again. Refused while there are no favorites.
+    pub fn toggle_favorites_only(&mut self) {
+        if !self.favorites_only && !self.tracks.iter().any(|track| self.favorites.contains(&track.path)) {
+            self.status_message = Some("No favorites yet: press f on a track to add one".to_string());
+            return;
+        }
+        self.favorites_only = !self.favorites_only;
+        self.clear_shuffle();
+        self.keep_selection_visible();
+    }
+
+    /// The mode, tracks and volume to pick up from next time
+    pub fn state(&self) -> MusicState {
+        let mut state = MusicState {
+            playback_mode: self.playback_mode.clone(),
+            current_track: self.current_track.and_then(|i| self.tracks.get(i)).map(|track| track.path.clone()),
+            selected_track: self.tracks.get(self.selected_index).map(|track| track.path.clone()),
+            volume: Some(self.volume),
+        };
+        // Quitting before the scan got to the remembered tracks keeps them for next time
+        if let Some(pending) = &self.pending_state {
+            state.current_track = pending.current_track.clone();
+            state.selected_track = pending.selected_track.clone();
+        }
+        state
+    }
+
+    /// Go back to a remembered state; tracks that can't be found any more are left unselected
+    pub fn restore_state(&mut self, state: MusicState) {
+        let position = |path: Option<PathBuf>| path.and_then(|path| self.tracks.iter().position(|track| track.path == path && track.is_playable()));
+        self.current_track = position(state.current_track);
+        if let Some(index) = position(state.selected_track) {
+            self.selected_index = index;
+        }
+        self.playback_mode = state.playback_mode;
+        self.volume = starting_volume(self.default_volume, state.volume);
+        self.keep_selection_visible();
+    }
+
+    fn load_state(&self) -> Option<MusicState> {
+        let content = fs::read_to_string(self.state_path.as_ref()?).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Write the state for the next run
+    pub fn save_state(&self) -> std::io::Result<()> {
+        let Some(path) = &self.state_path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.state())?)
+    }
+
+    fn load_favorites(&mut self) {
+        let Some(content) = self.favorites_path.as_ref().and_then(|path| fs::read_to_string(path).ok()) else {
+            return;
+        };
+        self.favorites = content.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(PathBuf::from)
+            .collect();
+    }
+
+    fn save_favorites(&self) -> std::io::Result<()> {
+        let Some(path) = &self.favorites_path else {
+            return Ok(());
+        };
+        let mut lines: Vec<String> = self.favorites.iter().map(|path| path.to_string_lossy().into_owned()).collect();
+        lines.sort();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, lines.iter().map(|line| format!("{}\n", line)).collect::<String>())
+    }
+
+    /// With nothing in the list, say so rather than quietly doing nothing; true when that's the case
+    fn refuse_when_empty(&mut self) -> bool {
+        if !self.tracks.is_empty() {
+            return false;
+        }
+        self.status_message = Some("No music to play yet: add files to the music folder and press R".to_string());
+        true
+    }
+
+    pub fn play_selected(&mut self) {
+        if self.refuse_when_empty() {
+            return;
+        }
+        if self.selected_index < self.tracks.len() {
+            self.play_track(self.selected_index);
+        }
+    }
+
+    pub fn play_track(&mut self, index: usize) {
+        if index >= self.tracks.len() {
+            return;
+        }
+
+        let track_path = self.tracks[index].path.clone();
+        let url = self.tracks[index].url.clone();
+        let noise = self.tracks[index].noise;
+        if url.is_none() && noise.is_none() && !track_path.exists() {
+            return;
+        }
+
+        // Stop current playback
+        self.stop();
+        self.pending_state = None;
+        // Tried again in case the file was fixed; it's marked again if not
+        self.tracks[index].error = None;
+
+        // Initialize audio stream if needed
+        if self.sink.is_none() {
+            if let Ok((stream, stream_handle)) = OutputStream::try_default() {
+                if let Ok(sink) = Sink::try_new(&stream_handle) {
+                    self.sink = Some(Arc::new(Mutex::new(sink)));
+                    self._stream = Some(stream);
                 }
-            });
+            }
+        }
+
+        if let Some(sink_arc) = &self.sink {
+            let sink_clone = Arc::clone(sink_arc);
+            let probed_duration = Arc::clone(&self.probed_duration);
+            let failed_playback = Arc::clone(&self.failed_playback);
+            let started_playback = Arc::clone(&self.started_playback);
+
+            if let Some(kind) = noise {
+                if let Ok(sink) = sink_clone.lock() {
+                    sink.append(Noise::new(kind));
+                    sink.play();
+                }
+            } else if let Some(url) = url {
+                let state = Arc::new(Mutex::new(StreamState::Connecting));
+                self.stream_state = Some(Arc::clone(&state));
+                thread::spawn(move || stream::play(&url, &sink_clone, state));
+            } else {
+                thread::spawn(move || {
+                    let source = match open_track(&track_path) {
+                        Ok(source) => source,
+                        Err(message) => {
+                            *failed_playback.lock().unwrap_or_else(PoisonError::into_inner) = Some((track_path, message));
+                            return;
+                        }
+                    };
+                    // The decoder knows the length of most files from their headers
+                    if let (Some(total), Ok(mut probed)) = (source.total_duration(), probed_duration.lock()) {
+                        *probed = Some((track_path.clone(), total));
+                    }
+                    if let Ok(sink) = sink_clone.lock() {
+                        sink.append(source);
+                        sink.play();
+                    }
+                    *started_playback.lock().unwrap_or_else(PoisonError::into_inner) = Some(track_path);
+                });
+            }
 
             self.current_track = Some(index);
             self.is_playing = true;
             self.is_paused = false;
+            // A new sink plays at full volume, and each track at its own gain
+            self.apply_volume();
         }
     }
 
     pub fn toggle_play_pause(&mut self) {
+        if self.refuse_when_empty() {
+            return;
+        }
         if let Some(sink_arc) = &self.sink {
             let mut should_play_selected = false;
             let mut should_play_current = false;
@@ -341,72 +1363,332 @@ impl TrackList {
         }
     }
 
+    /// Pause playback; returns true if something was playing
+    pub fn pause(&mut self) -> bool {
+        if !self.is_playing || self.is_paused {
+            return false;
+        }
+        if let Some(Ok(sink)) = self.sink.as_ref().map(|sink_arc| sink_arc.lock()) {
+            sink.pause();
+            self.is_paused = true;
+            return true;
+        }
+        false
+    }
+
+    /// Resume paused playback; does nothing if the music isn't paused
+    pub fn resume(&mut self) {
+        if !self.is_paused {
+            return;
+        }
+        if let Some(Ok(sink)) = self.sink.as_ref().map(|sink_arc| sink_arc.lock()) {
+            sink.play();
+            self.is_paused = false;
+        }
+    }
+
+    /// Make sure music is playing: resume if paused, otherwise start a track according to the playback mode.
+    /// Does nothing when there are no playable files.
+    pub fn ensure_playing(&mut self) {
+        if self.is_paused {
+            self.resume();
+            return;
+        }
+        if self.is_playing || !self.tracks.iter().any(Track::is_playable) {
+            return;
+        }
+
+        if self.playback_mode == PlaybackMode::Random {
+            self.play_random_track();
+        } else {
+            let visible = self.visible_indices();
+            let preferred = self.current_track.unwrap_or(self.selected_index);
+            let index = if visible.contains(&preferred) && self.tracks[preferred].is_playable() {
+                Some(preferred)
+            } else {
+                visible.into_iter().find(|&i| self.tracks[i].is_playable())
+            };
+            if let Some(index) = index {
+                self.play_track(index);
+            }
+        }
+    }
+
     pub fn stop(&mut self) {
         if let Some(sink_arc) = &self.sink {
             if let Ok(sink) = sink_arc.lock() {
                 sink.stop();
             }
         }
+        // A stream still connecting is dropped once it gets through
+        if let Some(state) = self.stream_state.take() {
+            *state.lock().unwrap_or_else(PoisonError::into_inner) = StreamState::Stopped;
+        }
         self.is_playing = false;
         self.is_paused = false;
     }
 
     pub fn next_track(&mut self) {
-        if !self.tracks.is_empty() {
-            let next_index = self.current_track
-                .map(|i| (i + 1) % self.tracks.len())
-                .unwrap_or(0);
+        if self.refuse_when_empty() {
+            return;
+        }
+        let next_index = if self.playback_mode == PlaybackMode::Random {
+            self.shuffle_step(true)
+        } else {
+            self.neighbor(self.current_track, true, true)
+        };
+        if let Some(next_index) = next_index {
             self.play_track(next_index);
         }
     }
 
     pub fn previous_track(&mut self) {
-        if !self.tracks.is_empty() {
-            let prev_index = self.current_track
-                .map(|i| if i == 0 { self.tracks.len() - 1 } else { i - 1 })
-                .unwrap_or(0);
+        if self.refuse_when_empty() {
+            return;
+        }
+        let prev_index = if self.playback_mode == PlaybackMode::Random {
+            self.shuffle_step(false)
+        } else {
+            self.neighbor(self.current_track, false, true)
+        };
+        if let Some(prev_index) = prev_index {
             self.play_track(prev_index);
         }
     }
 
+    /// Carry out what a media key (or another program) asked for
+    pub fn handle_remote(&mut self, command: RemoteCommand) {
+        match command {
+            RemoteCommand::PlayPause => self.toggle_play_pause(),
+            RemoteCommand::Play => self.ensure_playing(),
+            RemoteCommand::Pause => {
+                self.pause();
+            }
+            RemoteCommand::Stop => self.stop(),
+            RemoteCommand::Next => self.next_track(),
+            RemoteCommand::Previous => self.previous_track(),
+        }
+    }
+
+    /// The playing (or paused) track, for other programs
+    #[cfg_attr(not(all(feature = "mpris", target_os = "linux")), allow(dead_code))]
+    pub fn now_playing(&self) -> NowPlaying {
+        let status = match (self.is_playing, self.is_paused) {
+            (true, false) => PlaybackStatus::Playing,
+            (true, true) => PlaybackStatus::Paused,
+            _ => PlaybackStatus::Stopped,
+        };
+        let Some((index, track)) = self.current_track.and_then(|i| Some((i, self.tracks.get(i)?))) else {
+            return NowPlaying { status, ..NowPlaying::default() };
+        };
+        let url = match (&track.url, track.noise) {
+            (Some(url), _) => Some(url.clone()),
+            (None, Some(_)) => None,
+            (None, None) => Some(format!("file://{}", track.path.display())),
+        };
+        NowPlaying {
+            status,
+            track: Some(index),
+            title: track.title.clone().unwrap_or_else(|| track.name.clone()),
+            artist: track.artist.clone(),
+            album: track.album.clone(),
+            url,
+            length: track.duration,
+        }
+    }
+
     pub fn cycle_playback_mode(&mut self) {
+        if self.refuse_when_empty() {
+            return;
+        }
         self.playback_mode = self.playback_mode.next();
+        // Random mode starts a fresh round
+        self.clear_shuffle();
+    }
+
+    fn clear_shuffle(&mut self) {
+        self.shuffle_order.clear();
+        self.shuffle_position = None;
+    }
+
+    /// Walk the shuffled order forward (or back) and return the track there. Going past the end
+    /// starts a new round in a new order that doesn't begin with the track just played; going
+    /// back from the start stays on the first track. Tracks no longer shown are skipped.
+    fn shuffle_step(&mut self, forward: bool) -> Option<usize> {
+        let visible = self.visible_indices();
+        if forward {
+            loop {
+                let mut next = self.shuffle_position.map_or(0, |position| position + 1);
+                if next >= self.shuffle_order.len() {
+                    // Streams and noise are left out, they'd never make way for the next track,
+                    // and so are files that wouldn't play
+                    let finite = visible.iter().copied().filter(|&i| self.tracks[i].can_follow()).collect();
+                    self.reshuffle(finite);
+                    if self.shuffle_order.is_empty() {
+                        return None;
+                    }
+                    next = 0;
+                }
+                self.shuffle_position = Some(next);
+                let index = self.shuffle_order[next];
+                if visible.contains(&index) && self.tracks[index].error.is_none() {
+                    return Some(index);
+                }
+            }
+        }
+        let position = self.shuffle_position?;
+        let previous = (0..position).rev().find(|&p| visible.contains(&self.shuffle_order[p])).unwrap_or(position);
+        self.shuffle_position = Some(previous);
+        Some(self.shuffle_order[previous])
+    }
+
+    fn reshuffle(&mut self, mut order: Vec<usize>) {
+        order.shuffle(&mut self.rng);
+        // No track twice in a row across rounds
+        if order.len() > 1 && Some(order[0]) == self.current_track {
+            let other = self.rng.gen_range(1..order.len());
+            order.swap(0, other);
+        }
+        self.shuffle_order = order;
+        self.shuffle_position = None;
     }
 
     pub fn refresh_library(&mut self) {
         self.stop();
+        self.pending_state = None;
         self.load_tracks();
         self.selected_index = 0;
-        self.list_state.select(Some(0));
         self.current_track = None;
+        self.clear_shuffle();
+        self.keep_selection_visible();
     }
 
-    /// Update the music directory and reload tracks
-    pub fn update_music_directory(&mut self, music_directory: Option<&str>) {
-        let new_folder = if let Some(dir) = music_directory {
-            // Expand ~ to home directory if present
-            if dir.starts_with("~/") {
-                if let Some(home) = dirs::home_dir() {
-                    home.join(&dir[2..])
-                } else {
-                    PathBuf::from(dir)
-                }
-            } else {
-                PathBuf::from(dir)
-            }
-        } else {
-            // Use default logic if no config provided
-            dirs::audio_dir()
-                .or_else(|| dirs::home_dir().map(|p| p.join("Music")))
-                .unwrap_or_else(|| PathBuf::from("./music"))
+    /// Set up separate work and break playlists; a playlist without a directory of its own uses
+    /// `music_directory`, and without either there is just the one list. The other playlist is
+    /// scanned here, the one in use only by the next `refresh_library`.
+    pub fn set_playlists(&mut self, work_directory: Option<&str>, break_directory: Option<&str>, music_directory: Option<&str>) {
+        if work_directory.is_none() && break_directory.is_none() {
+            self.music_folder = music_folder(music_directory);
+            self.playlist = Playlist::Work;
+            self.standby = None;
+            return;
+        }
+        let work_folder = music_folder(work_directory.or(music_directory));
+        let break_folder = music_folder(break_directory.or(music_directory));
+        let (active, other) = match self.playlist {
+            Playlist::Work => (work_folder, break_folder),
+            Playlist::Break => (break_folder, work_folder),
+        };
+        self.music_folder = active;
+        let scan = Some(Scan::start(other.clone(), self.scan_filter.clone()));
+        self.standby = Some(Standby { tracks: self.endless_tracks(), folder: other, selected_index: 0, scan });
+    }
+
+    /// Change to the work or break playlist, carrying on playing if music was playing.
+    /// Nothing changes while the playlist is pinned, and an empty playlist is refused with a notice.
+    /// Returns whether the playlist changed.
+    pub fn switch_playlist(&mut self, playlist: Playlist) -> bool {
+        if self.playlist == playlist || self.playlist_pinned {
+            return false;
+        }
+        let Some(mut standby) = self.standby.take() else {
+            return false;
         };
+        if !standby.tracks.iter().any(Track::is_playable) {
+            let state = if standby.scan.is_some() { "still being scanned" } else { "empty" };
+            self.status_message = Some(format!("{} playlist is {}, staying on {}", playlist.name(), state, self.playlist.name()));
+            self.standby = Some(standby);
+            return false;
+        }
 
-        self.music_folder = new_folder;
-        self.refresh_library();
+        let was_playing = self.is_playing && !self.is_paused;
+        self.stop();
+        std::mem::swap(&mut self.music_folder, &mut standby.folder);
+        std::mem::swap(&mut self.tracks, &mut standby.tracks);
+        std::mem::swap(&mut self.selected_index, &mut standby.selected_index);
+        std::mem::swap(&mut self.scan, &mut standby.scan);
+        self.standby = Some(standby);
+        self.playlist = playlist;
+        self.current_track = None;
+        self.clear_shuffle();
+        self.keep_selection_visible();
+        if was_playing {
+            self.ensure_playing();
+        }
+        true
+    }
+
+    /// Keep the current playlist when the timer changes phase, or switch automatically again
+    pub fn toggle_playlist_pin(&mut self) {
+        if self.standby.is_none() {
+            self.status_message = Some("No work/break playlists set up (see work_directory in the config)".to_string());
+            return;
+        }
+        self.playlist_pinned = !self.playlist_pinned;
+    }
+
+    /// "03:12 / 47:00" for the current track while it is playing or paused; "--:--" for an unknown length.
+    /// For a stream, how far it has got (or why it stopped).
+    fn position_label(&self) -> Option<String> {
+        if let Some(state) = &self.stream_state {
+            let label = match &*state.lock().ok()? {
+                StreamState::Connecting => "📡 connecting…".to_string(),
+                StreamState::Live => format!("📡 live {}", format_duration(self.position)),
+                StreamState::Failed(message) => format!("📡 {}", message),
+                StreamState::Stopped => return None,
+            };
+            return Some(label);
+        }
+        if !self.is_playing {
+            return None;
+        }
+        let track = self.tracks.get(self.current_track?)?;
+        let unknown = if track.noise.is_some() { "∞" } else { "--:--" };
+        let total = track.duration.map_or(unknown.to_string(), format_duration);
+        Some(format!("{} / {}", format_duration(self.position), total))
+    }
+
+    /// Catch up with the sink's playback position. The playback thread holds the sink while it
+    /// starts a track, so a frame that can't have it right away shows the last position instead.
+    fn read_position(&mut self) {
+        if !self.is_playing {
+            self.position = Duration::ZERO;
+            return;
+        }
+        if let Some(Ok(sink)) = self.sink.as_ref().map(|sink_arc| sink_arc.try_lock()) {
+            self.position = sink.get_pos();
+        }
+    }
+
+    /// How much of the current track has been played, 0.0 to 1.0; None while stopped or when its length isn't known
+    fn progress(&self) -> Option<f64> {
+        if !self.is_playing || self.stream_state.is_some() {
+            return None;
+        }
+        let total = self.tracks.get(self.current_track?)?.duration.filter(|total| !total.is_zero())?;
+        Some((self.position.as_secs_f64() / total.as_secs_f64()).clamp(0.0, 1.0))
     }
 
     /// Check if current track has finished and handle auto-advance
     pub fn update_playback_state(&mut self) {
+        self.advance_volume_ramp();
+        self.check_sleep_timer();
+        self.receive_scans();
+        self.receive_tags();
+        // Lengths found while starting playback go onto their tracks
+        if let Some((path, total)) = self.probed_duration.lock().ok().and_then(|mut probed| probed.take()) {
+            for track in self.tracks.iter_mut().filter(|track| track.path == path) {
+                track.duration = Some(total);
+            }
+        }
+        if let Some((path, message)) = self.failed_playback.lock().ok().and_then(|mut failed| failed.take()) {
+            self.mark_failed(&path, message);
+        }
+        if let Some(path) = self.started_playback.lock().ok().and_then(|mut started| started.take()) {
+            self.count_play(&path);
+        }
+        self.play_counts.save_if_due();
+
         let should_advance = if let Some(sink_arc) = &self.sink {
             if let Ok(sink) = sink_arc.lock() {
                 // Check if the sink is empty (track finished) and we were playing
@@ -419,75 +1701,240 @@ impl TrackList {
         };
 
         if should_advance {
+            if let Some(state) = self.stream_state.clone() {
+                // Streams don't finish: nothing is playing yet while connecting, and afterwards it broke off
+                if let Ok(mut state) = state.lock() {
+                    match *state {
+                        StreamState::Connecting => return,
+                        StreamState::Live => *state = StreamState::Failed("Stream ended".to_string()),
+                        StreamState::Failed(_) | StreamState::Stopped => {}
+                    }
+                }
+                self.is_playing = false;
+                return;
+            }
             // Track has finished, handle auto-advance based on playback mode
             self.handle_track_finished();
+            self.follow();
         }
     }
 
-    /// Temporarily lower the music volume during alarm
-    pub fn lower_volume_for_alarm(&mut self, alarm_volume: f32) {
-        if let Some(sink_arc) = &self.sink {
-            if let Ok(sink) = sink_arc.lock() {
-                sink.set_volume(alarm_volume);
-            }
+    /// Count a play of the file at `path` for every track that is that file, in both playlists
+    fn count_play(&mut self, path: &Path) {
+        let Some(real_path) = self.tracks.iter().find(|track| track.path == path).and_then(|track| track.real_path.clone()) else {
+            return;
+        };
+        let plays = self.play_counts.record(&real_path, Local::now());
+        let standby_tracks = self.standby.iter_mut().flat_map(|standby| &mut standby.tracks);
+        for track in self.tracks.iter_mut().chain(standby_tracks).filter(|track| track.real_path.as_ref() == Some(&real_path)) {
+            track.plays = plays;
+        }
+        if self.sort == TrackSort::Plays {
+            self.sort_tracks();
         }
     }
 
-    /// Restore the normal music volume after alarm
-    pub fn restore_volume(&mut self, normal_volume: f32) {
-        if let Some(sink_arc) = &self.sink {
-            if let Ok(sink) = sink_arc.lock() {
-                sink.set_volume(normal_volume);
-            }
+    /// Write the play counts, on the way out
+    pub fn save_play_counts(&mut self) -> std::io::Result<()> {
+        self.play_counts.save()
+    }
+
+    /// Mark the file that wouldn't play, and stop rather than claim to be playing silence
+    fn mark_failed(&mut self, path: &Path, message: String) {
+        let mut name = None;
+        for track in self.tracks.iter_mut().filter(|track| track.path == path) {
+            track.error = Some(message.clone());
+            name = Some(track.name.clone());
+        }
+        let current = self.current_track.and_then(|i| self.tracks.get(i));
+        if current.is_some_and(|track| track.path == path) {
+            self.is_playing = false;
+            self.is_paused = false;
+            self.status_message = Some(format!("Can't play {}: {}", name.unwrap_or_default(), message));
+        }
+    }
+
+    /// Set the sleep timer to the next preset (15, 30 or 60 minutes from now), or turn it off after the last one
+    pub fn cycle_sleep_timer(&mut self) {
+        let next = match &self.sleep_timer {
+            None => SLEEP_PRESETS.first(),
+            Some(timer) => SLEEP_PRESETS.iter().find(|&&minutes| minutes > timer.minutes),
+        };
+        self.cancel_sleep_timer();
+        self.sleep_timer = next.map(|&minutes| SleepTimer { minutes, ends_at: Instant::now() + Duration::from_secs(minutes * 60) });
+    }
+
+    /// Turn the sleep timer off, bringing the music back up if it was fading out
+    pub fn cancel_sleep_timer(&mut self) {
+        if self.sleep_timer.take().is_some() {
+            self.apply_volume();
+        }
+    }
+
+    /// Whether the music is fading out for the sleep timer, which wants frequent updates
+    pub fn is_fading_out(&self) -> bool {
+        self.sleep_timer.as_ref().is_some_and(|timer| timer.ends_at <= Instant::now())
+    }
+
+    /// Once the sleep timer is up, turn the music down a bit more on every update until the
+    /// fade is over, then stop it
+    fn check_sleep_timer(&mut self) {
+        let Some(timer) = &self.sleep_timer else {
+            return;
+        };
+        let Some(fading) = Instant::now().checked_duration_since(timer.ends_at) else {
+            return;
+        };
+        if fading >= SLEEP_FADE || !self.is_playing {
+            self.sleep_timer = None;
+            self.stop();
+            // The next track starts at the usual level again
+            self.apply_volume();
+            return;
+        }
+        let left = 1.0 - fading.as_secs_f32() / SLEEP_FADE.as_secs_f32();
+        if let Some(Ok(sink)) = self.sink.as_ref().map(|sink_arc| sink_arc.lock()) {
+            sink.set_volume(self.effective_volume() * left);
+        }
+    }
+
+    /// How much the current track's ReplayGain tag turns it up or down; 1.0 without one
+    fn track_gain(&self) -> f32 {
+        let gain = self.current_track.and_then(|i| self.tracks.get(i)).and_then(|track| track.gain);
+        match gain {
+            Some(db) if self.replaygain => 10f32.powf(db / 20.0).min(MAX_TRACK_GAIN),
+            _ => 1.0,
+        }
+    }
+
+    /// The level the sink plays at: the one set with +/- (or the alarm's, while it sounds, or
+    /// on the way back up from it), times the track's gain
+    fn effective_volume(&self) -> f32 {
+        let level = match &self.volume_ramp {
+            Some(ramp) => ramp.level(Instant::now()),
+            None => self.ducked_to.unwrap_or(self.volume),
+        };
+        (level * self.track_gain()).min(MAX_VOLUME)
+    }
+
+    /// Whether the music is coming back up after the alarm, which wants frequent updates
+    pub fn is_ramping_volume(&self) -> bool {
+        self.volume_ramp.is_some()
+    }
+
+    /// Turn the music up a bit further on the way back from the alarm's level (the sleep timer's
+    /// fade, checked after, turns it down from there)
+    fn advance_volume_ramp(&mut self) {
+        let Some(ramp) = &self.volume_ramp else {
+            return;
+        };
+        if ramp.started.elapsed() >= VOLUME_RAMP {
+            self.volume_ramp = None;
         }
+        self.apply_volume();
+    }
+
+    /// Set the sink to the effective volume, after anything it depends on changed
+    fn apply_volume(&self) {
+        if let Some(Ok(sink)) = self.sink.as_ref().map(|sink_arc| sink_arc.lock()) {
+            sink.set_volume(self.effective_volume());
+        }
+    }
+
+    /// Temporarily lower the music volume during alarm, right away so the alarm is heard: to
+    /// `duck_percent` of the level set with +/- when given, or else to `alarm_volume`
+    pub fn lower_volume_for_alarm(&mut self, alarm_volume: f32, duck_percent: Option<u32>) {
+        self.volume_ramp = None;
+        self.ducked_to = Some(match duck_percent {
+            Some(percent) => self.volume * percent.min(100) as f32 / 100.0,
+            None => alarm_volume,
+        });
+        self.apply_volume();
+    }
+
+    /// Bring the music back up after the alarm to the level set with +/-, gradually
+    pub fn restore_volume(&mut self) {
+        let Some(ducked_to) = self.ducked_to.take() else {
+            return;
+        };
+        self.volume_ramp = Some(VolumeRamp { from: ducked_to, to: self.volume, started: Instant::now() });
+        self.apply_volume();
+    }
+
+    /// Use a newly configured default volume right away, also for the music already playing
+    pub fn set_default_volume(&mut self, default_volume: f32) {
+        if self.default_volume != default_volume {
+            self.default_volume = default_volume;
+            self.volume = starting_volume(default_volume, None);
+            self.volume_ramp = None;
+            self.apply_volume();
+        }
+    }
+
+    /// Make the music louder (or quieter) by one step, between silent and 150%
+    pub fn step_volume(&mut self, up: bool) {
+        let step = if up { VOLUME_STEP } else { -VOLUME_STEP };
+        // Rounded to whole steps, so repeated presses don't drift
+        self.volume = ((self.volume + step) / VOLUME_STEP).round() * VOLUME_STEP;
+        self.volume = self.volume.clamp(0.0, MAX_VOLUME);
+        // Takes over from a ramp still on its way to the old level
+        self.volume_ramp = None;
+        self.apply_volume();
+    }
+
+    /// Jump `seconds` forward (or back, when negative) in the playing track; paused music stays paused.
+    /// Formats whose decoder can't seek leave the position alone and say so.
+    pub fn seek(&mut self, seconds: i64) {
+        if !self.is_playing {
+            return;
+        }
+        if self.stream_state.is_some() {
+            self.status_message = Some("Can't seek in a live stream".to_string());
+            return;
+        }
+        let Some(Ok(sink)) = self.sink.as_ref().map(|sink_arc| sink_arc.lock()) else {
+            return;
+        };
+        let position = sink.get_pos();
+        let step = Duration::from_secs(seconds.unsigned_abs());
+        let target = if seconds < 0 { position.saturating_sub(step) } else { position + step };
+        // Seeking past the end stops at the end, where the next track takes over
+        self.status_message = match sink.try_seek(target) {
+            Ok(()) => None,
+            Err(SeekError::NotSupported { .. }) => Some("Seek unsupported for this file".to_string()),
+            Err(e) => Some(format!("Seek failed: {}", e)),
+        };
     }
 
     /// Handle what happens when a track finishes playing
     fn handle_track_finished(&mut self) {
-        if self.tracks.is_empty() {
-            return;
+        let next = match after_track(&self.playback_mode, self.auto_play_next, self.current_track, self.tracks.len()) {
+            AfterTrack::Stop => None,
+            AfterTrack::Next { wrap } => self.next_finite(wrap),
+            AfterTrack::Shuffle => self.shuffle_step(true),
+            AfterTrack::Replay => self.current_track,
+        };
+        match next {
+            Some(index) => self.play_track(index),
+            None => self.stop(),
         }
+    }
 
-        match self.playback_mode {
-            PlaybackMode::TrackList => {
-                // Play next track in order, stop at the end
-                if let Some(current) = self.current_track {
-                    let next_index = current + 1;
-                    if next_index < self.tracks.len() {
-                        self.play_track(next_index);
-                    } else {
-                        // Reached the end of the playlist
-                        self.stop();
-                    }
-                }
-            }
-            PlaybackMode::Random => {
-                // Play a random track
-                self.play_random_track();
-            }
-            PlaybackMode::Repeat => {
-                // Play next track in order, loop back to beginning
-                if let Some(current) = self.current_track {
-                    let next_index = (current + 1) % self.tracks.len();
-                    self.play_track(next_index);
-                } else {
-                    self.play_track(0);
-                }
-            }
-            PlaybackMode::CurrentOnly => {
-                // Repeat the same track
-                if let Some(current) = self.current_track {
-                    self.play_track(current);
-                }
+    /// The shown track after the current one that isn't a stream or noise, for auto-advance
+    fn next_finite(&self, wrap: bool) -> Option<usize> {
+        let mut index = self.current_track;
+        for _ in 0..self.tracks.len() {
+            index = self.neighbor(index, true, wrap);
+            if self.tracks[index?].can_follow() {
+                return index;
             }
         }
+        None
     }
 
-    /// Play a random track from the playlist
+    /// Play the next track of the shuffled order
     fn play_random_track(&mut self) {
-        if !self.tracks.is_empty() {
-            let mut rng = rand::thread_rng();
-            let random_index = rng.gen_range(0..self.tracks.len());
+        if let Some(random_index) = self.shuffle_step(true) {
             self.play_track(random_index);
         }
     }
@@ -497,4 +1944,664 @@ impl TrackList {
         // This is now handled by load_tracks() from filesystem
         let _ = track; // Suppress unused parameter warning
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A track list over a folder of (empty) files, with favorites kept next to them
+    fn track_list(name: &str, files: &[&str]) -> (TrackList, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("sessio-tracks-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("music")).unwrap();
+        for file in files {
+            fs::write(dir.join("music").join(file), b"").unwrap();
+        }
+        let mut track_list = TrackList::new(Some(&dir.join("music").to_string_lossy()), &MusicConfig { default_volume: 1.0, ..MusicConfig::default() });
+        finish_scans(&mut track_list);
+        // Start from defaults rather than whatever the real state file says
+        track_list.favorites.clear();
+        track_list.favorites_path = Some(dir.join("favorites.txt"));
+        track_list.state_path = Some(dir.join("music_state.json"));
+        track_list.play_counts = PlayCounts::load(Some(dir.join("play_counts.json")));
+        track_list.tracks.iter_mut().for_each(|track| track.plays = 0);
+        track_list.restore_state(MusicState::default());
+        track_list.volume = 1.0;
+        track_list.tracks.sort_by(|a, b| a.name.cmp(&b.name));
+        (track_list, dir)
+    }
+
+    /// Wait until both playlists have been scanned
+    fn finish_scans(track_list: &mut TrackList) {
+        while track_list.scan.is_some() || track_list.standby.as_ref().is_some_and(|standby| standby.scan.is_some()) {
+            thread::sleep(Duration::from_millis(1));
+            track_list.receive_scans();
+        }
+    }
+
+    fn shown(track_list: &TrackList) -> Vec<&str> {
+        track_list.visible_indices().iter().map(|&i| track_list.tracks[i].name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_favorites_only() {
+        let (mut track_list, dir) = track_list("favorites", &["a.mp3", "b.mp3", "c.mp3", "d.mp3"]);
+        track_list.toggle_favorites_only();
+        assert!(!track_list.favorites_only && track_list.status_message.take().is_some());
+
+        // ♥ b and d, then show only those
+        track_list.selected_index = 1;
+        track_list.toggle_favorite();
+        track_list.selected_index = 3;
+        track_list.toggle_favorite();
+        track_list.selected_index = 2;
+        track_list.toggle_favorites_only();
+        assert_eq!(shown(&track_list), ["b", "d"]);
+        // The hidden selection moved on to the next favorite, shown second in the list
+        assert_eq!((track_list.selected_index, track_list.list_state.selected()), (3, Some(1)));
+
+        // Moving, next and previous stay on favorites, also from a track that isn't one
+        track_list.move_selection_down();
+        assert_eq!((track_list.selected_index, track_list.list_state.selected()), (1, Some(0)));
+        assert_eq!(track_list.neighbor(Some(0), true, false), Some(1));
+        assert_eq!(track_list.neighbor(Some(2), true, false), Some(3));
+        assert_eq!(track_list.neighbor(Some(3), true, false), None);
+        assert_eq!(track_list.neighbor(Some(3), true, true), Some(1));
+        assert_eq!(track_list.neighbor(Some(1), false, true), Some(3));
+
+        // Saved, and read back by a new track list
+        let saved = fs::read_to_string(dir.join("favorites.txt")).unwrap();
+        assert_eq!(saved.lines().count(), 2);
+        track_list.load_favorites();
+        assert_eq!(track_list.favorites.len(), 2);
+
+        // Unmarking the last favorite shown leaves favorites-only
+        track_list.toggle_favorite();
+        assert_eq!(shown(&track_list), ["d"]);
+        track_list.toggle_favorite();
+        assert!(!track_list.favorites_only);
+        assert_eq!(shown(&track_list), ["a", "b", "c", "d"]);
+        assert_eq!(fs::read_to_string(dir.join("favorites.txt")).unwrap(), "");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_state_is_remembered() {
+        let (mut player, dir) = track_list("state", &["a.mp3", "b.mp3", "c.mp3"]);
+        player.playback_mode = PlaybackMode::Random;
+        player.selected_index = 2;
+        player.current_track = Some(1);
+        player.volume = 0.45;
+        player.save_state().unwrap();
+        let saved = fs::read_to_string(dir.join("music_state.json")).unwrap();
+        assert!(saved.contains("\"playback_mode\": \"random\""), "{}", saved);
+
+        let (mut restored, other_dir) = track_list("state-restored", &[]);
+        restored.tracks = player.tracks.clone();
+        restored.restore_state(serde_json::from_str(&saved).unwrap());
+        assert_eq!(restored.playback_mode, PlaybackMode::Random);
+        assert_eq!((restored.selected_index, restored.current_track, restored.volume), (2, Some(1), 0.45));
+
+        // A library that changed since keeps what it can
+        fs::remove_file(&player.tracks[1].path).unwrap();
+        restored.restore_state(MusicState::default());
+        restored.restore_state(serde_json::from_str(&saved).unwrap());
+        assert_eq!((restored.selected_index, restored.current_track), (2, None));
+        restored.restore_state(serde_json::from_str(r#"{"volume": 9.0, "selected_track": "/not/there.mp3"}"#).unwrap());
+        assert_eq!((restored.playback_mode.clone(), restored.selected_index, restored.volume), (PlaybackMode::TrackList, 2, MAX_VOLUME));
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_dir_all(&other_dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_in_background() {
+        let (mut player, dir) = track_list("scan", &["a.mp3", "b.mp3"]);
+        let remembered = MusicState { selected_track: Some(player.tracks[1].path.clone()), ..MusicState::default() };
+
+        // The list fills in as the scan goes; the remembered track is picked once it's found
+        player.pending_state = Some(remembered);
+        player.load_tracks();
+        assert!(player.tracks.is_empty() && player.scan.is_some());
+        // Starting again cancels the first scan, without doubling the tracks
+        player.load_tracks();
+        finish_scans(&mut player);
+        assert_eq!(shown(&player), ["a", "b"]);
+        assert_eq!((player.selected_index, player.pending_state.is_none()), (1, true));
+
+        // Finding nothing leaves the list empty, and playing it does nothing but say so
+        fs::remove_dir_all(dir.join("music")).unwrap();
+        fs::create_dir(dir.join("music")).unwrap();
+        player.load_tracks();
+        finish_scans(&mut player);
+        assert!(player.tracks.is_empty());
+        player.keep_selection_visible();
+        assert_eq!(player.list_state.selected(), None);
+        for action in [TrackList::next_track, TrackList::previous_track, TrackList::toggle_play_pause, TrackList::cycle_playback_mode] {
+            player.status_message = None;
+            action(&mut player);
+            assert!(player.status_message.as_deref().is_some_and(|message| message.starts_with("No music to play")));
+        }
+        assert_eq!((player.is_playing, player.current_track, player.playback_mode.clone()), (false, None, PlaybackMode::TrackList));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_filter() {
+        let (mut player, dir) = track_list("filter", &["a.mp3"]);
+        for file in [".git/x.mp3", "audiobooks/b.mp3", "node_modules/e.mp3", "albums/one/c.mp3", "albums/one/deep/d.mp3"] {
+            let path = dir.join("music").join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"").unwrap();
+        }
+        let config = MusicConfig { ignore: vec!["audiobooks".to_string(), "node_*".to_string()], ..MusicConfig::default() };
+
+        // Hidden and ignored folders are left out and counted; too deep ones just aren't reached
+        player.scan_filter = ScanFilter::new(&config);
+        player.refresh_library();
+        finish_scans(&mut player);
+        player.tracks.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(shown(&player), ["a", "c"]);
+        assert_eq!(player.skipped_folders, 3);
+
+        player.scan_filter = ScanFilter::new(&MusicConfig { scan_depth: 4, skip_hidden: false, ..config });
+        player.refresh_library();
+        finish_scans(&mut player);
+        player.tracks.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(shown(&player), ["a", "c", "d", "x"]);
+        assert_eq!(player.skipped_folders, 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_duplicates_are_skipped() {
+        let (mut player, dir) = track_list("duplicates", &[]);
+        let music = dir.join("music");
+        for (file, content) in [("albums/a.mp3", "aaaa"), ("albums/b.mp3", "bb"), ("backup/a.mp3", "aaaa"), ("backup/c.mp3", "c")] {
+            fs::create_dir_all(music.join(file).parent().unwrap()).unwrap();
+            fs::write(music.join(file), content).unwrap();
+        }
+        // The albums again through a symlinked folder, and one of them through a symlinked file
+        std::os::unix::fs::symlink(music.join("albums"), music.join("linked")).unwrap();
+        std::os::unix::fs::symlink(music.join("albums/b.mp3"), music.join("b.mp3")).unwrap();
+
+        player.refresh_library();
+        finish_scans(&mut player);
+        player.tracks.sort_by(|a, b| a.path.cmp(&b.path));
+        let listed: Vec<_> = player.tracks.iter().map(|track| track.path.strip_prefix(&music).unwrap().to_path_buf()).collect();
+        assert_eq!(listed, ["albums/a.mp3", "albums/b.mp3", "backup/a.mp3", "backup/c.mp3"].map(PathBuf::from));
+        assert_eq!(player.duplicates_skipped, 3);
+
+        // Copies elsewhere only go with dedupe_copies
+        player.scan_filter.dedupe_copies = true;
+        player.refresh_library();
+        finish_scans(&mut player);
+        player.tracks.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(shown(&player), ["a", "b", "c"]);
+        assert_eq!(player.duplicates_skipped, 4);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_starting_volume() {
+        // The configured volume, not a new sink's full volume, unless one was remembered
+        assert_eq!(starting_volume(0.7, None), 0.7);
+        assert_eq!(starting_volume(0.7, Some(0.45)), 0.45);
+        assert_eq!(starting_volume(0.7, Some(f32::NAN)), 0.7);
+        assert_eq!(starting_volume(3.0, None), MAX_VOLUME);
+
+        let (mut player, dir) = track_list("volume", &["a.mp3"]);
+        player.restore_state(MusicState::default());
+        assert_eq!(player.volume, 1.0);
+        player.set_default_volume(0.3);
+        assert_eq!(player.volume, 0.3);
+        // Reloading an unchanged config leaves the level set with +/- alone
+        player.step_volume(true);
+        player.set_default_volume(0.3);
+        assert!((player.volume - 0.35).abs() < 1e-6);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_shuffle_plays_every_track_once_per_round() {
+        let (mut track_list, dir) = track_list("shuffle", &["a.mp3", "b.mp3", "c.mp3", "d.mp3"]);
+        track_list.playback_mode = PlaybackMode::Random;
+        for seed in 0..20 {
+            track_list.rng = StdRng::seed_from_u64(seed);
+            track_list.clear_shuffle();
+            track_list.current_track = None;
+            let played: Vec<usize> = (0..12)
+                .map(|_| {
+                    let index = track_list.shuffle_step(true).unwrap();
+                    track_list.current_track = Some(index);
+                    index
+                })
+                .collect();
+            for round in played.chunks(4) {
+                let mut sorted = round.to_vec();
+                sorted.sort();
+                assert_eq!(sorted, [0, 1, 2, 3], "seed {}: {:?}", seed, played);
+            }
+            assert!(played.windows(2).all(|pair| pair[0] != pair[1]), "seed {}: {:?}", seed, played);
+        }
+
+        // Back walks the same order, and stops at its start
+        track_list.rng = StdRng::seed_from_u64(7);
+        track_list.clear_shuffle();
+        let forward: Vec<usize> = (0..3).map(|_| track_list.shuffle_step(true).unwrap()).collect();
+        assert_eq!(track_list.shuffle_step(false), Some(forward[1]));
+        assert_eq!(track_list.shuffle_step(false), Some(forward[0]));
+        assert_eq!(track_list.shuffle_step(false), Some(forward[0]));
+
+        // Only favorites are shuffled in favorites-only mode; a rescan starts over
+        track_list.favorites = [1, 3].iter().map(|&i| track_list.tracks[i].path.clone()).collect();
+        track_list.toggle_favorites_only();
+        let mut round = [track_list.shuffle_step(true).unwrap(), track_list.shuffle_step(true).unwrap()];
+        round.sort();
+        assert_eq!(round, [1, 3]);
+        track_list.refresh_library();
+        assert!(track_list.shuffle_order.is_empty() && track_list.shuffle_position.is_none());
+        finish_scans(&mut track_list);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_work_and_break_playlists() {
+        let (mut track_list, dir) = track_list("playlists", &["focus.mp3", "deep.mp3"]);
+        let work = dir.join("music").to_string_lossy().to_string();
+        let breaks = dir.join("breaks").to_string_lossy().to_string();
+        fs::create_dir_all(&breaks).unwrap();
+
+        // Without playlists there is nothing to switch to or pin
+        assert!(!track_list.switch_playlist(Playlist::Break));
+        track_list.toggle_playlist_pin();
+        assert!(!track_list.playlist_pinned && track_list.status_message.take().is_some());
+
+        // An empty break folder keeps the work tracks
+        track_list.set_playlists(Some(&work), Some(&breaks), None);
+        finish_scans(&mut track_list);
+        assert!(!track_list.switch_playlist(Playlist::Break));
+        assert_eq!(track_list.playlist, Playlist::Work);
+        assert!(track_list.status_message.take().is_some());
+
+        fs::write(dir.join("breaks").join("lyrics.mp3"), b"").unwrap();
+        track_list.set_playlists(Some(&work), Some(&breaks), None);
+        finish_scans(&mut track_list);
+        track_list.selected_index = 1;
+        assert!(track_list.switch_playlist(Playlist::Break));
+        assert_eq!(shown(&track_list), ["lyrics"]);
+        assert_eq!(track_list.selected_index, 0);
+
+        // Pinned, the break playlist stays through the next work session
+        track_list.toggle_playlist_pin();
+        assert!(!track_list.switch_playlist(Playlist::Work));
+        assert_eq!(track_list.playlist, Playlist::Break);
+        track_list.toggle_playlist_pin();
+
+        // Back to work, where the selection was left
+        assert!(track_list.switch_playlist(Playlist::Work));
+        assert_eq!(track_list.tracks.len(), 2);
+        assert_eq!(track_list.selected_index, 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_streams_never_finish() {
+        let (mut player, dir) = track_list("streams", &["a.mp3", "b.mp3"]);
+        player.selected_index = 1;
+        let lofi = Stream { name: "lofi".to_string(), url: "https://example.com/lofi.mp3".to_string() };
+        player.set_endless_tracks(&[lofi], false);
+        assert_eq!(shown(&player), ["lofi", "a", "b"]);
+        assert_eq!(player.selected_index, 2);
+
+        // A stream that broke off stays the current track, with the reason in the title
+        player.sink = Some(Arc::new(Mutex::new(Sink::new_idle().0)));
+        player.current_track = Some(0);
+        player.is_playing = true;
+        player.stream_state = Some(Arc::new(Mutex::new(StreamState::Live)));
+        player.update_playback_state();
+        assert_eq!((player.current_track, player.is_playing), (Some(0), false));
+        assert_eq!(player.position_label().as_deref(), Some("📡 Stream ended"));
+
+        player.set_endless_tracks(&[], false);
+        assert_eq!(shown(&player), ["a", "b"]);
+        assert_eq!((player.current_track, player.selected_index), (None, 1));
+        assert!(player.position_label().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_noise_is_never_advanced_to() {
+        let (mut player, dir) = track_list("noise", &["a.mp3", "b.mp3"]);
+        player.set_endless_tracks(&[], true);
+        assert_eq!(shown(&player), ["White noise", "Brown noise", "Rain-ish filtered noise", "a", "b"]);
+
+        // Auto-advance goes from the last file back to the first one, and from noise to the files
+        player.current_track = Some(4);
+        assert_eq!(player.next_finite(true), Some(3));
+        assert_eq!(player.next_finite(false), None);
+        player.current_track = Some(1);
+        assert_eq!(player.next_finite(false), Some(3));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_broken_files_are_marked() {
+        let (mut player, dir) = track_list("broken", &["a.mp3", "b.m4a", "c.mp3"]);
+        fs::write(dir.join("music").join("b.m4a"), b"not really music").unwrap();
+        assert!(open_track(&player.tracks[1].path).err().unwrap().starts_with("can't decode"));
+        assert!(open_track(&dir.join("music").join("missing.mp3")).err().unwrap().starts_with("can't open file"));
+
+        // The playback thread's failure stops playback and marks the track
+        player.current_track = Some(1);
+        player.is_playing = true;
+        *player.failed_playback.lock().unwrap() = Some((player.tracks[1].path.clone(), "can't decode (unsupported)".to_string()));
+        player.update_playback_state();
+        assert!(!player.is_playing);
+        assert_eq!(player.tracks[1].error.as_deref(), Some("can't decode (unsupported)"));
+        assert_eq!(player.status_message.as_deref(), Some("Can't play b: can't decode (unsupported)"));
+
+        // Auto-advance passes it by, in order and shuffled
+        player.current_track = Some(0);
+        assert_eq!(player.next_finite(false), Some(2));
+        player.rng = StdRng::seed_from_u64(1);
+        let shuffled: Vec<_> = (0..6).filter_map(|_| player.shuffle_step(true)).collect();
+        assert!(!shuffled.is_empty() && !shuffled.contains(&1));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_jump_to_playing() {
+        let names: Vec<String> = (0..30).map(|i| format!("{:02}.mp3", i)).collect();
+        let (mut player, dir) = track_list("jump", &names.iter().map(String::as_str).collect::<Vec<_>>());
+        player.list_height = 10;
+        player.jump_to_playing();
+        assert_eq!(player.status_message.as_deref(), Some("Nothing playing"));
+
+        // The playing track ends up selected, in the middle of the list
+        player.current_track = Some(20);
+        player.jump_to_playing();
+        assert_eq!((player.selected_index, player.list_state.selected(), player.list_state.offset()), (20, Some(20), 15));
+
+        // Following waits while the selection is being moved by hand
+        player.toggle_follow_playing();
+        player.move_selection_down();
+        player.current_track = Some(25);
+        player.follow();
+        assert_eq!(player.selected_index, 21);
+        player.selection_moved_at = Some(Instant::now() - FOLLOW_PAUSE);
+        player.follow();
+        assert_eq!(player.selected_index, 25);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_now_playing() {
+        let (mut player, dir) = track_list("remote", &["a.mp3", "b.mp3"]);
+        assert_eq!(player.now_playing(), NowPlaying::default());
+
+        player.tracks[1].artist = Some("Nils Frahm".to_string());
+        player.tracks[1].duration = Some(Duration::from_secs(200));
+        player.current_track = Some(1);
+        player.is_playing = true;
+        let now = player.now_playing();
+        assert_eq!((now.status, now.track, now.title.as_str()), (PlaybackStatus::Playing, Some(1), "b"));
+        assert_eq!((now.artist.as_deref(), now.length), (Some("Nils Frahm"), Some(Duration::from_secs(200))));
+        assert_eq!(now.url, Some(format!("file://{}", dir.join("music").join("b.mp3").display())));
+
+        // Without a sound device there's nothing to pause; stopping works all the same
+        player.handle_remote(RemoteCommand::Pause);
+        player.handle_remote(RemoteCommand::Stop);
+        assert_eq!(player.now_playing().status, PlaybackStatus::Stopped);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sleep_timer() {
+        let (mut player, dir) = track_list("sleep", &["a.mp3"]);
+        let presets: Vec<Option<u64>> = (0..4).map(|_| {
+            player.cycle_sleep_timer();
+            player.sleep_timer.as_ref().map(|timer| timer.minutes)
+        }).collect();
+        assert_eq!(presets, [Some(15), Some(30), Some(60), None]);
+
+        // The music plays on while fading out, and stops after the fade
+        player.cycle_sleep_timer();
+        player.current_track = Some(0);
+        player.is_playing = true;
+        player.sleep_timer.as_mut().unwrap().ends_at = Instant::now() - SLEEP_FADE / 2;
+        assert!(player.is_fading_out());
+        player.update_playback_state();
+        assert!(player.is_playing && player.sleep_timer.is_some());
+        player.sleep_timer.as_mut().unwrap().ends_at = Instant::now() - SLEEP_FADE;
+        player.update_playback_state();
+        assert!(!player.is_playing && player.sleep_timer.is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_progress() {
+        let (mut player, dir) = track_list("progress", &["a.mp3", "b.mp3"]);
+        player.current_track = Some(0);
+        player.position = Duration::from_secs(30);
+        assert_eq!(player.progress(), None);
+        player.is_playing = true;
+        // No gauge until the length is known
+        assert_eq!(player.progress(), None);
+        player.tracks[0].duration = Some(Duration::from_secs(120));
+        assert_eq!(player.progress(), Some(0.25));
+        player.position = Duration::from_secs(130);
+        assert_eq!(player.progress(), Some(1.0));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_library_size() {
+        let (mut player, dir) = track_list("library", &["a.mp3", "b.mp3", "c.mp3"]);
+        player.set_endless_tracks(&[], true);
+        // Just the files, counted before their lengths are known
+        assert_eq!(player.library_size().as_deref(), Some("3 tracks"));
+        player.tracks.iter_mut().filter(|track| !track.is_endless()).for_each(|track| track.duration = Some(Duration::from_secs(1200)));
+        assert_eq!(player.library_size().as_deref(), Some("3 tracks, 1h 0m"));
+        let b = player.tracks.iter().position(|track| track.name == "b").unwrap();
+        player.tracks[b].duration = None;
+        assert_eq!(player.library_size().as_deref(), Some("3 tracks, 0h 40m+"));
+        // Only what's in view
+        player.favorites.insert(player.tracks[b].path.clone());
+        player.favorites_only = true;
+        assert_eq!(player.library_size().as_deref(), Some("1 track"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_after_track() {
+        use PlaybackMode::*;
+        // Going on by itself
+        assert_eq!(after_track(&TrackList, true, Some(1), 3), AfterTrack::Next { wrap: false });
+        assert_eq!(after_track(&Repeat, true, Some(2), 3), AfterTrack::Next { wrap: true });
+        assert_eq!(after_track(&Random, true, Some(0), 3), AfterTrack::Shuffle);
+        assert_eq!(after_track(&CurrentOnly, true, Some(0), 3), AfterTrack::Replay);
+        // Without auto_play_next only the current track repeats
+        assert_eq!(after_track(&TrackList, false, Some(1), 3), AfterTrack::Stop);
+        assert_eq!(after_track(&Repeat, false, Some(2), 3), AfterTrack::Stop);
+        assert_eq!(after_track(&Random, false, Some(0), 3), AfterTrack::Stop);
+        assert_eq!(after_track(&CurrentOnly, false, Some(0), 3), AfterTrack::Replay);
+        // Nothing to go on from
+        assert_eq!(after_track(&Repeat, true, None, 3), AfterTrack::Stop);
+        assert_eq!(after_track(&CurrentOnly, true, Some(3), 3), AfterTrack::Stop);
+        assert_eq!(after_track(&TrackList, true, Some(0), 0), AfterTrack::Stop);
+
+        let (mut player, dir) = track_list("auto-play", &["a.mp3", "b.mp3"]);
+        player.auto_play_next = false;
+        player.current_track = Some(0);
+        player.is_playing = true;
+        player.handle_track_finished();
+        assert_eq!((player.is_playing, player.current_track), (false, Some(0)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replaygain() {
+        let (mut player, dir) = track_list("replaygain", &["a.mp3", "b.mp3"]);
+        player.volume = 0.8;
+        player.tracks[0].gain = Some(-6.0);
+        player.tracks[1].gain = Some(12.0);
+        player.current_track = Some(0);
+        // Tags are ignored unless enabled
+        assert_eq!(player.effective_volume(), 0.8);
+
+        player.replaygain = true;
+        assert!((player.effective_volume() - 0.8 * 0.501).abs() < 0.001);
+        // Turned up by at most half again, and never past the loudest volume
+        player.current_track = Some(1);
+        assert_eq!(player.effective_volume(), 1.2);
+        player.volume = 1.4;
+        assert_eq!(player.effective_volume(), MAX_VOLUME);
+
+        // The alarm's level takes the place of ours, still at the track's gain
+        player.current_track = Some(0);
+        player.lower_volume_for_alarm(0.3, None);
+        assert!((player.effective_volume() - 0.3 * 0.501).abs() < 0.001);
+        player.restore_volume();
+        player.volume_ramp.as_mut().unwrap().started -= VOLUME_RAMP;
+        player.update_playback_state();
+        assert!((player.effective_volume() - 1.4 * 0.501).abs() < 0.001);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_volume_ramp() {
+        let (mut player, dir) = track_list("ramp", &["a.mp3"]);
+        player.volume = 0.8;
+        player.lower_volume_for_alarm(0.2, None);
+        assert_eq!(player.effective_volume(), 0.2);
+        // Back up bit by bit, not all at once
+        player.restore_volume();
+        assert!(player.is_ramping_volume());
+        assert!((player.effective_volume() - 0.2).abs() < 0.05);
+        let ramp = player.volume_ramp.as_ref().unwrap();
+        assert!((ramp.level(ramp.started + VOLUME_RAMP / 2) - 0.5).abs() < 0.001);
+        assert_eq!(ramp.level(ramp.started + VOLUME_RAMP * 2), 0.8);
+        player.volume_ramp.as_mut().unwrap().started -= VOLUME_RAMP;
+        player.update_playback_state();
+        assert!(!player.is_ramping_volume());
+        assert_eq!(player.effective_volume(), 0.8);
+
+        // Turning it up by hand on the way takes over right away
+        player.lower_volume_for_alarm(0.2, None);
+        player.restore_volume();
+        player.step_volume(true);
+        assert!(!player.is_ramping_volume());
+        assert_eq!(player.effective_volume(), 0.85);
+        // And nothing to restore without an alarm
+        player.restore_volume();
+        assert!(!player.is_ramping_volume());
+
+        // Ducked relative to the level listened at, which it goes back to
+        player.volume = 0.2;
+        player.lower_volume_for_alarm(0.3, Some(30));
+        assert!((player.effective_volume() - 0.06).abs() < 0.001);
+        player.restore_volume();
+        player.volume_ramp.as_mut().unwrap().started -= VOLUME_RAMP;
+        player.update_playback_state();
+        assert_eq!(player.effective_volume(), 0.2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_marquee() {
+        // Rests at the start, scrolls a cell per step, rests at the end and starts over
+        let overflow = 4;
+        let offsets: Vec<usize> = [0, 1400, 1500, 1800, 2400, 2700, 3000, 4100, 4200]
+            .iter()
+            .map(|&ms| marquee_offset(Duration::from_millis(ms), overflow))
+            .collect();
+        assert_eq!(offsets, [0, 0, 0, 1, 3, 4, 4, 4, 0]);
+        assert_eq!(marquee_offset(Duration::from_secs(100), 0), 0);
+
+        assert_eq!(marquee_window("Nils Frahm — Says", 5, 5), "Frahm");
+        // Wide characters take two cells, and a half-shown one leaves a space
+        assert_eq!(marquee_window("坂本龍一 — Aqua", 0, 5), "坂本 ");
+        assert_eq!(marquee_window("坂本龍一 — Aqua", 1, 5), " 本龍");
+        assert_eq!(marquee_window("坂本龍一 — Aqua", 2, 5), "本龍 ");
+        assert_eq!(marquee_window("abc", 1, 10), "bc");
+        for offset in 0..16 {
+            assert!(marquee_window("坂本龍一 — Aqua", offset, 5).width() <= 5);
+        }
+    }
+
+    #[test]
+    fn test_sorting_follows_the_tracks() {
+        let (mut player, dir) = track_list("sort", &["b.mp3", "A.mp3", "c.mp3"]);
+        for (name, age) in [("c.mp3", 300), ("A.mp3", 200), ("b.mp3", 100)] {
+            let file = fs::File::options().write(true).open(dir.join("music").join(name)).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(age)).unwrap();
+        }
+        player.refresh_library();
+        finish_scans(&mut player);
+        assert_eq!(shown(&player), ["A", "b", "c"]);
+
+        // The selected and the playing track stay the same tracks
+        player.selected_index = 1;
+        player.current_track = Some(2);
+        player.cycle_sort();
+        assert_eq!(player.sort, TrackSort::Path);
+        player.cycle_sort();
+        assert_eq!(shown(&player), ["b", "A", "c"]);
+        assert_eq!(player.tracks[player.selected_index].name, "b");
+        assert_eq!(player.tracks[player.current_track.unwrap()].name, "c");
+
+        // Untagged tracks go last
+        for track in &mut player.tracks {
+            track.artist = match track.name.as_str() {
+                "b" => Some("zed".to_string()),
+                "c" => Some("Abba".to_string()),
+                _ => None,
+            };
+        }
+        player.cycle_sort();
+        assert_eq!(shown(&player), ["c", "b", "A"]);
+        assert_eq!((player.selected_index, player.current_track), (1, Some(0)));
+        player.cycle_sort();
+        player.cycle_sort();
+        player.cycle_sort();
+        assert_eq!(player.sort, TrackSort::Name);
+        assert_eq!(shown(&player), ["A", "b", "c"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_merging_a_scan_batch() {
+        let track = |name: &str| Track::new(name.to_string(), PathBuf::from(format!("/music/{}.mp3", name)));
+        let mut tracks = vec![Track::noise(NoiseKind::ALL[0]), track("b"), track("D")];
+        let moved_to = merge_tracks(&mut tracks, vec![track("e"), track("a"), track("C")], TrackSort::Name);
+        let names: Vec<&str> = tracks.iter().skip(1).map(|track| track.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "C", "D", "e"]);
+        assert_eq!(moved_to, [0, 2, 4]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_play_counts() {
+        let (mut player, dir) = track_list("plays", &["a.mp3", "b.mp3", "c.mp3"]);
+        std::os::unix::fs::symlink(dir.join("music"), dir.join("link")).unwrap();
+        player.set_sort(TrackSort::Plays);
+        for name in ["b.mp3", "c.mp3", "b.mp3"] {
+            *player.started_playback.lock().unwrap() = Some(dir.join("music").join(name));
+            player.update_playback_state();
+        }
+        // Most played first
+        assert_eq!(shown(&player), ["b", "c", "a"]);
+        assert_eq!(player.tracks.iter().map(|track| track.plays).collect::<Vec<_>>(), [2, 1, 0]);
+
+        // Found again by a rescan, also through another path to the same files
+        player.save_play_counts().unwrap();
+        let mut rescanned = TrackList::new(Some(&dir.join("link").to_string_lossy()), &MusicConfig::default());
+        rescanned.play_counts = PlayCounts::load(Some(dir.join("play_counts.json")));
+        rescanned.set_sort(TrackSort::Plays);
+        rescanned.load_tracks();
+        finish_scans(&mut rescanned);
+        assert_eq!(shown(&rescanned), ["b", "c", "a"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}